@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use account::{Account, AccountError, AccountTransactionInteraction, AccountType, PrunedAccount, Receipt, Receipts};
 use account::inherent::{AccountInherentInteraction, Inherent};
@@ -9,6 +9,8 @@ use keys::Address;
 use transaction::{Transaction, TransactionFlags};
 use tree_primitives::accounts_proof::AccountsProof;
 use tree_primitives::accounts_tree_chunk::AccountsTreeChunk;
+use tree_primitives::accounts_tree_node::AccountsTreeNode;
+use tree_primitives::address_nibbles::AddressNibbles;
 
 use crate::tree::AccountsTree;
 
@@ -26,6 +28,12 @@ impl Accounts {
         Accounts { env, tree, }
     }
 
+    /// Opens the accounts tree stored in the database `name` instead of the default one.
+    pub fn with_name(env: Environment, name: &str) -> Self {
+        let tree = AccountsTree::with_name(env.clone(), name);
+        Accounts { env, tree, }
+    }
+
     pub fn init(&self, txn: &mut WriteTransaction, genesis_accounts: Vec<(Address, Account)>) {
         for (address, account) in genesis_accounts {
             self.tree.put_batch(txn, &address, account);
@@ -33,6 +41,27 @@ impl Accounts {
         self.tree.finalize_batch(txn);
     }
 
+    /// Copies the accounts at `addresses` from `source`, e.g. to bring a copy of `source` that
+    /// only differs in these accounts up to date. Both trees are read and written in `txn`.
+    pub fn copy_from<'a, I: IntoIterator<Item=&'a Address>>(&self, txn: &mut WriteTransaction, source: &Accounts, addresses: I) {
+        for address in addresses {
+            let account = source.get(address, Some(&txn));
+            self.tree.put_batch(txn, address, account);
+        }
+        self.tree.finalize_batch(txn);
+    }
+
+    /// Replaces all accounts in the tree with `accounts`.
+    pub fn replace(&self, txn: &mut WriteTransaction, accounts: Vec<(Address, Account)>) {
+        let addresses: HashSet<&Address> = accounts.iter().map(|(address, _)| address).collect();
+        for (address, _) in self.export(Some(&txn)) {
+            if !addresses.contains(&address) {
+                self.tree.put_batch(txn, &address, Account::INITIAL);
+            }
+        }
+        self.init(txn, accounts);
+    }
+
     pub fn get(&self, address: &Address, txn_option: Option<&db::Transaction>) -> Account {
         match txn_option {
             Some(txn) => self.tree.get(txn, address),
@@ -51,6 +80,22 @@ impl Accounts {
         self.tree.get_accounts_proof(txn, addresses)
     }
 
+    /// Returns all accounts in the tree, ordered by address.
+    /// Importing them with `init` into an empty tree yields the same root hash.
+    pub fn export(&self, txn_option: Option<&db::Transaction>) -> Vec<(Address, Account)> {
+        let nodes = match txn_option {
+            Some(txn) => self.tree.get_terminal_nodes(txn, &AddressNibbles::empty(), usize::max_value()),
+            None => self.tree.get_terminal_nodes(&ReadTransaction::new(&self.env), &AddressNibbles::empty(), usize::max_value()),
+        };
+        nodes.unwrap_or_default()
+            .into_iter()
+            .filter_map(|node| match node {
+                AccountsTreeNode::TerminalNode { prefix, account } => Some((prefix.to_address()?, account)),
+                AccountsTreeNode::BranchNode { .. } => None,
+            })
+            .collect()
+    }
+
     pub fn hash(&self, txn_option: Option<&db::Transaction>) -> Blake2bHash {
         match txn_option {
             Some(txn) => self.tree.root_hash(txn),
@@ -20,7 +20,12 @@ impl<A: AccountsTreeLeave> AccountsTree<A> {
     const DB_NAME: &'static str = "accounts";
 
     pub fn new(env: Environment) -> Self {
-        let db = env.open_database(Self::DB_NAME.to_string());
+        Self::with_name(env, Self::DB_NAME)
+    }
+
+    /// Opens the tree stored in the database `name`, so that several trees can share an environment.
+    pub fn with_name(env: Environment, name: &str) -> Self {
+        let db = env.open_database(name.to_string());
         let tree = AccountsTree { db, _account: PhantomData };
 
         let mut txn = WriteTransaction::new(&env);
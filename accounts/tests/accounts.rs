@@ -358,3 +358,30 @@ fn can_generate_accounts_proof() {
     assert_eq!(None, proof2.get_account(&address_recipient1));
     assert_eq!(Account::Basic(BasicAccount { balance: value2 }), proof2.get_account(&address_recipient2).unwrap());
 }
+
+#[test]
+fn it_can_export_and_import_accounts() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let genesis_accounts: Vec<(Address, Account)> = (1u8..=4).map(|i| {
+        (Address::from([i; Address::SIZE]), Account::Basic(BasicAccount { balance: Coin::try_from(u64::from(i) * 1000).unwrap() }))
+    }).collect();
+
+    {
+        let mut txn = WriteTransaction::new(&env);
+        accounts.init(&mut txn, genesis_accounts.clone());
+        txn.commit();
+    }
+
+    let exported = accounts.export(None);
+    assert_eq!(exported, genesis_accounts);
+
+    let env2 = VolatileEnvironment::new(10).unwrap();
+    let accounts2 = Accounts::new(env2.clone());
+    {
+        let mut txn = WriteTransaction::new(&env2);
+        accounts2.init(&mut txn, exported);
+        txn.commit();
+    }
+    assert_eq!(accounts2.hash(None), accounts.hash(None));
+}
@@ -16,7 +16,7 @@ use block::{Block, BlockError, BlockHeader, BlockType, ForkProof, MacroBlock, Ma
 use blockchain_base::{AbstractBlockchain, BlockchainError, Direction};
 #[cfg(feature = "metrics")]
 use blockchain_base::chain_metrics::BlockchainMetrics;
-use bls::bls12_381::PublicKey;
+use bls::bls12_381::{CompressedPublicKey, PublicKey};
 use collections::bitset::BitSet;
 use database::{Environment, ReadTransaction, Transaction, WriteTransaction};
 use hash::{Blake2bHash, Hash};
@@ -36,8 +36,11 @@ use vrf::{VrfSeed, VrfUseCase, AliasMethod};
 
 use crate::chain_info::ChainInfo;
 use crate::chain_store::ChainStore;
-use crate::reward_registry::{EpochStateError, SlashRegistry, SlashedSetSelector};
+use crate::reward_registry::{EpochStateError, SlashEvent, SlashRegistry, SlashedSetSelector};
+use crate::snapshot::{AccountsSnapshot, SnapshotAccount, SnapshotError, SnapshotTransactions, SNAPSHOT_VERSION};
 use crate::transaction_cache::TransactionCache;
+#[cfg(feature = "transaction-store")]
+use crate::transaction_store::TransactionStore;
 
 
 pub type PushResult = blockchain_base::PushResult;
@@ -108,12 +111,24 @@ pub struct Blockchain {
     pub(crate) state: RwLock<BlockchainState>,
     push_lock: Mutex<()>,
 
+    /// Light blockchains only follow the macro block chain and don't keep an accounts tree.
+    light: bool,
+
+    /// Number of finalized epochs whose micro block bodies are kept. `None` keeps all epochs.
+    keep_epochs: Option<u32>,
+
+    #[cfg(feature = "transaction-store")]
+    pub(crate) transaction_store: TransactionStore,
+
     #[cfg(feature = "metrics")]
     metrics: BlockchainMetrics,
 }
 
 pub struct BlockchainState {
     pub accounts: Accounts,
+    /// The accounts at the macro head, from which accounts proofs for the macro head are served
+    /// without reverting the micro blocks on top of it.
+    pub(crate) macro_accounts: Accounts,
     pub transaction_cache: TransactionCache,
     pub reward_registry: SlashRegistry,
 
@@ -174,15 +189,90 @@ impl BlockchainState {
 }
 
 impl Blockchain {
+    /// Minimum number of finalized epochs to keep when pruning. All blocks in the transaction
+    /// validity window must be available to check for duplicate transactions.
+    pub const MIN_KEEP_EPOCHS: u32 = policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS / policy::EPOCH_LENGTH + 1;
+
+    const MACRO_ACCOUNTS_DB_NAME: &'static str = "MacroAccounts";
+
     pub fn new(env: Environment, network_id: NetworkId) -> Result<Self, BlockchainError> {
+        Self::with_mode(env, network_id, false)
+    }
+
+    /// Creates a light blockchain that only follows the macro block chain.
+    /// Macro blocks are pushed with `push_light_macro_block` and only verified using their
+    /// justification. No accounts tree is kept, so accounts must be requested from peers.
+    pub fn new_light(env: Environment, network_id: NetworkId) -> Result<Self, BlockchainError> {
+        Self::with_mode(env, network_id, true)
+    }
+
+    fn with_mode(env: Environment, network_id: NetworkId, light: bool) -> Result<Self, BlockchainError> {
         let chain_store = Arc::new(ChainStore::new(env.clone()));
         Ok(match chain_store.get_head(None) {
-            Some(head_hash) => Blockchain::load(env, network_id, chain_store, head_hash)?,
-            None => Blockchain::init(env, network_id, chain_store)?
+            Some(head_hash) => Blockchain::load(env, network_id, chain_store, head_hash, light)?,
+            None => Blockchain::init(env, network_id, chain_store, light)?
         })
     }
 
-    fn load(env: Environment, network_id: NetworkId, chain_store: Arc<ChainStore>, head_hash: Blake2bHash) -> Result<Self, BlockchainError> {
+    /// Bootstraps a blockchain from an accounts snapshot instead of syncing from genesis.
+    /// The rebuilt accounts tree must match the state root of the snapshot's macro block.
+    /// Blocks before that macro block stay unknown except for the hashes of their transactions,
+    /// which are used to reject replayed transactions.
+    pub fn from_snapshot(env: Environment, network_id: NetworkId, snapshot: AccountsSnapshot) -> Result<Self, SnapshotError> {
+        snapshot.verify(network_id)?;
+
+        let chain_store = Arc::new(ChainStore::new(env.clone()));
+        if chain_store.get_head(None).is_some() {
+            return Err(SnapshotError::ChainNotEmpty);
+        }
+
+        let network_info = NetworkInfo::from_network_id(network_id);
+        let genesis_block = network_info.genesis_block::<Block>();
+        let genesis_hash = network_info.genesis_hash().clone();
+        let AccountsSnapshot { macro_block, previous_macro_block, election_blocks, transactions, slash_registry: slash_registry_snapshot, accounts: snapshot_accounts, .. } = snapshot;
+        let macro_block_number = macro_block.header.block_number;
+        let macro_hash = macro_block.hash();
+        let state_root = macro_block.header.state_root.clone();
+        let previous_hash = previous_macro_block.hash();
+
+        let accounts = Accounts::new(env.clone());
+        let macro_accounts = Accounts::with_name(env.clone(), Self::MACRO_ACCOUNTS_DB_NAME);
+        let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(&chain_store));
+        let mut txn = WriteTransaction::new(&env);
+
+        // Store the genesis block and the election blocks. The other blocks before the snapshot stay unknown.
+        chain_store.put_chain_info(&mut txn, &genesis_hash, &ChainInfo::initial(genesis_block), true);
+        for block in election_blocks.into_iter().filter(|block| block.hash() != previous_hash) {
+            chain_store.put_chain_info(&mut txn, &block.hash(), &ChainInfo::initial(Block::Macro(block)), true);
+        }
+        let mut previous_info = ChainInfo::initial(Block::Macro(previous_macro_block));
+        previous_info.main_chain_successor = Some(macro_hash.clone());
+        chain_store.put_chain_info(&mut txn, &previous_hash, &previous_info, true);
+        chain_store.put_chain_info(&mut txn, &macro_hash, &ChainInfo::initial(Block::Macro(macro_block)), true);
+        chain_store.set_head(&mut txn, &macro_hash);
+        chain_store.set_snapshot_transactions(&mut txn, &transactions);
+
+        // Rebuild the accounts tree and check it against the state root of the macro block.
+        // The snapshot is taken at a macro block, so the macro accounts tree starts out the same.
+        let snapshot_accounts: Vec<(Address, Account)> = snapshot_accounts.into_iter()
+            .map(|entry| (entry.address, entry.account))
+            .collect();
+        accounts.init(&mut txn, snapshot_accounts.clone());
+        macro_accounts.init(&mut txn, snapshot_accounts);
+        if accounts.hash(Some(&txn)) != state_root {
+            warn!("Rejecting snapshot - accounts don't match the state root of macro block #{}", macro_block_number);
+            txn.abort();
+            return Err(SnapshotError::StateRootMismatch);
+        }
+
+        slash_registry.restore_snapshot(&mut txn, macro_block_number, &slash_registry_snapshot);
+        txn.commit();
+
+        info!("Bootstrapped blockchain from snapshot at macro block #{}", macro_block_number);
+        Ok(Blockchain::load(env, network_id, chain_store, macro_hash, false)?)
+    }
+
+    fn load(env: Environment, network_id: NetworkId, chain_store: Arc<ChainStore>, head_hash: Blake2bHash, light: bool) -> Result<Self, BlockchainError> {
         // Check that the correct genesis block is stored.
         let network_info = NetworkInfo::from_network_id(network_id);
         let genesis_info = chain_store.get_chain_info(network_info.genesis_hash(), false, None);
@@ -196,10 +286,12 @@ impl Blockchain {
             .ok_or(BlockchainError::FailedLoadingMainChain)?;
 
         // Check that chain/accounts state is consistent.
+        // Light blockchains don't have any accounts state.
         let accounts = Accounts::new(env.clone());
-        if main_chain.head.state_root() != &accounts.hash(None) {
+        if !light && main_chain.head.state_root() != &accounts.hash(None) {
             return Err(BlockchainError::InconsistentState);
         }
+        let macro_accounts = Accounts::with_name(env.clone(), Self::MACRO_ACCOUNTS_DB_NAME);
 
         // Load macro chain from store.
         let macro_chain_info = chain_store
@@ -212,17 +304,20 @@ impl Blockchain {
         let macro_head_hash = macro_head.hash();
 
         // Initialize TransactionCache.
+        // Light blockchains don't know any micro blocks, so they don't need one.
         let mut transaction_cache = TransactionCache::new();
-        let blocks = chain_store.get_blocks_backward(&head_hash, transaction_cache.missing_blocks() - 1, true, None);
-        for block in blocks.iter().rev() {
-            transaction_cache.push_block(block);
+        if !light {
+            transaction_cache.push_block(&main_chain.head);
+            Self::prepend_cache_blocks(&chain_store, &mut transaction_cache, &head_hash, None);
+            assert_eq!(transaction_cache.missing_blocks(), Self::missing_cache_blocks(main_chain.head.block_number()));
         }
-        transaction_cache.push_block(&main_chain.head);
-        assert_eq!(transaction_cache.missing_blocks(), policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS.saturating_sub(main_chain.head.block_number() + 1));
 
         // Initialize SlashRegistry.
         let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(&chain_store));
 
+        #[cfg(feature = "transaction-store")]
+        let transaction_store = TransactionStore::new(env.clone());
+
         // Current slots and validators
         let current_slots = Self::slots_from_block(&macro_head);
 
@@ -234,7 +329,7 @@ impl Blockchain {
             _ => return Err(BlockchainError::InconsistentState),
         };
 
-        Ok(Blockchain {
+        let blockchain = Blockchain {
             env,
             network_id,
             //network_time,
@@ -243,6 +338,7 @@ impl Blockchain {
             chain_store,
             state: RwLock::new(BlockchainState {
                 accounts,
+                macro_accounts,
                 transaction_cache,
                 reward_registry: slash_registry,
                 main_chain,
@@ -254,12 +350,30 @@ impl Blockchain {
             }),
             push_lock: Mutex::new(()),
 
+            light,
+            keep_epochs: None,
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store,
+
             #[cfg(feature = "metrics")]
             metrics: BlockchainMetrics::default()
-        })
+        };
+
+        // Databases created before the macro accounts tree existed don't have it yet.
+        let macro_accounts_consistent = {
+            let state = blockchain.state.read();
+            state.macro_head.header.state_root == state.macro_accounts.hash(None)
+        };
+        if !light && !macro_accounts_consistent {
+            info!("Rebuilding the accounts tree at the macro head");
+            blockchain.rebuild_macro_accounts();
+        }
+
+        Ok(blockchain)
     }
 
-    fn init(env: Environment, network_id: NetworkId, chain_store: Arc<ChainStore>) -> Result<Self, BlockchainError> {
+    fn init(env: Environment, network_id: NetworkId, chain_store: Arc<ChainStore>, light: bool) -> Result<Self, BlockchainError> {
         // Initialize chain & accounts with genesis block.
         let network_info = NetworkInfo::from_network_id(network_id);
         let genesis_block = network_info.genesis_block::<Block>();
@@ -267,10 +381,14 @@ impl Blockchain {
         let main_chain = ChainInfo::initial(genesis_block.clone());
         let head_hash = network_info.genesis_hash().clone();
 
-        // Initialize accounts.
+        // Initialize accounts. Light blockchains don't keep an accounts tree.
         let accounts = Accounts::new(env.clone());
+        let macro_accounts = Accounts::with_name(env.clone(), Self::MACRO_ACCOUNTS_DB_NAME);
         let mut txn = WriteTransaction::new(&env);
-        accounts.init(&mut txn, network_info.genesis_accounts());
+        if !light {
+            accounts.init(&mut txn, network_info.genesis_accounts());
+            macro_accounts.init(&mut txn, network_info.genesis_accounts());
+        }
 
         // Commit genesis block to accounts.
         // XXX Don't distribute any reward for the genesis block, so there is nothing to commit.
@@ -286,6 +404,9 @@ impl Blockchain {
         // Initialize SlashRegistry.
         let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(&chain_store));
 
+        #[cfg(feature = "transaction-store")]
+        let transaction_store = TransactionStore::new(env.clone());
+
         // current slots and validators
         let current_slots = Self::slots_from_block(&genesis_macro_block);
         let last_slots = Slots::default();
@@ -299,6 +420,7 @@ impl Blockchain {
             chain_store,
             state: RwLock::new(BlockchainState {
                 accounts,
+                macro_accounts,
                 transaction_cache,
                 reward_registry: slash_registry,
                 main_chain,
@@ -310,6 +432,12 @@ impl Blockchain {
             }),
             push_lock: Mutex::new(()),
 
+            light,
+            keep_epochs: None,
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store,
+
             #[cfg(feature = "metrics")]
             metrics: BlockchainMetrics::default()
         })
@@ -356,6 +484,27 @@ impl Blockchain {
         Some(validators)
     }
 
+    /// Returns the slash events of epoch `epoch` together with the slashed slot.
+    /// If `validator_key` is given, only events slashing slots of this validator are returned.
+    pub fn get_slash_events(&self, epoch: u32, validator_key: Option<&CompressedPublicKey>) -> Vec<(SlashEvent, Slot)> {
+        let slots = match self.get_slots_for_epoch(epoch) {
+            Some(slots) => slots,
+            None => return Vec::new(),
+        };
+
+        let state = self.state.read();
+        let events = match validator_key {
+            Some(validator_key) => state.reward_registry.slash_events_of_validator(epoch, validator_key, None),
+            None => state.reward_registry.slash_events(epoch, None),
+        };
+        events.into_iter()
+            .filter_map(|event| {
+                let slot = slots.get(SlotIndex::Slot(event.slot_number))?;
+                Some((event, slot))
+            })
+            .collect()
+    }
+
     pub fn verify_block_header(&self, header: &BlockHeader, view_change_proof: OptionalCheck<&ViewChangeProof>, intended_slot_owner: &MappedRwLockReadGuard<PublicKey>, txn_opt: Option<&Transaction>) -> Result<(), PushError> {
         // Check if the block's immediate predecessor is part of the chain.
         let prev_info_opt = self.chain_store.get_chain_info(&header.parent_hash(), false, txn_opt);
@@ -722,19 +871,24 @@ impl Blockchain {
             slashed_set = Some(state.reward_registry.slashed_set(policy::epoch_at(chain_info.head.block_number()) - 1, SlashedSetSelector::All, Some(&txn)));
         }
 
-        if let Err(e) = state.reward_registry.commit_block(&mut txn, &chain_info.head, prev_info.head.next_view_number()) {
+        let slots = state.current_slots().expect("Missing current epoch's slots");
+        let prev_slots = state.last_slots().expect("Missing previous epoch's slots");
+        if let Err(e) = state.reward_registry.commit_block(&mut txn, &chain_info.head, prev_info.head.next_view_number(), slots, prev_slots) {
             warn!("Rejecting block - slash commit failed: {:?}", e);
             return Err(PushError::InvalidSuccessor);
         }
 
         // Commit block to AccountsTree.
-        if let Err(e) = self.commit_accounts(&state, prev_info.head.next_view_number(), &mut txn, &chain_info.head) {
-            warn!("Rejecting block - commit failed: {:?}", e);
-            txn.abort();
-            #[cfg(feature = "metrics")]
-                self.metrics.note_invalid_block();
-            return Err(e);
-        }
+        let inherents = match self.commit_accounts(&state, prev_info.head.next_view_number(), &mut txn, &chain_info.head) {
+            Ok(inherents) => inherents,
+            Err(e) => {
+                warn!("Rejecting block - commit failed: {:?}", e);
+                txn.abort();
+                #[cfg(feature = "metrics")]
+                    self.metrics.note_invalid_block();
+                return Err(e);
+            }
+        };
 
         drop(state);
 
@@ -768,11 +922,28 @@ impl Blockchain {
         self.chain_store.put_chain_info(&mut txn, &chain_info.head.parent_hash(), &prev_info, false);
         self.chain_store.set_head(&mut txn, &block_hash);
 
+        #[cfg(feature = "transaction-store")]
+        self.transaction_store.put(&chain_info.head, &inherents, &mut txn);
+
+        // The accounts at the new macro head differ from the previous one only in the accounts
+        // touched by the micro blocks since then and by the inherents of the macro block.
+        let mut macro_addresses = None;
+        if let Block::Macro(ref macro_block) = chain_info.head {
+            let num_micro_blocks = macro_block.header.block_number - self.macro_head().header.block_number - 1;
+            let micro_blocks = self.chain_store.get_blocks_backward(&block_hash, num_micro_blocks, true, Some(&txn));
+            let transactions = micro_blocks.iter()
+                .filter_map(Block::transactions)
+                .flatten();
+            macro_addresses = Some(self.touched_addresses(transactions, &inherents));
+        }
+
         // Acquire write lock & commit changes.
         let mut state = self.state.write();
         state.transaction_cache.push_block(&chain_info.head);
 
         if let Block::Macro(ref macro_block) = chain_info.head {
+            Self::update_macro_accounts(&state, &mut txn, &macro_addresses.unwrap());
+
             state.macro_head = macro_block.clone();
             state.macro_head_hash = block_hash.clone();
 
@@ -794,6 +965,7 @@ impl Blockchain {
 
         if block_type == BlockType::Macro {
             self.notifier.read().notify(BlockchainEvent::Finalized(block_hash));
+            self.prune_epochs();
         }
         else {
             self.notifier.read().notify(BlockchainEvent::Extended(block_hash));
@@ -880,11 +1052,8 @@ impl Blockchain {
         } else {
             cache_txn.tail_hash()
         };
-        let blocks = self.chain_store.get_blocks_backward(&start_hash, cache_txn.missing_blocks(), true, Some(&read_txn));
-        for block in blocks.iter() {
-            cache_txn.prepend_block(block)
-        }
-        assert_eq!(cache_txn.missing_blocks(), policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS.saturating_sub(ancestor.1.head.block_number() + 1));
+        Self::prepend_cache_blocks(&self.chain_store, &mut cache_txn, &start_hash, Some(&read_txn));
+        assert_eq!(cache_txn.missing_blocks(), Self::missing_cache_blocks(ancestor.1.head.block_number()));
 
         // Check each fork block against TransactionCache & commit to AccountsTree and SlashRegistry.
         let mut prev_view_number = ancestor.1.head.next_view_number();
@@ -894,7 +1063,9 @@ impl Blockchain {
                 Block::Macro(_) => unreachable!(),
                 Block::Micro(ref micro_block) => {
                     let result = if !cache_txn.contains_any(&fork_block.1.head) {
-                        state.reward_registry.commit_block(&mut write_txn, &fork_block.1.head, prev_view_number)
+                        let slots = state.current_slots().expect("Missing current epoch's slots");
+                        let prev_slots = state.last_slots().expect("Missing previous epoch's slots");
+                        state.reward_registry.commit_block(&mut write_txn, &fork_block.1.head, prev_view_number, slots, prev_slots)
                             .map_err(|_| PushError::InvalidBlock(BlockError::InvalidSlash))
                             .and_then(|_| self.commit_accounts(&state, prev_view_number, &mut write_txn, &fork_block.1.head))
 
@@ -934,6 +1105,9 @@ impl Blockchain {
             reverted_block.1.on_main_chain = false;
             reverted_block.1.main_chain_successor = None;
             self.chain_store.put_chain_info(&mut write_txn, &reverted_block.0, &reverted_block.1, false);
+
+            #[cfg(feature = "transaction-store")]
+            self.transaction_store.remove(&reverted_block.1.head, &mut write_txn);
         }
 
         // Update the mainChainSuccessor of the common ancestor block.
@@ -954,6 +1128,10 @@ impl Blockchain {
 
             // Include the body of the new block (at position 0).
             self.chain_store.put_chain_info(&mut write_txn, &fork_block.0, &fork_block.1, i == 0);
+
+            // Micro blocks don't distribute any rewards, so there are no inherents to index.
+            #[cfg(feature = "transaction-store")]
+            self.transaction_store.put(&fork_block.1.head, &[], &mut write_txn);
         }
 
         // Commit transaction & update head.
@@ -977,13 +1155,17 @@ impl Blockchain {
         let event = BlockchainEvent::Rebranched(reverted_blocks, adopted_blocks);
         self.notifier.read().notify(event);
 
+        // The adopted blocks might include a macro block.
+        self.prune_epochs();
+
         Ok(PushResult::Rebranched)
     }
 
-    fn commit_accounts(&self, state: &BlockchainState, first_view_number: u32, txn: &mut WriteTransaction, block: &Block) -> Result<(), PushError> {
+    /// Returns the inherents that were applied along with the block.
+    fn commit_accounts(&self, state: &BlockchainState, first_view_number: u32, txn: &mut WriteTransaction, block: &Block) -> Result<Vec<Inherent>, PushError> {
         let accounts = &state.accounts;
 
-        match block {
+        let inherents = match block {
             Block::Macro(ref macro_block) => {
                 // We can rely on `state` here, since we cannot revert macro blocks.
                 let mut inherents = self.finalize_last_epoch(state, &macro_block.header);
@@ -998,6 +1180,8 @@ impl Blockchain {
                 if let Err(e) = receipts {
                     return Err(PushError::AccountsError(e));
                 }
+
+                inherents
             },
             Block::Micro(ref micro_block) => {
                 let extrinsics = micro_block.extrinsics.as_ref().unwrap();
@@ -1013,8 +1197,10 @@ impl Blockchain {
                 // Store receipts.
                 let receipts = receipts.unwrap();
                 self.chain_store.put_receipts(txn, micro_block.header.block_number, &receipts);
+
+                inherents
             }
-        }
+        };
 
         // Verify accounts hash.
         let accounts_hash = accounts.hash(Some(&txn));
@@ -1024,7 +1210,7 @@ impl Blockchain {
             return Err(PushError::InvalidBlock(BlockError::AccountsHashMismatch));
         }
 
-        Ok(())
+        Ok(inherents)
     }
 
     fn revert_accounts(&self, accounts: &Accounts, txn: &mut WriteTransaction, micro_block: &MicroBlock, prev_view_number: u32) -> Result<(), PushError> {
@@ -1170,6 +1356,8 @@ impl Blockchain {
 
         drop(state);
 
+        let macro_addresses = self.touched_addresses(transactions.iter(), &inherents);
+
         self.chain_store.clear_receipts(&mut txn);
 
         // Only now can we check macro extrinsics.
@@ -1195,6 +1383,10 @@ impl Blockchain {
         self.chain_store.put_chain_info(&mut txn, &chain_info.head.parent_hash(), &prev_info, false);
         self.chain_store.set_head(&mut txn, &block_hash);
 
+        // The micro blocks of the epoch are unknown, so its transactions refer to the macro block.
+        #[cfg(feature = "transaction-store")]
+        self.transaction_store.put_epoch(&chain_info.head, transactions, &inherents, &mut txn);
+
         // Acquire write lock & commit changes.
         let mut state = self.state.write();
         // FIXME: Macro block sync does not preserve transaction replay protection right now.
@@ -1202,6 +1394,8 @@ impl Blockchain {
         //state.transaction_cache.push_block(&chain_info.head);
 
         if let Block::Macro(ref macro_block) = chain_info.head {
+            Self::update_macro_accounts(&state, &mut txn, &macro_addresses);
+
             state.macro_head = macro_block.clone();
             state.macro_head_hash = block_hash.clone();
 
@@ -1223,10 +1417,344 @@ impl Blockchain {
         drop(push_lock);
 
         self.notifier.read().notify(BlockchainEvent::Finalized(block_hash));
+        self.prune_epochs();
 
         Ok(PushResult::Extended)
     }
 
+    /// Pushes a macro block onto a light blockchain.
+    ///
+    /// Neither transactions nor accounts are available, so the block is only verified using its
+    /// justification, which has to be signed by the validators of the previous epoch. Since the
+    /// justification covers the header, this also authenticates the next epoch's validators.
+    pub fn push_light_macro_block(&self, block: Block) -> Result<PushResult, PushError> {
+        if !self.light {
+            warn!("Rejecting block - not a light blockchain");
+            return Err(PushError::InvalidSuccessor);
+        }
+
+        // Only one push operation at a time.
+        let push_lock = self.push_lock.lock();
+
+        let read_txn = ReadTransaction::new(&self.env);
+
+        let macro_block = if let Block::Macro(ref block) = block {
+            block
+        } else {
+            return Err(PushError::InvalidSuccessor)
+        };
+
+        // Check if we already know this block.
+        let hash: Blake2bHash = block.hash();
+        if self.chain_store.get_chain_info(&hash, false, Some(&read_txn)).is_some() {
+            return Ok(PushResult::Known);
+        }
+
+        // We can only accept macro blocks that follow our current macro head.
+        if self.macro_head_hash() != macro_block.header.parent_macro_hash {
+            warn!("Rejecting block - does not follow on our current macro head");
+            return Err(PushError::Orphan);
+        }
+
+        // Check (sort of) intrinsic block invariants.
+        if let Err(e) = block.verify(self.network_id) {
+            warn!("Rejecting block - verification failed ({:?})", e);
+            return Err(PushError::InvalidBlock(e));
+        }
+
+        let mut prev_info = self.chain_store.get_chain_info(&macro_block.header.parent_macro_hash, false, Some(&read_txn))
+            .ok_or_else(|| {
+                warn!("Rejecting block - unknown predecessor");
+                PushError::Orphan
+            })?;
+
+        // Check the block number
+        if policy::macro_block_after(prev_info.head.block_number()) != macro_block.header.block_number {
+            warn!("Rejecting block - wrong block number ({:?})", macro_block.header.block_number);
+            return Err(PushError::InvalidSuccessor);
+        }
+
+        // Check the justification against the validators of the previous epoch.
+        match macro_block.justification {
+            None => {
+                warn!("Rejecting block - macro block without justification");
+                return Err(PushError::InvalidBlock(BlockError::NoJustification));
+            },
+            Some(ref justification) => {
+                if let Err(e) = justification.verify(macro_block.hash(), &self.current_validators(), policy::TWO_THIRD_SLOTS) {
+                    warn!("Rejecting block - macro block with bad justification: {:?}", e);
+                    return Err(PushError::InvalidBlock(BlockError::NoJustification));
+                }
+            },
+        }
+
+        // The extrinsics can't be recomputed without the epoch's transactions, but they must
+        // match the (justified) header.
+        if let Some(ref extrinsics) = macro_block.extrinsics {
+            let extrinsics_hash: Blake2bHash = extrinsics.hash();
+            if extrinsics_hash != macro_block.header.extrinsics_root {
+                warn!("Rejecting block - Header extrinsics hash doesn't match real extrinsics hash");
+                return Err(PushError::InvalidBlock(BlockError::ExtrinsicsHashMismatch));
+            }
+        } else {
+            return Err(PushError::InvalidBlock(BlockError::MissingExtrinsics))
+        }
+
+        // Drop read transaction before creating the write transaction.
+        drop(read_txn);
+
+        let mut chain_info = ChainInfo::new(block);
+        chain_info.on_main_chain = true;
+        prev_info.main_chain_successor = Some(hash.clone());
+
+        let mut txn = WriteTransaction::new(&self.env);
+        self.chain_store.put_chain_info(&mut txn, &hash, &chain_info, true);
+        self.chain_store.put_chain_info(&mut txn, &chain_info.head.unwrap_macro_ref().header.parent_macro_hash, &prev_info, false);
+        self.chain_store.set_head(&mut txn, &hash);
+
+        // Acquire write lock & commit changes.
+        let mut state = self.state.write();
+        if let Block::Macro(ref macro_block) = chain_info.head {
+            state.macro_head = macro_block.clone();
+            state.macro_head_hash = hash.clone();
+
+            let slots = state.current_slots.take().unwrap();
+            state.previous_slots.replace(slots);
+
+            let slots = Self::slots_from_block(&macro_block);
+            state.current_slots.replace(slots);
+        }
+
+        state.main_chain = chain_info;
+        state.head_hash = hash.clone();
+        txn.commit();
+
+        // Give up lock before notifying.
+        drop(state);
+        drop(push_lock);
+
+        self.notifier.read().notify(BlockchainEvent::Finalized(hash));
+
+        Ok(PushResult::Extended)
+    }
+
+    /// Enables pruning of micro block bodies and receipts of all but the last `keep_epochs`
+    /// finalized epochs. Pruning is done whenever a macro block is pushed. Pruned epochs can't be
+    /// served to peers anymore.
+    pub fn set_pruning(&mut self, keep_epochs: u32) {
+        if keep_epochs < Self::MIN_KEEP_EPOCHS {
+            warn!("Keeping at least {} epochs to cover the transaction validity window", Self::MIN_KEEP_EPOCHS);
+        }
+        self.keep_epochs = Some(cmp::max(keep_epochs, Self::MIN_KEEP_EPOCHS));
+    }
+
+    pub fn is_pruning(&self) -> bool {
+        self.keep_epochs.is_some()
+    }
+
+    /// Returns whether the micro block bodies of the epoch of `block_number` have been pruned.
+    pub fn is_pruned(&self, block_number: u32) -> bool {
+        self.chain_store.get_pruned_epoch(None)
+            .map_or(false, |pruned| policy::epoch_at(block_number) <= pruned)
+    }
+
+    /// Prunes the epochs that are too old to be kept after a macro block has been committed.
+    ///
+    /// Every epoch is pruned in its own database transaction, so that catching up on many epochs,
+    /// e.g. after enabling pruning, doesn't build up one huge transaction.
+    fn prune_epochs(&self) {
+        let keep_epochs = match self.keep_epochs {
+            Some(keep_epochs) => keep_epochs,
+            None => return,
+        };
+
+        let epoch = policy::epoch_at(self.state.read().macro_head.header.block_number);
+        if epoch <= keep_epochs {
+            return;
+        }
+        let last_epoch = epoch - keep_epochs;
+
+        loop {
+            let mut txn = WriteTransaction::new(&self.env);
+            let next_epoch = self.chain_store.get_pruned_epoch(Some(&txn)).map_or(1, |pruned| pruned + 1);
+            if next_epoch > last_epoch {
+                break;
+            }
+            self.chain_store.prune_epochs(&mut txn, next_epoch);
+            txn.commit();
+        }
+    }
+
+    /// Exports the accounts tree at the current macro head. Micro blocks on top of the macro head
+    /// are reverted in a transaction that is never committed.
+    pub fn export_accounts_snapshot(&self) -> Result<AccountsSnapshot, SnapshotError> {
+        if self.light {
+            return Err(SnapshotError::NoAccountsTree);
+        }
+
+        // Only one push operation at a time, so the chain doesn't change while we're exporting.
+        let push_lock = self.push_lock.lock();
+        let state = self.state.read();
+
+        let macro_block = state.macro_head.clone();
+        if macro_block.header.block_number == 0 {
+            return Err(SnapshotError::NoFinalizedEpoch);
+        }
+
+        let mut txn = WriteTransaction::new(&self.env);
+        self.revert_to_macro_head(&state, &mut txn);
+
+        let state_root = state.accounts.hash(Some(&txn));
+        assert_eq!(state_root, macro_block.header.state_root, "Failed to export snapshot - inconsistent state");
+
+        let previous_macro_block = match self.chain_store.get_block(&macro_block.header.parent_macro_hash, true, Some(&txn)) {
+            Some(Block::Macro(block)) => block,
+            _ => panic!("Corrupted store: Failed to find previous macro block while exporting snapshot"),
+        };
+
+        // The election blocks link the previous macro block to the genesis block. Chains that were
+        // bootstrapped from a snapshot don't know the other macro blocks before it.
+        let genesis_block = NetworkInfo::from_network_id(self.network_id).genesis_block::<Block>();
+        let mut validators = genesis_block.unwrap_macro().header.validators;
+        let mut election_blocks = Vec::new();
+        let mut block_number = policy::macro_block_after(0);
+        while block_number < previous_macro_block.header.block_number {
+            if let Some(Block::Macro(block)) = self.chain_store.get_block_at(block_number, false, Some(&txn)) {
+                if block.header.validators != validators {
+                    validators = block.header.validators.clone();
+                    election_blocks.push(block);
+                }
+            }
+            block_number = policy::macro_block_after(block_number);
+        }
+
+        // The transactions in the validity window before the macro block.
+        let macro_hash = macro_block.hash();
+        let mut transaction_cache = TransactionCache::new();
+        transaction_cache.push_block(&Block::Macro(macro_block.clone()));
+        Self::prepend_cache_blocks(&self.chain_store, &mut transaction_cache, &macro_hash, Some(&txn));
+        let transactions = SnapshotTransactions {
+            blocks: transaction_cache.descriptors()
+                .filter(|block| block.hash != macro_hash)
+                .cloned()
+                .collect(),
+        };
+
+        let slash_registry = state.reward_registry.snapshot(&txn, macro_block.header.block_number);
+        let accounts = state.accounts.export(Some(&txn)).into_iter()
+            .map(|(address, account)| SnapshotAccount { address, account })
+            .collect();
+
+        txn.abort();
+        drop(push_lock);
+
+        Ok(AccountsSnapshot {
+            version: SNAPSHOT_VERSION,
+            network_id: self.network_id,
+            macro_block,
+            previous_macro_block,
+            election_blocks,
+            transactions,
+            state_root,
+            slash_registry,
+            accounts,
+        })
+    }
+
+    /// Number of blocks missing from the transaction cache when its newest block is at `block_number`.
+    fn missing_cache_blocks(block_number: u32) -> u32 {
+        policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS.saturating_sub(block_number + 1)
+    }
+
+    /// Prepends the blocks before `start_hash`, the oldest block in `cache`, until the cache is full.
+    /// Chains bootstrapped from a snapshot only know the blocks before the snapshot by the
+    /// transactions stored with it.
+    fn prepend_cache_blocks(chain_store: &ChainStore, cache: &mut TransactionCache, start_hash: &Blake2bHash, txn_option: Option<&Transaction>) {
+        let blocks = chain_store.get_blocks_backward(start_hash, cache.missing_blocks(), true, txn_option);
+        for block in blocks.iter() {
+            cache.prepend_block(block);
+        }
+
+        if cache.missing_blocks() > 0 {
+            // Skip the blocks before the snapshot that are cached already.
+            let snapshot_blocks = chain_store.get_snapshot_transactions(txn_option).unwrap_or_default().blocks;
+            for block in snapshot_blocks.into_iter().rev() {
+                if cache.missing_blocks() == 0 {
+                    break;
+                }
+                if block.hash == cache.tail_prev_hash() {
+                    cache.prepend_descriptor(block);
+                }
+            }
+        }
+    }
+
+    /// Reverts the micro blocks on top of the macro head in `txn`, which must never be committed.
+    /// The caller must hold the push lock.
+    fn revert_to_macro_head(&self, state: &BlockchainState, txn: &mut WriteTransaction) {
+        let mut current = state.main_chain.head.clone();
+        while let Block::Micro(ref micro_block) = current {
+            let prev_info = self.chain_store
+                .get_chain_info(&micro_block.header.parent_hash, true, Some(&txn))
+                .expect("Corrupted store: Failed to find main chain predecessor while reverting to the macro head");
+
+            self.revert_accounts(&state.accounts, txn, &micro_block, prev_info.head.view_number())
+                .expect("Failed to revert micro block while reverting to the macro head");
+            state.reward_registry.revert_block(txn, &current).unwrap();
+
+            current = prev_info.head;
+        }
+    }
+
+    /// Returns the addresses of the accounts that `transactions` and `inherents` might have changed.
+    /// Slash inherents of micro blocks aren't known here, but they only touch the staking contract.
+    fn touched_addresses<'a, I: Iterator<Item=&'a BlockchainTransaction>>(&self, transactions: I, inherents: &[Inherent]) -> HashSet<Address> {
+        let validator_registry = NetworkInfo::from_network_id(self.network_id).validator_registry_address().expect("No ValidatorRegistry");
+        let mut addresses: HashSet<Address> = transactions
+            .flat_map(|transaction| vec![transaction.sender.clone(), transaction.recipient.clone()])
+            .collect();
+        addresses.extend(inherents.iter().map(|inherent| inherent.target.clone()));
+        addresses.insert(validator_registry.clone());
+        addresses
+    }
+
+    /// Copies the accounts at `addresses` to the macro accounts tree, once the accounts tree in
+    /// `txn` is at the new macro head.
+    fn update_macro_accounts(state: &BlockchainState, txn: &mut WriteTransaction, addresses: &HashSet<Address>) {
+        state.macro_accounts.copy_from(txn, &state.accounts, addresses);
+
+        let accounts_hash = state.accounts.hash(Some(&txn));
+        if state.macro_accounts.hash(Some(&txn)) != accounts_hash {
+            warn!("Macro accounts tree diverged from the accounts tree, copying all accounts");
+            let accounts = state.accounts.export(Some(&txn));
+            state.macro_accounts.replace(txn, accounts);
+        }
+    }
+
+    /// Rebuilds the macro accounts tree by reverting the accounts tree to the macro head.
+    fn rebuild_macro_accounts(&self) {
+        let push_lock = self.push_lock.lock();
+        let state = self.state.read();
+
+        let mut txn = WriteTransaction::new(&self.env);
+        self.revert_to_macro_head(&state, &mut txn);
+        let accounts = state.accounts.export(Some(&txn));
+        txn.abort();
+
+        let mut txn = WriteTransaction::new(&self.env);
+        state.macro_accounts.replace(&mut txn, accounts);
+        assert_eq!(state.macro_accounts.hash(Some(&txn)), state.macro_head.header.state_root,
+                   "Failed to rebuild macro accounts - inconsistent state");
+        txn.commit();
+
+        drop(push_lock);
+    }
+
+    /// Returns true if this blockchain only follows the macro block chain.
+    pub fn is_light(&self) -> bool {
+        self.light
+    }
+
     pub fn contains(&self, hash: &Blake2bHash, include_forks: bool) -> bool {
         match self.chain_store.get_chain_info(hash, false, None) {
             Some(chain_info) => include_forks || chain_info.on_main_chain,
@@ -1253,6 +1781,12 @@ impl Blockchain {
     }
 
     pub fn get_epoch_transactions(&self, epoch: u32, txn_option: Option<&Transaction>) -> Option<TransactionsIterator> {
+        // The transactions of pruned epochs are gone.
+        if self.chain_store.get_pruned_epoch(txn_option).map_or(false, |pruned| epoch <= pruned) {
+            debug!("Epoch {} has been pruned", epoch);
+            return None;
+        }
+
         let first_block = policy::first_block_of(epoch);
         let first_block = self.chain_store.get_block_at(first_block, true, txn_option)
             .or_else(|| {
@@ -1509,6 +2043,8 @@ impl Blockchain {
         let mut inherents = Vec::new();
         // Remember the number of eligible slots a stake had (that was able to accept the inherent)
         let mut num_eligible_slots_for_accepted_inherent = Vec::new();
+        // Remember the validator key of each accepted inherent
+        let mut validator_keys_for_accepted_inherent = Vec::new();
 
         // Compute inherents
         for validator_slot in validator_slots.iter() {
@@ -1552,6 +2088,7 @@ impl Blockchain {
                 remainder += reward;
             } else {
                 num_eligible_slots_for_accepted_inherent.push(num_eligible_slots);
+                validator_keys_for_accepted_inherent.push(validator_slot.public_key().compressed());
                 inherents.push(inherent);
             }
         }
@@ -1572,10 +2109,39 @@ impl Blockchain {
             remainder -= Coin::from_u64_unchecked(1);
         }
 
-        // Push finalize epoch inherent for automatically retiring inactive/malicious validators.
         let validator_registry = NetworkInfo::from_network_id(self.network_id)
             .validator_registry_address()
             .expect("No ValidatorRegistry");
+        let staking_contract = match state.accounts.get(validator_registry, None) {
+            Account::Staking(contract) => contract,
+            _ => panic!("ValidatorRegistry is not a staking contract"),
+        };
+
+        // Split the reward of each validator between the validator and its stakers.
+        // The validator keeps its commission and the share of its own deposit, the stakers' share
+        // is sent to the staking contract, which adds it to their stake.
+        let mut inherents: Vec<Inherent> = inherents.into_iter()
+            .zip(validator_keys_for_accepted_inherent)
+            .flat_map(|(mut inherent, validator_key)| {
+                let stakers_inherent = staking_contract.get_validator(validator_key)
+                    .map(|validator| validator.stakers_reward(inherent.value))
+                    .filter(|stakers_reward| !stakers_reward.is_zero())
+                    .map(|stakers_reward| Inherent {
+                        ty: InherentType::Reward,
+                        target: validator_registry.clone(),
+                        value: stakers_reward,
+                        data: validator_key.serialize_to_vec(),
+                    })
+                    .filter(|stakers_inherent| staking_contract.check_inherent(stakers_inherent, macro_header.block_number).is_ok());
+
+                if let Some(ref stakers_inherent) = stakers_inherent {
+                    inherent.value -= stakers_inherent.value;
+                }
+                std::iter::once(inherent).chain(stakers_inherent)
+            })
+            .collect();
+
+        // Push finalize epoch inherent for automatically retiring inactive/malicious validators.
         inherents.push(Inherent {
             ty: InherentType::FinalizeEpoch,
             target: validator_registry.clone(),
@@ -1750,7 +2316,25 @@ impl AbstractBlockchain for Blockchain {
 
     #[allow(unused_variables)]
     fn get_accounts_proof(&self, block_hash: &Blake2bHash, addresses: &[Address]) -> Option<AccountsProof<Account>> {
-        unimplemented!()
+        // Light blockchains don't have an accounts tree.
+        if self.light {
+            return None;
+        }
+
+        // Changes are committed while holding the state lock, so the transaction matches the state.
+        let state = self.state.read();
+        let txn = ReadTransaction::new(&self.env);
+        if block_hash == &state.head_hash {
+            return Some(state.accounts.get_accounts_proof(&txn, addresses));
+        }
+
+        // Besides the head, we only support accounts proofs for the macro head, which is what
+        // light blockchains know the state root of.
+        if block_hash == &state.macro_head_hash {
+            return Some(state.macro_accounts.get_accounts_proof(&txn, addresses));
+        }
+
+        None
     }
 
     #[allow(unused_variables)]
@@ -1758,9 +2342,11 @@ impl AbstractBlockchain for Blockchain {
         unimplemented!()
     }
 
-    #[allow(unused_variables)]
     fn get_transaction_receipts_by_address(&self, address: &Address, sender_limit: usize, recipient_limit: usize) -> Vec<TransactionReceipt> {
-        unimplemented!()
+        #[cfg(feature = "transaction-store")]
+        return self.get_transaction_receipts_by_address(address, sender_limit, recipient_limit);
+        #[cfg(not(feature = "transaction-store"))]
+        Vec::new()
     }
 
     fn register_listener<T: Listener<BlockchainEvent> + 'static>(&self, listener: T) -> ListenerHandle {
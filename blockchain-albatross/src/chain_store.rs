@@ -8,6 +8,7 @@ use hash::Blake2bHash;
 use primitives::policy;
 
 use crate::chain_info::ChainInfo;
+use crate::snapshot::SnapshotTransactions;
 
 #[derive(Debug)]
 pub struct ChainStore {
@@ -25,6 +26,8 @@ impl ChainStore {
     const RECEIPT_DB_NAME: &'static str = "Receipts";
 
     const HEAD_KEY: &'static str = "head";
+    const PRUNED_KEY: &'static str = "pruned";
+    const SNAPSHOT_KEY: &'static str = "snapshot";
 
     pub fn new(env: Environment) -> Self {
         let chain_db = env.open_database(Self::CHAIN_DB_NAME.to_string());
@@ -47,6 +50,18 @@ impl ChainStore {
         txn.put(&self.chain_db, ChainStore::HEAD_KEY, hash);
     }
 
+    /// Returns the transactions before the macro block the chain was bootstrapped from, if any.
+    pub fn get_snapshot_transactions(&self, txn_option: Option<&Transaction>) -> Option<SnapshotTransactions> {
+        match txn_option {
+            Some(txn) => txn.get(&self.chain_db, ChainStore::SNAPSHOT_KEY),
+            None => ReadTransaction::new(&self.env).get(&self.chain_db, ChainStore::SNAPSHOT_KEY)
+        }
+    }
+
+    pub fn set_snapshot_transactions(&self, txn: &mut WriteTransaction, transactions: &SnapshotTransactions) {
+        txn.put_reserve(&self.chain_db, ChainStore::SNAPSHOT_KEY, transactions);
+    }
+
     pub fn get_chain_info(&self, hash: &Blake2bHash, include_body: bool, txn_option: Option<&Transaction>) -> Option<ChainInfo> {
         let read_txn: ReadTransaction;
         let txn = match txn_option {
@@ -354,6 +369,48 @@ impl ChainStore {
         txn.get(&self.receipt_db, &block_height)
     }
 
+    /// Returns the last epoch whose micro block bodies have been pruned.
+    pub fn get_pruned_epoch(&self, txn_option: Option<&Transaction>) -> Option<u32> {
+        match txn_option {
+            Some(txn) => txn.get(&self.chain_db, ChainStore::PRUNED_KEY),
+            None => ReadTransaction::new(&self.env).get(&self.chain_db, ChainStore::PRUNED_KEY)
+        }
+    }
+
+    /// Deletes the bodies and receipts of all micro blocks (including forks) up to and including
+    /// `epoch`. Block headers and macro blocks are kept.
+    pub fn prune_epochs(&self, txn: &mut WriteTransaction, epoch: u32) {
+        let first_epoch = self.get_pruned_epoch(Some(txn)).map(|pruned| pruned + 1).unwrap_or(1);
+        if first_epoch > epoch {
+            return;
+        }
+
+        for block_number in policy::first_block_of(first_epoch)..policy::macro_block_of(epoch) {
+            if policy::is_macro_block_at(block_number) {
+                continue;
+            }
+
+            for hash in self.get_hashes_at(block_number, txn) {
+                txn.remove(&self.block_db, &hash);
+            }
+            txn.remove(&self.receipt_db, &block_number);
+        }
+
+        txn.put(&self.chain_db, ChainStore::PRUNED_KEY, &epoch);
+        debug!("Pruned micro block bodies of epochs {} to {}", first_epoch, epoch);
+    }
+
+    fn get_hashes_at(&self, block_height: u32, txn: &Transaction) -> Vec<Blake2bHash> {
+        let mut hashes = Vec::new();
+        let mut cursor = txn.cursor(&self.height_idx);
+        let mut hash_opt = cursor.seek_key::<u32, Blake2bHash>(&block_height);
+        while let Some(hash) = hash_opt {
+            hashes.push(hash);
+            hash_opt = cursor.next_duplicate::<u32, Blake2bHash>().map(|(_, hash)| hash);
+        }
+        hashes
+    }
+
     pub fn clear_receipts(&self, txn: &mut WriteTransaction) {
         let mut cursor = txn.write_cursor(&self.receipt_db);
         let mut pos: Option<(u32, Receipts)> = cursor.first();
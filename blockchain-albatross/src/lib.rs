@@ -23,6 +23,10 @@ pub mod blockchain;
 pub mod chain_info;
 pub mod chain_store;
 pub mod reward_registry;
+pub mod snapshot;
 pub mod transaction_cache;
 
+#[cfg(feature = "transaction-store")]
+pub mod transaction_store;
+
 pub use blockchain::{Blockchain, ForkEvent};
@@ -1,4 +1,5 @@
 mod reward_pot;
+mod slash_log;
 
 
 use std::borrow::Cow;
@@ -9,6 +10,7 @@ use failure::Fail;
 
 use beserial::{Deserialize, Serialize};
 use block::{Block, MacroBlock, MicroBlock};
+use bls::bls12_381::CompressedPublicKey;
 use collections::bitset::BitSet;
 use database::{AsDatabaseBytes, Database, DatabaseFlags, Environment, FromDatabaseValue,
                ReadTransaction, Transaction, WriteTransaction};
@@ -21,6 +23,8 @@ use vrf::VrfUseCase;
 
 use crate::chain_store::ChainStore;
 use crate::reward_registry::reward_pot::RewardPot;
+use crate::reward_registry::slash_log::SlashLog;
+pub use crate::reward_registry::slash_log::{SlashEvent, SlashReason};
 use vrf::rng::Rng;
 
 
@@ -29,6 +33,7 @@ pub struct SlashRegistry {
     chain_store: Arc<ChainStore>,
     slash_registry_db: Database,
     reward_pot: RewardPot,
+    slash_log: SlashLog,
 }
 
 // TODO Better error messages
@@ -52,6 +57,16 @@ pub enum EpochStateError {
     HistoricEpoch,
 }
 
+/// Slash and reward state at a macro block. This is part of an accounts snapshot, so that a
+/// node bootstrapping from it can distribute the rewards at the end of the next epoch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SlashRegistrySnapshot {
+    pub view_change_epoch_state: BitSet,
+    pub fork_proof_epoch_state: BitSet,
+    pub prev_epoch_state: BitSet,
+    pub previous_reward_pot: Coin,
+}
+
 #[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum SlashedSetSelector {
     ViewChanges,
@@ -74,12 +89,14 @@ impl SlashRegistry {
     pub fn new(env: Environment, chain_store: Arc<ChainStore>) -> Self {
         let slash_registry_db = env.open_database_with_flags(SlashRegistry::SLASH_REGISTRY_DB_NAME.to_string(), DatabaseFlags::UINT_KEYS);
         let reward_pot = RewardPot::new(env.clone());
+        let slash_log = SlashLog::new(env.clone());
 
         Self {
             env,
             chain_store,
             slash_registry_db,
             reward_pot,
+            slash_log,
         }
     }
 
@@ -97,18 +114,20 @@ impl SlashRegistry {
     ///  * `block` - Block to commit
     ///  * `seed`- Seed of previous block
     ///  * `staking_contract` - Contract used to check minimum stakes
+    ///  * `slots` - Slots of the block's epoch
+    ///  * `prev_slots` - Slots of the previous epoch, for fork proofs slashing it
     #[inline]
-    pub fn commit_block(&self, txn: &mut WriteTransaction, block: &Block, prev_view_number: u32) -> Result<(), SlashPushError> {
+    pub fn commit_block(&self, txn: &mut WriteTransaction, block: &Block, prev_view_number: u32, slots: &Slots, prev_slots: &Slots) -> Result<(), SlashPushError> {
         match block {
             Block::Macro(ref macro_block) => {
                 self.reward_pot.commit_macro_block(macro_block, txn);
-                self.commit_macro_block(txn, macro_block, prev_view_number)?;
+                self.commit_macro_block(txn, macro_block, prev_view_number, slots, prev_slots)?;
                 self.gc(txn, policy::epoch_at(macro_block.header.block_number));
                 Ok(())
             },
             Block::Micro(ref micro_block) => {
                 self.reward_pot.commit_micro_block(micro_block, txn);
-                self.commit_micro_block(txn, micro_block, prev_view_number)
+                self.commit_micro_block(txn, micro_block, prev_view_number, slots, prev_slots)
             },
         }
     }
@@ -172,21 +191,27 @@ impl SlashRegistry {
         BlockDescriptor { prev_epoch_state, view_change_epoch_state, fork_proof_epoch_state }
     }
 
-    fn slash_view_changes(&self, epoch_diff: &mut BitSet, txn: &mut WriteTransaction, block_number: u32, view_number: u32, prev_view_number: u32) {
+    fn slash_view_changes(&self, epoch_diff: &mut BitSet, events: &mut Vec<SlashEvent>, txn: &mut WriteTransaction, block_number: u32, view_number: u32, prev_view_number: u32) {
         // Mark from view changes, ignoring duplicates.
         for view in prev_view_number..view_number {
             let slot_number = self.get_slot_number_at(block_number, view, Some(&txn))
                 .unwrap();
             epoch_diff.insert(slot_number as usize);
+            events.push(SlashEvent {
+                block_number,
+                slot_number,
+                reason: SlashReason::ViewChange { block_number, view_number: view },
+            });
         }
     }
 
-    fn commit_macro_block(&self, txn: &mut WriteTransaction, block: &MacroBlock, prev_view_number: u32) -> Result<(), SlashPushError> {
+    fn commit_macro_block(&self, txn: &mut WriteTransaction, block: &MacroBlock, prev_view_number: u32, slots: &Slots, prev_slots: &Slots) -> Result<(), SlashPushError> {
         let mut epoch_diff = BitSet::new();
+        let mut events = Vec::new();
 
         let BlockDescriptor { fork_proof_epoch_state, prev_epoch_state, mut view_change_epoch_state } = self.get_epoch_state(txn, block.header.block_number);
 
-        self.slash_view_changes(&mut epoch_diff, txn, block.header.block_number, block.header.view_number, prev_view_number);
+        self.slash_view_changes(&mut epoch_diff, &mut events, txn, block.header.block_number, block.header.view_number, prev_view_number);
 
         // Apply slashes.
         view_change_epoch_state |= epoch_diff;
@@ -196,15 +221,18 @@ impl SlashRegistry {
 
         // Put descriptor into database.
         txn.put(&self.slash_registry_db, &block.header.block_number, &descriptor);
+        let events = Self::with_validator_keys(events, block.header.block_number, slots, prev_slots);
+        self.slash_log.commit_block(txn, block.header.block_number, events);
 
         Ok(())
     }
 
-    fn commit_micro_block(&self, txn: &mut WriteTransaction, block: &MicroBlock, prev_view_number: u32) -> Result<(), SlashPushError> {
+    fn commit_micro_block(&self, txn: &mut WriteTransaction, block: &MicroBlock, prev_view_number: u32, slots: &Slots, prev_slots: &Slots) -> Result<(), SlashPushError> {
         let block_epoch = policy::epoch_at(block.header.block_number);
         let mut view_change_epoch_diff = BitSet::new();
         let mut fork_proof_epoch_diff = BitSet::new();
         let mut fork_proof_prev_epoch_diff = BitSet::new();
+        let mut events = Vec::new();
 
         // Mark from fork proofs.
         let fork_proofs = &block.extrinsics.as_ref().unwrap().fork_proofs;
@@ -228,6 +256,12 @@ impl SlashRegistry {
             } else {
                 return Err(SlashPushError::InvalidEpochTarget);
             }
+
+            events.push(SlashEvent {
+                block_number: block.header.block_number,
+                slot_number,
+                reason: SlashReason::ForkProof(fork_proof.clone()),
+            });
         }
 
         let BlockDescriptor { mut fork_proof_epoch_state, mut prev_epoch_state, mut view_change_epoch_state } = self.get_epoch_state(txn, block.header.block_number);
@@ -238,7 +272,7 @@ impl SlashRegistry {
             return Err(SlashPushError::SlotAlreadySlashed);
         }
 
-        self.slash_view_changes(&mut view_change_epoch_diff, txn, block.header.block_number, block.header.view_number, prev_view_number);
+        self.slash_view_changes(&mut view_change_epoch_diff, &mut events, txn, block.header.block_number, block.header.view_number, prev_view_number);
 
         // Apply slashes.
         prev_epoch_state |= fork_proof_prev_epoch_diff;
@@ -254,10 +288,62 @@ impl SlashRegistry {
 
         // Put descriptor into database.
         txn.put(&self.slash_registry_db, &block.header.block_number, &descriptor);
+        let events = Self::with_validator_keys(events, block.header.block_number, slots, prev_slots);
+        self.slash_log.commit_block(txn, block.header.block_number, events);
 
         Ok(())
     }
 
+    /// Pairs the slash events of block `block_number` with the key of the validator owning the
+    /// slashed slot.
+    fn with_validator_keys(events: Vec<SlashEvent>, block_number: u32, slots: &Slots, prev_slots: &Slots) -> Vec<(SlashEvent, CompressedPublicKey)> {
+        let block_epoch = policy::epoch_at(block_number);
+        events.into_iter()
+            .map(|event| {
+                let slots = if event.epoch() == block_epoch { slots } else { prev_slots };
+                let slot = slots.get(SlotIndex::Slot(event.slot_number))
+                    .unwrap_or_else(|| panic!("Expected slot {} to exist", event.slot_number));
+                let validator_key = slot.public_key().compressed().clone();
+                (event, validator_key)
+            })
+            .collect()
+    }
+
+    /// Exports the state recorded at the macro block `block_number`.
+    pub(crate) fn snapshot(&self, txn: &Transaction, block_number: u32) -> SlashRegistrySnapshot {
+        assert!(policy::is_macro_block_at(block_number));
+
+        let descriptor: Option<BlockDescriptor> = txn.get(&self.slash_registry_db, &block_number);
+        let BlockDescriptor { view_change_epoch_state, fork_proof_epoch_state, prev_epoch_state } = descriptor
+            .unwrap_or_else(|| BlockDescriptor {
+                view_change_epoch_state: BitSet::new(),
+                fork_proof_epoch_state: BitSet::new(),
+                prev_epoch_state: BitSet::new(),
+            });
+
+        SlashRegistrySnapshot {
+            view_change_epoch_state,
+            fork_proof_epoch_state,
+            prev_epoch_state,
+            previous_reward_pot: self.reward_pot.previous_reward_pot_with(txn),
+        }
+    }
+
+    /// Restores the state exported by `snapshot` at the macro block `block_number`.
+    /// The registry must not contain any later blocks.
+    pub(crate) fn restore_snapshot(&self, txn: &mut WriteTransaction, block_number: u32, snapshot: &SlashRegistrySnapshot) {
+        assert!(policy::is_macro_block_at(block_number));
+
+        self.reward_pot.restore_epoch(snapshot.previous_reward_pot, txn);
+
+        let descriptor = BlockDescriptor {
+            view_change_epoch_state: snapshot.view_change_epoch_state.clone(),
+            fork_proof_epoch_state: snapshot.fork_proof_epoch_state.clone(),
+            prev_epoch_state: snapshot.prev_epoch_state.clone(),
+        };
+        txn.put(&self.slash_registry_db, &block_number, &descriptor);
+    }
+
     fn gc(&self, txn: &mut WriteTransaction, current_epoch: u32) {
         let cutoff = policy::first_block_of_registry(current_epoch);
         if cutoff == 0u32 {
@@ -266,6 +352,8 @@ impl SlashRegistry {
             return;
         }
 
+        self.slash_log.gc(txn, cutoff);
+
         let mut cursor = txn.write_cursor(&self.slash_registry_db);
         let mut pos: Option<(u32, BlockDescriptor)> = cursor.first();
 
@@ -290,9 +378,38 @@ impl SlashRegistry {
 
     fn revert_micro_block(&self, txn: &mut WriteTransaction, block: &MicroBlock) -> Result<(), SlashPushError> {
         txn.remove(&self.slash_registry_db, &block.header.block_number);
+        self.slash_log.revert_block(txn, block.header.block_number);
         Ok(())
     }
 
+    /// Returns the slash events of epoch `epoch_number`, including fork proofs for this epoch
+    /// that were included in the next epoch. Only the current and the previous epoch are tracked.
+    pub fn slash_events(&self, epoch_number: u32, txn_option: Option<&Transaction>) -> Vec<SlashEvent> {
+        let read_txn;
+        let txn = if let Some(txn) = txn_option {
+            txn
+        } else {
+            read_txn = ReadTransaction::new(&self.env);
+            &read_txn
+        };
+
+        self.slash_log.events(txn, epoch_number)
+    }
+
+    /// Returns the slash events of epoch `epoch_number` that slashed slots of `validator_key`.
+    /// Unlike `slash_events`, this only reads the blocks that slashed the validator.
+    pub fn slash_events_of_validator(&self, epoch_number: u32, validator_key: &CompressedPublicKey, txn_option: Option<&Transaction>) -> Vec<SlashEvent> {
+        let read_txn;
+        let txn = if let Some(txn) = txn_option {
+            txn
+        } else {
+            read_txn = ReadTransaction::new(&self.env);
+            &read_txn
+        };
+
+        self.slash_log.events_of_validator(txn, epoch_number, validator_key)
+    }
+
     /// Get slot and slot number for a given block and view number
     pub fn get_slot_at(&self, block_number: u32, view_number: u32, slots: &Slots, txn_option: Option<&Transaction>) -> Option<(Slot, u16)> {
         let slot_number = self.get_slot_number_at(block_number, view_number, txn_option)?;
@@ -1,5 +1,5 @@
 use block::{MacroBlock, MicroBlock};
-use database::{Database, Environment, ReadTransaction, WriteTransaction};
+use database::{Database, Environment, ReadTransaction, Transaction, WriteTransaction};
 use primitives::coin::Coin;
 use primitives::policy;
 use transaction::Transaction as BlockchainTransaction;
@@ -74,6 +74,13 @@ impl RewardPot {
         txn.put(&self.reward_pot, Self::CURRENT_EPOCH_KEY, &u64::from(reward));
     }
 
+    /// Sets the reward pots to their state right after a macro block, whose epoch collected
+    /// `previous_reward`.
+    pub(super) fn restore_epoch(&self, previous_reward: Coin, txn: &mut WriteTransaction) {
+        txn.put(&self.reward_pot, Self::CURRENT_EPOCH_KEY, &0u64);
+        txn.put(&self.reward_pot, Self::PREVIOUS_EPOCH_KEY, &u64::from(previous_reward));
+    }
+
     fn reward_for_micro_block(block: &MicroBlock) -> Coin {
         // Block reward
         let mut reward = policy::block_reward_at(block.header.block_number);
@@ -98,6 +105,10 @@ impl RewardPot {
 
     pub fn previous_reward_pot(&self) -> Coin {
         let txn = ReadTransaction::new(&self.env);
+        self.previous_reward_pot_with(&txn)
+    }
+
+    pub(super) fn previous_reward_pot_with(&self, txn: &Transaction) -> Coin {
         Coin::from_u64_unchecked(txn.get(&self.reward_pot, Self::PREVIOUS_EPOCH_KEY).unwrap_or(0))
     }
 }
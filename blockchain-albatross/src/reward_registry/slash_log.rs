@@ -0,0 +1,276 @@
+use std::borrow::Cow;
+use std::io;
+
+use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
+use block::ForkProof;
+use bls::bls12_381::CompressedPublicKey;
+use database::{AsDatabaseBytes, Database, DatabaseFlags, Environment, FromDatabaseValue, Transaction, WriteTransaction};
+use database::cursor::ReadCursor;
+use primitives::policy;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[repr(u8)]
+enum SlashReasonType {
+    ForkProof = 1,
+    ViewChange = 2,
+}
+
+/// Why a slot was slashed.
+#[derive(Clone, Debug)]
+pub enum SlashReason {
+    /// The slot owner signed two different micro blocks at the same block and view number.
+    ForkProof(ForkProof),
+    /// The slot owner didn't produce block `block_number` at view `view_number`.
+    ViewChange { block_number: u32, view_number: u32 },
+}
+
+impl SlashReason {
+    /// The number of the block the slashed slot was supposed to produce.
+    pub fn block_number(&self) -> u32 {
+        match self {
+            SlashReason::ForkProof(fork_proof) => fork_proof.block_number(),
+            SlashReason::ViewChange { block_number, .. } => *block_number,
+        }
+    }
+
+    /// The view number the slashed slot was supposed to produce at.
+    pub fn view_number(&self) -> u32 {
+        match self {
+            SlashReason::ForkProof(fork_proof) => fork_proof.view_number(),
+            SlashReason::ViewChange { view_number, .. } => *view_number,
+        }
+    }
+
+    fn ty(&self) -> SlashReasonType {
+        match self {
+            SlashReason::ForkProof(_) => SlashReasonType::ForkProof,
+            SlashReason::ViewChange { .. } => SlashReasonType::ViewChange,
+        }
+    }
+}
+
+impl Serialize for SlashReason {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        let mut size = 0;
+        size += self.ty().serialize(writer)?;
+        size += match self {
+            SlashReason::ForkProof(fork_proof) => fork_proof.serialize(writer)?,
+            SlashReason::ViewChange { block_number, view_number } => {
+                block_number.serialize(writer)? + view_number.serialize(writer)?
+            },
+        };
+        Ok(size)
+    }
+
+    fn serialized_size(&self) -> usize {
+        let mut size = 0;
+        size += self.ty().serialized_size();
+        size += match self {
+            SlashReason::ForkProof(fork_proof) => fork_proof.serialized_size(),
+            SlashReason::ViewChange { block_number, view_number } => {
+                block_number.serialized_size() + view_number.serialized_size()
+            },
+        };
+        size
+    }
+}
+
+impl Deserialize for SlashReason {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let ty: SlashReasonType = Deserialize::deserialize(reader)?;
+        let reason = match ty {
+            SlashReasonType::ForkProof => SlashReason::ForkProof(Deserialize::deserialize(reader)?),
+            SlashReasonType::ViewChange => SlashReason::ViewChange {
+                block_number: Deserialize::deserialize(reader)?,
+                view_number: Deserialize::deserialize(reader)?,
+            },
+        };
+        Ok(reason)
+    }
+}
+
+/// A slot that was slashed by a block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlashEvent {
+    /// The number of the block that contains the slash.
+    pub block_number: u32,
+    pub slot_number: u16,
+    pub reason: SlashReason,
+}
+
+impl SlashEvent {
+    /// The epoch in which the slot is slashed.
+    pub fn epoch(&self) -> u32 {
+        policy::epoch_at(self.reason.block_number())
+    }
+}
+
+/// A slash event together with the key of the validator owning the slashed slot.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LoggedSlashEvent {
+    event: SlashEvent,
+    validator_key: CompressedPublicKey,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SlashEventList {
+    #[beserial(len_type(u16))]
+    events: Vec<LoggedSlashEvent>,
+}
+
+impl SlashEventList {
+    /// The distinct keys of the validators slashed by the events.
+    fn validator_keys(&self) -> Vec<ValidatorKey> {
+        let mut keys: Vec<ValidatorKey> = Vec::new();
+        for event in &self.events {
+            if !keys.iter().any(|key| key.0 == event.validator_key) {
+                keys.push(ValidatorKey(event.validator_key.clone()));
+            }
+        }
+        keys
+    }
+}
+
+/// Key of the validator index.
+#[derive(Clone, Debug)]
+struct ValidatorKey(CompressedPublicKey);
+
+/// Log of the slash events of the blocks tracked by the slash registry.
+///
+/// The events are stored by the number of the block that contains them. An index maps the key
+/// of each slashed validator to the numbers of these blocks.
+pub(super) struct SlashLog {
+    slash_log_db: Database,
+    validator_idx: Database,
+}
+
+impl SlashLog {
+    const SLASH_LOG_DB_NAME: &'static str = "SlashLog";
+    const VALIDATOR_IDX_NAME: &'static str = "SlashLogValidatorIdx";
+
+    pub fn new(env: Environment) -> Self {
+        let slash_log_db = env.open_database_with_flags(Self::SLASH_LOG_DB_NAME.to_string(), DatabaseFlags::UINT_KEYS);
+        let validator_idx = env.open_database_with_flags(
+            Self::VALIDATOR_IDX_NAME.to_string(),
+            DatabaseFlags::DUPLICATE_KEYS | DatabaseFlags::DUP_FIXED_SIZE_VALUES | DatabaseFlags::DUP_UINT_VALUES
+        );
+        SlashLog { slash_log_db, validator_idx }
+    }
+
+    /// Records the slash events of the block `block_number`, each together with the key of the
+    /// validator owning the slashed slot.
+    pub fn commit_block(&self, txn: &mut WriteTransaction, block_number: u32, events: Vec<(SlashEvent, CompressedPublicKey)>) {
+        if events.is_empty() {
+            return;
+        }
+
+        let list = SlashEventList {
+            events: events.into_iter()
+                .map(|(event, validator_key)| LoggedSlashEvent { event, validator_key })
+                .collect(),
+        };
+        for key in list.validator_keys() {
+            txn.put(&self.validator_idx, &key, &block_number);
+        }
+        txn.put(&self.slash_log_db, &block_number, &list);
+    }
+
+    pub fn revert_block(&self, txn: &mut WriteTransaction, block_number: u32) {
+        let list: Option<SlashEventList> = txn.get(&self.slash_log_db, &block_number);
+        if let Some(list) = list {
+            self.remove_block(txn, block_number, &list);
+        }
+    }
+
+    /// Removes the events of all blocks before `cutoff`.
+    pub fn gc(&self, txn: &mut WriteTransaction, cutoff: u32) {
+        let mut removed = Vec::new();
+        {
+            let mut cursor = txn.cursor(&self.slash_log_db);
+            let mut pos: Option<(u32, SlashEventList)> = cursor.first();
+
+            while let Some((block_number, list)) = pos {
+                if block_number >= cutoff {
+                    break;
+                }
+                removed.push((block_number, list));
+                pos = cursor.next();
+            }
+        }
+
+        for (block_number, list) in removed {
+            self.remove_block(txn, block_number, &list);
+        }
+    }
+
+    fn remove_block(&self, txn: &mut WriteTransaction, block_number: u32, list: &SlashEventList) {
+        for key in list.validator_keys() {
+            txn.remove_item(&self.validator_idx, &key, &block_number);
+        }
+        txn.remove(&self.slash_log_db, &block_number);
+    }
+
+    /// Returns all slash events slashing slots of epoch `epoch_number`.
+    pub fn events(&self, txn: &Transaction, epoch_number: u32) -> Vec<SlashEvent> {
+        let mut events = Vec::new();
+
+        let mut cursor = txn.cursor(&self.slash_log_db);
+        let mut pos: Option<(u32, SlashEventList)> = cursor.first();
+        while let Some((_, list)) = pos {
+            events.extend(list.events.into_iter()
+                .map(|logged| logged.event)
+                .filter(|event| event.epoch() == epoch_number));
+            pos = cursor.next();
+        }
+
+        events
+    }
+
+    /// Returns the slash events slashing slots of `validator_key` in epoch `epoch_number`.
+    /// Only the blocks that slashed the validator are read, using the validator index.
+    pub fn events_of_validator(&self, txn: &Transaction, epoch_number: u32, validator_key: &CompressedPublicKey) -> Vec<SlashEvent> {
+        let mut events = Vec::new();
+
+        let mut cursor = txn.cursor(&self.validator_idx);
+        let mut block_number: Option<u32> = cursor.seek_key(&ValidatorKey(validator_key.clone()));
+        while let Some(number) = block_number {
+            let list: SlashEventList = txn.get(&self.slash_log_db, &number)
+                .expect("Corrupted store: Slash events referenced from index not found");
+            events.extend(list.events.into_iter()
+                .filter(|logged| &logged.validator_key == validator_key)
+                .map(|logged| logged.event)
+                .filter(|event| event.epoch() == epoch_number));
+
+            block_number = cursor.next_duplicate().map(|(_, value): (ValidatorKey, u32)| value);
+        }
+
+        events
+    }
+}
+
+impl AsDatabaseBytes for SlashEventList {
+    fn as_database_bytes(&self) -> Cow<[u8]> {
+        let v = Serialize::serialize_to_vec(&self);
+        Cow::Owned(v)
+    }
+}
+
+impl FromDatabaseValue for SlashEventList {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+impl AsDatabaseBytes for ValidatorKey {
+    fn as_database_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.0.serialize_to_vec())
+    }
+}
+
+impl FromDatabaseValue for ValidatorKey {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(ValidatorKey(Deserialize::deserialize(&mut cursor)?))
+    }
+}
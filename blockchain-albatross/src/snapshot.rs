@@ -0,0 +1,268 @@
+use std::cmp;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use failure::Fail;
+
+use account::Account;
+use beserial::{Deserialize, Serialize, SerializingError};
+use block::{Block, BlockError, MacroBlock};
+use blockchain_base::BlockchainError;
+use database::{FromDatabaseValue, IntoDatabaseValue};
+use hash::{Blake2bHash, Hash};
+use keys::Address;
+use network_primitives::networks::NetworkInfo;
+use primitives::networks::NetworkId;
+use primitives::policy;
+
+use crate::reward_registry::SlashRegistrySnapshot;
+use crate::transaction_cache::BlockDescriptor;
+
+/// Version of the snapshot file format. Files with a different version are rejected.
+pub const SNAPSHOT_VERSION: u16 = 3;
+
+#[derive(Debug, Fail)]
+pub enum SnapshotError {
+    #[fail(display = "I/O error: {}", _0)]
+    Io(#[cause] std::io::Error),
+    #[fail(display = "Invalid snapshot encoding: {}", _0)]
+    Serialization(#[cause] SerializingError),
+    #[fail(display = "Unsupported snapshot version: {}", _0)]
+    UnsupportedVersion(u16),
+    #[fail(display = "Snapshot is for a different network: {:?}", _0)]
+    WrongNetwork(NetworkId),
+    #[fail(display = "Light blockchains don't have an accounts tree")]
+    NoAccountsTree,
+    #[fail(display = "No macro block finalized yet")]
+    NoFinalizedEpoch,
+    #[fail(display = "Invalid macro block: {}", _0)]
+    InvalidMacroBlock(#[cause] BlockError),
+    #[fail(display = "Macro block doesn't follow the previous macro block")]
+    InvalidPredecessor,
+    #[fail(display = "Previous macro block isn't linked to the genesis block")]
+    InvalidMacroChain,
+    #[fail(display = "Transactions don't cover the validity window before the macro block")]
+    InvalidTransactions,
+    #[fail(display = "Macro block has an invalid justification")]
+    InvalidJustification,
+    #[fail(display = "Accounts don't match the state root of the macro block")]
+    StateRootMismatch,
+    #[fail(display = "Consensus database is not empty")]
+    ChainNotEmpty,
+    #[fail(display = "{}", _0)]
+    Blockchain(#[cause] BlockchainError),
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<SerializingError> for SnapshotError {
+    fn from(e: SerializingError) -> Self {
+        SnapshotError::Serialization(e)
+    }
+}
+
+impl From<BlockchainError> for SnapshotError {
+    fn from(e: BlockchainError) -> Self {
+        SnapshotError::Blockchain(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotAccount {
+    pub address: Address,
+    pub account: Account,
+}
+
+/// The blocks in the transaction validity window before the macro block of a snapshot, oldest
+/// first. Only the hashes of their transactions are known, which is enough to reject transactions
+/// that were already included in them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotTransactions {
+    #[beserial(len_type(u16))]
+    pub blocks: Vec<BlockDescriptor>,
+}
+
+impl IntoDatabaseValue for SnapshotTransactions {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for SnapshotTransactions {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// The full accounts tree at a finalized macro block.
+///
+/// Next to the accounts, a snapshot contains the macro block itself and the macro block of the
+/// previous epoch, which determines the validators that signed it and the slots of the last epoch.
+/// The previous macro block is linked to the genesis block by the election blocks, the macro
+/// blocks that changed the validators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountsSnapshot {
+    pub version: u16,
+    pub network_id: NetworkId,
+    pub macro_block: MacroBlock,
+    pub previous_macro_block: MacroBlock,
+    /// The macro blocks between the genesis block and `previous_macro_block` that elected
+    /// different validators than the election block before them. Each of them, as well as
+    /// `previous_macro_block`, must be signed by the validators of the election block before it.
+    #[beserial(len_type(u32))]
+    pub election_blocks: Vec<MacroBlock>,
+    /// The blocks before `macro_block`, so that bootstrapped chains can detect replayed transactions.
+    pub transactions: SnapshotTransactions,
+    /// Root hash of `accounts`. Must be equal to the state root of `macro_block`.
+    pub state_root: Blake2bHash,
+    pub slash_registry: SlashRegistrySnapshot,
+    #[beserial(len_type(u32))]
+    pub accounts: Vec<SnapshotAccount>,
+}
+
+impl AccountsSnapshot {
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        // Check the version before trying to decode the rest of the file.
+        let version: u16 = Deserialize::deserialize(&mut reader)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        reader.seek(SeekFrom::Start(0))?;
+
+        Ok(Deserialize::deserialize(&mut reader)?)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.serialize(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Checks that the snapshot is usable on `network_id` and internally consistent.
+    /// The accounts themselves are checked against `state_root` when they are imported.
+    pub fn verify(&self, network_id: NetworkId) -> Result<(), SnapshotError> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(self.version));
+        }
+
+        if self.network_id != network_id {
+            return Err(SnapshotError::WrongNetwork(self.network_id));
+        }
+
+        if self.state_root != self.macro_block.header.state_root {
+            return Err(SnapshotError::StateRootMismatch);
+        }
+
+        if let Some(ref extrinsics) = self.macro_block.extrinsics {
+            let extrinsics_hash: Blake2bHash = extrinsics.hash();
+            if extrinsics_hash != self.macro_block.header.extrinsics_root {
+                return Err(SnapshotError::InvalidMacroBlock(BlockError::ExtrinsicsHashMismatch));
+            }
+        }
+
+        // Follow the election blocks from the genesis block to the previous macro block.
+        let genesis_block = NetworkInfo::from_network_id(network_id).genesis_block::<Block>();
+        let mut elector = match genesis_block {
+            Block::Macro(ref genesis_block) => genesis_block,
+            Block::Micro(_) => unreachable!(),
+        };
+        for block in self.election_blocks.iter() {
+            if block.header.validators == elector.header.validators {
+                warn!("Rejecting snapshot - macro block #{} didn't elect new validators", block.header.block_number);
+                return Err(SnapshotError::InvalidMacroChain);
+            }
+            Self::verify_elected(elector, block).map_err(|e| {
+                warn!("Rejecting snapshot - invalid election block #{}: {}", block.header.block_number, e);
+                SnapshotError::InvalidMacroChain
+            })?;
+            elector = block;
+        }
+        if self.previous_macro_block.header.block_number == 0 {
+            if self.previous_macro_block.hash() != elector.hash() {
+                return Err(SnapshotError::InvalidMacroChain);
+            }
+        } else {
+            Self::verify_elected(elector, &self.previous_macro_block)
+                .map_err(|_| SnapshotError::InvalidMacroChain)?;
+        }
+
+        Self::verify_successor(&self.previous_macro_block, &self.macro_block)?;
+        self.verify_transactions()
+    }
+
+    /// Checks that the transactions cover the validity window up to the macro block, without gaps.
+    /// Their hashes can't be checked against the blocks, they are trusted like the accounts.
+    fn verify_transactions(&self) -> Result<(), SnapshotError> {
+        let blocks = &self.transactions.blocks;
+        let num_blocks = cmp::min(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS - 1, self.macro_block.header.block_number);
+        if blocks.len() != num_blocks as usize {
+            return Err(SnapshotError::InvalidTransactions);
+        }
+
+        let mut next_prev_hash = &self.macro_block.header.parent_hash;
+        for block in blocks.iter().rev() {
+            if &block.hash != next_prev_hash {
+                return Err(SnapshotError::InvalidTransactions);
+            }
+            next_prev_hash = &block.prev_hash;
+        }
+
+        let mut transaction_hashes = HashSet::new();
+        for hash in blocks.iter().flat_map(|block| block.transaction_hashes.iter()) {
+            if !transaction_hashes.insert(hash) {
+                return Err(SnapshotError::InvalidTransactions);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `block` is a macro block after `elector` and that it was signed by the validators
+    /// elected in `elector`. The macro blocks in between must not have changed the validators.
+    fn verify_elected(elector: &MacroBlock, block: &MacroBlock) -> Result<(), SnapshotError> {
+        block.verify().map_err(SnapshotError::InvalidMacroBlock)?;
+
+        if block.header.block_number <= elector.header.block_number
+            || !policy::is_macro_block_at(block.header.block_number) {
+            return Err(SnapshotError::InvalidPredecessor);
+        }
+
+        Self::verify_justification(elector, block)
+    }
+
+    /// Checks that `block` is the macro block following `predecessor` and that it was signed by
+    /// the validators elected in `predecessor`.
+    fn verify_successor(predecessor: &MacroBlock, block: &MacroBlock) -> Result<(), SnapshotError> {
+        block.verify().map_err(SnapshotError::InvalidMacroBlock)?;
+
+        if block.header.parent_macro_hash != predecessor.hash()
+            || policy::macro_block_after(predecessor.header.block_number) != block.header.block_number {
+            return Err(SnapshotError::InvalidPredecessor);
+        }
+
+        Self::verify_justification(predecessor, block)
+    }
+
+    fn verify_justification(predecessor: &MacroBlock, block: &MacroBlock) -> Result<(), SnapshotError> {
+        match block.justification {
+            Some(ref justification) => justification
+                .verify(block.hash(), &predecessor.header.validators, policy::TWO_THIRD_SLOTS)
+                .map_err(|_| SnapshotError::InvalidJustification),
+            None => Err(SnapshotError::InvalidJustification),
+        }
+    }
+}
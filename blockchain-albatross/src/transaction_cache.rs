@@ -1,14 +1,18 @@
 use std::collections::{HashSet, VecDeque};
 
+use beserial::{Deserialize, Serialize};
 use hash::{Blake2bHash, Hash};
 use block::Block;
 use primitives::policy;
 
-#[derive(Debug, Clone)]
-struct BlockDescriptor {
-    hash: Blake2bHash,
-    prev_hash: Blake2bHash,
-    transaction_hashes: Vec<Blake2bHash>
+/// A block as far as the transaction cache is concerned. Chains bootstrapped from a snapshot only
+/// know the blocks before the snapshot by their descriptors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDescriptor {
+    pub hash: Blake2bHash,
+    pub prev_hash: Blake2bHash,
+    #[beserial(len_type(u16))]
+    pub transaction_hashes: Vec<Blake2bHash>
 }
 
 impl<'a> From<&'a Block> for BlockDescriptor {
@@ -89,10 +93,13 @@ impl TransactionCache {
     }
 
     pub fn prepend_block(&mut self, block: &Block) {
-        assert!(self.block_order.is_empty() || block.hash() == self.block_order.front().as_ref().unwrap().prev_hash);
+        self.prepend_descriptor(BlockDescriptor::from(block));
+    }
+
+    pub fn prepend_descriptor(&mut self, descriptor: BlockDescriptor) {
+        assert!(self.block_order.is_empty() || descriptor.hash == self.block_order.front().as_ref().unwrap().prev_hash);
         assert!(self.missing_blocks() > 0);
 
-        let descriptor = BlockDescriptor::from(block);
         for hash in &descriptor.transaction_hashes {
             let is_new = self.transaction_hashes.insert(hash.clone());
             assert!(is_new);
@@ -109,6 +116,11 @@ impl TransactionCache {
         }
     }
 
+    /// The cached blocks, oldest first.
+    pub fn descriptors(&self) -> impl Iterator<Item=&BlockDescriptor> {
+        self.block_order.iter()
+    }
+
     pub fn missing_blocks(&self) -> u32 {
         policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS - self.block_order.len() as u32
     }
@@ -124,4 +136,8 @@ impl TransactionCache {
     pub fn tail_hash(&self) -> Blake2bHash {
         self.block_order.front().as_ref().unwrap().hash.clone()
     }
+
+    pub fn tail_prev_hash(&self) -> Blake2bHash {
+        self.block_order.front().as_ref().unwrap().prev_hash.clone()
+    }
 }
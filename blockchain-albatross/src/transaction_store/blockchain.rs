@@ -0,0 +1,47 @@
+use std::os::raw::c_uint;
+
+use database::ReadTransaction;
+use hash::Blake2bHash;
+use keys::Address;
+use transaction::TransactionReceipt;
+
+use crate::blockchain::Blockchain;
+use crate::transaction_store::{HistoryEntry, TransactionInfo};
+
+impl From<TransactionInfo> for TransactionReceipt {
+    fn from(info: TransactionInfo) -> Self {
+        TransactionReceipt {
+            transaction_hash: info.transaction_hash,
+            block_hash: info.block_hash,
+            block_height: info.block_number,
+        }
+    }
+}
+
+impl Blockchain {
+    pub fn get_transaction_receipts_by_address(&self, address: &Address, sender_limit: usize, recipient_limit: usize) -> Vec<TransactionReceipt> {
+        let mut entries;
+
+        let txn = ReadTransaction::new(&self.env);
+        entries = self.transaction_store.get_by_sender(address, sender_limit, None, Some(&txn));
+        entries.extend(self.transaction_store.get_by_recipient(address, recipient_limit, None, Some(&txn)));
+
+        // Rewards don't have a transaction hash and thus can't be represented as receipts.
+        entries.drain(..)
+            .filter_map(|(_, entry)| match entry {
+                HistoryEntry::Transaction(info) => Some(TransactionReceipt::from(info)),
+                HistoryEntry::Reward(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns up to `limit` transactions and rewards of `address`, newest first, together with
+    /// their cursor. Pass the cursor of the last entry as `before` to get the next page.
+    pub fn get_history_by_address(&self, address: &Address, limit: usize, before: Option<c_uint>) -> Vec<(c_uint, HistoryEntry)> {
+        self.transaction_store.get_history(address, limit, before, None)
+    }
+
+    pub fn get_transaction_info_by_hash(&self, transaction_hash: &Blake2bHash) -> Option<TransactionInfo> {
+        self.transaction_store.get_by_hash(transaction_hash, None)
+    }
+}
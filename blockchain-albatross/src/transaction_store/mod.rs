@@ -0,0 +1,472 @@
+use std::io;
+use std::os::raw::c_uint;
+
+use account::{Inherent, InherentType};
+use beserial::{Deserialize, Serialize};
+use block::Block;
+use database::{Database, DatabaseFlags, Environment, FromDatabaseValue, IntoDatabaseValue, ReadTransaction, Transaction, WriteTransaction};
+use database::cursor::ReadCursor;
+use hash::Blake2bHash;
+use hash::Hash;
+use keys::Address;
+use primitives::coin::Coin;
+use transaction::Transaction as BlockchainTransaction;
+
+pub mod blockchain;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TransactionInfo {
+    pub transaction_hash: Blake2bHash,
+    pub block_hash: Blake2bHash,
+    pub block_number: u32,
+    pub index: u16,
+}
+
+/// A reward inherent that was applied to an address when the previous epoch was finalized.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RewardInfo {
+    pub recipient: Address,
+    pub value: Coin,
+    pub block_hash: Blake2bHash,
+    pub block_number: u32,
+    pub index: u16,
+}
+
+/// An entry of the transaction store. Transactions are indexed by hash, sender and recipient,
+/// rewards only by recipient.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[repr(u8)]
+pub enum HistoryEntry {
+    Transaction(TransactionInfo),
+    Reward(RewardInfo),
+}
+
+impl HistoryEntry {
+    pub fn block_hash(&self) -> &Blake2bHash {
+        match self {
+            HistoryEntry::Transaction(ref info) => &info.block_hash,
+            HistoryEntry::Reward(ref info) => &info.block_hash,
+        }
+    }
+
+    pub fn block_number(&self) -> u32 {
+        match self {
+            HistoryEntry::Transaction(ref info) => info.block_number,
+            HistoryEntry::Reward(ref info) => info.block_number,
+        }
+    }
+}
+
+impl FromDatabaseValue for HistoryEntry {
+    fn copy_from_database(bytes: &[u8]) -> Result<Self, io::Error> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+impl IntoDatabaseValue for HistoryEntry {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl TransactionInfo {
+    /// Returns the transactions of a micro block together with their info.
+    /// Macro blocks don't contain any transactions, thus the result will be empty for them.
+    pub fn from_block(block: &Block) -> Vec<(&BlockchainTransaction, TransactionInfo)> {
+        let transactions = match block.transactions() {
+            Some(transactions) => transactions,
+            None => return Vec::new(),
+        };
+
+        let block_hash = block.hash();
+        let block_number = block.block_number();
+
+        transactions.iter().enumerate()
+            .map(|(index, tx)| (tx, TransactionInfo {
+                transaction_hash: tx.hash(),
+                block_hash: block_hash.clone(),
+                block_number,
+                index: index as u16,
+            }))
+            .collect()
+    }
+
+    /// Returns the transactions of an epoch that was synced without its micro blocks, together
+    /// with their info. They refer to the macro block that finalized the epoch, and their index is
+    /// their position within the epoch.
+    pub fn from_epoch<'a>(macro_block: &Block, transactions: &'a [BlockchainTransaction]) -> Vec<(&'a BlockchainTransaction, TransactionInfo)> {
+        let block_hash = macro_block.hash();
+        let block_number = macro_block.block_number();
+
+        transactions.iter().enumerate()
+            .map(|(index, tx)| (tx, TransactionInfo {
+                transaction_hash: tx.hash(),
+                block_hash: block_hash.clone(),
+                block_number,
+                index: index as u16,
+            }))
+            .collect()
+    }
+}
+
+impl RewardInfo {
+    /// Returns the reward inherents out of all inherents applied by a block.
+    pub fn from_inherents(block: &Block, inherents: &[Inherent]) -> Vec<RewardInfo> {
+        let block_hash = block.hash();
+        let block_number = block.block_number();
+
+        inherents.iter()
+            .filter(|inherent| inherent.ty == InherentType::Reward)
+            .enumerate()
+            .map(|(index, inherent)| RewardInfo {
+                recipient: inherent.target.clone(),
+                value: inherent.value,
+                block_hash: block_hash.clone(),
+                block_number,
+                index: index as u16,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct TransactionStore {
+    env: Environment,
+    transaction_db: Database,
+    sender_idx: Database,
+    recipient_idx: Database,
+    transaction_hash_idx: Database,
+}
+
+impl TransactionStore {
+    const TRANSACTION_DB_NAME: &'static str = "TransactionData";
+    const SENDER_IDX_NAME: &'static str = "SenderIdx";
+    const RECIPIENT_IDX_NAME: &'static str = "RecipientIdx";
+    const TRANSACTION_HASH_IDX_NAME: &'static str = "TransactionHashIdx";
+    const HEAD_KEY: c_uint = 0;
+    const HEAD_DEFAULT: c_uint = 1;
+
+    pub fn new(env: Environment) -> Self {
+        let transaction_db = env.open_database_with_flags(
+            Self::TRANSACTION_DB_NAME.to_string(),
+            DatabaseFlags::UINT_KEYS
+        );
+        let sender_idx = env.open_database_with_flags(
+            Self::SENDER_IDX_NAME.to_string(),
+            DatabaseFlags::DUPLICATE_KEYS | DatabaseFlags::DUP_FIXED_SIZE_VALUES | DatabaseFlags::DUP_UINT_VALUES
+        );
+        let recipient_idx = env.open_database_with_flags(
+            Self::RECIPIENT_IDX_NAME.to_string(),
+            DatabaseFlags::DUPLICATE_KEYS | DatabaseFlags::DUP_FIXED_SIZE_VALUES | DatabaseFlags::DUP_UINT_VALUES
+        );
+        let transaction_hash_idx = env.open_database(
+            Self::TRANSACTION_HASH_IDX_NAME.to_string()
+        );
+        TransactionStore { env, transaction_db, sender_idx, recipient_idx, transaction_hash_idx }
+    }
+
+    fn get_head(&self, txn_option: Option<&Transaction>) -> c_uint {
+        match txn_option {
+            Some(txn) => txn.get(&self.transaction_db, &TransactionStore::HEAD_KEY),
+            None => ReadTransaction::new(&self.env).get(&self.transaction_db, &TransactionStore::HEAD_KEY)
+        }.unwrap_or(Self::HEAD_DEFAULT)
+    }
+
+    fn set_head(&self, txn: &mut WriteTransaction, id: c_uint) {
+        txn.put(&self.transaction_db, &TransactionStore::HEAD_KEY, &id);
+    }
+
+    fn get_id(&self, transaction_hash: &Blake2bHash, txn_option: Option<&Transaction>) -> Option<c_uint> {
+        match txn_option {
+            Some(txn) => txn.get(&self.transaction_hash_idx, transaction_hash),
+            None => ReadTransaction::new(&self.env).get(&self.transaction_hash_idx, transaction_hash)
+        }
+    }
+
+    pub fn get_by_hash(&self, transaction_hash: &Blake2bHash, txn_option: Option<&Transaction>) -> Option<TransactionInfo> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let index = self.get_id(transaction_hash, Some(txn))?;
+        match txn.get(&self.transaction_db, &index)? {
+            HistoryEntry::Transaction(info) => Some(info),
+            HistoryEntry::Reward(_) => panic!("Corrupted store: Transaction hash references a reward"),
+        }
+    }
+
+    /// Returns up to `limit` entries of the given index for `address`, newest first.
+    /// If `before` is given, only entries with an id strictly smaller than it are returned.
+    fn get_by_address(&self, database: &Database, address: &Address, limit: usize, before: Option<c_uint>, txn: &Transaction) -> Vec<(c_uint, HistoryEntry)> {
+        let mut entries = Vec::new();
+
+        // Shortcut for a 0 limit.
+        if limit == 0 {
+            return entries;
+        }
+
+        // Start collecting entries.
+        let mut cursor = txn.cursor(database);
+
+        // Move to the last entry of that address that is older than `before`.
+        let nearest = before.and_then(|before| cursor.seek_key_nearest_value::<Address, c_uint>(address, &before));
+        let mut id: Option<c_uint> = if nearest.is_some() {
+            cursor.prev_duplicate().map(|(_, value): (Address, c_uint)| value)
+        } else {
+            // Address not found.
+            if cursor.seek_key::<Address, c_uint>(address).is_none() {
+                return entries;
+            }
+            cursor.last_duplicate()
+        };
+
+        while let Some(index) = id {
+            let entry = txn.get(&self.transaction_db, &index)
+                .expect("Corrupted store: HistoryEntry referenced from index not found");
+            entries.push((index, entry));
+
+            // Stop if we have enough entries.
+            if entries.len() >= limit {
+                break;
+            }
+
+            id = cursor.prev_duplicate().map(|(_, value): (Address, c_uint)| value);
+        }
+
+        entries
+    }
+
+    pub fn get_by_sender(&self, sender: &Address, limit: usize, before: Option<c_uint>, txn_option: Option<&Transaction>) -> Vec<(c_uint, HistoryEntry)> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        self.get_by_address(&self.sender_idx, sender, limit, before, txn)
+    }
+
+    pub fn get_by_recipient(&self, recipient: &Address, limit: usize, before: Option<c_uint>, txn_option: Option<&Transaction>) -> Vec<(c_uint, HistoryEntry)> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        self.get_by_address(&self.recipient_idx, recipient, limit, before, txn)
+    }
+
+    /// Returns up to `limit` entries that were sent or received by `address`, newest first.
+    /// The id of the last returned entry can be passed as `before` to fetch the next page.
+    pub fn get_history(&self, address: &Address, limit: usize, before: Option<c_uint>, txn_option: Option<&Transaction>) -> Vec<(c_uint, HistoryEntry)> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let mut entries = self.get_by_address(&self.sender_idx, address, limit, before, txn);
+        entries.extend(self.get_by_address(&self.recipient_idx, address, limit, before, txn));
+
+        // Merge both indices. Transactions to oneself appear in both of them.
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        entries.dedup_by_key(|entry| entry.0);
+        entries.truncate(limit);
+        entries
+    }
+
+    pub fn put(&self, block: &Block, inherents: &[Inherent], txn: &mut WriteTransaction) {
+        self.put_entries(TransactionInfo::from_block(block), RewardInfo::from_inherents(block, inherents), txn);
+    }
+
+    /// Adds the transactions and rewards of an epoch that was synced without its micro blocks.
+    pub fn put_epoch(&self, macro_block: &Block, transactions: &[BlockchainTransaction], inherents: &[Inherent], txn: &mut WriteTransaction) {
+        self.put_entries(TransactionInfo::from_epoch(macro_block, transactions), RewardInfo::from_inherents(macro_block, inherents), txn);
+    }
+
+    fn put_entries(&self, transactions: Vec<(&BlockchainTransaction, TransactionInfo)>, rewards: Vec<RewardInfo>, txn: &mut WriteTransaction) {
+        let mut current_id = self.get_head(Some(txn));
+
+        // Insert all transactions.
+        for (tx, info) in transactions {
+            txn.put(&self.transaction_hash_idx, &info.transaction_hash, &current_id);
+            txn.put_reserve(&self.transaction_db, &current_id, &HistoryEntry::Transaction(info));
+            txn.put(&self.sender_idx, &tx.sender, &current_id);
+            txn.put(&self.recipient_idx, &tx.recipient, &current_id);
+            current_id += 1;
+        }
+
+        // Insert all rewards.
+        for info in rewards {
+            txn.put(&self.recipient_idx, &info.recipient, &current_id);
+            txn.put_reserve(&self.transaction_db, &current_id, &HistoryEntry::Reward(info));
+            current_id += 1;
+        }
+
+        self.set_head(txn, current_id);
+    }
+
+    /// Removes the transactions of a reverted micro block.
+    /// Rewards are only distributed in macro blocks, which can't be reverted.
+    pub fn remove(&self, block: &Block, txn: &mut WriteTransaction) {
+        if let Some(transactions) = block.transactions() {
+            // Remove all transactions.
+            for tx in transactions.iter() {
+                let hash = tx.hash();
+                // Delete transaction from every store.
+                if let Some(id) = self.get_id(&hash, Some(txn)) {
+                    txn.remove(&self.transaction_hash_idx, &hash);
+                    txn.remove(&self.transaction_db, &id);
+                    txn.remove_item(&self.sender_idx, &tx.sender, &id);
+                    txn.remove_item(&self.recipient_idx, &tx.recipient, &id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::volatile::VolatileEnvironment;
+    use network_primitives::networks::{NetworkId, NetworkInfo};
+
+    use super::*;
+
+    fn reward(index: u16) -> HistoryEntry {
+        HistoryEntry::Reward(RewardInfo {
+            recipient: Address::default(),
+            value: Coin::ZERO,
+            block_hash: Blake2bHash::default(),
+            block_number: 1337,
+            index,
+        })
+    }
+
+    fn index_of(entry: &HistoryEntry) -> u16 {
+        match entry {
+            HistoryEntry::Transaction(ref info) => info.index,
+            HistoryEntry::Reward(ref info) => info.index,
+        }
+    }
+
+    #[test]
+    fn it_can_store_the_head_id() {
+        let env = VolatileEnvironment::new(4).unwrap();
+        let store = TransactionStore::new(env.clone());
+        assert_eq!(store.get_head(None), TransactionStore::HEAD_DEFAULT);
+
+        let head = 5;
+        let mut txn = WriteTransaction::new(&env);
+        store.set_head(&mut txn, head);
+        txn.commit();
+
+        assert_eq!(store.get_head(None), head);
+    }
+
+    #[test]
+    fn it_can_get_by_hash() {
+        let env = VolatileEnvironment::new(4).unwrap();
+        let store = TransactionStore::new(env.clone());
+
+        let hash = Blake2bHash::default();
+        let id = 5;
+        let info = TransactionInfo {
+            transaction_hash: hash.clone(),
+            block_hash: Blake2bHash::default(),
+            block_number: 1337,
+            index: 12
+        };
+
+        let mut txn = WriteTransaction::new(&env);
+        txn.put_reserve(&store.transaction_db, &id, &HistoryEntry::Transaction(info));
+        txn.put(&store.transaction_hash_idx, &hash, &id);
+        txn.commit();
+
+        assert_eq!(store.get_id(&hash, None), Some(id));
+        let stored = store.get_by_hash(&hash, None).unwrap();
+        assert_eq!(stored.block_number, 1337);
+        assert_eq!(stored.index, 12);
+    }
+
+    #[test]
+    fn it_can_page_by_address() {
+        let env = VolatileEnvironment::new(4).unwrap();
+        let store = TransactionStore::new(env.clone());
+        let address = Address::default();
+
+        {
+            let mut txn = WriteTransaction::new(&env);
+            for id in 1..=5 {
+                txn.put_reserve(&store.transaction_db, &id, &reward(id as u16));
+                txn.put(&store.recipient_idx, &address, &id);
+            }
+            txn.commit();
+        }
+
+        let txn = ReadTransaction::new(&env);
+        assert_eq!(store.get_by_address(&store.recipient_idx, &address, 0, None, &txn).len(), 0);
+
+        // First page.
+        let entries = store.get_by_address(&store.recipient_idx, &address, 2, None, &txn);
+        assert_eq!(entries.iter().map(|(id, _)| *id).collect::<Vec<c_uint>>(), vec![5, 4]);
+        assert_eq!(index_of(&entries[0].1), 5);
+
+        // Second page.
+        let entries = store.get_by_address(&store.recipient_idx, &address, 2, Some(4), &txn);
+        assert_eq!(entries.iter().map(|(id, _)| *id).collect::<Vec<c_uint>>(), vec![3, 2]);
+
+        // Last page.
+        let entries = store.get_by_address(&store.recipient_idx, &address, 2, Some(2), &txn);
+        assert_eq!(entries.iter().map(|(id, _)| *id).collect::<Vec<c_uint>>(), vec![1]);
+        assert!(store.get_by_address(&store.recipient_idx, &address, 2, Some(1), &txn).is_empty());
+
+        // Cursor beyond the newest entry.
+        let entries = store.get_by_address(&store.recipient_idx, &address, 10, Some(100), &txn);
+        assert_eq!(entries.len(), 5);
+    }
+
+    #[test]
+    fn it_indexes_the_transactions_of_synced_epochs() {
+        let env = VolatileEnvironment::new(4).unwrap();
+        let store = TransactionStore::new(env.clone());
+
+        let macro_block = NetworkInfo::from_network_id(NetworkId::UnitAlbatross).genesis_block::<Block>();
+        let sender = Address::from([1u8; Address::SIZE]);
+        let recipient = Address::from([2u8; Address::SIZE]);
+        let transactions: Vec<BlockchainTransaction> = (1..=2)
+            .map(|value| BlockchainTransaction::new_basic(sender.clone(), recipient.clone(), Coin::from_u64_unchecked(value), Coin::ZERO, 1, NetworkId::UnitAlbatross))
+            .collect();
+
+        let mut txn = WriteTransaction::new(&env);
+        store.put_epoch(&macro_block, &transactions, &[], &mut txn);
+        txn.commit();
+
+        // The transactions refer to the macro block.
+        let info = store.get_by_hash(&transactions[1].hash(), None).unwrap();
+        assert_eq!(info.block_hash, macro_block.hash());
+        assert_eq!(info.block_number, macro_block.block_number());
+        assert_eq!(info.index, 1);
+
+        assert_eq!(store.get_history(&sender, 10, None, None).len(), 2);
+        assert_eq!(store.get_history(&recipient, 10, None, None).len(), 2);
+    }
+}
@@ -4,14 +4,16 @@ use beserial::Deserialize;
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_block_production_albatross::BlockProducer;
 use nimiq_block_albatross::{Block, MacroBlock, PbftProposal, PbftProofBuilder, PbftPrepareMessage, PbftCommitMessage, SignedPbftPrepareMessage, SignedPbftCommitMessage};
-use nimiq_blockchain_albatross::blockchain::{Blockchain, PushResult};
+use nimiq_blockchain_albatross::blockchain::{Blockchain, PushError, PushResult};
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_hash::{Blake2bHash, Hash};
-use nimiq_network_primitives::{networks::NetworkId};
+use nimiq_network_primitives::networks::{NetworkId, NetworkInfo};
 use nimiq_primitives::policy;
 use nimiq_blockchain_base::AbstractBlockchain;
 use nimiq_blockchain_base::Direction;
 
+use super::TemporaryBlockProducer;
+
 /// Secret key of validator. Tests run with `network-primitives/src/genesis/unit-albatross.toml`
 const SECRET_KEY: &'static str = "49ea68eb6b8afdf4ca4d4c0a0b295c76ca85225293693bc30e755476492b707f";
 
@@ -67,7 +69,7 @@ fn produce_macro_blocks(num_macro: usize, producer: &BlockProducer, blockchain:
 
 #[test]
 fn it_can_sync_macro_blocks() {
-    let env = VolatileEnvironment::new(10).unwrap();
+    let env = VolatileEnvironment::new(20).unwrap();
     let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap());
     let genesis_hash = blockchain.head_hash();
 
@@ -80,7 +82,7 @@ fn it_can_sync_macro_blocks() {
     assert_eq!(macro_blocks.len(), 2);
 
     // Create a second blockchain to push these blocks.
-    let env2 = VolatileEnvironment::new(10).unwrap();
+    let env2 = VolatileEnvironment::new(20).unwrap();
     let blockchain2 = Arc::new(Blockchain::new(env2.clone(), NetworkId::UnitAlbatross).unwrap());
 
     for block in macro_blocks {
@@ -88,4 +90,74 @@ fn it_can_sync_macro_blocks() {
     }
 }
 
+#[test]
+fn it_can_sync_macro_blocks_light() {
+    let env = VolatileEnvironment::new(20).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap());
+    let genesis_hash = blockchain.head_hash();
+
+    let keypair = KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
+
+    produce_macro_blocks(2, &producer, &blockchain);
+
+    let macro_blocks = blockchain.get_macro_blocks(&genesis_hash, 10, true, Direction::Forward).unwrap();
+    assert_eq!(macro_blocks.len(), 2);
+
+    // Create a light blockchain to push these blocks.
+    let env2 = VolatileEnvironment::new(20).unwrap();
+    let blockchain2 = Arc::new(Blockchain::new_light(env2.clone(), NetworkId::UnitAlbatross).unwrap());
+    assert!(blockchain2.is_light());
+
+    // Micro blocks are not accepted.
+    let micro_block = blockchain.get_block_at(1, true).unwrap();
+    assert_eq!(blockchain2.push_light_macro_block(micro_block), Err(PushError::InvalidSuccessor));
+
+    for block in macro_blocks.iter() {
+        assert_eq!(blockchain2.push_light_macro_block(block.clone()), Ok(PushResult::Extended));
+    }
+    assert_eq!(blockchain2.push_light_macro_block(macro_blocks[1].clone()), Ok(PushResult::Known));
+
+    assert_eq!(blockchain2.head_hash(), blockchain.macro_head_hash());
+    assert_eq!(*blockchain2.current_validators(), *blockchain.current_validators());
+
+    // Light blockchains can't provide accounts proofs.
+    assert!(blockchain2.get_accounts_proof(&blockchain2.head_hash(), &[]).is_none());
+
+    // Full blockchains can't push light macro blocks.
+    let env3 = VolatileEnvironment::new(20).unwrap();
+    let blockchain3 = Arc::new(Blockchain::new(env3.clone(), NetworkId::UnitAlbatross).unwrap());
+    assert_eq!(blockchain3.push_light_macro_block(macro_blocks[0].clone()), Err(PushError::InvalidSuccessor));
+}
+
+#[test]
+fn it_can_prove_accounts_at_the_macro_head() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    // Finalize the first epoch and add some micro blocks on top.
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+    let micro_blocks: Vec<Block> = (0..3).map(|_| temp_producer.next_block(0, vec![])).collect();
+
+    // Light blockchains request proofs for the macro head, whose state root they know.
+    let blockchain = &temp_producer.blockchain;
+    let address = NetworkInfo::from_network_id(NetworkId::UnitAlbatross).validator_registry_address().unwrap().clone();
+    let mut proof = blockchain.get_accounts_proof(&macro_block.hash(), &[address.clone()]).unwrap();
+    assert!(proof.verify());
+    assert_eq!(proof.root_hash(), macro_block.header.state_root);
+    assert!(proof.get_account(&address).is_some());
+
+    // Proofs for the head are still supported, but not for other blocks.
+    let mut proof = blockchain.get_accounts_proof(&micro_blocks[2].hash(), &[address.clone()]).unwrap();
+    assert!(proof.verify());
+    assert_eq!(&proof.root_hash(), micro_blocks[2].state_root());
+    assert!(blockchain.get_accounts_proof(&micro_blocks[0].hash(), &[address]).is_none());
+
+    // Proving doesn't touch the chain.
+    assert_eq!(blockchain.head_hash(), micro_blocks[2].hash());
+}
+
 // TODO Test transactions
@@ -16,6 +16,9 @@ use nimiq_database::Environment;
 
 mod signed;
 mod macro_block_sync;
+mod pruning;
+mod snapshot;
+mod slash_log;
 
 /// Secret key of validator. Tests run with `network-primitives/src/genesis/unit-albatross.toml`
 const SECRET_KEY: &'static str = "49ea68eb6b8afdf4ca4d4c0a0b295c76ca85225293693bc30e755476492b707f";
@@ -28,8 +31,21 @@ struct TemporaryBlockProducer {
 
 impl TemporaryBlockProducer {
     fn new() -> Self {
-        let env = VolatileEnvironment::new(10).unwrap();
-        let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap());
+        let env = VolatileEnvironment::new(20).unwrap();
+        let blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap();
+        Self::with_blockchain(env, blockchain)
+    }
+
+    /// Creates a producer for a blockchain that keeps the last `keep_epochs` finalized epochs.
+    fn with_pruning(keep_epochs: u32) -> Self {
+        let env = VolatileEnvironment::new(20).unwrap();
+        let mut blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap();
+        blockchain.set_pruning(keep_epochs);
+        Self::with_blockchain(env, blockchain)
+    }
+
+    fn with_blockchain(env: Environment, blockchain: Blockchain) -> Self {
+        let blockchain = Arc::new(blockchain);
 
         let keypair = KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
         let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
@@ -0,0 +1,72 @@
+use nimiq_block_albatross::Block;
+use nimiq_blockchain_albatross::blockchain::Blockchain;
+use nimiq_database::WriteTransaction;
+use nimiq_primitives::policy;
+
+use super::TemporaryBlockProducer;
+
+#[test]
+fn it_can_prune_finalized_epochs() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let blockchain = &temp_producer.blockchain;
+
+    // Finalize two epochs.
+    for _ in 0..policy::macro_block_of(2) {
+        temp_producer.next_block(0, vec![]);
+    }
+    assert!(blockchain.get_epoch_transactions(1, None).is_some());
+
+    let mut txn = WriteTransaction::new(&temp_producer.env);
+    blockchain.chain_store.prune_epochs(&mut txn, 1);
+    txn.commit();
+    assert_eq!(blockchain.chain_store.get_pruned_epoch(None), Some(1));
+
+    // Micro block bodies of the first epoch are gone, but headers are kept.
+    match blockchain.chain_store.get_block_at(1, true, None) {
+        Some(Block::Micro(micro_block)) => assert!(micro_block.extrinsics.is_none()),
+        _ => panic!("Expected micro block header at height 1"),
+    }
+    assert!(blockchain.get_epoch_transactions(1, None).is_none());
+
+    // Macro blocks and later epochs are kept.
+    match blockchain.chain_store.get_block_at(policy::macro_block_of(1), true, None) {
+        Some(Block::Macro(macro_block)) => assert!(macro_block.extrinsics.is_some()),
+        _ => panic!("Expected macro block of the first epoch"),
+    }
+    assert!(blockchain.get_epoch_transactions(2, None).is_some());
+
+    // Pruning the same epoch again doesn't do anything.
+    let mut txn = WriteTransaction::new(&temp_producer.env);
+    blockchain.chain_store.prune_epochs(&mut txn, 1);
+    txn.commit();
+    assert_eq!(blockchain.chain_store.get_pruned_epoch(None), Some(1));
+}
+
+#[test]
+fn it_prunes_epochs_automatically() {
+    // Less than the minimum is raised to the minimum.
+    let temp_producer = TemporaryBlockProducer::with_pruning(1);
+    let blockchain = &temp_producer.blockchain;
+    assert!(blockchain.is_pruning());
+
+    // Nothing is pruned while all finalized epochs are within the limit.
+    for _ in 0..policy::macro_block_of(Blockchain::MIN_KEEP_EPOCHS) {
+        temp_producer.next_block(0, vec![]);
+    }
+    assert_eq!(blockchain.chain_store.get_pruned_epoch(None), None);
+    assert!(!blockchain.is_pruned(1));
+
+    // Finalizing the next epoch prunes the oldest one.
+    for _ in 0..policy::EPOCH_LENGTH {
+        temp_producer.next_block(0, vec![]);
+    }
+    assert_eq!(blockchain.chain_store.get_pruned_epoch(None), Some(1));
+    assert!(blockchain.is_pruned(policy::first_block_of(1)));
+    assert!(!blockchain.is_pruned(policy::first_block_of(2)));
+    match blockchain.chain_store.get_block_at(1, true, None) {
+        Some(Block::Micro(micro_block)) => assert!(micro_block.extrinsics.is_none()),
+        _ => panic!("Expected micro block header at height 1"),
+    }
+    assert!(blockchain.get_epoch_transactions(1, None).is_none());
+    assert!(blockchain.get_epoch_transactions(2, None).is_some());
+}
@@ -0,0 +1,42 @@
+use rand::thread_rng;
+
+use beserial::Deserialize;
+use nimiq_blockchain_albatross::reward_registry::SlashReason;
+use nimiq_bls::{KeyPair, SecretKey, SecureGenerate};
+use nimiq_primitives::policy;
+
+use super::{TemporaryBlockProducer, SECRET_KEY};
+
+#[test]
+fn it_logs_view_change_slashes() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let blockchain = &temp_producer.blockchain;
+    let keypair = KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let other_keypair = KeyPair::generate(&mut thread_rng());
+
+    temp_producer.next_block(0, vec![]);
+    temp_producer.next_block(2, vec![]);
+
+    let epoch = policy::epoch_at(blockchain.head_height());
+    let events = blockchain.get_slash_events(epoch, None);
+    assert_eq!(events.len(), 2);
+    for (view_number, (event, slot)) in events.iter().enumerate() {
+        assert_eq!(event.block_number, 2);
+        assert_eq!(event.epoch(), epoch);
+        match event.reason {
+            SlashReason::ViewChange { block_number, view_number: slashed_view_number } => {
+                assert_eq!(block_number, 2);
+                assert_eq!(slashed_view_number, view_number as u32);
+            },
+            _ => panic!("Expected view change slash"),
+        }
+        assert_eq!(slot.public_key().compressed(), &keypair.public.compress());
+    }
+
+    // Filter by validator.
+    assert_eq!(blockchain.get_slash_events(epoch, Some(&keypair.public.compress())).len(), 2);
+    assert!(blockchain.get_slash_events(epoch, Some(&other_keypair.public.compress())).is_empty());
+
+    // Other epochs have no slashes.
+    assert!(blockchain.get_slash_events(epoch - 1, None).is_empty());
+}
@@ -0,0 +1,134 @@
+use beserial::{Deserialize, Serialize};
+use nimiq_block_albatross::Block;
+use nimiq_blockchain_base::AbstractBlockchain;
+use nimiq_blockchain_albatross::blockchain::{Blockchain, PushResult};
+use nimiq_blockchain_albatross::snapshot::{AccountsSnapshot, SnapshotError};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_hash::Blake2bHash;
+use nimiq_network_primitives::networks::NetworkId;
+
+use super::TemporaryBlockProducer;
+
+#[test]
+fn it_can_bootstrap_from_accounts_snapshot() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    // Finalize the first epoch and add some micro blocks on top.
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+    let micro_blocks: Vec<Block> = (0..3).map(|_| temp_producer.next_block(0, vec![])).collect();
+
+    let snapshot = temp_producer.blockchain.export_accounts_snapshot().unwrap();
+    assert_eq!(snapshot.macro_block, macro_block);
+    assert_eq!(snapshot.state_root, macro_block.header.state_root);
+    // The transactions cover the blocks before the macro block, back to the genesis block.
+    assert_eq!(snapshot.transactions.blocks.len(), macro_block.header.block_number as usize);
+    assert_eq!(snapshot.transactions.blocks.last().unwrap().hash, macro_block.header.parent_hash);
+
+    // Exporting doesn't touch the chain.
+    assert_eq!(temp_producer.blockchain.head_hash(), micro_blocks[2].hash());
+
+    let snapshot = AccountsSnapshot::deserialize_from_vec(&snapshot.serialize_to_vec()).unwrap();
+
+    // Snapshots for other networks are rejected.
+    let env = VolatileEnvironment::new(20).unwrap();
+    match Blockchain::from_snapshot(env, NetworkId::DevAlbatross, snapshot.clone()) {
+        Err(SnapshotError::WrongNetwork(NetworkId::UnitAlbatross)) => {},
+        _ => panic!("Expected snapshot for wrong network to be rejected"),
+    }
+
+    // Accounts that don't match the state root are rejected.
+    let mut tampered = snapshot.clone();
+    tampered.accounts.pop();
+    let env = VolatileEnvironment::new(20).unwrap();
+    match Blockchain::from_snapshot(env, NetworkId::UnitAlbatross, tampered) {
+        Err(SnapshotError::StateRootMismatch) => {},
+        _ => panic!("Expected tampered snapshot to be rejected"),
+    }
+
+    // A previous macro block that isn't linked to the genesis block is rejected.
+    let mut tampered = snapshot.clone();
+    tampered.previous_macro_block.header.timestamp += 1;
+    let env = VolatileEnvironment::new(20).unwrap();
+    match Blockchain::from_snapshot(env, NetworkId::UnitAlbatross, tampered) {
+        Err(SnapshotError::InvalidMacroChain) => {},
+        _ => panic!("Expected tampered previous macro block to be rejected"),
+    }
+
+    // Transactions that don't lead up to the macro block are rejected.
+    let mut tampered = snapshot.clone();
+    tampered.transactions.blocks.remove(0);
+    let env = VolatileEnvironment::new(20).unwrap();
+    match Blockchain::from_snapshot(env, NetworkId::UnitAlbatross, tampered) {
+        Err(SnapshotError::InvalidTransactions) => {},
+        _ => panic!("Expected incomplete transactions to be rejected"),
+    }
+
+    // The blocks don't contain any transactions, so add one to check that it is known after bootstrapping.
+    let mut snapshot = snapshot;
+    let tx_hash: Blake2bHash = [1u8; 32].into();
+    snapshot.transactions.blocks[0].transaction_hashes.push(tx_hash.clone());
+
+    let env = VolatileEnvironment::new(20).unwrap();
+    let blockchain = Blockchain::from_snapshot(env.clone(), NetworkId::UnitAlbatross, snapshot).unwrap();
+    assert_eq!(blockchain.head_hash(), macro_block.hash());
+    assert!(blockchain.contains_tx_in_validity_window(&tx_hash));
+    assert_eq!(blockchain.macro_head_hash(), macro_block.hash());
+    assert_eq!(*blockchain.current_validators(), *temp_producer.blockchain.current_validators());
+
+    // The bootstrapped chain follows the blocks after the snapshot.
+    for block in micro_blocks {
+        assert_eq!(blockchain.push(block), Ok(PushResult::Extended));
+    }
+    assert_eq!(blockchain.head_hash(), temp_producer.blockchain.head_hash());
+
+    // The transactions before the snapshot are still known after a restart.
+    drop(blockchain);
+    let blockchain = Blockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+    assert_eq!(blockchain.head_hash(), temp_producer.blockchain.head_hash());
+    assert!(blockchain.contains_tx_in_validity_window(&tx_hash));
+}
+
+#[test]
+fn it_links_the_snapshot_to_the_genesis_block() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    // Finalize two epochs, so the previous macro block isn't the genesis block.
+    let mut macro_blocks = vec![];
+    while macro_blocks.len() < 2 {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            macro_blocks.push(macro_block);
+        }
+    }
+
+    let snapshot = temp_producer.blockchain.export_accounts_snapshot().unwrap();
+    assert_eq!(snapshot.macro_block, macro_blocks[1]);
+    assert_eq!(snapshot.previous_macro_block.hash(), macro_blocks[0].hash());
+    // There is only one validator, so no macro block elected different validators.
+    assert!(snapshot.election_blocks.is_empty());
+
+    // Macro blocks that didn't elect different validators are rejected.
+    let mut tampered = snapshot.clone();
+    tampered.election_blocks.push(macro_blocks[0].clone());
+    let env = VolatileEnvironment::new(20).unwrap();
+    match Blockchain::from_snapshot(env, NetworkId::UnitAlbatross, tampered) {
+        Err(SnapshotError::InvalidMacroChain) => {},
+        _ => panic!("Expected macro block without election to be rejected"),
+    }
+
+    let env = VolatileEnvironment::new(20).unwrap();
+    let blockchain = Blockchain::from_snapshot(env, NetworkId::UnitAlbatross, snapshot.clone()).unwrap();
+    assert_eq!(blockchain.head_hash(), macro_blocks[1].hash());
+    assert_eq!(*blockchain.current_validators(), *temp_producer.blockchain.current_validators());
+
+    // The election blocks and transactions are stored, so the bootstrapped chain can export
+    // snapshots itself.
+    let block = temp_producer.next_block(0, vec![]);
+    assert_eq!(blockchain.push(block), Ok(PushResult::Extended));
+    let exported = blockchain.export_accounts_snapshot().unwrap();
+    assert!(exported.election_blocks.is_empty());
+    assert_eq!(exported.transactions, snapshot.transactions);
+}
@@ -4,7 +4,7 @@ extern crate hex;
 extern crate nimiq_hash as hash;
 extern crate nimiq_utils as utils;
 
-use ff::Field;
+use ff::{Field, PrimeField};
 use group::{CurveAffine, CurveProjective};
 use hashbrown::HashSet;
 use pairing::Engine;
@@ -288,6 +288,34 @@ impl<E: Engine> Default for AggregateSignature<E> {
     }
 }
 
+/// Verifies a batch of (aggregate) signatures over the same message at once.
+///
+/// Each public key and signature is multiplied with a random 128 bit scalar, such that invalid
+/// signatures can't cancel each other out. Then the whole batch is checked with just two pairings.
+/// If this returns `false`, at least one signature in the batch is invalid.
+///
+/// As with `AggregatePublicKey`, there must be proofs of knowledge of the secret keys.
+pub fn verify_batch_hash<E: Engine, R: Rng + CryptoRng>(hash: SigHash, batch: &[(AggregatePublicKey<E>, AggregateSignature<E>)], rng: &mut R) -> bool {
+    let mut public_key = E::G2::zero();
+    let mut signature = E::G1::zero();
+
+    for (batch_public_key, batch_signature) in batch {
+        let mut r = <E::Fr as PrimeField>::Repr::default();
+        r.as_mut()[0] = rng.gen();
+        r.as_mut()[1] = rng.gen();
+
+        let mut p = (batch_public_key.0).p_pub;
+        p.mul_assign(r);
+        public_key.add_assign(&p);
+
+        let mut s = (batch_signature.0).s;
+        s.mul_assign(r);
+        signature.add_assign(&s);
+    }
+
+    PublicKey { p_pub: public_key }.verify_hash(hash, &Signature { s: signature })
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec::Vec;
@@ -336,6 +364,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn batch_verify() {
+        let mut rng = XorShiftRng::from_seed([0x44, 0x6d, 0x4f, 0xbc, 0x6c, 0x27, 0x2f, 0xd6, 0xd0, 0xaf, 0x63, 0xb9, 0x3d, 0x86, 0x55, 0x54]);
+        let mut batch_rng = ChaChaRng::from_seed([0x42; 32]);
+
+        let message = "Same message";
+        let keypairs = (0..20)
+            .map(|_| KeyPair::<Bls12>::generate_predictable(&mut rng))
+            .collect::<Vec<KeyPair<Bls12>>>();
+
+        // Individual signatures and an aggregate signature of the remaining keys
+        let mut batch = keypairs[..10].iter()
+            .map(|keypair| (AggregatePublicKey::from_public_keys(&[keypair.public.clone()]), AggregateSignature::from_signatures(&[keypair.sign(&message)])))
+            .collect::<Vec<(AggregatePublicKey<Bls12>, AggregateSignature<Bls12>)>>();
+        batch.push((
+            AggregatePublicKey::from_public_keys(&keypairs[10..].iter().map(|keypair| keypair.public.clone()).collect::<Vec<PublicKey<Bls12>>>()),
+            AggregateSignature::from_signatures(&keypairs[10..].iter().map(|keypair| keypair.sign(&message)).collect::<Vec<Signature<Bls12>>>()),
+        ));
+        assert!(verify_batch_hash(message.hash(), &batch, &mut batch_rng));
+
+        // Signature over a different message
+        batch[3].1 = AggregateSignature::from_signatures(&[keypairs[3].sign(&"Other message")]);
+        assert!(!verify_batch_hash(message.hash(), &batch, &mut batch_rng));
+
+        // Swapped signatures, whose sum is still valid
+        batch[3].1 = AggregateSignature::from_signatures(&[keypairs[4].sign(&message)]);
+        batch[4].1 = AggregateSignature::from_signatures(&[keypairs[3].sign(&message)]);
+        assert!(!verify_batch_hash(message.hash(), &batch, &mut batch_rng));
+    }
+
     #[test]
     fn aggregate_signatures_same_messages() {
         let mut rng = XorShiftRng::from_seed([0x44, 0x6d, 0x4f, 0xbc, 0x6c, 0x27, 0x2f, 0xd6, 0xd0, 0xaf, 0x63, 0xb9, 0x3d, 0x86, 0x55, 0x54]);
@@ -45,7 +45,10 @@ pub struct GenesisValidator {
     pub balance: Coin,
 
     #[serde(deserialize_with = "deserialize_bls_public_key")]
-    pub validator_key: BlsPublicKey
+    pub validator_key: BlsPublicKey,
+
+    #[serde(default)]
+    pub commission_rate: u16,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -119,7 +119,8 @@ impl GenesisBuilder {
         self.validators.push(config::GenesisValidator {
             validator_key,
             reward_address,
-            balance
+            balance,
+            commission_rate: 0,
         });
         self
     }
@@ -249,13 +250,16 @@ impl GenesisBuilder {
         let mut contract = StakingContract::default();
 
         for validator in self.validators.iter() {
-            contract.create_validator(validator.validator_key.compress(), validator.reward_address.clone(), validator.balance)?;
+            contract.create_validator(validator.validator_key.compress(), validator.reward_address.clone(), validator.commission_rate, validator.balance)?;
         }
 
         for stake in self.stakes.iter() {
             contract.stake(stake.staker_address.clone(), stake.balance, &stake.validator_key.compress())?;
         }
 
+        // The genesis stakes earn rewards in the first epoch.
+        contract.start_epoch()?;
+
         Ok(contract)
     }
 
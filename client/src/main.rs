@@ -5,11 +5,13 @@ extern crate nimiq_lib as nimiq;
 
 
 use std::convert::TryFrom;
+use std::process;
 use std::time::Duration;
 
 use futures::{future, Future, Stream, IntoFuture};
 use tokio;
 use tokio::timer::Interval;
+use tokio_signal;
 
 use nimiq::prelude::*;
 use nimiq::extras::logging::{initialize_logging, log_error_cause_chain};
@@ -17,6 +19,23 @@ use nimiq::extras::deadlock::initialize_deadlock_detection;
 use nimiq::extras::panic::initialize_panic_reporting;
 
 
+/// Resolves when the client is asked to shut down, i.e. on SIGINT or SIGTERM.
+fn shutdown_signal() -> Box<dyn Future<Item=(), Error=Error> + Send> {
+    let ctrl_c = tokio_signal::ctrl_c().flatten_stream();
+
+    #[cfg(unix)]
+    let signals = {
+        use tokio_signal::unix::{Signal, SIGTERM};
+        ctrl_c.select(Signal::new(SIGTERM).flatten_stream().map(|_| ()))
+    };
+    #[cfg(not(unix))]
+    let signals = ctrl_c;
+
+    Box::new(signals.into_future()
+        .map(|_| ())
+        .map_err(|(e, _)| Error::from(e)))
+}
+
 fn main_inner() -> Result<(), Error> {
     // Initialize deadlock detection
     initialize_deadlock_detection();
@@ -108,6 +127,7 @@ fn main_inner() -> Result<(), Error> {
             .and_then(move |client| {
                 // NOTE: This is the "monitor" future, which keeps the Client object alive.
 
+                let mempool = client.mempool();
                 let mut statistics_interval = config_file.log.statistics;
                 let mut show_statistics = true;
                 if statistics_interval == 0 {
@@ -128,6 +148,15 @@ fn main_inner() -> Result<(), Error> {
 
                         future::ok::<(), Error>(())
                     })
+                    .select(shutdown_signal().map(move |_| {
+                        info!("Shutting down");
+                        // The running servers keep the runtime alive, so exit explicitly after
+                        // storing the pending transactions.
+                        mempool.persist();
+                        process::exit(0);
+                    }))
+                    .map(|_| ())
+                    .map_err(|(e, _)| e)
             })
             .map_err(|e: Error| warn!("{}", e)));
 
@@ -5,12 +5,15 @@ use std::collections::HashMap;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+use futures::{future, Future};
 use parking_lot::RwLock;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
+use account::Account;
 use blockchain_base::{AbstractBlockchain, BlockchainEvent};
 use database::Environment;
+use keys::Address;
 use macros::upgrade_weak;
 use mempool::{Mempool, MempoolEvent, MempoolConfig};
 use network::{Network, NetworkConfig, NetworkEvent, Peer};
@@ -23,9 +26,10 @@ use utils::timers::Timers;
 
 use crate::accounts_chunk_cache::AccountsChunkCache;
 use crate::consensus_agent::{ConsensusAgent, ConsensusAgentEvent, sync::BlockQueue};
-use crate::error::Error;
+use crate::error::{AccountsRequestError, Error};
 use crate::inventory::InventoryManager;
 use crate::protocol::ConsensusProtocol;
+use crate::protocol::albatross::AlbatrossConsensusProtocol;
 
 pub struct Consensus<P: ConsensusProtocol + 'static> {
     pub blockchain: Arc<P::Blockchain>,
@@ -56,6 +60,7 @@ pub enum ConsensusEvent {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum ConsensusTimer {
     Sync,
+    PersistMempool,
 }
 
 type ConsensusAgentMap<P> = HashMap<Arc<Peer>, Arc<ConsensusAgent<P>>>;
@@ -74,7 +79,12 @@ impl<P: ConsensusProtocol> Consensus<P> {
     pub fn new(env: Environment, network_id: NetworkId, network_config: NetworkConfig, mempool_config: MempoolConfig) -> Result<Arc<Self>, Error> {
         let network_time = Arc::new(NetworkTime::new());
         let blockchain = Arc::new(<P::Blockchain as AbstractBlockchain>::new(env.clone(), network_id, Arc::clone(&network_time))?);
-        let mempool = Mempool::new(Arc::clone(&blockchain), mempool_config);
+        Self::with_blockchain(env, blockchain, network_time, network_id, network_config, mempool_config)
+    }
+
+    /// Creates the consensus for an already initialized blockchain, e.g. a light Albatross blockchain.
+    pub fn with_blockchain(env: Environment, blockchain: Arc<P::Blockchain>, network_time: Arc<NetworkTime>, network_id: NetworkId, network_config: NetworkConfig, mempool_config: MempoolConfig) -> Result<Arc<Self>, Error> {
+        let mempool = Mempool::with_environment(Arc::clone(&blockchain), mempool_config, env.clone());
         let network = Network::new(Arc::clone(&blockchain), network_config, network_time, network_id)?;
         let accounts_chunk_cache = AccountsChunkCache::new(env.clone(), Arc::clone(&blockchain));
         let block_queue = BlockQueue::new(Arc::clone(&blockchain));
@@ -104,6 +114,18 @@ impl<P: ConsensusProtocol> Consensus<P> {
         Ok(this)
     }
 
+    /// Starts storing the pending transactions of the mempool periodically, if enabled.
+    /// This must be called from within the tokio runtime.
+    pub fn initialize(&self) {
+        if let Some(interval) = self.mempool.persist_interval() {
+            let mempool = Arc::downgrade(&self.mempool);
+            self.timers.set_interval(ConsensusTimer::PersistMempool, move || {
+                let mempool = upgrade_weak!(mempool);
+                mempool.persist();
+            }, interval);
+        }
+    }
+
     pub fn init_listeners(this: &Arc<Consensus<P>>) {
         unsafe { this.self_weak.replace(Arc::downgrade(this)) };
 
@@ -127,6 +149,7 @@ impl<P: ConsensusProtocol> Consensus<P> {
                 MempoolEvent::TransactionRestored(transaction) => this.on_transaction_added(transaction),
                 MempoolEvent::TransactionEvicted(transaction) => this.on_transaction_removed(transaction),
                 MempoolEvent::TransactionMined(transaction) => this.on_transaction_removed(transaction),
+                MempoolEvent::TransactionReplaced(transaction, _) => this.on_transaction_removed(transaction),
             }
         });
 
@@ -270,15 +293,28 @@ impl<P: ConsensusProtocol> Consensus<P> {
         }
 
         let mut num_synced_full_nodes: usize = 0;
+        let mut synced_with_unpruned_node = false;
         let candidates: Vec<&Arc<ConsensusAgent<P>>> = state.agents.values()
             .filter(|&agent| {
                 let synced = agent.synced();
-                if synced && agent.peer.peer_address().services.is_full_node() {
+                let services = agent.peer.peer_address().services;
+                if synced && services.is_full_node() {
                     num_synced_full_nodes += 1;
+                    synced_with_unpruned_node |= !services.is_pruned();
                 }
                 !synced
             }).collect();
 
+        // Pruned peers can only serve the blocks of their most recent epochs. Until we know that
+        // our head is close to the head of the network, we would request blocks from beyond their
+        // horizon, so skip them.
+        let serves_old_blocks = state.established || synced_with_unpruned_node;
+        let num_candidates = candidates.len();
+        let candidates: Vec<&Arc<ConsensusAgent<P>>> = candidates.into_iter()
+            .filter(|&agent| serves_old_blocks || !agent.peer.peer_address().services.is_pruned())
+            .collect();
+        let skipped_pruned_peers = candidates.len() < num_candidates;
+
         // Choose a random peer which we aren't sync'd with yet.
         let mut rng = thread_rng();
         let agent = candidates.choose(&mut rng).map(|&agent| agent.clone());
@@ -304,9 +340,9 @@ impl<P: ConsensusProtocol> Consensus<P> {
             debug!("Syncing blockchain with peer {}", agent.peer.peer_address());
             agent.sync();
         } else {
-            // We are synced with all connected peers.
+            // We are synced with all connected peers, except for pruned ones we can't sync with yet.
             // Report consensus-established if we are connected to the minimum number of full nodes.
-            if num_synced_full_nodes >= Self::MIN_FULL_NODES {
+            if num_synced_full_nodes >= Self::MIN_FULL_NODES && !skipped_pruned_peers {
                 if !state.established {
                     info!("Synced with all connected peers ({}), consensus established", state.agents.len());
                     info!("Blockchain at block #{} [{}]", self.blockchain.head_height(), self.blockchain.head_hash());
@@ -334,3 +370,41 @@ impl<P: ConsensusProtocol> Consensus<P> {
         self.state.read().established
     }
 }
+
+impl Consensus<AlbatrossConsensusProtocol> {
+    /// Returns the accounts of `addresses`.
+    ///
+    /// Light blockchains don't keep an accounts tree, so the accounts are requested from a synced
+    /// full node, proven against the state root of our macro head. The request fails with
+    /// `AccountsRequestError::ProofUnavailable` if the peer has already moved past our macro head.
+    /// Other blockchains read the accounts from their own tree.
+    pub fn get_accounts(&self, addresses: Vec<Address>) -> Box<dyn Future<Item=Vec<Account>, Error=AccountsRequestError> + Send> {
+        if !self.blockchain.is_light() {
+            return Box::new(future::ok(addresses.iter()
+                .map(|address| self.blockchain.get_account(address))
+                .collect()));
+        }
+
+        let agent = {
+            let state = self.state.read();
+            let candidates: Vec<&Arc<ConsensusAgent<AlbatrossConsensusProtocol>>> = state.agents.values()
+                .filter(|&agent| agent.synced() && agent.peer.peer_address().services.is_full_node())
+                .collect();
+            candidates.choose(&mut thread_rng()).map(|&agent| Arc::clone(agent))
+        };
+
+        let agent = match agent {
+            Some(agent) => agent,
+            None => return Box::new(future::err(AccountsRequestError::NoPeer)),
+        };
+
+        let (block_hash, state_root) = {
+            let macro_head = self.blockchain.macro_head();
+            (macro_head.hash(), macro_head.header.state_root.clone())
+        };
+
+        Box::new(agent.get_accounts(block_hash, state_root, addresses)
+            .map_err(|_| AccountsRequestError::Cancelled)
+            .and_then(future::result))
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use futures::sync::oneshot;
+
+use account::Account;
+use hash::Blake2bHash;
+use keys::Address;
+use macros::upgrade_weak;
+use network::connection::close_type::CloseType;
+use network_messages::{AccountsProofMessage, GetAccountsProofMessage};
+
+use crate::consensus_agent::{ConsensusAgent, ConsensusAgentTimer};
+use crate::error::AccountsRequestError;
+use crate::ConsensusProtocol;
+
+pub type AccountsResult = Result<Vec<Account>, AccountsRequestError>;
+
+/// An outstanding GetAccountsProof request. Peers answer requests in order.
+pub(crate) struct AccountsRequest {
+    block_hash: Blake2bHash,
+    state_root: Blake2bHash,
+    addresses: Vec<Address>,
+    sender: oneshot::Sender<AccountsResult>,
+}
+
+impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
+    /// Maximum time to wait for an accounts proof.
+    const ACCOUNTS_PROOF_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Requests the accounts of `addresses` from the peer. The proof must be for block
+    /// `block_hash`, whose state root is `state_root`.
+    pub fn get_accounts(&self, block_hash: Blake2bHash, state_root: Blake2bHash, addresses: Vec<Address>) -> oneshot::Receiver<AccountsResult> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.accounts_requests.lock().push_back(AccountsRequest {
+            block_hash: block_hash.clone(),
+            state_root,
+            addresses: addresses.clone(),
+            sender,
+        });
+        self.peer.channel.send_or_close(GetAccountsProofMessage::new(block_hash, addresses));
+
+        if !self.timers.delay_exists(&ConsensusAgentTimer::AccountsProof) {
+            self.set_accounts_proof_timeout();
+        }
+
+        receiver
+    }
+
+    pub(super) fn on_accounts_proof(&self, msg: AccountsProofMessage) {
+        trace!("[ACCOUNTS-PROOF] from {}", self.peer.peer_address());
+
+        let request = {
+            let mut requests = self.accounts_requests.lock();
+            let request = requests.pop_front();
+            if requests.is_empty() {
+                self.timers.clear_delay(&ConsensusAgentTimer::AccountsProof);
+            } else {
+                self.set_accounts_proof_timeout();
+            }
+            request
+        };
+
+        let request = match request {
+            Some(request) => request,
+            None => {
+                warn!("Unsolicited accounts proof from {} - discarding", self.peer.peer_address());
+                return;
+            },
+        };
+
+        // Send the result to the requester. It doesn't matter if it's not interested anymore.
+        let _ = request.sender.send(self.verify_accounts_proof(msg, &request.block_hash, &request.state_root, &request.addresses));
+    }
+
+    fn verify_accounts_proof(&self, msg: AccountsProofMessage, block_hash: &Blake2bHash, state_root: &Blake2bHash, addresses: &[Address]) -> AccountsResult {
+        let mut proof = match msg.proof {
+            Some(proof) if &msg.block_hash == block_hash => proof,
+            _ => {
+                debug!("Peer {} can't provide an accounts proof for block {}", self.peer.peer_address(), block_hash);
+                return Err(AccountsRequestError::ProofUnavailable);
+            },
+        };
+
+        if !proof.verify() {
+            warn!("Invalid accounts proof from {} - closing the channel", self.peer.peer_address());
+            self.peer.channel.close(CloseType::InvalidAccountsProof);
+            return Err(AccountsRequestError::InvalidProof);
+        }
+
+        if &proof.root_hash() != state_root {
+            warn!("Accounts proof from {} doesn't match the state root - closing the channel", self.peer.peer_address());
+            self.peer.channel.close(CloseType::AccountsProofRootHashMismatch);
+            return Err(AccountsRequestError::InvalidProof);
+        }
+
+        // Accounts that are not part of the tree are empty basic accounts.
+        Ok(addresses.iter()
+            .map(|address| proof.get_account(address).unwrap_or(Account::INITIAL))
+            .collect())
+    }
+
+    fn set_accounts_proof_timeout(&self) {
+        let weak = self.self_weak.clone();
+        self.timers.reset_delay(ConsensusAgentTimer::AccountsProof, move || {
+            let this = upgrade_weak!(weak);
+            this.peer.channel.close(CloseType::GetAccountsProofTimeout);
+        }, Self::ACCOUNTS_PROOF_TIMEOUT);
+    }
+}
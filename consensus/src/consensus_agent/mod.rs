@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
@@ -31,10 +32,12 @@ use utils::rate_limit::RateLimit;
 use utils::timers::Timers;
 
 use crate::accounts_chunk_cache::AccountsChunkCache;
+use crate::consensus_agent::accounts::AccountsRequest;
 use crate::consensus_agent::sync::{SyncProtocol, BlockQueue};
 use crate::ConsensusProtocol;
 use crate::inventory::{InventoryAgent, InventoryEvent, InventoryManager};
 
+pub mod accounts;
 pub mod requests;
 pub mod sync;
 
@@ -89,6 +92,7 @@ pub struct ConsensusAgentState {
 enum ConsensusAgentTimer {
     Mempool,
     ResyncThrottle,
+    AccountsProof,
 }
 
 
@@ -107,6 +111,9 @@ pub struct ConsensusAgent<P: ConsensusProtocol + 'static> {
 
     sync_lock: Mutex<()>,
 
+    /// Our outstanding GetAccountsProof requests.
+    accounts_requests: Mutex<VecDeque<AccountsRequest>>,
+
     timers: Timers<ConsensusAgentTimer>,
 }
 
@@ -162,6 +169,7 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
             self_weak: MutableOnce::new(Weak::new()),
 
             sync_lock: Mutex::new(()),
+            accounts_requests: Mutex::new(VecDeque::new()),
             timers: Timers::new()
         });
         ConsensusAgent::init_listeners(&this);
@@ -191,6 +199,9 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         msg_notifier.get_accounts_proof.write().register(weak_passthru_listener(
             Arc::downgrade(this),
             |this, msg| this.on_get_accounts_proof(msg)));
+        msg_notifier.accounts_proof.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_accounts_proof(msg)));
         msg_notifier.get_accounts_tree_chunk.write().register(weak_passthru_listener(
             Arc::downgrade(this),
             |this, msg| this.on_get_accounts_tree_chunk(msg)));
@@ -239,7 +250,7 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         }
 
         // If we know our sync target block, the sync is finished.
-        if self.blockchain.contains(&self.state.read().sync_target, true) {
+        if self.sync_protocol.is_sync_complete() || self.blockchain.contains(&self.state.read().sync_target, true) {
             self.sync_finished(sync_guard);
             return;
         }
@@ -263,17 +274,22 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
     }
 
     fn sync_finished(&self, sync_guard: MutexGuard<()>) {
-        // Subscribe to all announcements from the peer.
-        self.inv_agent.subscribe(Subscription::Any);
-
-        // Request the peer's mempool.
-        let weak = self.self_weak.clone();
-        self.timers.set_delay(ConsensusAgentTimer::Mempool, move || {
-            let agent = upgrade_weak!(weak);
-            agent.timers.clear_delay(&ConsensusAgentTimer::Mempool);
-            agent.inv_agent.mempool();
-        }, Duration::from_millis(rand::thread_rng()
-            .gen_range(Self::MEMPOOL_DELAY_MIN, Self::MEMPOOL_DELAY_MAX)));
+        if self.sync_protocol.processes_transactions() {
+            // Subscribe to all announcements from the peer.
+            self.inv_agent.subscribe(Subscription::Any);
+
+            // Request the peer's mempool.
+            let weak = self.self_weak.clone();
+            self.timers.set_delay(ConsensusAgentTimer::Mempool, move || {
+                let agent = upgrade_weak!(weak);
+                agent.timers.clear_delay(&ConsensusAgentTimer::Mempool);
+                agent.inv_agent.mempool();
+            }, Duration::from_millis(rand::thread_rng()
+                .gen_range(Self::MEMPOOL_DELAY_MIN, Self::MEMPOOL_DELAY_MAX)));
+        } else if !self.state.read().synced {
+            // Only subscribe to block announcements.
+            self.inv_agent.subscribe(Subscription::MinFee(Coin::from_u64_unchecked(Coin::MAX_SAFE_VALUE)));
+        }
 
 
         self.inv_agent.bypass_mgr(false);
@@ -351,6 +367,13 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         if !state.synced && head_candidate {
             state.sync_target = hash.clone();
         }
+
+        // Announced blocks aren't downloaded by light sync, ask the peer for new macro blocks instead.
+        if state.synced && !self.sync_protocol.requests_announced_blocks() && !self.inv_agent.is_busy() {
+            drop(state);
+            self.sync_protocol.initiate_sync();
+            self.request_blocks();
+        }
     }
 
     fn on_known_block_announced(&self, hash: &Blake2bHash) {
@@ -33,6 +33,13 @@ pub trait SyncProtocol<B: AbstractBlockchain>: Send + Sync {
     fn on_epoch_transactions(&self, epoch_transactions: EpochTransactionsMessage);
     fn on_no_new_objects_announced(&self) {}
     fn on_all_objects_received(&self) {}
+    /// Returns true if the sync is complete even though we might not know the peer's head block.
+    fn is_sync_complete(&self) -> bool { false }
+    /// Returns false if we can't verify transactions and thus shouldn't receive any.
+    fn processes_transactions(&self) -> bool { true }
+    /// Returns false if announced blocks shouldn't be downloaded. Only blocks that the peer sends
+    /// in response to our own block requests are downloaded then.
+    fn requests_announced_blocks(&self) -> bool { true }
     fn register_listener<L: PassThroughListener<SyncEvent<<B::Block as Block>::Error>> + 'static>(&self, listener: L);
     fn deregister_listener(&self);
     fn notify(&self, event: SyncEvent<<B::Block as Block>::Error>);
@@ -362,6 +369,201 @@ impl SyncProtocol<AlbatrossBlockchain> for MacroBlockSync {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum LightSyncPhase {
+    Syncing,
+    Finished,
+}
+
+/// Only follows the macro block chain. Macro blocks are verified using their justification,
+/// micro blocks and transactions are ignored.
+pub struct LightSync {
+    blockchain: Arc<AlbatrossBlockchain>,
+    phase: RwLock<LightSyncPhase>,
+    peer: Arc<Peer>,
+    notifier: RwLock<PassThroughNotifier<'static, SyncEvent<AlbatrossBlockError>>>,
+}
+
+impl SyncProtocol<AlbatrossBlockchain> for LightSync {
+    fn new(blockchain: Arc<AlbatrossBlockchain>, _: Arc<RwLock<BlockQueue<AlbatrossBlockchain>>>, peer: Arc<Peer>) -> Arc<Self> {
+        Arc::new(Self {
+            blockchain,
+            phase: RwLock::new(LightSyncPhase::Finished),
+            peer,
+            notifier: RwLock::new(PassThroughNotifier::new()),
+        })
+    }
+
+    fn initiate_sync(&self) {
+        *self.phase.write() = LightSyncPhase::Syncing;
+    }
+
+    fn get_block_locators(&self, max_count: usize) -> Vec<Blake2bHash> {
+        self.blockchain.get_macro_block_locators(max_count)
+    }
+
+    fn request_blocks(&self, locators: Vec<Blake2bHash>, max_results: u16) {
+        self.peer.channel.send_or_close(GetBlocksMessage::new_with_macro(
+            locators,
+            max_results,
+            GetBlocksDirection::Forward,
+        ));
+    }
+
+    fn on_block(&self, block: AlbatrossBlock) {
+        // We only request macro blocks, so we shouldn't receive any micro blocks.
+        if let AlbatrossBlock::Micro(_) = block {
+            debug!("Ignoring micro block #{} from {}", block.block_number(), self.peer.peer_address());
+            return;
+        }
+
+        let hash = block.hash();
+        let result = self.blockchain.push_light_macro_block(block);
+        self.notifier.read().notify(SyncEvent::BlockProcessed(hash, result));
+    }
+
+    fn on_epoch_transactions(&self, _epoch_transactions: EpochTransactionsMessage) {
+        warn!("We didn't expect any epoch transactions from {} - discarding and closing the channel", self.peer.peer_address());
+        self.peer.channel.close(CloseType::UnexpectedEpochTransactions);
+    }
+
+    fn on_no_new_objects_announced(&self) {
+        *self.phase.write() = LightSyncPhase::Finished;
+    }
+
+    fn is_sync_complete(&self) -> bool {
+        *self.phase.read() == LightSyncPhase::Finished
+    }
+
+    fn processes_transactions(&self) -> bool {
+        false
+    }
+
+    fn requests_announced_blocks(&self) -> bool {
+        false
+    }
+
+    fn register_listener<L: PassThroughListener<SyncEvent<<AlbatrossBlock as Block>::Error>> + 'static>(&self, listener: L) {
+        self.notifier.write().register(listener)
+    }
+
+    fn deregister_listener(&self) {
+        self.notifier.write().deregister()
+    }
+
+    fn notify(&self, event: SyncEvent<<AlbatrossBlock as Block>::Error>) {
+        self.notifier.read().notify(event)
+    }
+}
+
+/// Selects the sync protocol for Albatross depending on the blockchain:
+/// Light blockchains use `LightSync`, all others `FullSync`.
+pub enum AlbatrossSync {
+    Full(Arc<FullSync<AlbatrossBlockchain>>),
+    Light(Arc<LightSync>),
+}
+
+impl SyncProtocol<AlbatrossBlockchain> for AlbatrossSync {
+    fn new(blockchain: Arc<AlbatrossBlockchain>, block_queue: Arc<RwLock<BlockQueue<AlbatrossBlockchain>>>, peer: Arc<Peer>) -> Arc<Self> {
+        Arc::new(if blockchain.is_light() {
+            AlbatrossSync::Light(LightSync::new(blockchain, block_queue, peer))
+        } else {
+            AlbatrossSync::Full(FullSync::new(blockchain, block_queue, peer))
+        })
+    }
+
+    fn initiate_sync(&self) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.initiate_sync(),
+            AlbatrossSync::Light(sync) => sync.initiate_sync(),
+        }
+    }
+
+    fn get_block_locators(&self, max_count: usize) -> Vec<Blake2bHash> {
+        match self {
+            AlbatrossSync::Full(sync) => sync.get_block_locators(max_count),
+            AlbatrossSync::Light(sync) => sync.get_block_locators(max_count),
+        }
+    }
+
+    fn request_blocks(&self, locators: Vec<Blake2bHash>, max_results: u16) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.request_blocks(locators, max_results),
+            AlbatrossSync::Light(sync) => sync.request_blocks(locators, max_results),
+        }
+    }
+
+    fn on_block(&self, block: AlbatrossBlock) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.on_block(block),
+            AlbatrossSync::Light(sync) => sync.on_block(block),
+        }
+    }
+
+    fn on_epoch_transactions(&self, epoch_transactions: EpochTransactionsMessage) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.on_epoch_transactions(epoch_transactions),
+            AlbatrossSync::Light(sync) => sync.on_epoch_transactions(epoch_transactions),
+        }
+    }
+
+    fn on_no_new_objects_announced(&self) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.on_no_new_objects_announced(),
+            AlbatrossSync::Light(sync) => sync.on_no_new_objects_announced(),
+        }
+    }
+
+    fn on_all_objects_received(&self) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.on_all_objects_received(),
+            AlbatrossSync::Light(sync) => sync.on_all_objects_received(),
+        }
+    }
+
+    fn is_sync_complete(&self) -> bool {
+        match self {
+            AlbatrossSync::Full(sync) => sync.is_sync_complete(),
+            AlbatrossSync::Light(sync) => sync.is_sync_complete(),
+        }
+    }
+
+    fn processes_transactions(&self) -> bool {
+        match self {
+            AlbatrossSync::Full(sync) => sync.processes_transactions(),
+            AlbatrossSync::Light(sync) => sync.processes_transactions(),
+        }
+    }
+
+    fn requests_announced_blocks(&self) -> bool {
+        match self {
+            AlbatrossSync::Full(sync) => sync.requests_announced_blocks(),
+            AlbatrossSync::Light(sync) => sync.requests_announced_blocks(),
+        }
+    }
+
+    fn register_listener<L: PassThroughListener<SyncEvent<<AlbatrossBlock as Block>::Error>> + 'static>(&self, listener: L) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.register_listener(listener),
+            AlbatrossSync::Light(sync) => sync.register_listener(listener),
+        }
+    }
+
+    fn deregister_listener(&self) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.deregister_listener(),
+            AlbatrossSync::Light(sync) => sync.deregister_listener(),
+        }
+    }
+
+    fn notify(&self, event: SyncEvent<<AlbatrossBlock as Block>::Error>) {
+        match self {
+            AlbatrossSync::Full(sync) => sync.notify(event),
+            AlbatrossSync::Light(sync) => sync.notify(event),
+        }
+    }
+}
+
 
 pub struct BlockQueue<B: AbstractBlockchain> {
     blockchain: Arc<B>,
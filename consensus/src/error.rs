@@ -22,3 +22,15 @@ impl From<BlockchainError> for Error {
         Error::BlockchainError(e)
     }
 }
+
+#[derive(Fail, Debug, Clone, PartialEq, Eq)]
+pub enum AccountsRequestError {
+    #[fail(display = "No synced full node available")]
+    NoPeer,
+    #[fail(display = "Peer can't provide an accounts proof for the requested block")]
+    ProofUnavailable,
+    #[fail(display = "Peer sent an invalid accounts proof")]
+    InvalidProof,
+    #[fail(display = "Request was cancelled")]
+    Cancelled,
+}
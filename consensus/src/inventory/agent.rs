@@ -358,7 +358,8 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
         // Give up state write lock.
         drop(state);
 
-        let head_candidate = !self.timers.delay_exists(&InventoryAgentTimer::GetBlocks)
+        let requesting_blocks = self.timers.delay_exists(&InventoryAgentTimer::GetBlocks);
+        let head_candidate = !requesting_blocks
             && vectors.len() == 1
             && vectors.first().unwrap().ty == InvVectorType::Block;
 
@@ -368,7 +369,10 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
                 InvVectorType::Block => {
                     if !self.blockchain.contains(&vector.hash, true) {
                         self.notifier.read().notify(InventoryEvent::NewBlockAnnounced(vector.hash.clone(), head_candidate));
-                        unknown_blocks.push(vector);
+                        // Some sync protocols only download the blocks they requested themselves.
+                        if requesting_blocks || self.sync_protocol.requests_announced_blocks() {
+                            unknown_blocks.push(vector);
+                        }
                     } else {
                         self.notifier.read().notify(InventoryEvent::KnownBlockAnnounced(vector.hash.clone()));
                     }
@@ -386,8 +390,8 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
             }
         }
 
-        // Clear get_blocks timeout.
-        if has_block {
+        // Clear get_blocks timeout. Don't clear a timeout set for a new request while notifying.
+        if has_block && requesting_blocks {
             self.timers.clear_delay(&InventoryAgentTimer::GetBlocks);
         }
 
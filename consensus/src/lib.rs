@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate log;
 
+extern crate nimiq_account as account;
 extern crate nimiq_block_albatross as block_albatross;
 extern crate nimiq_block_base as block_base;
 extern crate nimiq_blockchain as blockchain;
@@ -9,6 +10,7 @@ extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_collections as collections;
 extern crate nimiq_database as database;
 extern crate nimiq_hash as hash;
+extern crate nimiq_keys as keys;
 extern crate nimiq_macros as macros;
 extern crate nimiq_mempool as mempool;
 extern crate nimiq_messages as network_messages;
@@ -26,7 +28,7 @@ mod accounts_chunk_cache;
 mod protocol;
 
 pub use self::consensus::{Consensus, ConsensusEvent};
-pub use self::error::Error;
+pub use self::error::{AccountsRequestError, Error};
 pub use self::protocol::nimiq::NimiqConsensusProtocol;
 pub use self::protocol::albatross::AlbatrossConsensusProtocol;
 pub use self::protocol::ConsensusProtocol;
@@ -1,9 +1,11 @@
 use std::sync::{Arc, Weak};
 use std::fmt;
+use std::time::{Duration, Instant};
 
 use macros::upgrade_weak;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use futures::{future, Future};
+use rand::thread_rng;
 
 use utils::observer::PassThroughNotifier;
 use utils::mutable_once::MutableOnce;
@@ -18,6 +20,7 @@ use crate::todo::TodoList;
 use crate::protocol::Protocol;
 use crate::update::LevelUpdate;
 use crate::sender::Sender;
+use crate::timeout::TimeoutStrategy;
 
 
 
@@ -42,10 +45,16 @@ struct AggregationState {
     /// Our contribution
     contribution: Option<IndividualSignature>,
 
+    /// The next level that will be started by a timeout
     next_level_timeout: usize,
 }
 
 
+/// Source of the current time. This is `Instant::now` unless the aggregation is driven by the
+/// simulator.
+pub(crate) type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+
 pub struct Aggregation<P: Protocol> {
     /// Handel configuration, including the hash being signed, this node's contributed signature, etc.
     config: Config,
@@ -65,6 +74,12 @@ pub struct Aggregation<P: Protocol> {
     /// Internal state
     state: RwLock<AggregationState>,
 
+    /// Current time
+    clock: Clock,
+
+    /// When the aggregation was started
+    started: Instant,
+
     /// Weak reference to the Aggregation itself
     self_weak: MutableOnce<Weak<Self>>,
 
@@ -75,7 +90,26 @@ pub struct Aggregation<P: Protocol> {
 
 impl<P: Protocol + fmt::Debug> Aggregation<P> {
     pub fn new(protocol: P, config: Config) -> Arc<Self> {
-        let levels = Level::create_levels(protocol.partitioner());
+        let levels = Level::create_levels(protocol.partitioner(), &mut thread_rng());
+        let this = Self::with_levels(protocol, config, levels, Arc::new(Instant::now));
+
+        Self::init_background(&this);
+
+        this
+    }
+
+    /// Creates an aggregation that doesn't start any timers. Instead the owner has to call
+    /// `send_periodic_updates` and `next_level_timeout` itself. The peers of each level are
+    /// shuffled using `rng` and level completion times are measured with `clock`.
+    ///
+    /// This is used by the simulator, which drives aggregations with simulated time.
+    #[cfg(any(test, feature = "simulator"))]
+    pub(crate) fn new_without_timers<R: rand::Rng>(protocol: P, config: Config, rng: &mut R, clock: Clock) -> Arc<Self> {
+        let levels = Level::create_levels(protocol.partitioner(), rng);
+        Self::with_levels(protocol, config, levels, clock)
+    }
+
+    fn with_levels(protocol: P, config: Config, levels: Vec<Level>, clock: Clock) -> Arc<Self> {
         let todos = Arc::new(TodoList::new(protocol.evaluator()));
 
         // create aggregation
@@ -87,14 +121,19 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
             timers: Timers::new(),
             state: RwLock::new(AggregationState {
                 result: None,
-                next_level_timeout: 0,
+                // level 0 only contains our own contribution and is started right away
+                next_level_timeout: 1,
                 contribution: None,
             }),
+            started: clock(),
+            clock,
             self_weak: MutableOnce::new(Weak::new()),
             notifier: RwLock::new(PassThroughNotifier::new()),
         });
 
-        Self::init_background(&this);
+        unsafe { this.self_weak.replace(Arc::downgrade(&this)) };
+
+        this.start_level(0);
 
         this
     }
@@ -130,46 +169,86 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
     }
 
     fn init_background(this: &Arc<Self>) {
-        unsafe { this.self_weak.replace(Arc::downgrade(&this)) };
-
         // register timer for updates
         let weak = Arc::downgrade(this);
         this.timers.set_interval(AggregationTimer::Update, move || {
             let this = upgrade_weak!(weak);
-            trace!("Update for {:?}", this.protocol);
-            let store = this.protocol.store();
-            let store = store.read();
-            // NOTE: Skip level 0
-            for level in this.levels.iter().skip(1) {
-                // send update
-                if let Some(multisig) = store.combined(level.id - 1) {
-                    this.send_update(multisig, &level, this.config.update_count);
-                }
-            }
+            this.send_periodic_updates();
         }, this.config.update_interval);
 
         // register timer for level timeouts
-        // TODO: This ignores the timeout strategy
-        let weak = Arc::downgrade(this);
-        this.timers.set_interval(AggregationTimer::Timeout, move || {
-            let this = upgrade_weak!(weak);
-            let mut state = this.state.write();
-            let level = state.next_level_timeout;
-            if level < this.num_levels() {
-                trace!("Timeout for {:?} at level {}", this.protocol, level);
-                state.next_level_timeout += 1;
-                drop(state);
-                this.start_level(level);
-            }
-            else {
-                this.timers.clear_interval(&AggregationTimer::Timeout);
-            }
-        }, this.config.timeout);
+        Self::schedule_level_timeout(this);
 
         // spawn thread handling TODOs
         //tokio::spawn(Arc::clone(&this.todos).into_future());
     }
 
+    /// Schedules the timeout of the current level, which will start the next level.
+    fn schedule_level_timeout(this: &Arc<Self>) {
+        if let Some(timeout) = this.next_timeout() {
+            let weak = Arc::downgrade(this);
+            this.timers.reset_delay(AggregationTimer::Timeout, move || {
+                let this = upgrade_weak!(weak);
+                this.next_level_timeout();
+                Self::schedule_level_timeout(&this);
+            }, timeout);
+        }
+        else {
+            this.timers.clear_delay(&AggregationTimer::Timeout);
+        }
+    }
+
+    /// Sends our best signature of each level to the next `update_count` peers of that level.
+    pub(crate) fn send_periodic_updates(&self) {
+        trace!("Update for {:?}", self.protocol);
+        let store = self.protocol.store();
+        let store = store.read();
+        // NOTE: Skip level 0
+        for level in self.levels.iter().skip(1) {
+            // send update
+            if let Some(multisig) = store.combined(level.id - 1) {
+                self.send_update(multisig, &level, self.config.update_count);
+            }
+        }
+    }
+
+    /// Starts the next level that didn't time out yet. Returns `false` if all levels timed out
+    /// already.
+    pub(crate) fn next_level_timeout(&self) -> bool {
+        let mut state = self.state.write();
+        let level = state.next_level_timeout;
+        if level < self.num_levels() {
+            trace!("Timeout for {:?} at level {}", self.protocol, level);
+            state.next_level_timeout += 1;
+            drop(state);
+
+            // Let the timeouts learn from levels that didn't complete in time.
+            let previous = &self.levels[level - 1];
+            if !previous.receive_complete() {
+                let elapsed = (self.clock)().duration_since(self.started);
+                self.protocol.timeouts().level_timed_out(previous.id, elapsed);
+            }
+
+            self.start_level(level);
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Returns how long to wait until `next_level_timeout` should be called, or `None` if all
+    /// levels were started already.
+    pub(crate) fn next_timeout(&self) -> Option<Duration> {
+        let level = self.state.read().next_level_timeout;
+        if level < self.num_levels() {
+            Some(self.protocol.timeouts().timeout(level - 1))
+        }
+        else {
+            None
+        }
+    }
+
     pub fn num_levels(&self) -> usize {
         self.levels.len()
     }
@@ -232,6 +311,9 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
                 trace!("Level {} complete", level.id);
                 level_state.receive_completed = true;
 
+                let elapsed = (self.clock)().duration_since(self.started);
+                self.protocol.timeouts().level_completed(level.id, elapsed);
+
                 if level.id + 1 < self.levels.len() {
                     // activate next level
                     self.start_level(level.id + 1)
@@ -1,5 +1,8 @@
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::timeout::{TimeoutKind, TimeoutStrategy};
+
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -9,21 +12,34 @@ pub struct Config {
     /// Frequency at which updates are sent to peers
     pub update_interval: Duration,
 
-    /// Timeout for levels
+    /// Timeout for levels. How it is used depends on `timeout_strategy`.
     pub timeout: Duration,
 
+    /// Strategy for level timeouts
+    pub timeout_strategy: TimeoutKind,
+
     /// How many peers are contacted at each level
     pub peer_count: usize,
 
 }
 
 
+impl Config {
+    /// Creates the level timeouts selected by `timeout_strategy`. Adaptive timeouts learn from
+    /// previous aggregations, so they should be shared between aggregations.
+    pub fn create_timeouts(&self) -> Arc<dyn TimeoutStrategy> {
+        self.timeout_strategy.create(self.timeout)
+    }
+}
+
+
 impl Default for Config {
     fn default() -> Self {
         Config {
             update_count: 1,
             update_interval: Duration::from_millis(100),
             timeout: Duration::from_millis(500),
+            timeout_strategy: TimeoutKind::Linear,
             peer_count: 10,
         }
     }
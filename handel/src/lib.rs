@@ -39,4 +39,6 @@ pub mod protocol;
 pub mod update;
 pub mod aggregation;
 pub mod sender;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
 mod todo;
@@ -9,6 +9,7 @@ use crate::evaluator::Evaluator;
 use crate::partitioner::Partitioner;
 use crate::multisig::Signature;
 use crate::sender::Sender;
+use crate::timeout::TimeoutStrategy;
 
 
 pub trait Protocol: Send + Sync + 'static {
@@ -16,7 +17,7 @@ pub trait Protocol: Send + Sync + 'static {
     // the protocol (i.e. `Verifier`).
     type Registry: IdentityRegistry;
     type Verifier: Verifier;
-    //type Timeouts: TimeoutStrategy;
+    type Timeouts: TimeoutStrategy + ?Sized;
     type Store: SignatureStore;
     type Evaluator: Evaluator + Send + Sync;
     type Partitioner: Partitioner;
@@ -24,7 +25,7 @@ pub trait Protocol: Send + Sync + 'static {
 
     fn registry(&self) -> Arc<Self::Registry>;
     fn verifier(&self) -> Arc<Self::Verifier>;
-    //fn timeouts(&self) -> Arc<Self::Timeouts>;
+    fn timeouts(&self) -> Arc<Self::Timeouts>;
     fn store(&self) -> Arc<RwLock<Self::Store>>;
     fn evaluator(&self) -> Arc<Self::Evaluator>;
     fn partitioner(&self) -> Arc<Self::Partitioner>;
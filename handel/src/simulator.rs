@@ -0,0 +1,702 @@
+//! Deterministic in-process simulation of a Handel network.
+//!
+//! The simulator runs one `Aggregation` per node and drives all of them with a simulated clock
+//! instead of real timers. Messages between nodes are delayed by a configurable latency and jitter
+//! and may be lost. Some nodes can be byzantine. All randomness (keys, peer order, latency, loss)
+//! is derived from a single seed, so a simulation with the same configuration always yields the
+//! same result.
+//!
+//! This can be used to tune the `Config` and the timeout strategy of Handel (update interval,
+//! level timeouts, peer count, ...) on data rather than guesswork.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::Error as IoError;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{self, FutureResult};
+use parking_lot::{Mutex, RwLock};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::index;
+use tokio::runtime::current_thread::Runtime;
+
+use beserial::Serialize;
+use bls::SecureGenerate;
+use bls::bls12_381::{KeyPair, PublicKey};
+use collections::bitset::BitSet;
+use hash::{Blake2bHash, Blake2bHasher, Hasher};
+
+use crate::aggregation::{Aggregation, Clock};
+use crate::config::Config;
+use crate::evaluator::WeightedVote;
+use crate::identity::{IdentityRegistry, WeightRegistry};
+use crate::multisig::{IndividualSignature, Signature};
+use crate::partitioner::BinomialPartitioner;
+use crate::protocol::Protocol;
+use crate::sender::Sender;
+use crate::store::ReplaceStore;
+use crate::timeout::{LinearTimeout, TimeoutStrategy};
+use crate::update::LevelUpdate;
+use crate::verifier::{MultithreadedVerifier, VerificationResult, Verifier};
+
+
+/// How byzantine nodes behave
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByzantineBehavior {
+    /// The node is offline and neither sends nor receives anything.
+    Silent,
+    /// The node takes part in the protocol, but its contribution is a signature over a different
+    /// message. All signatures it sends are thus invalid.
+    Forging,
+}
+
+
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    /// Number of nodes
+    pub num_nodes: usize,
+
+    /// Number of votes needed for a final signature. Every node has a single vote.
+    pub threshold: usize,
+
+    /// Number of byzantine nodes. Those are picked at random.
+    pub num_byzantine: usize,
+
+    /// How byzantine nodes behave
+    pub byzantine_behavior: ByzantineBehavior,
+
+    /// Minimum delay of a message
+    pub latency: Duration,
+
+    /// Maximum additional random delay of a message
+    pub jitter: Duration,
+
+    /// Probability that a message is lost
+    pub loss_rate: f64,
+
+    /// Verify signatures using BLS. This is realistic, but slow. Otherwise forged signatures are
+    /// detected by their signers, which yields the same results.
+    pub verify_signatures: bool,
+
+    /// The simulation is aborted after this (simulated) time.
+    pub max_duration: Duration,
+
+    /// Seed for all randomness in the simulation
+    pub seed: u64,
+
+    /// The Handel configuration used by all nodes
+    pub handel: Config,
+
+    /// The level timeouts used by all nodes. Adaptive strategies learn from all nodes and keep
+    /// what they learned if they are reused for another simulation.
+    pub timeouts: Arc<dyn TimeoutStrategy>,
+}
+
+impl SimulationConfig {
+    /// The threshold used by Albatross: more than two thirds of the votes.
+    pub fn two_third_threshold(num_nodes: usize) -> usize {
+        2 * num_nodes / 3 + 1
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            num_nodes: 16,
+            threshold: Self::two_third_threshold(16),
+            num_byzantine: 0,
+            byzantine_behavior: ByzantineBehavior::Silent,
+            latency: Duration::from_millis(50),
+            jitter: Duration::from_millis(50),
+            loss_rate: 0.0,
+            verify_signatures: false,
+            max_duration: Duration::from_secs(60),
+            seed: 0,
+            handel: Config::default(),
+            timeouts: Arc::new(LinearTimeout::default()),
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationResult {
+    /// For every honest node: the node ID and the (simulated) time at which it had a final
+    /// signature, or `None` if it didn't get one.
+    pub completion_times: Vec<(usize, Option<Duration>)>,
+
+    /// Number of messages sent by all nodes
+    pub messages_sent: usize,
+
+    /// Number of messages that were lost
+    pub messages_lost: usize,
+
+    /// Size of all messages sent
+    pub bytes_sent: usize,
+
+    /// Simulated time when the simulation ended
+    pub duration: Duration,
+}
+
+impl SimulationResult {
+    /// Number of honest nodes that have a final signature
+    pub fn num_completed(&self) -> usize {
+        self.completion_times.iter()
+            .filter(|(_, time)| time.is_some())
+            .count()
+    }
+
+    /// Whether all honest nodes have a final signature
+    pub fn is_complete(&self) -> bool {
+        self.num_completed() == self.completion_times.len()
+    }
+
+    /// Time until all honest nodes had a final signature
+    pub fn time_to_threshold(&self) -> Option<Duration> {
+        self.completion_times.iter()
+            .map(|(_, time)| *time)
+            .collect::<Option<Vec<Duration>>>()?
+            .into_iter()
+            .max()
+    }
+
+    /// Median time until an honest node had a final signature, counting nodes that didn't get
+    /// one as slowest.
+    pub fn median_time_to_threshold(&self) -> Option<Duration> {
+        let mut times = self.completion_times.iter()
+            .map(|(_, time)| *time)
+            .collect::<Vec<Option<Duration>>>();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_by(|a, b| match (a, b) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        times[times.len() / 2]
+    }
+
+    /// Average number of messages sent per node
+    pub fn messages_per_node(&self) -> f64 {
+        if self.completion_times.is_empty() {
+            0f64
+        } else {
+            self.messages_sent as f64 / self.completion_times.len() as f64
+        }
+    }
+}
+
+
+/// Identities of the simulated nodes. Every node has a weight of 1.
+pub struct SimulatedRegistry {
+    public_keys: Vec<PublicKey>,
+}
+
+impl IdentityRegistry for SimulatedRegistry {
+    fn public_key(&self, id: usize) -> Option<PublicKey> {
+        self.public_keys.get(id).cloned()
+    }
+}
+
+impl WeightRegistry for SimulatedRegistry {
+    fn weight(&self, id: usize) -> Option<usize> {
+        if id < self.public_keys.len() { Some(1) } else { None }
+    }
+}
+
+
+/// Verifies signatures synchronously, so that the processing order doesn't depend on thread
+/// scheduling.
+pub struct SimulatedVerifier {
+    message_hash: Blake2bHash,
+    registry: Arc<SimulatedRegistry>,
+    /// Signers whose signatures are forged
+    forgers: BitSet,
+    verify_signatures: bool,
+}
+
+impl Verifier for SimulatedVerifier {
+    type Output = FutureResult<VerificationResult, ()>;
+
+    fn verify(&self, signature: &Signature) -> Self::Output {
+        let result = if self.verify_signatures {
+            match signature {
+                Signature::Individual(individual) => {
+                    MultithreadedVerifier::verify_individual(Arc::clone(&self.registry), self.message_hash.clone(), individual)
+                },
+                Signature::Multi(multisig) => {
+                    MultithreadedVerifier::verify_multisig(Arc::clone(&self.registry), self.message_hash.clone(), multisig)
+                },
+            }
+        }
+        else if let Some(signer) = signature.signers().find(|&signer| signer >= self.registry.public_keys.len()) {
+            VerificationResult::UnknownSigner { signer }
+        }
+        else if signature.signers().any(|signer| self.forgers.contains(signer)) {
+            VerificationResult::Forged
+        }
+        else {
+            VerificationResult::Ok
+        };
+        future::ok(result)
+    }
+}
+
+
+/// A message that was sent by a node, but not yet scheduled for delivery.
+struct Outgoing {
+    to: usize,
+    update: LevelUpdate,
+}
+
+/// Collects the messages sent by a node. The simulator picks them up after every event.
+pub struct SimulatedSender {
+    outbox: Arc<Mutex<Vec<Outgoing>>>,
+}
+
+impl Sender for SimulatedSender {
+    type Error = IoError;
+
+    fn send_to(&self, peer_id: usize, update: LevelUpdate) {
+        self.outbox.lock().push(Outgoing { to: peer_id, update });
+    }
+}
+
+
+pub type SimulatedEvaluator = WeightedVote<ReplaceStore<BinomialPartitioner>, SimulatedRegistry, BinomialPartitioner>;
+
+pub struct SimulatedProtocol {
+    node_id: usize,
+    registry: Arc<SimulatedRegistry>,
+    verifier: Arc<SimulatedVerifier>,
+    partitioner: Arc<BinomialPartitioner>,
+    store: Arc<RwLock<ReplaceStore<BinomialPartitioner>>>,
+    evaluator: Arc<SimulatedEvaluator>,
+    timeouts: Arc<dyn TimeoutStrategy>,
+    sender: Arc<SimulatedSender>,
+}
+
+impl fmt::Debug for SimulatedProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "SimulatedProtocol {{ node_id: {} }}", self.node_id)
+    }
+}
+
+impl Protocol for SimulatedProtocol {
+    type Registry = SimulatedRegistry;
+    type Verifier = SimulatedVerifier;
+    type Store = ReplaceStore<BinomialPartitioner>;
+    type Evaluator = SimulatedEvaluator;
+    type Partitioner = BinomialPartitioner;
+    type Sender = SimulatedSender;
+    type Timeouts = dyn TimeoutStrategy;
+
+    fn registry(&self) -> Arc<Self::Registry> {
+        Arc::clone(&self.registry)
+    }
+
+    fn verifier(&self) -> Arc<Self::Verifier> {
+        Arc::clone(&self.verifier)
+    }
+
+    fn store(&self) -> Arc<RwLock<Self::Store>> {
+        Arc::clone(&self.store)
+    }
+
+    fn evaluator(&self) -> Arc<Self::Evaluator> {
+        Arc::clone(&self.evaluator)
+    }
+
+    fn timeouts(&self) -> Arc<Self::Timeouts> {
+        Arc::clone(&self.timeouts)
+    }
+
+    fn partitioner(&self) -> Arc<Self::Partitioner> {
+        Arc::clone(&self.partitioner)
+    }
+
+    fn sender(&self) -> Arc<Self::Sender> {
+        Arc::clone(&self.sender)
+    }
+
+    fn node_id(&self) -> usize {
+        self.node_id
+    }
+}
+
+
+#[derive(Debug)]
+enum Event {
+    Contribute { node_id: usize, contribution: IndividualSignature },
+    Deliver { to: usize, update: LevelUpdate },
+    Update { node_id: usize },
+    Timeout { node_id: usize },
+}
+
+/// An event in the queue. Events are ordered by time and then by the order they were scheduled
+/// in, which makes the simulation deterministic.
+struct ScheduledEvent {
+    time: Duration,
+    seq: u64,
+    event: Event,
+}
+
+impl PartialEq for ScheduledEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time && self.seq == other.seq
+    }
+}
+
+impl Eq for ScheduledEvent {}
+
+impl PartialOrd for ScheduledEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap, but we want the earliest event first.
+        other.time.cmp(&self.time)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+
+pub struct Simulation {
+    config: SimulationConfig,
+    rng: StdRng,
+
+    /// The aggregation of every node, `None` for silent nodes
+    nodes: Vec<Option<Arc<Aggregation<SimulatedProtocol>>>>,
+
+    /// Byzantine nodes
+    byzantine: BitSet,
+
+    outbox: Arc<Mutex<Vec<Outgoing>>>,
+    queue: BinaryHeap<ScheduledEvent>,
+    next_seq: u64,
+    now: Duration,
+    /// `now` shared with the clock of the aggregations
+    clock_time: Arc<RwLock<Duration>>,
+    runtime: Runtime,
+
+    completion_times: Vec<Option<Duration>>,
+    messages_sent: usize,
+    messages_lost: usize,
+    bytes_sent: usize,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        assert!(config.num_nodes > 0, "Simulation needs at least one node");
+        assert!(config.num_byzantine <= config.num_nodes, "More byzantine nodes than nodes");
+        assert!(config.loss_rate >= 0.0 && config.loss_rate <= 1.0, "Loss rate must be between 0 and 1");
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let key_pairs = (0..config.num_nodes)
+            .map(|_| KeyPair::generate(&mut rng))
+            .collect::<Vec<KeyPair>>();
+        let registry = Arc::new(SimulatedRegistry {
+            public_keys: key_pairs.iter().map(|key_pair| key_pair.public.clone()).collect(),
+        });
+
+        let mut byzantine = BitSet::new();
+        for node_id in index::sample(&mut rng, config.num_nodes, config.num_byzantine).iter() {
+            byzantine.insert(node_id);
+        }
+        let forgers = if config.byzantine_behavior == ByzantineBehavior::Forging {
+            byzantine.clone()
+        } else {
+            BitSet::new()
+        };
+
+        let message_hash = Blake2bHasher::default().digest(b"handel-simulation");
+        let forged_hash = Blake2bHasher::default().digest(b"handel-simulation-forged");
+
+        let outbox = Arc::new(Mutex::new(Vec::new()));
+        let verifier = Arc::new(SimulatedVerifier {
+            message_hash: message_hash.clone(),
+            registry: Arc::clone(&registry),
+            forgers,
+            verify_signatures: config.verify_signatures,
+        });
+
+        // The aggregations measure level completion times with the simulated time.
+        let clock_time = Arc::new(RwLock::new(Duration::default()));
+        let clock: Clock = {
+            let epoch = Instant::now();
+            let clock_time = Arc::clone(&clock_time);
+            Arc::new(move || epoch + *clock_time.read())
+        };
+
+        let mut nodes = Vec::with_capacity(config.num_nodes);
+        let mut contributions = Vec::new();
+        for (node_id, key_pair) in key_pairs.iter().enumerate() {
+            let is_byzantine = byzantine.contains(node_id);
+            if is_byzantine && config.byzantine_behavior == ByzantineBehavior::Silent {
+                nodes.push(None);
+                continue;
+            }
+
+            let partitioner = Arc::new(BinomialPartitioner::new(node_id, config.num_nodes));
+            let store = Arc::new(RwLock::new(ReplaceStore::new(Arc::clone(&partitioner))));
+            let evaluator = Arc::new(WeightedVote::new(
+                Arc::clone(&store),
+                Arc::clone(&registry),
+                Arc::clone(&partitioner),
+                config.threshold,
+            ));
+            let protocol = SimulatedProtocol {
+                node_id,
+                registry: Arc::clone(&registry),
+                verifier: Arc::clone(&verifier),
+                partitioner,
+                store,
+                evaluator,
+                timeouts: Arc::clone(&config.timeouts),
+                sender: Arc::new(SimulatedSender { outbox: Arc::clone(&outbox) }),
+            };
+            nodes.push(Some(Aggregation::new_without_timers(protocol, config.handel.clone(), &mut rng, Arc::clone(&clock))));
+
+            let hash = if is_byzantine { forged_hash.clone() } else { message_hash.clone() };
+            contributions.push(IndividualSignature::new(key_pair.sign_hash(hash), node_id));
+        }
+
+        let completion_times = vec![None; config.num_nodes];
+        let mut simulation = Simulation {
+            config,
+            rng,
+            nodes,
+            byzantine,
+            outbox,
+            queue: BinaryHeap::new(),
+            next_seq: 0,
+            now: Duration::default(),
+            clock_time,
+            runtime: Runtime::new().expect("Failed to create runtime"),
+            completion_times,
+            messages_sent: 0,
+            messages_lost: 0,
+            bytes_sent: 0,
+        };
+
+        // All nodes start at the same time. The timers fire for the first time after one period.
+        for contribution in contributions {
+            let node_id = contribution.signer;
+            simulation.schedule(Duration::default(), Event::Contribute { node_id, contribution });
+            simulation.schedule(simulation.config.handel.update_interval, Event::Update { node_id });
+            let timeout = simulation.nodes[node_id].as_ref()
+                .and_then(|aggregation| aggregation.next_timeout());
+            if let Some(timeout) = timeout {
+                simulation.schedule(timeout, Event::Timeout { node_id });
+            }
+        }
+
+        simulation
+    }
+
+    /// Runs the simulation until all honest nodes have a final signature, no more events are
+    /// scheduled or `max_duration` is reached.
+    pub fn run(mut self) -> SimulationResult {
+        while !self.is_complete() {
+            let ScheduledEvent { time, event, .. } = match self.queue.pop() {
+                Some(scheduled) => scheduled,
+                None => break,
+            };
+            if time > self.config.max_duration {
+                break;
+            }
+            self.now = time;
+            *self.clock_time.write() = time;
+
+            self.process(event);
+            self.dispatch_messages();
+            self.check_completion();
+        }
+
+        let byzantine = &self.byzantine;
+        SimulationResult {
+            completion_times: self.completion_times.iter()
+                .enumerate()
+                .filter(|(node_id, _)| !byzantine.contains(*node_id))
+                .map(|(node_id, time)| (node_id, *time))
+                .collect(),
+            messages_sent: self.messages_sent,
+            messages_lost: self.messages_lost,
+            bytes_sent: self.bytes_sent,
+            duration: self.now,
+        }
+    }
+
+    fn schedule(&mut self, time: Duration, event: Event) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.push(ScheduledEvent { time, seq, event });
+    }
+
+    fn process(&mut self, event: Event) {
+        let node_id = match event {
+            Event::Contribute { node_id, .. } | Event::Update { node_id } | Event::Timeout { node_id } => node_id,
+            Event::Deliver { to, .. } => to,
+        };
+        let aggregation = match self.nodes[node_id] {
+            Some(ref aggregation) => Arc::clone(aggregation),
+            // Messages to silent nodes are dropped.
+            None => return,
+        };
+
+        let update_interval = self.config.handel.update_interval;
+        let mut next_event = None;
+        // The aggregation spawns the processing of updates, so this must run on the runtime.
+        // `run` returns once everything that was spawned is done.
+        self.runtime.block_on(future::lazy(|| {
+            match event {
+                Event::Contribute { contribution, .. } => aggregation.push_contribution(contribution),
+                Event::Deliver { update, .. } => aggregation.push_update(update),
+                Event::Update { node_id } => {
+                    aggregation.send_periodic_updates();
+                    next_event = Some((update_interval, Event::Update { node_id }));
+                },
+                Event::Timeout { node_id } => {
+                    aggregation.next_level_timeout();
+                    next_event = aggregation.next_timeout()
+                        .map(|timeout| (timeout, Event::Timeout { node_id }));
+                },
+            }
+            future::ok::<(), ()>(())
+        })).expect("Simulation event failed");
+        self.runtime.run().expect("Simulation runtime failed");
+
+        if let Some((delay, event)) = next_event {
+            let time = self.now + delay;
+            self.schedule(time, event);
+        }
+    }
+
+    /// Schedules the delivery of all messages that were sent while processing the last event.
+    fn dispatch_messages(&mut self) {
+        let outgoing = self.outbox.lock().drain(..).collect::<Vec<Outgoing>>();
+        for Outgoing { to, update } in outgoing {
+            self.messages_sent += 1;
+            self.bytes_sent += update.serialized_size();
+
+            if self.config.loss_rate > 0.0 && self.rng.gen_bool(self.config.loss_rate) {
+                self.messages_lost += 1;
+                continue;
+            }
+
+            let jitter = self.config.jitter.as_nanos() as u64;
+            let delay = self.config.latency + Duration::from_nanos(self.rng.gen_range(0, jitter + 1));
+            let time = self.now + delay;
+            self.schedule(time, Event::Deliver { to, update });
+        }
+    }
+
+    fn check_completion(&mut self) {
+        for (node_id, node) in self.nodes.iter().enumerate() {
+            if self.completion_times[node_id].is_some() || self.byzantine.contains(node_id) {
+                continue;
+            }
+            if let Some(aggregation) = node {
+                if aggregation.result().is_some() {
+                    self.completion_times[node_id] = Some(self.now);
+                }
+            }
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.completion_times.iter()
+            .enumerate()
+            .all(|(node_id, time)| time.is_some() || self.byzantine.contains(node_id))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeout::AdaptiveTimeout;
+
+    fn config(num_nodes: usize) -> SimulationConfig {
+        SimulationConfig {
+            num_nodes,
+            threshold: SimulationConfig::two_third_threshold(num_nodes),
+            ..SimulationConfig::default()
+        }
+    }
+
+    #[test]
+    fn it_reaches_the_threshold() {
+        let result = Simulation::new(config(8)).run();
+
+        assert_eq!(result.completion_times.len(), 8);
+        assert!(result.is_complete());
+        assert!(result.time_to_threshold().unwrap() <= result.duration);
+        assert!(result.messages_sent > 0);
+        assert_eq!(result.messages_lost, 0);
+    }
+
+    #[test]
+    fn it_is_deterministic() {
+        let config = SimulationConfig {
+            loss_rate: 0.1,
+            ..config(8)
+        };
+
+        let result1 = Simulation::new(config.clone()).run();
+        let result2 = Simulation::new(config).run();
+        assert_eq!(result1, result2);
+    }
+
+    #[test]
+    fn it_tolerates_byzantine_nodes() {
+        for &behavior in &[ByzantineBehavior::Silent, ByzantineBehavior::Forging] {
+            let config = SimulationConfig {
+                num_byzantine: 2,
+                byzantine_behavior: behavior,
+                ..config(8)
+            };
+
+            let result = Simulation::new(config).run();
+            assert_eq!(result.completion_times.len(), 6);
+            assert!(result.is_complete(), "Not complete with {:?} nodes", behavior);
+        }
+    }
+
+    #[test]
+    fn it_learns_level_timeouts() {
+        let timeouts = Arc::new(AdaptiveTimeout::default());
+        let config = SimulationConfig {
+            timeouts: Arc::clone(&timeouts) as Arc<dyn TimeoutStrategy>,
+            ..config(16)
+        };
+
+        let result = Simulation::new(config).run();
+        assert!(result.is_complete());
+        // Level 1 completes once the message of the single peer of that level arrived.
+        let level1 = timeouts.completion_time(1).expect("Level 1 never completed");
+        assert!(level1 >= Duration::from_millis(50) && level1 <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn it_fails_without_enough_honest_nodes() {
+        let config = SimulationConfig {
+            num_byzantine: 3,
+            max_duration: Duration::from_secs(10),
+            ..config(8)
+        };
+
+        let result = Simulation::new(config).run();
+        assert_eq!(result.num_completed(), 0);
+        assert_eq!(result.time_to_threshold(), None);
+    }
+}
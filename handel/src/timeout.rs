@@ -1,22 +1,75 @@
-/// A timeout strategy defines after which period a level timeouts
+/// A timeout strategy defines how long an aggregation waits for a level to complete, before it
+/// starts the next level.
 ///
-/// TODO: This is not used currently
+/// Levels are started one after another, each after the timeout of the previous level. A level
+/// that completes early starts the next level right away, independently of the timeouts.
 
 
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
-use std::ops::Range;
 
-use futures::{Stream, stream};
-use tokio::util::StreamExt;
+use parking_lot::RwLock;
 
 
-pub trait TimeoutStrategy {
-    type Timeouts: Stream<Item=usize, Error=()>;
+pub trait TimeoutStrategy: Debug + Send + Sync {
+    /// Time to wait for level `level` before level `level + 1` is started
+    fn timeout(&self, level: usize) -> Duration;
 
-    fn timeouts(&self, num_levels: usize) -> Self::Timeouts;
+    /// Called when level `level` of an aggregation completed, `elapsed` after the aggregation
+    /// was started.
+    fn level_completed(&self, _level: usize, _elapsed: Duration) {}
+
+    /// Called when level `level` of an aggregation timed out before it completed, `elapsed` after
+    /// the aggregation was started.
+    fn level_timed_out(&self, _level: usize, _elapsed: Duration) {}
+}
+
+
+/// Selects a timeout strategy, e.g. from a configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeoutKind {
+    Linear,
+    Exponential,
+    Adaptive,
+}
+
+impl TimeoutKind {
+    /// Creates the strategy. `timeout` is the period of the linear strategy, the timeout of
+    /// level 0 of the exponential strategy and the initial timeout of the adaptive strategy.
+    pub fn create(self, timeout: Duration) -> Arc<dyn TimeoutStrategy> {
+        match self {
+            TimeoutKind::Linear => Arc::new(LinearTimeout::new(timeout)),
+            TimeoutKind::Exponential => Arc::new(ExponentialTimeout::new(timeout, 2, timeout * 32)),
+            TimeoutKind::Adaptive => Arc::new(AdaptiveTimeout::new(timeout, Duration::from_millis(20), timeout * 10, 1.5, 0.2)),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeoutKind::Linear => "linear",
+            TimeoutKind::Exponential => "exponential",
+            TimeoutKind::Adaptive => "adaptive",
+        }
+    }
 }
 
+impl FromStr for TimeoutKind {
+    type Err = ();
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(TimeoutKind::Linear),
+            "exponential" => Ok(TimeoutKind::Exponential),
+            "adaptive" => Ok(TimeoutKind::Adaptive),
+            _ => Err(()),
+        }
+    }
+}
+
+
+/// Every level has the same timeout
 #[derive(Clone, Debug)]
 pub struct LinearTimeout {
     period: Duration,
@@ -37,18 +90,213 @@ impl Default for LinearTimeout {
 }
 
 impl TimeoutStrategy for LinearTimeout {
-    type Timeouts = Box<dyn Stream<Item=usize, Error=()> + Send>;
-
-    fn timeouts(&self, num_levels: usize) -> Self::Timeouts {
-        debug!("Creating timeout stream: period={:?}, levels={}", self.period, num_levels);
-        Box::new(stream::iter_ok::<Range<usize>, ()>(0..num_levels)
-            .throttle(self.period.clone())
-            .map(|level| {
-                debug!("Timeout for level {}", level);
-                level
-            })
-            .map_err(|e| {
-                warn!("Throttle error: {:?}", e);
-            }))
+    fn timeout(&self, _level: usize) -> Duration {
+        self.period
+    }
+}
+
+
+/// The timeout grows by `factor` with every level, since higher levels contain more peers.
+#[derive(Clone, Debug)]
+pub struct ExponentialTimeout {
+    /// Timeout of level 0
+    base: Duration,
+    factor: u32,
+    /// Upper limit for the timeout of a level
+    max: Duration,
+}
+
+impl ExponentialTimeout {
+    pub fn new(base: Duration, factor: u32, max: Duration) -> Self {
+        ExponentialTimeout {
+            base,
+            factor,
+            max,
+        }
+    }
+}
+
+impl Default for ExponentialTimeout {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(50), 2, Duration::from_secs(2))
+    }
+}
+
+impl TimeoutStrategy for ExponentialTimeout {
+    fn timeout(&self, level: usize) -> Duration {
+        self.factor.checked_pow(level as u32)
+            .and_then(|multiplier| self.base.checked_mul(multiplier))
+            .map(|timeout| timeout.min(self.max))
+            .unwrap_or(self.max)
+    }
+}
+
+
+/// Learns how long levels take to complete from previous aggregations.
+///
+/// For every level, it keeps a moving average of the time between the start of an aggregation
+/// and the completion of that level. The timeout of a level is the average time between the
+/// completion of the previous level and the completion of this level, with a safety margin.
+/// Levels that never completed yet use `initial`.
+///
+/// A single instance should be shared between all aggregations that run over the same network.
+#[derive(Debug)]
+pub struct AdaptiveTimeout {
+    /// Timeout for levels without observations
+    initial: Duration,
+    min: Duration,
+    max: Duration,
+    /// The timeout is the expected time for a level multiplied by this
+    margin: f64,
+    /// Weight of a new observation in the moving average
+    alpha: f64,
+    /// Average time until a level completed
+    completion_times: RwLock<Vec<Option<Duration>>>,
+}
+
+impl AdaptiveTimeout {
+    pub fn new(initial: Duration, min: Duration, max: Duration, margin: f64, alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in (0, 1]");
+        AdaptiveTimeout {
+            initial,
+            min,
+            max,
+            margin,
+            alpha,
+            completion_times: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// The average time until level `level` completed, if it ever completed.
+    pub fn completion_time(&self, level: usize) -> Option<Duration> {
+        self.completion_times.read().get(level).and_then(|time| *time)
+    }
+
+    fn nanos(duration: Duration) -> f64 {
+        duration.as_nanos() as f64
+    }
+
+    /// Adds `elapsed` to the moving average of level `level`. If the level timed out, `elapsed`
+    /// is only a lower bound for its completion time, so it can only increase the average.
+    fn observe(&self, level: usize, elapsed: Duration, timed_out: bool) {
+        let mut completion_times = self.completion_times.write();
+        if completion_times.len() <= level {
+            completion_times.resize(level + 1, None);
+        }
+
+        let average = match completion_times[level] {
+            Some(average) if timed_out && elapsed <= average => return,
+            Some(average) => {
+                let average = Self::nanos(average) * (1.0 - self.alpha) + Self::nanos(elapsed) * self.alpha;
+                Duration::from_nanos(average as u64)
+            },
+            None => elapsed,
+        };
+        trace!("Level {} {} after {:?}, average: {:?}", level, if timed_out { "timed out" } else { "completed" }, elapsed, average);
+        completion_times[level] = Some(average);
+    }
+}
+
+impl Default for AdaptiveTimeout {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_millis(20), Duration::from_secs(5), 1.5, 0.2)
+    }
+}
+
+impl TimeoutStrategy for AdaptiveTimeout {
+    fn timeout(&self, level: usize) -> Duration {
+        let completion_times = self.completion_times.read();
+        let completed = match completion_times.get(level).and_then(|time| *time) {
+            Some(time) => time,
+            None => return self.initial,
+        };
+        let previous = if level > 0 {
+            completion_times.get(level - 1).and_then(|time| *time).unwrap_or_default()
+        } else {
+            Duration::default()
+        };
+
+        let expected = Self::nanos(completed).max(Self::nanos(previous)) - Self::nanos(previous);
+        let timeout = Duration::from_nanos((expected * self.margin) as u64);
+        timeout.max(self.min).min(self.max)
+    }
+
+    fn level_completed(&self, level: usize, elapsed: Duration) {
+        self.observe(level, elapsed, false);
+    }
+
+    fn level_timed_out(&self, level: usize, elapsed: Duration) {
+        self.observe(level, elapsed, true);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_timeout() {
+        let timeouts = ExponentialTimeout::new(Duration::from_millis(50), 2, Duration::from_secs(1));
+
+        assert_eq!(timeouts.timeout(0), Duration::from_millis(50));
+        assert_eq!(timeouts.timeout(1), Duration::from_millis(100));
+        assert_eq!(timeouts.timeout(3), Duration::from_millis(400));
+        assert_eq!(timeouts.timeout(5), Duration::from_secs(1));
+        assert_eq!(timeouts.timeout(100), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_adaptive_timeout() {
+        let timeouts = AdaptiveTimeout::new(Duration::from_millis(500), Duration::from_millis(10), Duration::from_secs(2), 2.0, 0.5);
+
+        // No observations yet
+        assert_eq!(timeouts.timeout(1), Duration::from_millis(500));
+
+        timeouts.level_completed(0, Duration::from_millis(0));
+        timeouts.level_completed(1, Duration::from_millis(100));
+        timeouts.level_completed(2, Duration::from_millis(300));
+
+        assert_eq!(timeouts.timeout(0), Duration::from_millis(10));
+        assert_eq!(timeouts.timeout(1), Duration::from_millis(200));
+        assert_eq!(timeouts.timeout(2), Duration::from_millis(400));
+        assert_eq!(timeouts.timeout(3), Duration::from_millis(500));
+
+        // Moving average
+        timeouts.level_completed(1, Duration::from_millis(200));
+        assert_eq!(timeouts.completion_time(1), Some(Duration::from_millis(150)));
+        assert_eq!(timeouts.timeout(1), Duration::from_millis(300));
+
+        // Slow levels are capped
+        timeouts.level_completed(3, Duration::from_secs(10));
+        assert_eq!(timeouts.timeout(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_adaptive_timeout_with_timed_out_levels() {
+        let timeouts = AdaptiveTimeout::new(Duration::from_millis(500), Duration::from_millis(10), Duration::from_secs(2), 2.0, 0.5);
+
+        // A level that never completed learns from its timeouts.
+        timeouts.level_timed_out(1, Duration::from_millis(500));
+        assert_eq!(timeouts.completion_time(1), Some(Duration::from_millis(500)));
+        timeouts.level_timed_out(1, Duration::from_millis(700));
+        assert_eq!(timeouts.completion_time(1), Some(Duration::from_millis(600)));
+
+        // Timeouts before the average don't say anything about the completion time.
+        timeouts.level_timed_out(1, Duration::from_millis(100));
+        assert_eq!(timeouts.completion_time(1), Some(Duration::from_millis(600)));
+
+        timeouts.level_completed(1, Duration::from_millis(200));
+        assert_eq!(timeouts.completion_time(1), Some(Duration::from_millis(400)));
+    }
+
+    #[test]
+    fn test_timeout_kind() {
+        assert_eq!(TimeoutKind::from_str("adaptive"), Ok(TimeoutKind::Adaptive));
+        assert_eq!(TimeoutKind::from_str(TimeoutKind::Linear.as_str()), Ok(TimeoutKind::Linear));
+        assert!(TimeoutKind::from_str("quadratic").is_err());
+
+        let timeouts = TimeoutKind::Linear.create(Duration::from_millis(300));
+        assert_eq!(timeouts.timeout(5), Duration::from_millis(300));
     }
 }
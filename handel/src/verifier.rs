@@ -1,12 +1,19 @@
+use std::mem;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::{future, Future};
-use futures::future::FutureResult;
+use futures::future::{FutureResult, MapErr};
+use futures::sync::oneshot;
 use futures_cpupool::{CpuPool, CpuFuture};
 use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use rand::{CryptoRng, Rng};
+use rand::thread_rng;
+use tokio::timer::Delay;
 
 use hash::Blake2bHash;
-use bls::bls12_381::AggregatePublicKey;
+use bls::bls12_381::{AggregatePublicKey, AggregateSignature};
 
 use crate::multisig::{Signature, IndividualSignature, MultiSignature};
 use crate::identity::IdentityRegistry;
@@ -73,7 +80,7 @@ impl<I: IdentityRegistry> MultithreadedVerifier<I> {
         Self::new(message_hash, identity_registry, Arc::clone(&SHARED_CPU_POOL))
     }
 
-    fn verify_individual(identity_registry: Arc<I>, message_hash: Blake2bHash, individual: &IndividualSignature) -> VerificationResult {
+    pub(crate) fn verify_individual(identity_registry: Arc<I>, message_hash: Blake2bHash, individual: &IndividualSignature) -> VerificationResult {
         if let Some(public_key) = identity_registry.public_key(individual.signer) {
             if public_key.verify_hash(message_hash, &individual.signature) {
                 VerificationResult::Ok
@@ -87,7 +94,7 @@ impl<I: IdentityRegistry> MultithreadedVerifier<I> {
         }
     }
 
-    fn verify_multisig(identity_registry: Arc<I>, message_hash: Blake2bHash, multisig: &MultiSignature) -> VerificationResult {
+    pub(crate) fn verify_multisig(identity_registry: Arc<I>, message_hash: Blake2bHash, multisig: &MultiSignature) -> VerificationResult {
         let mut aggregated_public_key = AggregatePublicKey::new();
         for signer in multisig.signers.iter() {
             if let Some(public_key) = identity_registry.public_key(signer) {
@@ -129,3 +136,255 @@ impl<I: IdentityRegistry + Sync + Send + 'static> Verifier for MultithreadedVeri
         })
     }
 }
+
+
+
+/// A signature that waits for its batch to be verified
+struct PendingSignature {
+    signature: Signature,
+    result_tx: oneshot::Sender<VerificationResult>,
+}
+
+/// A signature of a batch, with the public keys of its signers already aggregated
+struct BatchItem {
+    public_key: AggregatePublicKey,
+    signature: AggregateSignature,
+    result_tx: oneshot::Sender<VerificationResult>,
+}
+
+impl BatchItem {
+    fn resolve(self, result: VerificationResult) {
+        // The receiver might be gone already, if the aggregation was dropped.
+        self.result_tx.send(result).unwrap_or(());
+    }
+}
+
+/// Verifier that collects signatures for a short time and then verifies them together.
+///
+/// All signatures of a batch are checked at once with a randomized batch verification, which
+/// needs only two pairings. If the batch is invalid, it is split in half until the invalid
+/// signatures are found.
+pub struct BatchVerifier<I: IdentityRegistry> {
+    message_hash: Blake2bHash,
+    identity_registry: Arc<I>,
+    cpu_pool: Arc<CpuPool>,
+
+    /// How long signatures are collected before the batch is verified
+    batch_window: Duration,
+
+    /// Number of signatures at which the batch is verified right away
+    max_batch_size: usize,
+
+    /// Signatures of the current batch
+    pending: Arc<Mutex<Vec<PendingSignature>>>,
+}
+
+impl<I: IdentityRegistry + Sync + Send + 'static> BatchVerifier<I> {
+    pub const DEFAULT_BATCH_WINDOW: Duration = Duration::from_millis(10);
+    pub const DEFAULT_MAX_BATCH_SIZE: usize = 64;
+
+    pub fn new(message_hash: Blake2bHash, identity_registry: Arc<I>, cpu_pool: Arc<CpuPool>, batch_window: Duration, max_batch_size: usize) -> Self {
+        Self {
+            message_hash,
+            identity_registry,
+            cpu_pool,
+            batch_window,
+            max_batch_size,
+            pending: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn shared(message_hash: Blake2bHash, identity_registry: Arc<I>) -> Self {
+        Self::new(message_hash, identity_registry, Arc::clone(&SHARED_CPU_POOL), Self::DEFAULT_BATCH_WINDOW, Self::DEFAULT_MAX_BATCH_SIZE)
+    }
+
+    /// Takes the current batch and verifies it on the CPU pool.
+    fn flush(pending: &Mutex<Vec<PendingSignature>>, cpu_pool: &CpuPool, identity_registry: Arc<I>, message_hash: Blake2bHash) {
+        let batch = mem::replace(&mut *pending.lock(), Vec::new());
+        if batch.is_empty() {
+            return;
+        }
+
+        cpu_pool.spawn_fn(move || {
+            Self::verify_batch(&*identity_registry, message_hash, batch);
+            Ok::<(), ()>(())
+        }).forget();
+    }
+
+    fn verify_batch(identity_registry: &I, message_hash: Blake2bHash, batch: Vec<PendingSignature>) {
+        trace!("Verifying batch of {} signatures", batch.len());
+
+        let mut items = Vec::with_capacity(batch.len());
+        for PendingSignature { signature, result_tx } in batch {
+            match Self::aggregate(identity_registry, &signature) {
+                Ok((public_key, signature)) => items.push(BatchItem { public_key, signature, result_tx }),
+                Err(result) => result_tx.send(result).unwrap_or(()),
+            }
+        }
+
+        Self::bisect(&message_hash, items, false, &mut thread_rng());
+    }
+
+    /// Aggregates the public keys of the signers of `signature`.
+    fn aggregate(identity_registry: &I, signature: &Signature) -> Result<(AggregatePublicKey, AggregateSignature), VerificationResult> {
+        match signature {
+            Signature::Individual(individual) => {
+                let public_key = identity_registry.public_key(individual.signer)
+                    .ok_or(VerificationResult::UnknownSigner { signer: individual.signer })?;
+                Ok((AggregatePublicKey::from_public_keys(&[public_key]), AggregateSignature::from_signatures(&[individual.signature.clone()])))
+            },
+            Signature::Multi(multisig) => {
+                let mut aggregated_public_key = AggregatePublicKey::new();
+                for signer in multisig.signers.iter() {
+                    let public_key = identity_registry.public_key(signer)
+                        .ok_or(VerificationResult::UnknownSigner { signer })?;
+                    aggregated_public_key.aggregate(&public_key);
+                }
+                Ok((aggregated_public_key, multisig.signature.clone()))
+            },
+        }
+    }
+
+    /// Verifies `batch` and resolves all its signatures. If the batch is invalid, both halves are
+    /// verified separately. If `known_invalid` is set, the batch isn't checked as a whole.
+    ///
+    /// Returns whether the whole batch was valid.
+    fn bisect<R: Rng + CryptoRng>(message_hash: &Blake2bHash, mut batch: Vec<BatchItem>, known_invalid: bool, rng: &mut R) -> bool {
+        if batch.is_empty() {
+            return true;
+        }
+
+        if !known_invalid {
+            let signatures = batch.iter()
+                .map(|item| (item.public_key.clone(), item.signature.clone()))
+                .collect::<Vec<(AggregatePublicKey, AggregateSignature)>>();
+            if bls::verify_batch_hash(message_hash.clone(), &signatures, rng) {
+                for item in batch {
+                    item.resolve(VerificationResult::Ok);
+                }
+                return true;
+            }
+        }
+
+        if batch.len() == 1 {
+            batch.pop().unwrap().resolve(VerificationResult::Forged);
+            return false;
+        }
+
+        let second_half = batch.split_off(batch.len() / 2);
+        // If the first half is valid, the invalid signatures must be in the second half.
+        let first_valid = Self::bisect(message_hash, batch, false, rng);
+        Self::bisect(message_hash, second_half, first_valid, rng);
+        false
+    }
+}
+
+fn canceled(_: oneshot::Canceled) {}
+
+impl<I: IdentityRegistry + Sync + Send + 'static> Verifier for BatchVerifier<I> {
+    type Output = MapErr<oneshot::Receiver<VerificationResult>, fn(oneshot::Canceled)>;
+
+    fn verify(&self, signature: &Signature) -> Self::Output {
+        let (result_tx, result_rx) = oneshot::channel();
+
+        let mut pending = self.pending.lock();
+        pending.push(PendingSignature {
+            signature: signature.clone(),
+            result_tx,
+        });
+        let batch_size = pending.len();
+        drop(pending);
+
+        if batch_size >= self.max_batch_size {
+            Self::flush(&self.pending, &self.cpu_pool, Arc::clone(&self.identity_registry), self.message_hash.clone());
+        }
+        else if batch_size == 1 {
+            // First signature of a new batch: verify the batch once the window is over.
+            let pending = Arc::clone(&self.pending);
+            let cpu_pool = Arc::clone(&self.cpu_pool);
+            let identity_registry = Arc::clone(&self.identity_registry);
+            let message_hash = self.message_hash.clone();
+            tokio::spawn(Delay::new(Instant::now() + self.batch_window)
+                .then(move |_| {
+                    Self::flush(&pending, &cpu_pool, identity_registry, message_hash);
+                    Ok::<(), ()>(())
+                }));
+        }
+
+        result_rx.map_err(canceled as fn(oneshot::Canceled))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use tokio::runtime::current_thread::Runtime;
+
+    use bls::SecureGenerate;
+    use bls::bls12_381::{KeyPair, PublicKey};
+    use collections::bitset::BitSet;
+    use hash::{Blake2bHasher, Hasher};
+
+    use crate::multisig::{IndividualSignature, MultiSignature};
+    use super::*;
+
+    struct TestRegistry {
+        public_keys: Vec<PublicKey>,
+    }
+
+    impl IdentityRegistry for TestRegistry {
+        fn public_key(&self, id: usize) -> Option<PublicKey> {
+            self.public_keys.get(id).cloned()
+        }
+    }
+
+    #[test]
+    fn it_batch_verifies_signatures() {
+        let message_hash = Blake2bHasher::default().digest(b"message");
+        let key_pairs = (0..8)
+            .map(|_| KeyPair::generate(&mut thread_rng()))
+            .collect::<Vec<KeyPair>>();
+        let registry = Arc::new(TestRegistry {
+            public_keys: key_pairs.iter().map(|key_pair| key_pair.public.clone()).collect(),
+        });
+
+        let individual = |signer: usize, hash: &Blake2bHash| {
+            IndividualSignature::new(key_pairs[signer].sign_hash(hash.clone()), signer)
+        };
+        let mut multisig = individual(4, &message_hash).as_multisig();
+        for signer in 5..8 {
+            multisig.add_individual(&individual(signer, &message_hash)).unwrap();
+        }
+        let mut signers = BitSet::new();
+        signers.insert(1);
+        signers.insert(9);
+
+        let forged_hash = Blake2bHasher::default().digest(b"forged");
+        let signatures = vec![
+            Signature::Individual(individual(0, &message_hash)),
+            Signature::Individual(individual(1, &forged_hash)),
+            Signature::Multi(multisig),
+            Signature::Individual(individual(2, &message_hash)),
+            Signature::Multi(MultiSignature::new(AggregateSignature::new(), signers)),
+            Signature::Individual(individual(3, &forged_hash)),
+        ];
+
+        // Flush the batch after 4 signatures, so that both triggers are tested.
+        let verifier = BatchVerifier::new(message_hash, registry, Arc::new(CpuPool::new(1)), Duration::from_millis(10), 4);
+        let results = Runtime::new().unwrap().block_on(future::lazy(|| {
+            future::join_all(signatures.iter()
+                .map(|signature| verifier.verify(signature))
+                .collect::<Vec<_>>())
+        })).unwrap();
+
+        assert_eq!(results, vec![
+            VerificationResult::Ok,
+            VerificationResult::Forged,
+            VerificationResult::Ok,
+            VerificationResult::Ok,
+            VerificationResult::UnknownSigner { signer: 9 },
+            VerificationResult::Forged,
+        ]);
+    }
+}
@@ -11,6 +11,8 @@ use curve25519_dalek::traits::Identity;
 use ed25519_dalek::ExpandedSecretKey;
 use sha2::{self, Digest};
 
+use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
+
 use utils::key_rng::{CryptoRng, Rng, SecureGenerate};
 
 use crate::{KeyPair, PublicKey, Signature};
@@ -67,6 +69,25 @@ impl<'a> From<&'a [u8; Commitment::SIZE]> for Commitment {
     }
 }
 
+impl Deserialize for Commitment {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let mut buf = [0u8; Commitment::SIZE];
+        reader.read_exact(&mut buf)?;
+        Commitment::from_bytes(buf).ok_or(SerializingError::InvalidValue)
+    }
+}
+
+impl Serialize for Commitment {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(self.serialized_size())
+    }
+
+    fn serialized_size(&self) -> usize {
+        Self::SIZE
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct InvalidScalarError;
 
@@ -163,6 +184,25 @@ impl<'a> From<&'a [u8; PartialSignature::SIZE]> for PartialSignature {
     }
 }
 
+impl Deserialize for PartialSignature {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let mut buf = [0u8; PartialSignature::SIZE];
+        reader.read_exact(&mut buf)?;
+        Ok(PartialSignature::from(buf))
+    }
+}
+
+impl Serialize for PartialSignature {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        writer.write_all(self.as_bytes())?;
+        Ok(self.serialized_size())
+    }
+
+    fn serialized_size(&self) -> usize {
+        Self::SIZE
+    }
+}
+
 impl KeyPair {
     pub fn partial_sign(&self, public_keys: &[PublicKey], secret: &RandomSecret, commitments: &[Commitment], data: &[u8]) -> (PartialSignature, PublicKey, Commitment) {
         if public_keys.len() != commitments.len() {
@@ -218,6 +258,14 @@ impl KeyPair {
 }
 
 impl PublicKey {
+    /// Aggregates `public_keys` into the public key that verifies their combined signature, i.e.
+    /// the key returned by `KeyPair::partial_sign` for the same keys. The order of the keys matters.
+    pub fn aggregate(public_keys: &[PublicKey]) -> PublicKey {
+        let public_keys_hash = hash_public_keys(public_keys);
+        let delinearized_pk_sum: EdwardsPoint = public_keys.iter().map(|public_key| { public_key.delinearize(&public_keys_hash) }).sum();
+        PublicKey::from(delinearized_pk_sum.compress().to_bytes())
+    }
+
     fn to_edwards_point(&self) -> Option<EdwardsPoint> {
         let mut bits: [u8; PublicKey::SIZE] = [0u8; PublicKey::SIZE];
         bits.copy_from_slice(&self.as_bytes()[..PublicKey::SIZE]);
@@ -1,4 +1,5 @@
 use std::convert::TryFrom;
+use std::path::Path;
 use std::sync::{Arc, Weak};
 
 #[cfg(feature="validator")]
@@ -6,15 +7,24 @@ use validator::validator::Validator;
 use consensus::{
     Consensus as AbstractConsensus,
     AlbatrossConsensusProtocol,
+    Error as ConsensusError,
 };
 use database::Environment;
 use network::{NetworkConfig, Network as GenericNetwork};
 use mempool::Mempool as GenericMempool;
 use network_primitives::services::ServiceFlags;
+use network_primitives::time::NetworkTime;
 use blockchain::Blockchain;
+use blockchain::chain_store::ChainStore;
+use blockchain::snapshot::AccountsSnapshot;
+use primitives::networks::NetworkId;
+#[cfg(feature="rpc-server")]
+use rpc_server::handlers::UnlockedWalletManager;
+#[cfg(feature="rpc-server")]
+use parking_lot::RwLock;
 
 use crate::error::Error;
-use crate::config::config::{ClientConfig, ProtocolConfig};
+use crate::config::config::{ClientConfig, ConsensusConfig, ProtocolConfig};
 
 
 /// Alias for the Consensus specialized over Albatross
@@ -43,7 +53,11 @@ pub(crate) struct ClientInner {
 
     /// The block production logic. This is optional and can also be fully disabled at compile-time
     #[cfg(feature="validator")]
-    validator: Option<Arc<Validator>>
+    validator: Option<Arc<Validator>>,
+
+    /// The wallet accounts unlocked through the RPC servers. All RPC servers share them.
+    #[cfg(feature="rpc-server")]
+    unlocked_wallets: Arc<RwLock<UnlockedWalletManager>>,
 }
 
 
@@ -105,6 +119,32 @@ impl TryFrom<ClientConfig> for ClientInner {
             }
         }
 
+        // Light clients only provide light services and need full nodes to request accounts from.
+        if config.consensus == ConsensusConfig::Light {
+            #[cfg(feature="validator")]
+            {
+                if config.validator.is_some() {
+                    return Err(Error::config_error("Validators can't run with light consensus"));
+                }
+            }
+
+            if config.prune_epochs.is_some() {
+                return Err(Error::config_error("Pruning is only available with full consensus"));
+            }
+
+            let mut services = network_config.services().clone();
+            services.provided = ServiceFlags::LIGHT;
+            services.accepted = ServiceFlags::FULL;
+            network_config.set_services(services);
+        }
+
+        // Tell peers that we can't serve the transactions of pruned epochs.
+        if config.prune_epochs.is_some() {
+            let mut services = network_config.services().clone();
+            services.provided |= ServiceFlags::PRUNED;
+            network_config.set_services(services);
+        }
+
         // Open database
         let environment = config.storage.database(config.network, config.consensus, config.database)?;
 
@@ -112,16 +152,38 @@ impl TryFrom<ClientConfig> for ClientInner {
         if !config.network.is_albatross() {
             return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
         }
-        let consensus = Consensus::new(
-            environment.clone(),
-            config.network,
-            network_config,
-            config.mempool,
-        )?;
+        let consensus = if config.consensus == ConsensusConfig::Light {
+            let blockchain = Blockchain::new_light(environment.clone(), config.network)
+                .map_err(ConsensusError::from)?;
+            Consensus::with_blockchain(
+                environment.clone(),
+                Arc::new(blockchain),
+                Arc::new(NetworkTime::new()),
+                config.network,
+                network_config,
+                config.mempool,
+            )?
+        } else {
+            let mut blockchain = match config.snapshot {
+                Some(ref snapshot_path) => Self::bootstrap_blockchain(environment.clone(), config.network, snapshot_path)?,
+                None => Blockchain::new(environment.clone(), config.network).map_err(ConsensusError::from)?,
+            };
+            if let Some(keep_epochs) = config.prune_epochs {
+                blockchain.set_pruning(keep_epochs);
+            }
+            Consensus::with_blockchain(
+                environment.clone(),
+                Arc::new(blockchain),
+                Arc::new(NetworkTime::new()),
+                config.network,
+                network_config,
+                config.mempool,
+            )?
+        };
 
         #[cfg(feature="validator")]
-        let validator = config.validator.map(|_config| {
-            Validator::new(Arc::clone(&consensus), validator_key, validator_wallet_key)
+        let validator = config.validator.map(|config| {
+            Validator::new(Arc::clone(&consensus), validator_key, validator_wallet_key, config.handel)
         }).transpose()?;
 
         Ok(ClientInner {
@@ -129,10 +191,27 @@ impl TryFrom<ClientConfig> for ClientInner {
             consensus,
             #[cfg(feature="validator")]
             validator,
+            #[cfg(feature="rpc-server")]
+            unlocked_wallets: Arc::new(RwLock::new(UnlockedWalletManager::new())),
         })
     }
 }
 
+impl ClientInner {
+    /// Bootstraps the blockchain from the accounts snapshot at `path`. If the database already
+    /// contains a chain, the snapshot is ignored and the chain is loaded as usual.
+    fn bootstrap_blockchain(environment: Environment, network_id: NetworkId, path: &Path) -> Result<Blockchain, Error> {
+        if ChainStore::new(environment.clone()).get_head(None).is_some() {
+            debug!("Consensus database is not empty, ignoring snapshot {}", path.display());
+            return Ok(Blockchain::new(environment, network_id).map_err(ConsensusError::from)?);
+        }
+
+        info!("Bootstrapping blockchain from snapshot {}", path.display());
+        let snapshot = AccountsSnapshot::read_from_file(path)?;
+        Ok(Blockchain::from_snapshot(environment, network_id, snapshot)?)
+    }
+}
+
 
 
 /// Entry point for the Nimiq client API.
@@ -162,6 +241,7 @@ impl Client {
     /// Initializes the Nimiq network stack.
     pub fn initialize(&self) -> Result<(), Error> {
         self.inner.consensus.network.initialize()?;
+        self.inner.consensus.initialize();
         Ok(())
     }
 
@@ -202,6 +282,12 @@ impl Client {
         self.inner.environment.clone()
     }
 
+    /// Returns the wallet accounts unlocked through the RPC servers.
+    #[cfg(feature="rpc-server")]
+    pub fn unlocked_wallets(&self) -> Arc<RwLock<UnlockedWalletManager>> {
+        Arc::clone(&self.inner.unlocked_wallets)
+    }
+
     /// Short-cut to get weak reference to the inner client object.
     /// TODO: We'll use this to register listeners
     pub(crate) fn inner_weak(&self) -> Weak<ClientInner> {
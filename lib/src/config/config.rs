@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::net::IpAddr;
 use std::str::FromStr;
+use std::time::Duration;
 
 use derive_builder::Builder;
 use enum_display_derive::Display;
@@ -38,7 +39,8 @@ use crate::error::Error;
 ///
 /// # Notes
 ///
-/// core-rs / Albatross is currently only supporting full consensus.
+/// core-rs / Albatross is currently only supporting full consensus and a light consensus that
+/// only follows the macro block chain.
 ///
 /// # ToDo
 ///
@@ -48,6 +50,7 @@ use crate::error::Error;
 pub enum ConsensusConfig {
     Full,
     MacroSync,
+    Light,
 }
 
 impl Default for ConsensusConfig {
@@ -123,6 +126,9 @@ pub struct ValidatorConfig {
     // TODO
     /// Validator wallet key
     validator_wallet_key: Option<keys::KeyPair>,
+
+    /// Configuration of the Handel signature aggregation
+    pub handel: handel::config::Config,
 }
 
 #[cfg(feature="validator")]
@@ -184,8 +190,8 @@ pub struct DatabaseConfig {
     #[builder(default="50 * 1024 * 1024")]
     size: usize,
 
-    /// Max number of DBs. Recommended: 10
-    #[builder(default="10")]
+    /// Max number of DBs. Recommended: 20
+    #[builder(default="20")]
     max_dbs: u32,
 
     /// Additional LMDB flags
@@ -197,7 +203,7 @@ impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             size: 50 * 1024 * 1024,
-            max_dbs: 10,
+            max_dbs: 20,
             flags: LmdbFlags::NOMETASYNC,
         }
     }
@@ -423,7 +429,17 @@ pub struct WsRpcServerConfig {
     #[builder(default="consts::WS_RPC_DEFAULT_PORT")]
     pub port: u16,
 
-    /// If specified, require HTTP basic auth with these credentials
+    /// Origins of web pages that may connect to the server. Connections from browsers with any
+    /// other origin are rejected.
+    #[builder(setter(strip_option))]
+    pub corsdomain: Option<Vec<String>>,
+
+    /// If specified, only allow these RPC methods
+    ///
+    #[builder(setter(strip_option))]
+    pub allowed_methods: Option<Vec<String>>,
+
+    /// If specified, clients have to call `authorize` with these credentials
     #[builder(setter(strip_option))]
     pub credentials: Option<Credentials>,
 }
@@ -507,6 +523,19 @@ pub struct ClientConfig {
     #[builder(default)]
     pub database: DatabaseConfig,
 
+    /// Accounts snapshot to bootstrap the blockchain from, instead of syncing from genesis.
+    /// It is only used if the consensus database is empty.
+    ///
+    #[builder(default)]
+    pub snapshot: Option<PathBuf>,
+
+    /// If set, micro block bodies of all but this many finalized epochs are deleted.
+    ///
+    /// Default is to keep all epochs.
+    ///
+    #[builder(default)]
+    pub prune_epochs: Option<u32>,
+
     /// The mempool filter rules
     ///
     #[builder(default, setter(custom))]
@@ -600,6 +629,13 @@ impl ClientConfigBuilder {
         self.consensus(ConsensusConfig::MacroSync)
     }
 
+    /// Sets the client to only follow the macro block chain. It doesn't keep an accounts tree and
+    /// requests accounts from peers instead.
+    ///
+    pub fn light(&mut self) -> &mut Self {
+        self.consensus(ConsensusConfig::Light)
+    }
+
     /// Sets the *Dumb* protocol - i.e. no incoming connections will be accepted.
     ///
     /// # Notes
@@ -678,7 +714,17 @@ impl ClientConfigBuilder {
 
     /// Sets the mempool filter rules
     pub fn mempool(&mut self, filter_rules: MempoolRules, filter_limit: usize) -> &mut Self {
-        self.mempool = Some(MempoolConfig { filter_rules, filter_limit });
+        let mempool = self.mempool.get_or_insert_with(MempoolConfig::default);
+        mempool.filter_rules = filter_rules;
+        mempool.filter_limit = filter_limit;
+        self
+    }
+
+    /// Persists pending transactions of the mempool every `interval` and on shutdown
+    pub fn persist_mempool(&mut self, interval: Duration) -> &mut Self {
+        let mempool = self.mempool.get_or_insert_with(MempoolConfig::default);
+        mempool.persist = true;
+        mempool.persist_interval = interval;
         self
     }
 
@@ -699,12 +745,13 @@ impl ClientConfigBuilder {
         self.seed(Seed::new_list(SeedList::new(url, public_key_opt)))
     }
 
-    /// Sets the validator config. Since there is no configuration for validators (except key file)
-    /// yet, this will just enable the validator.
+    /// Sets the validator config. This enables the validator with the default Handel settings and
+    /// without a wallet key.
     #[cfg(feature="validator")]
     pub fn validator(&mut self) -> &mut Self {
         self.validator = Some(Some(ValidatorConfig {
-            validator_wallet_key: None
+            validator_wallet_key: None,
+            handel: handel::config::Config::default(),
         }));
         self
     }
@@ -747,6 +794,12 @@ impl ClientConfigBuilder {
         // Configure network
         self.network(config_file.consensus.network);
 
+        // Configure snapshot to bootstrap from
+        self.snapshot(config_file.consensus.snapshot.as_ref().map(PathBuf::from));
+
+        // Configure pruning
+        self.prune_epochs(config_file.consensus.prune_epochs);
+
         // Configure storage config.
         let mut file_storage = FileStorageConfig::default();
         config_file.database.path.as_ref()
@@ -767,6 +820,12 @@ impl ClientConfigBuilder {
         // Configure database
         self.database(config_file.database.clone());
 
+        // Configure mempool
+        config_file.mempool.as_ref()
+            .map(|mempool| {
+                self.mempool = Some(mempool.clone().into());
+            });
+
         // Configure reverse proxy config
         config_file.reverse_proxy.as_ref()
             .map(|reverse_proxy| {
@@ -827,6 +886,8 @@ impl ClientConfigBuilder {
                 self.ws_rpc_server = Some(Some(WsRpcServerConfig {
                     bind_to,
                     port: ws_rpc_config.port.unwrap_or(consts::WS_RPC_DEFAULT_PORT),
+                    corsdomain: Some(ws_rpc_config.corsdomain.clone()),
+                    allowed_methods: Some(ws_rpc_config.methods.clone()),
                     credentials,
                 }));
             }
@@ -858,10 +919,8 @@ impl ClientConfigBuilder {
 
         // Configure validator
         #[cfg(feature="validator")] {
-            if config_file.validator.is_some() {
-                let wallet_private_key = config_file.validator.as_ref()
-                    .map(|settings| settings.clone().wallet_private_key)
-                    .expect("Failed to load validator settings");
+            if let Some(settings) = config_file.validator.as_ref() {
+                let wallet_private_key = settings.wallet_private_key.clone();
 
                 let wallet_key = wallet_private_key.map(|config_private_key| {
                     Some(keys::KeyPair::from(
@@ -870,8 +929,18 @@ impl ClientConfigBuilder {
                     ))
                 }).unwrap_or_else(|| None);
 
+                let mut handel = handel::config::Config::default();
+                if let Some(timeout) = settings.handel_timeout {
+                    handel.timeout = Duration::from_millis(timeout);
+                }
+                if let Some(strategy) = &settings.handel_timeout_strategy {
+                    handel.timeout_strategy = strategy.parse()
+                        .map_err(|_| Error::config_error(format!("Invalid Handel timeout strategy: {}", strategy)))?;
+                }
+
                 self.validator = Some(Some(ValidatorConfig {
-                    validator_wallet_key: wallet_key
+                    validator_wallet_key: wallet_key,
+                    handel,
                 }));
             }
         }
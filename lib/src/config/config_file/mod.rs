@@ -5,6 +5,7 @@ use std::fs::read_to_string;
 use std::path::Path;
 use std::str::FromStr;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 use log::LevelFilter;
 use url::Url;
@@ -250,6 +251,8 @@ pub struct ConsensusSettings {
     pub consensus_type: ConsensusType,
     #[serde(default)]
     pub network: Network,
+    pub snapshot: Option<String>,
+    pub prune_epochs: Option<u32>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -257,6 +260,7 @@ pub struct ConsensusSettings {
 pub enum ConsensusType {
     Full,
     MacroSync,
+    Light,
 }
 
 impl Default for ConsensusType {
@@ -276,6 +280,7 @@ impl FromStr for ConsensusType {
         Ok(match s.to_lowercase().as_str() {
             "full" => Self::Full,
             "macro-sync" => Self::MacroSync,
+            "light" => Self::Light,
             _ => return Err(ConsensusTypeParseError(s.to_string()))
         })
     }
@@ -286,6 +291,7 @@ impl From<ConsensusType> for config::ConsensusConfig {
         match consensus_type {
             ConsensusType::Full => Self::Full,
             ConsensusType::MacroSync => Self::MacroSync,
+            ConsensusType::Light => Self::Light,
         }
     }
 }
@@ -360,6 +366,10 @@ pub struct WsRpcServerSettings {
     #[serde(default)]
     pub bind: Option<address::NetAddress>,
     pub port: Option<u16>,
+    #[serde(default)]
+    pub corsdomain: Vec<String>,
+    #[serde(default)]
+    pub methods: Vec<String>,
     pub username: Option<String>,
     pub password: Option<String>,
 }
@@ -447,7 +457,7 @@ impl Default for DatabaseSettings {
         DatabaseSettings {
             path: None,
             size: Some(1024 * 1024 * 50),
-            max_dbs: Some(10),
+            max_dbs: Some(20),
             no_lmdb_sync: None,
         }
     }
@@ -458,6 +468,9 @@ impl Default for DatabaseSettings {
 pub struct MempoolSettings {
     pub filter: Option<MempoolFilterSettings>,
     pub blacklist_limit: Option<usize>,
+    pub persist: Option<bool>,
+    /// Interval in seconds in which pending transactions are persisted
+    pub persist_interval: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -506,6 +519,11 @@ impl From<MempoolSettings> for MempoolConfig {
             filter_rules: mempool.filter
                 .map(MempoolRules::from)
                 .unwrap_or_default(),
+            persist: mempool.persist
+                .unwrap_or(false),
+            persist_interval: mempool.persist_interval
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| MempoolConfig::default().persist_interval),
         }
     }
 }
@@ -535,4 +553,6 @@ impl From<MempoolFilterSettings> for MempoolRules {
 pub struct ValidatorSettings {
     pub key_file: Option<String>,
     pub wallet_private_key: Option<String>,
+    pub handel_timeout: Option<u64>,
+    pub handel_timeout_strategy: Option<String>,
 }
@@ -9,6 +9,7 @@ use database::volatile::VolatileDatabaseError;
 use network::error::Error as NetworkError;
 use utils::key_store::Error as KeyStoreError;
 use consensus::Error as ConsensusError;
+use blockchain::snapshot::SnapshotError;
 #[cfg(feature="validator")]
 use validator::error::Error as ValidatorError;
 #[cfg(feature="rpc-server")]
@@ -35,6 +36,9 @@ pub enum Error {
     #[fail(display = "Consensus error: {}", _0)]
     Consensus(#[cause] ConsensusError),
 
+    #[fail(display = "Snapshot error: {}", _0)]
+    Snapshot(#[cause] SnapshotError),
+
     #[fail(display = "Config file parsing error: {}", _0)]
     Toml(#[cause] TomlError),
 
@@ -101,6 +105,12 @@ impl From<ConsensusError> for Error {
     }
 }
 
+impl From<SnapshotError> for Error {
+    fn from(e: SnapshotError) -> Self {
+        Self::Snapshot(e)
+    }
+}
+
 impl From<TomlError> for Error {
     fn from(e: TomlError) -> Self {
         Self::Toml(e)
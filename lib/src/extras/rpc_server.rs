@@ -2,7 +2,7 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use std::iter::FromIterator;
 
-use rpc_server::{RpcServer, JsonRpcConfig};
+use rpc_server::{Handler, RpcServer, JsonRpcConfig};
 use rpc_server::handlers::*;
 
 use crate::client::Client;
@@ -40,17 +40,25 @@ pub fn initialize_rpc_server(client: &Client, config: RpcServerConfig) -> Result
 
     // Initialize RPC server
     let rpc_server = RpcServer::new(ip, config.port, json_rpc_config)?;
-    let handler = Arc::clone(&rpc_server.handler);
+    install_rpc_modules(client, &rpc_server.handler);
 
-    // Install RPC modules
+    Ok(rpc_server)
+}
+
+/// Installs all RPC modules of the client into the handler. Unlocked wallet accounts are shared
+/// between all handlers.
+pub fn install_rpc_modules(client: &Client, handler: &Handler) {
     #[cfg(feature="validator")] {
         if let Some(validator) = client.validator() {
-            let block_production_handler = BlockProductionAlbatrossHandler::new(validator);
+            let block_production_handler = BlockProductionAlbatrossHandler::new(Arc::clone(&validator));
             handler.add_module(block_production_handler);
+
+            let validator_handler = ValidatorAlbatrossHandler::new(validator);
+            handler.add_module(validator_handler);
         }
     }
 
-    let blockchain_handler = BlockchainAlbatrossHandler::new(client.blockchain());
+    let blockchain_handler = BlockchainAlbatrossHandler::new(client.consensus());
     handler.add_module(blockchain_handler);
 
     let consensus_handler = ConsensusHandler::new(client.consensus());
@@ -59,12 +67,10 @@ pub fn initialize_rpc_server(client: &Client, config: RpcServerConfig) -> Result
     let network_handler = NetworkHandler::new(&client.consensus());
     handler.add_module(network_handler);
 
-    let wallet_handler = WalletHandler::new(client.environment());
-    let wallet_manager = Arc::clone(&wallet_handler.unlocked_wallets);
+    let accounts_in_use = BlockchainAlbatrossHandler::accounts_in_use(client.consensus());
+    let wallet_handler = WalletHandler::new(client.environment(), client.unlocked_wallets(), accounts_in_use);
     handler.add_module(wallet_handler);
 
-    let mempool_handler = MempoolAlbatrossHandler::new(client.mempool(), client.validator(), Some(wallet_manager));
+    let mempool_handler = MempoolAlbatrossHandler::new(client.consensus(), client.validator(), Some(client.unlocked_wallets()));
     handler.add_module(mempool_handler);
-
-    Ok(rpc_server)
 }
@@ -1,17 +1,48 @@
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use rpc_server::{Handler, JsonRpcConfig};
 use ws_rpc_server::WsRpcServer;
 
 use crate::error::Error;
 use crate::client::Client;
 use crate::config::config::WsRpcServerConfig;
 use crate::config::consts::default_bind;
+use crate::extras::rpc_server::install_rpc_modules;
 
 pub fn initialize_ws_rcp_server(client: &Client, config: WsRpcServerConfig) -> Result<WsRpcServer, Error> {
     let ip = config.bind_to.unwrap_or_else(default_bind);
 
     info!("Initializing websocket RPC server: {}:{}", ip, config.port);
 
-    let server = WsRpcServer::new(ip, config.port)?;
+    // Configure RPC handler
+    let (username, password) = if let Some(credentials) = config.credentials {
+        (Some(credentials.username), Some(credentials.password))
+    }
+    else {
+        warn!("No password set for websocket RPC server!");
+        (None, None)
+    };
+
+    let methods = config.allowed_methods
+        .map(|methods| HashSet::from_iter(methods))
+        .unwrap_or_default();
+
+    let corsdomain = config.corsdomain.unwrap_or_default();
+
+    let handler = Handler::new(JsonRpcConfig {
+        username,
+        password,
+        methods,
+        allowip: (),
+        corsdomain: corsdomain.clone(),
+    });
+    install_rpc_modules(client, &handler);
+
+    let server = WsRpcServer::new(ip, config.port, Arc::new(handler), corsdomain)?;
     server.register_blockchain(client.consensus());
+    server.register_mempool(client.consensus());
     #[cfg(feature="validator")] {
         if let Some(validator) = client.validator() {
             server.register_validator(validator)
@@ -17,6 +17,8 @@ extern crate nimiq_blockchain_albatross as blockchain;
 extern crate nimiq_validator as validator;
 #[cfg(feature="validator")]
 extern crate nimiq_bls as bls;
+#[cfg(feature="validator")]
+extern crate nimiq_handel as handel;
 
 #[cfg(feature="rpc-server")]
 extern crate nimiq_rpc_server as rpc_server;
@@ -0,0 +1,188 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Arc;
+
+use beserial::Serialize;
+use block_base::Block;
+use hash::Blake2bHash;
+use transaction::Transaction;
+
+/// Number of recent blocks that are taken into account when estimating fees.
+pub const FEE_HISTORY_BLOCKS: usize = 20;
+
+/// A block counts as full if less than this ratio of the maximum block size is left unused.
+const FULL_BLOCK_THRESHOLD: f64 = 0.1;
+
+/// Fee statistics of a single block.
+#[derive(Debug, Clone)]
+struct BlockFeeStats {
+    /// Hash of the block, to remove the stats if the block is reverted.
+    block_hash: Blake2bHash,
+    /// Lowest fee/byte of all transactions included in the block.
+    min_fee_per_byte: f64,
+    /// Total size of all transactions included in the block.
+    size: usize,
+}
+
+impl BlockFeeStats {
+    fn from_transactions(block_hash: Blake2bHash, transactions: &[Transaction]) -> Self {
+        BlockFeeStats {
+            block_hash,
+            min_fee_per_byte: transactions.iter()
+                .map(Transaction::fee_per_byte)
+                .fold(None, |min: Option<f64>, fee| Some(min.map_or(fee, |min| min.min(fee))))
+                .unwrap_or(0f64),
+            size: transactions.iter().map(|tx| tx.serialized_size()).sum(),
+        }
+    }
+
+    /// The fee/byte a transaction needed to get into this block.
+    /// If the block had room left, any transaction could have been included.
+    fn required_fee_per_byte(&self, max_block_size: usize) -> f64 {
+        if (max_block_size.saturating_sub(self.size) as f64) < max_block_size as f64 * FULL_BLOCK_THRESHOLD {
+            self.min_fee_per_byte
+        } else {
+            0f64
+        }
+    }
+}
+
+/// A suggested fee for a transaction that should be included within `target_blocks` blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimate {
+    pub target_blocks: u32,
+    /// The suggested fee/byte.
+    pub fee_per_byte: f64,
+    /// The fee/byte needed to outbid the transactions currently waiting in the mempool.
+    pub mempool_fee_per_byte: f64,
+    /// The fee/byte that would have been sufficient in recent blocks.
+    pub history_fee_per_byte: f64,
+    /// Number of recent blocks the history estimate is based on.
+    pub history_blocks: usize,
+}
+
+/// Keeps track of the fees paid in recent blocks.
+pub(crate) struct FeeEstimator {
+    /// Space available for transactions in a block.
+    max_block_size: usize,
+    history: VecDeque<BlockFeeStats>,
+}
+
+impl FeeEstimator {
+    pub fn new(max_block_size: usize) -> Self {
+        FeeEstimator {
+            max_block_size,
+            history: VecDeque::with_capacity(FEE_HISTORY_BLOCKS),
+        }
+    }
+
+    /// Records the fees of a newly adopted block. Blocks without transaction body
+    /// (e.g. macro blocks) are ignored.
+    pub fn push_block<BL: Block>(&mut self, block: &BL) {
+        if let Some(transactions) = block.transactions() {
+            if self.history.len() >= FEE_HISTORY_BLOCKS {
+                self.history.pop_front();
+            }
+            self.history.push_back(BlockFeeStats::from_transactions(block.hash(), transactions));
+        }
+    }
+
+    /// Removes the stats of a reverted block. Blocks that aren't tracked are ignored.
+    pub fn pop_block<BL: Block>(&mut self, block: &BL) {
+        if block.transactions().is_none() {
+            return;
+        }
+
+        let block_hash = block.hash();
+        if let Some(index) = self.history.iter().rposition(|stats| stats.block_hash == block_hash) {
+            self.history.remove(index);
+        }
+    }
+
+    /// Estimates the fee/byte a transaction needs to be included within `target_blocks` blocks.
+    /// `transactions_sorted_fee` are the transactions in the mempool, sorted by fee/byte ascending.
+    pub fn estimate(&self, transactions_sorted_fee: &BTreeSet<Arc<Transaction>>, target_blocks: u32) -> FeeEstimate {
+        let target_blocks = target_blocks.max(1);
+
+        let mempool_fee_per_byte = self.estimate_from_mempool(transactions_sorted_fee, target_blocks);
+        let history_fee_per_byte = self.estimate_from_history(target_blocks);
+
+        FeeEstimate {
+            target_blocks,
+            fee_per_byte: mempool_fee_per_byte.max(history_fee_per_byte),
+            mempool_fee_per_byte,
+            history_fee_per_byte,
+            history_blocks: self.history.len(),
+        }
+    }
+
+    /// Fills `target_blocks` blocks with the highest paying transactions of the mempool and
+    /// returns the fee/byte of the first transaction that doesn't fit anymore.
+    /// Returns 0 if all transactions fit.
+    fn estimate_from_mempool(&self, transactions_sorted_fee: &BTreeSet<Arc<Transaction>>, target_blocks: u32) -> f64 {
+        let capacity = self.max_block_size.saturating_mul(target_blocks as usize);
+        let mut size = 0;
+        for tx in transactions_sorted_fee.iter().rev() {
+            size += tx.serialized_size();
+            if size > capacity {
+                return tx.fee_per_byte();
+            }
+        }
+        0f64
+    }
+
+    /// Returns the fee/byte that would have been sufficient to be included in
+    /// (at least) every `target_blocks`-th recent block.
+    fn estimate_from_history(&self, target_blocks: u32) -> f64 {
+        if self.history.is_empty() {
+            return 0f64;
+        }
+
+        let mut fees: Vec<f64> = self.history.iter()
+            .map(|stats| stats.required_fee_per_byte(self.max_block_size))
+            .collect();
+        fees.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let target_blocks = target_blocks as usize;
+        let count = (fees.len() + target_blocks - 1) / target_blocks;
+        fees[count - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nimiq_block::{Block as PowBlock, BlockBody};
+
+    use super::*;
+
+    fn block(nonce: u32) -> PowBlock {
+        let mut block = PowBlock::default();
+        block.header.nonce = nonce;
+        block.body = Some(BlockBody::default());
+        block
+    }
+
+    fn history(estimator: &FeeEstimator) -> Vec<Blake2bHash> {
+        estimator.history.iter().map(|stats| stats.block_hash.clone()).collect()
+    }
+
+    #[test]
+    fn it_removes_the_stats_of_reverted_blocks() {
+        let mut estimator = FeeEstimator::new(PowBlock::MAX_SIZE);
+        let blocks: Vec<PowBlock> = (1..=3).map(block).collect();
+        for block in &blocks {
+            estimator.push_block(block);
+        }
+
+        // Not necessarily the most recent block is reverted.
+        estimator.pop_block(&blocks[1]);
+        assert_eq!(history(&estimator), vec![blocks[0].hash(), blocks[2].hash()]);
+
+        // Unknown blocks and blocks without body are ignored.
+        estimator.pop_block(&blocks[1]);
+        let mut light_block = block(4);
+        light_block.body = None;
+        estimator.push_block(&light_block);
+        estimator.pop_block(&light_block);
+        assert_eq!(history(&estimator), vec![blocks[0].hash(), blocks[2].hash()]);
+    }
+}
@@ -4,6 +4,7 @@ extern crate nimiq_account as account;
 extern crate nimiq_block_base as block_base;
 extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_collections as collections;
+extern crate nimiq_database as database;
 extern crate nimiq_hash as hash;
 extern crate nimiq_keys as keys;
 extern crate nimiq_primitives as primitives;
@@ -13,6 +14,7 @@ extern crate nimiq_utils as utils;
 use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
 
@@ -20,21 +22,31 @@ use account::{Account, AccountTransactionInteraction};
 use beserial::Serialize;
 use block_base::Block;
 use blockchain_base::{AbstractBlockchain, BlockchainEvent};
+use database::Environment;
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use transaction::{Transaction, TransactionFlags};
 use utils::observer::{Notifier, weak_listener};
 use primitives::networks::NetworkId;
 
+use crate::fee_estimator::{FEE_HISTORY_BLOCKS, FeeEstimate, FeeEstimator};
 use crate::filter::{MempoolFilter, Rules};
+use crate::store::MempoolStore;
 
+pub mod fee_estimator;
 pub mod filter;
+pub mod selection;
+pub mod store;
 
 pub struct Mempool<B: AbstractBlockchain> {
     blockchain: Arc<B>,
     pub notifier: RwLock<Notifier<'static, MempoolEvent>>,
     state: RwLock<MempoolState>,
+    fee_estimator: RwLock<FeeEstimator>,
     mut_lock: Mutex<()>,
+    /// Where the pending transactions are persisted, if enabled
+    store: Option<MempoolStore>,
+    persist_interval: Duration,
 }
 
 struct MempoolState {
@@ -43,6 +55,16 @@ struct MempoolState {
     transactions_by_recipient: HashMap<Address, BTreeSet<Arc<Transaction>>>,
     transactions_sorted_fee: BTreeSet<Arc<Transaction>>, // sorted by fee, ascending
     filter: MempoolFilter,
+    /// Accounts given to `push_transaction_with_accounts`, used instead of the blockchain's accounts
+    /// while there are pending transactions from or to them.
+    known_accounts: HashMap<Address, Account>,
+}
+
+impl MempoolState {
+    fn persist(&self, store: &MempoolStore) {
+        store.put_all(self.transactions_by_hash.iter()
+            .map(|(hash, transaction)| (hash, transaction.as_ref())));
+    }
 }
 
 #[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
@@ -51,25 +73,52 @@ pub enum MempoolEvent {
     TransactionRestored(Arc<Transaction>),
     TransactionMined(Arc<Transaction>),
     TransactionEvicted(Arc<Transaction>),
+    /// A pending transaction was replaced by the transaction with the given hash.
+    TransactionReplaced(Arc<Transaction>, Blake2bHash),
 }
 
 #[derive(Debug, Clone)]
 pub struct MempoolConfig {
     pub filter_rules: Rules,
     pub filter_limit: usize,
+    /// Store the pending transactions in the database, so that they survive a restart
+    pub persist: bool,
+    /// How often the pending transactions are stored, if `persist` is set
+    pub persist_interval: Duration,
 }
 
 impl Default for MempoolConfig {
     fn default() -> MempoolConfig {
         MempoolConfig {
             filter_rules: Rules::default(),
-            filter_limit: MempoolFilter::DEFAULT_BLACKLIST_SIZE
+            filter_limit: MempoolFilter::DEFAULT_BLACKLIST_SIZE,
+            persist: false,
+            persist_interval: Duration::from_secs(60),
         }
     }
 }
 
 impl<B: AbstractBlockchain + 'static> Mempool<B> {
     pub fn new(blockchain: Arc<B>, config: MempoolConfig) -> Arc<Self> {
+        Self::with_store(blockchain, config, None)
+    }
+
+    /// Creates a mempool that persists its transactions in `env`, if `config.persist` is set.
+    ///
+    /// The transactions persisted by a previous run are re-validated against the current head of
+    /// the chain and added to the mempool. Transactions that became invalid in the meantime, e.g.
+    /// because they expired, are dropped.
+    pub fn with_environment(blockchain: Arc<B>, config: MempoolConfig, env: Environment) -> Arc<Self> {
+        if !config.persist {
+            return Self::new(blockchain, config);
+        }
+
+        let this = Self::with_store(blockchain, config, Some(MempoolStore::new(env)));
+        this.restore_persisted();
+        this
+    }
+
+    fn with_store(blockchain: Arc<B>, config: MempoolConfig, store: Option<MempoolStore>) -> Arc<Self> {
         let arc = Arc::new(Self {
             blockchain: blockchain.clone(),
             notifier: RwLock::new(Notifier::new()),
@@ -79,8 +128,12 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 transactions_by_recipient: HashMap::new(),
                 transactions_sorted_fee: BTreeSet::new(),
                 filter: MempoolFilter::new(config.filter_rules, config.filter_limit),
+                known_accounts: HashMap::new(),
             }),
+            fee_estimator: RwLock::new(Self::init_fee_estimator(&blockchain)),
             mut_lock: Mutex::new(()),
+            store,
+            persist_interval: config.persist_interval,
         });
 
         // register listener to blockchain through weak reference
@@ -92,11 +145,87 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         arc
     }
 
+    /// Seeds the fee estimator with the most recent blocks of the chain.
+    fn init_fee_estimator(blockchain: &B) -> FeeEstimator {
+        let mut blocks = Vec::with_capacity(FEE_HISTORY_BLOCKS);
+        let head_height = blockchain.head_height();
+        // Blocks without body don't count, but don't look back too far for blocks with body.
+        let min_height = head_height.saturating_sub(2 * FEE_HISTORY_BLOCKS as u32);
+        let mut height = head_height;
+        while height > min_height && blocks.len() < FEE_HISTORY_BLOCKS {
+            if let Some(block) = blockchain.get_block_at(height, true) {
+                if block.transactions().is_some() {
+                    blocks.push(block);
+                }
+            }
+            height -= 1;
+        }
+
+        let mut fee_estimator = FeeEstimator::new(B::Block::MAX_SIZE);
+        for block in blocks.iter().rev() {
+            fee_estimator.push_block(block);
+        }
+        fee_estimator
+    }
+
+    /// Adds the transactions persisted by a previous run to the mempool.
+    fn restore_persisted(&self) {
+        let store = match self.store {
+            Some(ref store) => store,
+            None => return,
+        };
+
+        let transactions = store.get_all();
+        let total = transactions.len();
+        let block_height = self.blockchain.head_height() + 1;
+
+        let mut expired = 0;
+        let mut restored = 0;
+        for transaction in transactions {
+            // Transactions that left their validity window while the node was offline can never be
+            // included anymore.
+            if !transaction.is_valid_at(block_height) {
+                expired += 1;
+                continue;
+            }
+
+            if self.push_transaction(transaction) == ReturnCode::Accepted {
+                restored += 1;
+            }
+        }
+
+        info!("Restored {} of {} persisted transactions ({} expired)", restored, total, expired);
+
+        // Forget about the transactions that weren't accepted.
+        self.persist();
+    }
+
+    /// Stores the pending transactions, if persistence is enabled.
+    ///
+    /// This should be called every `persist_interval` and on shutdown.
+    pub fn persist(&self) {
+        if let Some(ref store) = self.store {
+            self.state.read().persist(store);
+        }
+    }
+
+    /// How often the pending transactions should be stored, or `None` if persistence is disabled.
+    pub fn persist_interval(&self) -> Option<Duration> {
+        self.store.as_ref().map(|_| self.persist_interval)
+    }
+
     pub fn is_filtered(&self, hash: &Blake2bHash) -> bool {
         self.state.read().filter.blacklisted(hash)
     }
 
-    pub fn push_transaction(&self, mut transaction: Transaction) -> ReturnCode {
+    pub fn push_transaction(&self, transaction: Transaction) -> ReturnCode {
+        self.push_transaction_with_accounts(transaction, HashMap::new())
+    }
+
+    /// Like `push_transaction`, but the transaction is checked against the given `accounts` instead
+    /// of the blockchain's accounts. Light blockchains don't have an accounts tree, so the accounts
+    /// of sender and recipient must be requested from full nodes first.
+    pub fn push_transaction_with_accounts(&self, mut transaction: Transaction, accounts: HashMap<Address, Account>) -> ReturnCode {
         let hash: Blake2bHash = transaction.hash();
 
         // Synchronize with `Blockchain::push`
@@ -108,6 +237,9 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         // Transactions that are invalidated by the new transaction are stored here.
         let mut txs_to_remove = Vec::new();
 
+        // The pending transaction that is replaced by the new transaction, if any.
+        let replaced_tx;
+
         {
             let state = self.state.upgradable_read();
 
@@ -129,12 +261,25 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 return ReturnCode::Invalid;
             }
 
-            // Check limit for free transactions.
+            // Check if the transaction replaces a pending transaction, i.e. one with the same sender,
+            // recipient and validity start height, but a lower fee/byte. Replacements must raise the
+            // fee/byte by enough to not be relayed for every small bump.
             let txs_by_sender_opt = state.transactions_by_sender.get(&transaction.sender);
+            replaced_tx = txs_by_sender_opt.and_then(|transactions| transactions.iter()
+                .find(|tx| Self::is_replaced_by(tx, &transaction))
+                .cloned());
+            if let Some(tx) = &replaced_tx {
+                if transaction.fee_per_byte() < Self::min_replacement_fee_per_byte(tx) {
+                    return ReturnCode::FeeTooLow;
+                }
+            }
+            let is_replaced = |tx: &Arc<Transaction>| replaced_tx.as_ref() == Some(tx);
+
+            // Check limit for free transactions.
             if transaction.fee_per_byte() < TRANSACTION_RELAY_FEE_MIN {
                 let mut num_free_tx = 0;
                 if let Some(transactions) = txs_by_sender_opt {
-                    for tx in transactions {
+                    for tx in transactions.iter().filter(|tx| !is_replaced(tx)) {
                         if tx.fee_per_byte() < TRANSACTION_RELAY_FEE_MIN {
                             num_free_tx += 1;
                             if num_free_tx >= FREE_TRANSACTIONS_PER_SENDER_MAX {
@@ -161,7 +306,8 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             // Retrieve recipient account and check account type.
             // TODO Eliminate copy
-            let mut recipient_account = self.blockchain.get_account(&transaction.recipient);
+            let mut recipient_account = accounts.get(&transaction.recipient).cloned()
+                .unwrap_or_else(|| self.get_account(&state, &transaction.recipient));
             let is_contract_creation = transaction.flags.contains(TransactionFlags::CONTRACT_CREATION);
             let is_type_change = recipient_account.account_type() != transaction.recipient_type;
             if is_contract_creation != is_type_change {
@@ -190,7 +336,8 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             // Retrieve sender account and check account type.
             // TODO Eliminate copy
-            let mut sender_account = self.blockchain.get_account(&transaction.sender);
+            let mut sender_account = accounts.get(&transaction.sender).cloned()
+                .unwrap_or_else(|| self.get_account(&state, &transaction.sender));
             if sender_account.account_type() != transaction.sender_type {
                 return ReturnCode::Invalid;
             }
@@ -205,7 +352,7 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                     empty_btree = BTreeSet::new();
                     empty_btree.iter()
                 }
-            };
+            }.filter(|tx| !is_replaced(tx));
 
             // First apply all transactions with a higher fee/byte.
             // These are not affected by the new transaction and should never fail to apply.
@@ -264,8 +411,16 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         {
             // Transaction is valid, add it to the mempool.
             let mut state = self.state.write();
+            state.known_accounts.extend(accounts.into_iter()
+                .filter(|(address, _)| address == &tx_arc.sender || address == &tx_arc.recipient));
             Self::add_transaction(&mut state, hash.clone(), tx_arc.clone());
 
+            // Remove the replaced transaction and make sure it doesn't come back.
+            if let Some(ref tx) = replaced_tx {
+                Self::remove_transaction(&mut state, tx);
+                state.filter.blacklist(tx.hash());
+            }
+
             // Evict transactions that were invalidated by the new transaction.
             for tx in txs_to_remove.iter() {
                 Self::remove_transaction(&mut *state, tx);
@@ -286,7 +441,13 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         drop(_push_lock);
 
         // Tell listeners about the new transaction we received.
-        self.notifier.read().notify(MempoolEvent::TransactionAdded(hash, tx_arc));
+        self.notifier.read().notify(MempoolEvent::TransactionAdded(hash.clone(), tx_arc));
+
+        // Tell listeners about the transaction we replaced.
+        if let Some(tx) = replaced_tx {
+            trace!("Transaction replaced: {:?}", tx);
+            self.notifier.read().notify(MempoolEvent::TransactionReplaced(tx, hash));
+        }
 
         // Tell listeners about the transactions we evicted.
         for tx in removed_transactions {
@@ -296,6 +457,21 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         ReturnCode::Accepted
     }
 
+    /// A pending transaction is replaced by a new transaction with the same sender, recipient and
+    /// validity start height, but a strictly higher fee/byte. The replacement is only accepted if
+    /// it pays at least `min_replacement_fee_per_byte`.
+    fn is_replaced_by(pending: &Transaction, transaction: &Transaction) -> bool {
+        pending.sender == transaction.sender
+            && pending.recipient == transaction.recipient
+            && pending.validity_start_height == transaction.validity_start_height
+            && pending.fee_per_byte() < transaction.fee_per_byte()
+    }
+
+    /// The fee/byte a transaction needs to replace `pending`.
+    fn min_replacement_fee_per_byte(pending: &Transaction) -> f64 {
+        pending.fee_per_byte() * (1f64 + TRANSACTION_REPLACEMENT_FEE_BUMP) + TRANSACTION_RELAY_FEE_MIN
+    }
+
     pub fn contains(&self, hash: &Blake2bHash) -> bool {
         self.state.read().transactions_by_hash.contains_key(hash)
     }
@@ -312,63 +488,91 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
             .collect()
     }
 
+    /// Selects the transactions for the next block, see `selection::select_transactions`.
+    ///
+    /// Signalling transactions to the staking contract, e.g. to retire or unpark a validator, are
+    /// preferred over other transactions, but may only fill a part of the block.
     pub fn get_transactions_for_block(&self, max_size: usize) -> Vec<Transaction> {
-        let mut txs = Vec::new();
-        let mut size = 0;
-
-        let mut validator_registry = None;
         let block_height = self.blockchain.head_height() + 1;
+        let validator_registry_address = self.blockchain.validator_registry_address();
+
+        let is_priority = |tx: &Transaction| {
+            Some(&tx.recipient) == validator_registry_address && tx.flags.contains(TransactionFlags::SIGNALLING)
+        };
 
         let state = self.state.read();
-        for tx in state.transactions_sorted_fee.iter() {
-            // Check validity of transactions concerning the staking contract.
+
+        // Copies of the accounts touched by the selected transactions.
+        let mut accounts: HashMap<Address, Account> = HashMap::new();
+        let apply = |tx: &Transaction| {
+            // TODO Eliminate copy
+            let sender_account = accounts.entry(tx.sender.clone())
+                .or_insert_with(|| self.get_account(&state, &tx.sender));
+            let outgoing_receipt = match sender_account.commit_outgoing_transaction(tx, block_height) {
+                Err(_) => return false,
+                Ok(receipt) => receipt,
+            };
+
+            // Check the recipient side for the staking contract.
             // We only do it for the staking contract, since this is the only place
             // where the transaction validity depends on the recipient's state.
-            if let Some(validator_registry_address) = self.blockchain.validator_registry_address() {
-                // First apply the sender side to the staking contract if necessary.
-                // This could for example drop a validator and make subsequent update transactions invalid.
-                let mut outgoing_receipt = None;
-                if &tx.sender == validator_registry_address {
-                    // Get copy of staking contract if required.
-                    if validator_registry.is_none() {
-                        validator_registry = Some(self.blockchain.get_account(validator_registry_address));
-                    }
-
-                    let sender_account = validator_registry.as_mut().unwrap();
-                    match sender_account.commit_outgoing_transaction(&tx, block_height) {
-                        Err(_) => continue, // Ignore transaction.
-                        Ok(receipt) => outgoing_receipt = receipt,
-                    }
+            if Some(&tx.recipient) == validator_registry_address {
+                let recipient_account = accounts.entry(tx.recipient.clone())
+                    .or_insert_with(|| self.get_account(&state, &tx.recipient));
+                if recipient_account.commit_incoming_transaction(tx, block_height).is_err() {
+                    // Revert sender side.
+                    accounts.get_mut(&tx.sender).unwrap()
+                        .revert_outgoing_transaction(tx, block_height, outgoing_receipt.as_ref()).unwrap();
+                    return false;
                 }
+            }
 
-                // Then apply the recipient side to the staking contract.
-                if &tx.recipient == validator_registry_address {
-                    // Get copy of staking contract if required.
-                    if validator_registry.is_none() {
-                        validator_registry = Some(self.blockchain.get_account(validator_registry_address));
-                    }
+            true
+        };
 
-                    let recipient_account = validator_registry.as_mut().unwrap();
-                    if recipient_account.commit_incoming_transaction(&tx, block_height).is_err() {
-                        // Potentially revert sender side and ignore transaction.
-                        if &tx.sender == validator_registry_address {
-                            recipient_account.revert_outgoing_transaction(&tx, block_height, outgoing_receipt.as_ref()).unwrap();
-                        }
-                        continue;
-                    }
-                }
+        let validate = |transactions: &[Arc<Transaction>]| {
+            self.check_block_order(&state, transactions, block_height)
+        };
+
+        let transactions = state.transactions_sorted_fee.iter()
+            .cloned()
+            .collect();
+        let priority_size_max = (max_size as f64 * PRIORITY_BLOCK_SHARE) as usize;
+
+        selection::select_transactions(transactions, max_size, priority_size_max, is_priority, apply, validate)
+            .into_iter()
+            .map(|tx| Transaction::clone(&tx))
+            .collect()
+    }
+
+    /// Checks `transactions` against the state the next block is built on in the way the block
+    /// applies them: first the sender side of all transactions, then the recipient side. As in
+    /// `get_transactions_for_block`, the recipient side is only checked for the staking contract.
+    /// Returns the index of the first invalid transaction.
+    fn check_block_order(&self, state: &MempoolState, transactions: &[Arc<Transaction>], block_height: u32) -> Result<(), usize> {
+        let validator_registry_address = self.blockchain.validator_registry_address();
+
+        // TODO Eliminate copy
+        let mut accounts: HashMap<Address, Account> = HashMap::new();
+        for (index, tx) in transactions.iter().enumerate() {
+            let sender_account = accounts.entry(tx.sender.clone())
+                .or_insert_with(|| self.get_account(state, &tx.sender));
+            if sender_account.commit_outgoing_transaction(tx, block_height).is_err() {
+                return Err(index);
             }
+        }
 
-            let tx_size = tx.serialized_size();
-            if size + tx_size <= max_size {
-                txs.push(Transaction::clone(tx));
-                size += tx_size;
-            } else if max_size - size < Transaction::MIN_SIZE {
-                // Break if we can't fit the smallest possible transaction anymore.
-                break;
+        for (index, tx) in transactions.iter().enumerate() {
+            if Some(&tx.recipient) == validator_registry_address {
+                let recipient_account = accounts.entry(tx.recipient.clone())
+                    .or_insert_with(|| self.get_account(state, &tx.recipient));
+                if recipient_account.commit_incoming_transaction(tx, block_height).is_err() {
+                    return Err(index);
+                }
             }
-        };
-        txs
+        }
+
+        Ok(())
     }
 
     pub fn get_transactions_by_addresses(&self, addresses: HashSet<Address>, max_count: usize) -> Vec<Arc<Transaction>> {
@@ -395,6 +599,13 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         txs
     }
 
+    /// Suggests a fee/byte for a transaction that should be included within `target_blocks` blocks,
+    /// based on the current mempool content and the fees paid in recent blocks.
+    pub fn estimate_fee(&self, target_blocks: u32) -> FeeEstimate {
+        let state = self.state.read();
+        self.fee_estimator.read().estimate(&state.transactions_sorted_fee, target_blocks)
+    }
+
     pub fn current_height(&self) -> u32 {
         self.blockchain.head_height()
     }
@@ -405,16 +616,62 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
     fn on_blockchain_event(&self, event: &BlockchainEvent<B::Block>) {
         match event {
-            BlockchainEvent::Extended(_) | BlockchainEvent::Finalized(_) => {
+            BlockchainEvent::Extended(hash) | BlockchainEvent::Finalized(hash) => {
+                if let Some(block) = self.blockchain.get_block(hash, true) {
+                    self.fee_estimator.write().push_block(&block);
+                }
+                self.invalidate_known_accounts();
                 self.evict_transactions()
             },
-            BlockchainEvent::Rebranched(reverted_blocks, _) => {
+            BlockchainEvent::Rebranched(reverted_blocks, adopted_blocks) => {
+                {
+                    let mut fee_estimator = self.fee_estimator.write();
+                    for (_, block) in reverted_blocks.iter() {
+                        fee_estimator.pop_block(block);
+                    }
+                    for (_, block) in adopted_blocks.iter() {
+                        fee_estimator.push_block(block);
+                    }
+                }
                 self.restore_transactions(reverted_blocks);
+                self.invalidate_known_accounts();
                 self.evict_transactions();
             },
         }
     }
 
+    /// Accounts given to `push_transaction_with_accounts` were proven at a previous head and might
+    /// have changed since then. Drop them and evict the transactions that were checked against them,
+    /// since they can't be checked again without requesting the accounts anew.
+    fn invalidate_known_accounts(&self) {
+        // Only one mutating operation at a time.
+        let _lock = self.mut_lock.lock();
+
+        let mut txs_evicted = BTreeSet::new();
+        {
+            let mut state = self.state.write();
+            if state.known_accounts.is_empty() {
+                return;
+            }
+
+            for address in state.known_accounts.keys() {
+                let by_sender = state.transactions_by_sender.get(address).into_iter().flatten();
+                let by_recipient = state.transactions_by_recipient.get(address).into_iter().flatten();
+                txs_evicted.extend(by_sender.chain(by_recipient).cloned());
+            }
+            for tx in txs_evicted.iter() {
+                Self::remove_transaction(&mut state, tx);
+            }
+            state.known_accounts.clear();
+        }
+
+        // Notify listeners.
+        for tx in txs_evicted {
+            trace!("Transaction evicted: {:?}", tx);
+            self.notifier.read().notify(MempoolEvent::TransactionEvicted(tx));
+        }
+    }
+
     /// Evict all transactions from the pool that have become invalid due to changes in the
     /// account state (i.e. typically because the were included in a newly mined block). No need to re-check signatures.
     fn evict_transactions(&self) {
@@ -429,7 +686,7 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             for (address, transactions) in state.transactions_by_sender.iter() {
                 // TODO Eliminate copy
-                let mut sender_account = self.blockchain.get_account(&address);
+                let mut sender_account = self.get_account(&state, &address);
                 for tx in transactions.iter().rev() {
                     // Check if the transaction has expired.
                     if !tx.is_valid_at(block_height) {
@@ -445,7 +702,7 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
                     // Check if transaction is still valid for recipient.
                     // TODO Eliminate copy
-                    let mut recipient_account = self.blockchain.get_account(&tx.recipient);
+                    let mut recipient_account = self.get_account(&state, &tx.recipient);
                     if recipient_account.commit_incoming_transaction(&tx, block_height).is_err() {
                         txs_evicted.push(tx.clone());
                         continue;
@@ -556,7 +813,7 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 };
 
                 // TODO Eliminate copy.
-                let sender_account = self.blockchain.get_account(&sender);
+                let sender_account = self.get_account(&state, &sender);
                 let (txs_to_add, txs_to_remove) = Self::merge_transactions(sender_account, block_height, existing_txs, &restored_txs);
                 for tx in txs_to_add {
                     let transaction = Arc::new(tx.clone());
@@ -630,6 +887,18 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         if remove_key {
             state.transactions_by_recipient.remove(&tx.recipient);
         }
+
+        for address in &[&tx.sender, &tx.recipient] {
+            if !state.transactions_by_sender.contains_key(address) && !state.transactions_by_recipient.contains_key(address) {
+                state.known_accounts.remove(address);
+            }
+        }
+    }
+
+    /// Returns the account at `address`, preferring the accounts given to `push_transaction_with_accounts`.
+    fn get_account(&self, state: &MempoolState, address: &Address) -> Account {
+        state.known_accounts.get(address).cloned()
+            .unwrap_or_else(|| self.blockchain.get_account(address))
     }
 
     fn merge_transactions<'a>(mut sender_account: Account, block_height: u32, old_txs: &BTreeSet<Arc<Transaction>>, new_txs: &BTreeSet<&'a Transaction>) -> (Vec<&'a Transaction>, Vec<Arc<Transaction>>) {
@@ -680,6 +949,15 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
     }
 }
 
+impl<B: AbstractBlockchain> Drop for Mempool<B> {
+    fn drop(&mut self) {
+        // Store the pending transactions on shutdown.
+        if let Some(ref store) = self.store {
+            self.state.read().persist(store);
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ReturnCode {
     FeeTooLow,
@@ -690,7 +968,11 @@ pub enum ReturnCode {
 }
 
 /// Fee threshold in sat/byte below which transactions are considered "free".
-const TRANSACTION_RELAY_FEE_MIN : f64 = 1f64;
+pub const TRANSACTION_RELAY_FEE_MIN : f64 = 1f64;
+
+/// Minimum relative increase of the fee/byte for a transaction to replace a pending one. The new
+/// fee/byte must also exceed the old one by at least `TRANSACTION_RELAY_FEE_MIN`.
+pub const TRANSACTION_REPLACEMENT_FEE_BUMP : f64 = 0.1;
 
 /// Maximum number of transactions per sender.
 const TRANSACTIONS_PER_SENDER_MAX : u32 = 500;
@@ -700,3 +982,6 @@ const FREE_TRANSACTIONS_PER_SENDER_MAX : u32 = 10;
 
 /// Maximum number of transactions in the mempool.
 pub const SIZE_MAX : usize = 100_000;
+
+/// Share of a block that may be filled with prioritized staking transactions.
+pub const PRIORITY_BLOCK_SHARE : f64 = 0.25;
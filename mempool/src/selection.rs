@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use beserial::Serialize;
+use transaction::Transaction;
+
+/// Selects the transactions for a block of at most `max_size` bytes, trying to maximize the total
+/// fee.
+///
+/// Transactions are selected greedily by their fee/byte. Transactions for which `is_priority`
+/// holds are selected before all others, but only up to a total size of `priority_size_max`.
+/// This keeps important transactions, e.g. to the staking contract, from starving behind payments
+/// when blocks are full. Priority transactions that don't fit into that share compete with all
+/// other transactions.
+///
+/// `apply` is called for every candidate in selection order and must apply it to the state the
+/// block is built on. If it returns `false`, the candidate is invalid and isn't selected.
+///
+/// The block applies its transactions in block order (see `Transaction::cmp_block_order`), so the
+/// selected transactions are finally sorted into that order and passed to `validate`, which must
+/// check them against the state the block is built on in the given order. It returns the index of
+/// the first invalid transaction, which is then dropped, until the whole selection is valid.
+///
+/// The selected transactions are returned in block order.
+pub fn select_transactions<P, A, V>(mut transactions: Vec<Arc<Transaction>>, max_size: usize, priority_size_max: usize, is_priority: P, mut apply: A, mut validate: V) -> Vec<Arc<Transaction>>
+    where P: Fn(&Transaction) -> bool,
+          A: FnMut(&Transaction) -> bool,
+          V: FnMut(&[Arc<Transaction>]) -> Result<(), usize> {
+    // Highest fee/byte first.
+    transactions.sort_by(|a, b| b.cmp(a));
+
+    let mut selected = Vec::new();
+    let mut size = 0;
+
+    // Fill the priority share of the block first.
+    let mut priority_size = 0;
+    let mut remaining = Vec::with_capacity(transactions.len());
+    for tx in transactions {
+        let tx_size = tx.serialized_size();
+        if is_priority(&tx) && priority_size + tx_size <= priority_size_max && size + tx_size <= max_size {
+            if apply(&tx) {
+                priority_size += tx_size;
+                size += tx_size;
+                selected.push(tx);
+            }
+            continue;
+        }
+        remaining.push(tx);
+    }
+
+    for tx in remaining {
+        if max_size - size < Transaction::MIN_SIZE {
+            // We can't fit the smallest possible transaction anymore.
+            break;
+        }
+
+        let tx_size = tx.serialized_size();
+        if size + tx_size <= max_size && apply(&tx) {
+            size += tx_size;
+            selected.push(tx);
+        }
+    }
+
+    // Drop the transactions that are invalid in the order the block applies them.
+    selected.sort_by(|a, b| a.cmp_block_order(b));
+    while let Err(index) = validate(&selected) {
+        selected.remove(index);
+    }
+
+    selected
+}
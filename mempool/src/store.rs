@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use database::{AsDatabaseBytes, Database, Environment, FromDatabaseValue, ReadTransaction, WriteTransaction};
+use database::cursor::{ReadCursor, WriteCursor};
+use hash::Blake2bHash;
+use transaction::Transaction;
+
+/// Wrapper to store transactions in the database.
+struct StoredTransaction(Transaction);
+
+impl AsDatabaseBytes for StoredTransaction {
+    fn as_database_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.0.serialize_to_vec())
+    }
+}
+
+impl FromDatabaseValue for StoredTransaction {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(StoredTransaction(Deserialize::deserialize(&mut cursor)?))
+    }
+}
+
+/// Stores the pending transactions of the mempool, so that they survive a restart of the node.
+#[derive(Debug)]
+pub struct MempoolStore {
+    env: Environment,
+    transaction_db: Database,
+}
+
+impl MempoolStore {
+    const TRANSACTION_DB_NAME: &'static str = "MempoolTransactions";
+
+    pub fn new(env: Environment) -> Self {
+        let transaction_db = env.open_database(Self::TRANSACTION_DB_NAME.to_string());
+        MempoolStore { env, transaction_db }
+    }
+
+    /// Replaces the stored transactions with `transactions`.
+    pub fn put_all<'a, I: IntoIterator<Item=(&'a Blake2bHash, &'a Transaction)>>(&self, transactions: I) {
+        let mut txn = WriteTransaction::new(&self.env);
+        self.remove_all(&mut txn);
+        for (hash, transaction) in transactions {
+            // TODO Eliminate copy
+            txn.put(&self.transaction_db, hash, &StoredTransaction(transaction.clone()));
+        }
+        txn.commit();
+    }
+
+    /// Returns all stored transactions.
+    pub fn get_all(&self) -> Vec<Transaction> {
+        let txn = ReadTransaction::new(&self.env);
+        let mut cursor = txn.cursor(&self.transaction_db);
+
+        let mut transactions = Vec::new();
+        let mut pos: Option<(Blake2bHash, StoredTransaction)> = cursor.first();
+        while let Some((_, transaction)) = pos {
+            transactions.push(transaction.0);
+            pos = cursor.next();
+        }
+        transactions
+    }
+
+    fn remove_all(&self, txn: &mut WriteTransaction) {
+        let mut cursor = txn.write_cursor(&self.transaction_db);
+        let mut pos: Option<(Blake2bHash, StoredTransaction)> = cursor.first();
+        while pos.is_some() {
+            cursor.remove();
+            pos = cursor.next();
+        }
+    }
+}
@@ -1,18 +1,21 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
 use hex;
+use parking_lot::Mutex;
 
 use beserial::{Deserialize, Serialize};
-use nimiq_account::Receipts;
+use nimiq_account::{Account, BasicAccount, Receipts};
 use nimiq_block::BlockBody;
 use nimiq_blockchain::Blockchain;
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_database::WriteTransaction;
-use nimiq_hash::Hash;
+use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::{KeyPair, SecureGenerate};
 use nimiq_keys::Address;
-use nimiq_mempool::{Mempool, MempoolConfig, ReturnCode};
+use nimiq_mempool::{Mempool, MempoolConfig, MempoolEvent, ReturnCode};
+use nimiq_mempool::store::MempoolStore;
 use nimiq_network_primitives::time::NetworkTime;
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
@@ -68,6 +71,29 @@ fn push_tx_with_insufficient_balance() {
     assert_eq!(mempool.push_transaction(t), ReturnCode::Invalid);
 }
 
+#[test]
+fn push_tx_with_given_accounts() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::Main, Arc::new(NetworkTime::new())).unwrap());
+    let mempool = Mempool::new(blockchain, MempoolConfig::default());
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    let mut tx = Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(0).unwrap(), 1, NetworkId::Main);
+    let signature_proof = SignatureProof::from(keypair_a.public.clone(), keypair_a.sign(&tx.serialize_content()));
+    tx.proof = signature_proof.serialize_to_vec();
+
+    // address_a has no balance on the chain, but the given account has.
+    assert_eq!(mempool.push_transaction(tx.clone()), ReturnCode::Invalid);
+
+    let mut accounts = HashMap::new();
+    accounts.insert(address_a, Account::Basic(BasicAccount { balance: Coin::try_from(1000).unwrap() }));
+    assert_eq!(mempool.push_transaction_with_accounts(tx.clone(), accounts), ReturnCode::Accepted);
+    assert!(mempool.contains(&tx.hash()));
+}
+
 #[test]
 fn push_and_get_valid_tx() {
     let env = VolatileEnvironment::new(10).unwrap();
@@ -161,3 +187,204 @@ fn reject_free_tx_beyond_limit() {
         }
     }
 }
+
+#[test]
+fn estimate_fee_from_mempool() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::Main, Arc::new(NetworkTime::new())).unwrap());
+    let mempool = Mempool::new(blockchain.clone(), MempoolConfig::default());
+
+    // An empty mempool doesn't require any fee.
+    let estimate = mempool.estimate_fee(1);
+    assert_eq!(estimate.fee_per_byte, 0f64);
+    assert_eq!(mempool.estimate_fee(0).target_blocks, 1);
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let keypair_b = KeyPair::generate_default_csprng();
+    let address_b = Address::from(&keypair_b.public);
+    let address_c = Address::from([2u8; Address::SIZE]);
+
+    // Give address_a and address_b balance
+    let body = BlockBody { miner: address_a.clone(), extra_data: Vec::new(), transactions: Vec::new(), receipts: Receipts::default() };
+    let body2 = BlockBody { miner: address_b.clone(), extra_data: Vec::new(), transactions: Vec::new(), receipts: Receipts::default() };
+    let mut txn = WriteTransaction::new(&env);
+    blockchain.state().accounts().commit(&mut txn, &body.transactions, &vec![body.get_reward_inherent(1), body2.get_reward_inherent(1)], 1).unwrap();
+    txn.commit();
+
+    // Fill the mempool with more than one block worth of transactions with increasing fees.
+    for i in 1..=400 {
+        for keypair in [&keypair_a, &keypair_b].iter() {
+            let mut tx = Transaction::new_basic(Address::from(&keypair.public), address_c.clone(), Coin::try_from(1).unwrap(), Coin::try_from(i * 138).unwrap(), 1, NetworkId::Main);
+            let signature_proof = SignatureProof::from(keypair.public.clone(), keypair.sign(&tx.serialize_content()));
+            tx.proof = signature_proof.serialize_to_vec();
+            assert_eq!(mempool.push_transaction(tx), ReturnCode::Accepted);
+        }
+    }
+
+    // The transactions don't fit into one block, so a fee is required.
+    let estimate = mempool.estimate_fee(1);
+    assert!(estimate.mempool_fee_per_byte > 1f64);
+    assert!(estimate.fee_per_byte >= estimate.mempool_fee_per_byte);
+
+    // Two blocks can hold all transactions.
+    let estimate = mempool.estimate_fee(2);
+    assert_eq!(estimate.mempool_fee_per_byte, 0f64);
+}
+
+#[test]
+fn replace_tx_with_higher_fee() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::Main, Arc::new(NetworkTime::new())).unwrap());
+    let mempool = Mempool::new(blockchain.clone(), MempoolConfig::default());
+
+    let replaced = Arc::new(Mutex::new(Vec::new()));
+    let replaced1 = Arc::clone(&replaced);
+    mempool.notifier.write().register(move |e: &MempoolEvent| {
+        if let MempoolEvent::TransactionReplaced(tx, replaced_by) = e {
+            replaced1.lock().push((tx.hash::<Blake2bHash>(), replaced_by.clone()));
+        }
+    });
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    // Give address_a balance
+    let body = BlockBody { miner: address_a.clone(), extra_data: Vec::new(), transactions: Vec::new(), receipts: Receipts::default() };
+    let mut txn = WriteTransaction::new(&env);
+    blockchain.state().accounts().commit(&mut txn, &body.transactions, &vec![body.get_reward_inherent(1)], 1).unwrap();
+    txn.commit();
+
+    let sign = |mut tx: Transaction| {
+        let signature_proof = SignatureProof::from(keypair_a.public.clone(), keypair_a.sign(&tx.serialize_content()));
+        tx.proof = signature_proof.serialize_to_vec();
+        tx
+    };
+
+    // Push a free transaction.
+    let tx1 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(0).unwrap(), 1, NetworkId::Main));
+    let hash1 = tx1.hash();
+    assert_eq!(mempool.push_transaction(tx1.clone()), ReturnCode::Accepted);
+
+    // A transaction to a different recipient doesn't replace it.
+    let tx2 = sign(Transaction::new_basic(address_a.clone(), Address::from([3u8; Address::SIZE]), Coin::try_from(10).unwrap(), Coin::try_from(1000).unwrap(), 1, NetworkId::Main));
+    let hash2 = tx2.hash();
+    assert_eq!(mempool.push_transaction(tx2), ReturnCode::Accepted);
+    assert!(mempool.contains(&hash1));
+
+    // A transaction with the same sender, recipient and validity start height, but higher fee replaces it.
+    let tx3 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(500).unwrap(), 1, NetworkId::Main));
+    let hash3 = tx3.hash();
+    assert_eq!(mempool.push_transaction(tx3), ReturnCode::Accepted);
+    assert!(!mempool.contains(&hash1));
+    assert!(mempool.contains(&hash2));
+    assert!(mempool.contains(&hash3));
+    assert_eq!(replaced.lock().as_slice(), &[(hash1.clone(), hash3.clone())]);
+
+    // The replaced transaction is blacklisted.
+    assert!(mempool.is_filtered(&hash1));
+    assert_eq!(mempool.push_transaction(tx1), ReturnCode::Filtered);
+
+    // A transaction with a lower fee doesn't replace the pending one.
+    let tx4 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(200).unwrap(), 1, NetworkId::Main));
+    assert_eq!(mempool.push_transaction(tx4), ReturnCode::Accepted);
+    assert!(mempool.contains(&hash3));
+    assert_eq!(replaced.lock().len(), 1);
+}
+
+#[test]
+fn reject_replacement_with_small_fee_bump() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::Main, Arc::new(NetworkTime::new())).unwrap());
+    let mempool = Mempool::new(blockchain.clone(), MempoolConfig::default());
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    // Give address_a balance
+    let body = BlockBody { miner: address_a.clone(), extra_data: Vec::new(), transactions: Vec::new(), receipts: Receipts::default() };
+    let mut txn = WriteTransaction::new(&env);
+    blockchain.state().accounts().commit(&mut txn, &body.transactions, &vec![body.get_reward_inherent(1)], 1).unwrap();
+    txn.commit();
+
+    let sign = |mut tx: Transaction| {
+        let signature_proof = SignatureProof::from(keypair_a.public.clone(), keypair_a.sign(&tx.serialize_content()));
+        tx.proof = signature_proof.serialize_to_vec();
+        tx
+    };
+
+    let tx1 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(1000).unwrap(), 1, NetworkId::Main));
+    let hash1 = tx1.hash();
+    assert_eq!(mempool.push_transaction(tx1), ReturnCode::Accepted);
+
+    // A fee bump of 5% is too small to replace the pending transaction.
+    let tx2 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(1050).unwrap(), 1, NetworkId::Main));
+    let hash2 = tx2.hash();
+    assert_eq!(mempool.push_transaction(tx2), ReturnCode::FeeTooLow);
+    assert!(mempool.contains(&hash1));
+    assert!(!mempool.contains(&hash2));
+
+    // A fee bump of 30% replaces it.
+    let tx3 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(1300).unwrap(), 1, NetworkId::Main));
+    let hash3 = tx3.hash();
+    assert_eq!(mempool.push_transaction(tx3), ReturnCode::Accepted);
+    assert!(!mempool.contains(&hash1));
+    assert!(mempool.contains(&hash3));
+}
+
+#[test]
+fn restore_persisted_txs() {
+    let env = VolatileEnvironment::new(20).unwrap();
+    let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::Main, Arc::new(NetworkTime::new())).unwrap());
+    let config = MempoolConfig { persist: true, ..Default::default() };
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    // Give address_a balance
+    let body = BlockBody { miner: address_a.clone(), extra_data: Vec::new(), transactions: Vec::new(), receipts: Receipts::default() };
+    let mut txn = WriteTransaction::new(&env);
+    blockchain.state().accounts().commit(&mut txn, &body.transactions, &vec![body.get_reward_inherent(1)], 1).unwrap();
+    txn.commit();
+
+    let sign = |mut tx: Transaction| {
+        let signature_proof = SignatureProof::from(keypair_a.public.clone(), keypair_a.sign(&tx.serialize_content()));
+        tx.proof = signature_proof.serialize_to_vec();
+        tx
+    };
+
+    let tx1 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(10).unwrap(), Coin::try_from(0).unwrap(), 1, NetworkId::Main));
+    let hash1: Blake2bHash = tx1.hash();
+    let tx2 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(20).unwrap(), Coin::try_from(0).unwrap(), 1, NetworkId::Main));
+    let hash2: Blake2bHash = tx2.hash();
+
+    // Pending transactions are stored when the mempool is dropped.
+    let mempool = Mempool::with_environment(blockchain.clone(), config.clone(), env.clone());
+    assert_eq!(mempool.push_transaction(tx1), ReturnCode::Accepted);
+    assert_eq!(mempool.push_transaction(tx2), ReturnCode::Accepted);
+    drop(mempool);
+
+    // Add a transaction that is outside of its validity window.
+    let tx3 = sign(Transaction::new_basic(address_a.clone(), address_b.clone(), Coin::try_from(30).unwrap(), Coin::try_from(0).unwrap(), 1000, NetworkId::Main));
+    let hash3: Blake2bHash = tx3.hash();
+    let store = MempoolStore::new(env.clone());
+    let mut stored = store.get_all();
+    assert_eq!(stored.len(), 2);
+    stored.push(tx3);
+    let hashes = stored.iter().map(|tx| tx.hash()).collect::<Vec<Blake2bHash>>();
+    store.put_all(hashes.iter().zip(stored.iter()));
+
+    // After a restart, the valid transactions are back.
+    let mempool = Mempool::with_environment(blockchain.clone(), config, env.clone());
+    assert!(mempool.contains(&hash1));
+    assert!(mempool.contains(&hash2));
+    assert!(!mempool.contains(&hash3));
+    assert_eq!(store.get_all().len(), 2);
+
+    // Without persistence, nothing is restored.
+    let mempool = Mempool::with_environment(blockchain, MempoolConfig::default(), env);
+    assert!(!mempool.contains(&hash1));
+}
@@ -0,0 +1,108 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use beserial::Serialize;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::Address;
+use nimiq_mempool::selection::select_transactions;
+use nimiq_primitives::coin::Coin;
+use nimiq_primitives::networks::NetworkId;
+use nimiq_transaction::Transaction;
+
+fn tx(sender: u8, recipient: u8, fee: u64, validity_start_height: u32) -> Arc<Transaction> {
+    let mut tx = Transaction::new_basic(
+        Address::from([sender; Address::SIZE]),
+        Address::from([recipient; Address::SIZE]),
+        Coin::try_from(100).unwrap(),
+        Coin::try_from(fee).unwrap(),
+        validity_start_height,
+        NetworkId::Dummy,
+    );
+    // Make the transactions as large as real ones.
+    tx.proof = vec![0; 100];
+    Arc::new(tx)
+}
+
+fn hashes(transactions: &[Arc<Transaction>]) -> Vec<Blake2bHash> {
+    transactions.iter().map(|tx| tx.hash()).collect()
+}
+
+/// The hashes of `transactions` in the order in which the selection returns them.
+fn in_block_order(transactions: &[Arc<Transaction>]) -> Vec<Blake2bHash> {
+    let mut transactions = transactions.to_vec();
+    transactions.sort_by(|a, b| a.cmp_block_order(b));
+    hashes(&transactions)
+}
+
+fn valid(_: &[Arc<Transaction>]) -> Result<(), usize> {
+    Ok(())
+}
+
+#[test]
+fn it_selects_transactions_by_fee_per_byte() {
+    let tx1 = tx(1, 10, 100, 1);
+    let tx2 = tx(1, 10, 1000, 2);
+    let tx3 = tx(2, 10, 500, 1);
+    let size = tx1.serialized_size();
+
+    // Transactions of the same sender don't depend on each other.
+    let selected = select_transactions(vec![tx1.clone(), tx2.clone(), tx3.clone()], 2 * size, 0, |_| false, |_| true, valid);
+    assert_eq!(hashes(&selected), in_block_order(&[tx2.clone(), tx3.clone()]));
+
+    // With enough space, everything is selected.
+    let selected = select_transactions(vec![tx1.clone(), tx2.clone(), tx3.clone()], 3 * size, 0, |_| false, |_| true, valid);
+    assert_eq!(hashes(&selected), in_block_order(&[tx1.clone(), tx2.clone(), tx3.clone()]));
+
+    // With room for a single transaction, the one paying the most is selected.
+    let selected = select_transactions(vec![tx1, tx2.clone(), tx3], size, 0, |_| false, |_| true, valid);
+    assert_eq!(hashes(&selected), hashes(&[tx2]));
+}
+
+#[test]
+fn it_skips_invalid_transactions() {
+    let tx1 = tx(1, 10, 100, 1);
+    let tx2 = tx(1, 10, 1000, 2);
+    let tx3 = tx(2, 10, 500, 1);
+    let size = tx1.serialized_size();
+
+    let invalid: Blake2bHash = tx2.hash();
+    let selected = select_transactions(vec![tx1.clone(), tx2, tx3.clone()], 3 * size, 0, |_| false, |tx| tx.hash::<Blake2bHash>() != invalid, valid);
+    assert_eq!(hashes(&selected), in_block_order(&[tx1, tx3]));
+}
+
+#[test]
+fn it_validates_in_block_order() {
+    let tx1 = tx(1, 10, 100, 1);
+    let tx2 = tx(1, 11, 1000, 1);
+    let tx3 = tx(2, 12, 500, 1);
+    let size = tx1.serialized_size();
+
+    // Sender 1 can only afford the transaction that comes first in the block, which is tx1 even
+    // though tx2 was selected first.
+    let validate = |transactions: &[Arc<Transaction>]| {
+        match transactions.iter().filter(|tx| tx.sender == Address::from([1u8; Address::SIZE])).nth(1) {
+            Some(second) => Err(transactions.iter().position(|tx| tx == second).unwrap()),
+            None => Ok(()),
+        }
+    };
+    let selected = select_transactions(vec![tx1.clone(), tx2, tx3.clone()], 3 * size, 0, |_| false, |_| true, validate);
+    assert_eq!(hashes(&selected), in_block_order(&[tx1, tx3]));
+}
+
+#[test]
+fn it_prioritizes_transactions() {
+    let staking = tx(1, 99, 0, 1);
+    let payment1 = tx(2, 10, 1000, 1);
+    let payment2 = tx(3, 10, 1000, 1);
+    let size = staking.serialized_size();
+    let is_priority = |tx: &Transaction| tx.recipient == Address::from([99u8; Address::SIZE]);
+
+    // The free staking transaction gets into the full block.
+    let selected = select_transactions(vec![staking.clone(), payment1.clone(), payment2.clone()], 2 * size, size, is_priority, |_| true, valid);
+    assert_eq!(selected.len(), 2);
+    assert!(hashes(&selected).contains(&staking.hash()));
+
+    // But not beyond the priority share of the block.
+    let selected = select_transactions(vec![staking, payment1.clone(), payment2.clone()], 2 * size, 0, is_priority, |_| true, valid);
+    assert_eq!(hashes(&selected), in_block_order(&[payment1, payment2]));
+}
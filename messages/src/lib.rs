@@ -1004,6 +1004,15 @@ pub struct GetAccountsProofMessage {
     pub addresses: Vec<Address>
 }
 
+impl GetAccountsProofMessage {
+    pub fn new(block_hash: Blake2bHash, addresses: Vec<Address>) -> Message {
+        Message::GetAccountsProof(Box::new(GetAccountsProofMessage {
+            block_hash,
+            addresses,
+        }))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AccountsProofMessage {
     pub block_hash: Blake2bHash,
@@ -8,6 +8,8 @@ extern crate nimiq_mempool as mempool;
 extern crate nimiq_network as network;
 extern crate nimiq_block as block;
 extern crate nimiq_block_albatross as block_albatross;
+#[cfg(feature = "validator")]
+extern crate nimiq_validator as validator;
 
 use std::io;
 use std::io::Read;
@@ -28,6 +30,8 @@ use crate::error::Error;
 use crate::metrics::mempool::MempoolMetrics;
 use crate::metrics::network::NetworkMetrics;
 pub use crate::metrics::chain::{AbstractChainMetrics, NimiqChainMetrics, AlbatrossChainMetrics};
+#[cfg(feature = "validator")]
+pub use crate::metrics::validator::ValidatorMetrics;
 
 macro_rules! attributes {
     // Empty attributes.
@@ -74,7 +78,9 @@ pub struct MetricsServer {
 }
 
 impl MetricsServer {
-    pub fn new<P, CM>(ip: IpAddr, port: u16, username: Option<String>, password: Option<String>, pkcs12_key_file: &str, pkcs12_passphrase: &str, consensus: Arc<Consensus<P>>) -> Result<MetricsServer, Error>
+    /// Creates a metrics server for `consensus`. Metrics of other components (e.g. the validator)
+    /// can be passed in `additional_metrics`.
+    pub fn new<P, CM>(ip: IpAddr, port: u16, username: Option<String>, password: Option<String>, pkcs12_key_file: &str, pkcs12_passphrase: &str, consensus: Arc<Consensus<P>>, additional_metrics: Vec<Arc<dyn server::Metrics>>) -> Result<MetricsServer, Error>
         where P: ConsensusProtocol + 'static,
               CM: AbstractChainMetrics<P> + server::Metrics + 'static
     {
@@ -96,12 +102,14 @@ impl MetricsServer {
                         .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
                 }),
                 move || {
+                    let mut metrics: Vec<Arc<dyn server::Metrics>> = vec![
+                        Arc::new(CM::new(consensus.blockchain.clone())),
+                        Arc::new(MempoolMetrics::new(consensus.mempool.clone())),
+                        Arc::new(NetworkMetrics::new(consensus.network.clone()))
+                    ];
+                    metrics.extend(additional_metrics.iter().cloned());
                     server::MetricsServer::new(
-                        vec![
-                            Arc::new(CM::new(consensus.blockchain.clone())),
-                            Arc::new(MempoolMetrics::new(consensus.mempool.clone())),
-                            Arc::new(NetworkMetrics::new(consensus.network.clone()))
-                        ],
+                        metrics,
                         attributes! { "peer" => consensus.network.network_config.peer_address() },
                         username.clone(),
                         password.clone())
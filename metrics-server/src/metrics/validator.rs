@@ -0,0 +1,56 @@
+use std::io;
+use std::sync::Arc;
+
+use validator::validator::{Validator, ValidatorStatus};
+
+use crate::server;
+use crate::server::SerializationType;
+
+pub struct ValidatorMetrics {
+    validator: Arc<Validator>,
+}
+
+impl ValidatorMetrics {
+    pub fn new(validator: Arc<Validator>) -> Self {
+        ValidatorMetrics {
+            validator,
+        }
+    }
+}
+
+impl server::Metrics for ValidatorMetrics {
+    fn metrics(&self, serializer: &mut server::MetricsSerializer<SerializationType>) -> Result<(), io::Error> {
+        let info = self.validator.state_info();
+
+        for (status, name) in [
+            (ValidatorStatus::None, "none"),
+            (ValidatorStatus::Synced, "synced"),
+            (ValidatorStatus::Potential, "potential"),
+            (ValidatorStatus::Active, "active"),
+        ].iter() {
+            serializer.metric_with_attributes(
+                "validator_status",
+                if info.status == *status { 1 } else { 0 },
+                attributes!{"status" => name}
+            )?;
+        }
+        serializer.metric("validator_slots", info.slot_numbers.len())?;
+        serializer.metric("validator_view_number", info.view_number)?;
+        serializer.metric_with_attributes("validator_blocks", info.epoch_stats.blocks_produced, attributes!{"action" => "produced"})?;
+        serializer.metric_with_attributes("validator_blocks", info.epoch_stats.blocks_missed, attributes!{"action" => "missed"})?;
+        serializer.metric("validator_view_change_active", if info.active_view_change.is_some() { 1 } else { 0 })?;
+
+        let view_changes = self.validator.validator_network.view_change_progress();
+        serializer.metric("validator_view_changes_pending", view_changes.len())?;
+
+        // Progress of the most advanced pBFT proposal
+        let (prepare_votes, commit_votes) = self.validator.validator_network.pbft_progress().iter()
+            .map(|progress| (progress.prepare_votes, progress.commit_votes))
+            .max()
+            .unwrap_or((0, 0));
+        serializer.metric_with_attributes("validator_pbft_votes", prepare_votes, attributes!{"phase" => "prepare"})?;
+        serializer.metric_with_attributes("validator_pbft_votes", commit_votes, attributes!{"phase" => "commit"})?;
+
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@ bitflags! {
         const NANO  = 0b0000_0001;
         const LIGHT = 0b0000_0010;
         const FULL  = 0b0000_0100;
+        // Node prunes micro block bodies of old epochs and can't serve their transactions
+        const PRUNED = 0b0000_1000;
         // Node supports validator protocol
         const VALIDATOR  = 0b0100_0000_0000;
     }
@@ -26,6 +28,8 @@ impl ServiceFlags {
     }
 
     pub fn is_validator(self) -> bool { self.contains(ServiceFlags::VALIDATOR) }
+
+    pub fn is_pruned(self) -> bool { self.contains(ServiceFlags::PRUNED) }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,7 +11,7 @@ use transaction::Transaction;
 
 use crate::{Account, AccountError, AccountTransactionInteraction, AccountType, Inherent, InherentType, StakingContract};
 use crate::inherent::AccountInherentInteraction;
-use crate::staking_contract::actions::staker::InactiveStakeReceipt;
+use crate::staking_contract::actions::staker::{InactiveStakeReceipt, StakerRewardReceipt};
 use crate::staking_contract::actions::validator::{DropValidatorReceipt, InactiveValidatorReceipt, UnparkReceipt, UpdateValidatorReceipt};
 use crate::staking_contract::SlashReceipt;
 
@@ -61,15 +61,15 @@ impl AccountTransactionInteraction for StakingContract {
 
             let receipt: Option<Vec<u8>> = match data {
                 IncomingStakingTransactionData::CreateValidator {
-                    validator_key, reward_address, ..
+                    validator_key, reward_address, commission_rate, ..
                 } => {
-                    self.create_validator(validator_key, reward_address, transaction.value)?;
+                    self.create_validator(validator_key, reward_address, commission_rate, transaction.value)?;
                     None
                 },
                 IncomingStakingTransactionData::UpdateValidator {
-                    old_validator_key, new_validator_key, new_reward_address, ..
+                    old_validator_key, new_validator_key, new_reward_address, new_commission_rate, ..
                 } => {
-                    let receipt = self.update_validator(&old_validator_key, new_validator_key, new_reward_address)?;
+                    let receipt = self.update_validator(&old_validator_key, new_validator_key, new_reward_address, new_commission_rate)?;
                     Some(receipt.serialize_to_vec())
                 },
                 IncomingStakingTransactionData::RetireValidator {
@@ -317,8 +317,8 @@ impl AccountTransactionInteraction for StakingContract {
 impl AccountInherentInteraction for StakingContract {
     fn check_inherent(&self, inherent: &Inherent, _block_height: u32) -> Result<(), AccountError> {
         trace!("check inherent: {:?}", inherent);
-        // Inherent slashes nothing
-        if inherent.value != Coin::ZERO {
+        // Only reward inherents transfer value.
+        if (inherent.ty == InherentType::Reward) == (inherent.value == Coin::ZERO) {
             return Err(AccountError::InvalidInherent);
         }
 
@@ -345,7 +345,19 @@ impl AccountInherentInteraction for StakingContract {
 
                 Ok(())
             },
-            InherentType::Reward => Err(AccountError::InvalidForTarget)
+            InherentType::Reward => {
+                // Invalid data length
+                if inherent.data.len() != BlsPublicKey::SIZE {
+                    return Err(AccountError::InvalidInherent);
+                }
+
+                // Validator doesn't exist in contract or doesn't have any stakers for the whole epoch
+                let validator_key: BlsPublicKey = Deserialize::deserialize(&mut &inherent.data[..])?;
+                match self.get_validator(&validator_key) {
+                    Some(validator) if !validator.eligible_stake().is_zero() => Ok(()),
+                    _ => Err(AccountError::InvalidInherent),
+                }
+            },
         }
     }
 
@@ -378,10 +390,18 @@ impl AccountInherentInteraction for StakingContract {
                     }
                 }
 
+                // The rewards of the finalized epoch were paid out by the preceding inherents.
+                self.start_epoch()?;
+
                 // Since finalized epochs cannot be reverted, we don't need any receipts.
                 Ok(None)
             },
-            _ => unreachable!(),
+            InherentType::Reward => {
+                // Add the reward to the stake of the validator's stakers.
+                let validator_key: BlsPublicKey = Deserialize::deserialize(&mut &inherent.data[..])?;
+                let receipt = self.distribute_stakers_reward(&validator_key, inherent.value)?;
+                Ok(Some(receipt.serialize_to_vec()))
+            },
         }
     }
 
@@ -404,7 +424,11 @@ impl AccountInherentInteraction for StakingContract {
                 // We should not be able to revert finalized epochs!
                 return Err(AccountError::InvalidForTarget);
             },
-            _ => unreachable!(),
+            InherentType::Reward => {
+                let receipt: StakerRewardReceipt = Deserialize::deserialize_from_vec(&receipt.ok_or(AccountError::InvalidReceipt)?)?;
+                let validator_key: BlsPublicKey = Deserialize::deserialize(&mut &inherent.data[..])?;
+                self.revert_distribute_stakers_reward(&validator_key, inherent.value, receipt)?;
+            },
         }
 
         Ok(())
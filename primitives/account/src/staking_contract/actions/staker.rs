@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use beserial::{Deserialize, Serialize};
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
 use keys::Address;
@@ -11,6 +13,12 @@ pub(super) struct InactiveStakeReceipt {
     retire_time: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub(super) struct StakerRewardReceipt {
+    #[beserial(len_type(u32))]
+    reward_by_address: BTreeMap<Address, Coin>,
+}
+
 /// Actions concerning a staker are:
 /// 1. Stake: Delegate stake from an outside address to a validator.
 /// 2. Retire: Remove stake from a validator and make it inactive
@@ -18,6 +26,8 @@ pub(super) struct InactiveStakeReceipt {
 /// 3. Re-activate: Re-delegate inactive stake to a validator.
 /// 4. Unstake: Remove inactive stake from the staking contract
 ///             (after it has been inactive for the cooldown period).
+/// 5. Reward: Add the stakers' share of a validator's epoch reward to their stake
+///            (a reward inherent to the staking contract).
 ///
 /// The actions can be summarized by the following state diagram:
 ///        +--------+   retire    +----------+
@@ -217,4 +227,49 @@ impl StakingContract {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Distributes the stakers' share of an epoch reward of validator `validator_key` pro rata
+    /// to the active stake of its stakers.
+    pub(super) fn distribute_stakers_reward(&mut self, validator_key: &BlsPublicKey, reward: Coin) -> Result<StakerRewardReceipt, AccountError> {
+        let new_balance = Account::balance_add(self.balance, reward)?;
+
+        let mut entry = self.remove_validator(validator_key)
+            .ok_or(AccountError::InvalidInherent)?;
+        let reward_by_address = entry.as_validator().split_stakers_reward(reward);
+        if reward_by_address.is_empty() {
+            self.restore_validator(entry)?;
+            return Err(AccountError::InvalidInherent);
+        }
+        entry.try_add_stakes(&reward_by_address);
+        self.restore_validator(entry)?;
+
+        self.balance = new_balance;
+
+        Ok(StakerRewardReceipt {
+            reward_by_address,
+        })
+    }
+
+    /// Reverts distributing an epoch reward to the stakers of validator `validator_key`.
+    pub(super) fn revert_distribute_stakers_reward(&mut self, validator_key: &BlsPublicKey, reward: Coin, receipt: StakerRewardReceipt) -> Result<(), AccountError> {
+        let new_balance = Account::balance_sub(self.balance, reward)?;
+
+        // The receipt must account for the whole reward.
+        let mut total_reward = Coin::ZERO;
+        for &staker_reward in receipt.reward_by_address.values() {
+            total_reward = Account::balance_add(total_reward, staker_reward)?;
+        }
+        if total_reward != reward {
+            return Err(AccountError::InvalidReceipt);
+        }
+
+        let mut entry = self.remove_validator(validator_key)
+            .ok_or(AccountError::InvalidInherent)?;
+        entry.try_sub_stakes(&receipt.reward_by_address, AccountError::InvalidReceipt);
+        self.restore_validator(entry)?;
+
+        self.balance = new_balance;
+
+        Ok(())
+    }
+}
@@ -21,6 +21,7 @@ pub(super) struct UnparkReceipt {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub(super) struct UpdateValidatorReceipt {
     old_reward_address: Address,
+    old_pending_commission_rate: Option<u16>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -32,8 +33,12 @@ pub(super) struct RetirementReceipt {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub(super) struct DropValidatorReceipt {
     reward_address: Address,
+    commission_rate: u16,
+    pending_commission_rate: Option<u16>,
     #[beserial(len_type(u32))]
     retirement_by_address: BTreeMap<Address, RetirementReceipt>,
+    #[beserial(len_type(u32))]
+    epoch_stake_by_address: BTreeMap<Address, Coin>,
     retire_time: u32,
     unpark_receipt: UnparkReceipt,
 }
@@ -45,7 +50,8 @@ pub(super) struct InactiveValidatorReceipt {
 
 /// Actions concerning a validator are:
 /// 1. Create: Creates a validator entry.
-/// 2. Update: Updates reward address and key of the validator entry.
+/// 2. Update: Updates reward address and key of the validator entry. A new commission rate only
+///            takes effect in the next epoch.
 /// 3. Retire: Inactivates a validator entry (also starts a cooldown period used for Drop).
 /// 4. Re-activate: Re-activates a validator entry.
 /// 5. Drop: Drops a validator entry (validator must have been inactive for the cooldown period).
@@ -75,7 +81,7 @@ impl StakingContract {
     /// Creates a new validator entry.
     /// The initial stake can only be retrieved by dropping the validator again.
     /// XXX This is public to fill the genesis staking contract
-    pub fn create_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, initial_stake: Coin) -> Result<(), AccountError> {
+    pub fn create_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, commission_rate: u16, initial_stake: Coin) -> Result<(), AccountError> {
         if self.active_validators_by_key.contains_key(&validator_key)
             || self.inactive_validators_by_key.contains_key(&validator_key) {
             return Err(AccountError::InvalidForRecipient);
@@ -85,7 +91,7 @@ impl StakingContract {
 
         // All checks passed, not allowed to fail from here on!
         let validator = Arc::new(
-            Validator::new(initial_stake, reward_address, validator_key.clone())
+            Validator::new(initial_stake, reward_address, validator_key.clone(), commission_rate)
         );
 
         self.active_validators_sorted.insert(Arc::clone(&validator));
@@ -108,16 +114,20 @@ impl StakingContract {
 
     /// Update validator details.
     /// This can be used to update active and inactive validators.
-    pub(super) fn update_validator(&mut self, old_validator_key: &BlsPublicKey, new_validator_key: Option<BlsPublicKey>, new_reward_address: Option<Address>) -> Result<UpdateValidatorReceipt, AccountError> {
+    /// The new commission rate stays pending until the current epoch is finalized, so it doesn't
+    /// apply to the rewards of the current epoch.
+    pub(super) fn update_validator(&mut self, old_validator_key: &BlsPublicKey, new_validator_key: Option<BlsPublicKey>, new_reward_address: Option<Address>, new_commission_rate: Option<u16>) -> Result<UpdateValidatorReceipt, AccountError> {
         let mut entry = self.remove_validator(old_validator_key)
             .ok_or(AccountError::InvalidForRecipient)?;
 
         let old_reward_address = entry.as_validator().reward_address.clone();
-        entry.update_validator(new_reward_address, new_validator_key);
+        let old_pending_commission_rate = entry.as_validator().pending_commission_rate;
+        entry.update_validator(new_reward_address, new_validator_key, new_commission_rate.or(old_pending_commission_rate));
         self.restore_validator(entry)?;
 
         Ok(UpdateValidatorReceipt {
             old_reward_address,
+            old_pending_commission_rate,
         })
     }
 
@@ -126,7 +136,7 @@ impl StakingContract {
         let mut entry = self.remove_validator(new_validator_key.as_ref().unwrap_or(&old_validator_key))
             .ok_or(AccountError::InvalidForRecipient)?;
 
-        entry.update_validator(Some(receipt.old_reward_address), Some(old_validator_key));
+        entry.update_validator(Some(receipt.old_reward_address), Some(old_validator_key), receipt.old_pending_commission_rate);
         self.restore_validator(entry)?;
 
         Ok(())
@@ -169,7 +179,10 @@ impl StakingContract {
 
         Ok(DropValidatorReceipt {
             reward_address: validator.reward_address.clone(),
+            commission_rate: validator.commission_rate,
+            pending_commission_rate: validator.pending_commission_rate,
             retirement_by_address,
+            epoch_stake_by_address: validator.epoch_stake_by_address.read().clone(),
             retire_time: inactive_validator.retire_time,
             unpark_receipt,
         })
@@ -195,7 +208,10 @@ impl StakingContract {
                 balance: total_value,
                 reward_address: receipt.reward_address,
                 validator_key,
+                commission_rate: receipt.commission_rate,
+                pending_commission_rate: receipt.pending_commission_rate,
                 active_stake_by_address: RwLock::new(active_stake_by_address),
+                epoch_stake_by_address: RwLock::new(receipt.epoch_stake_by_address),
             }),
             retire_time: receipt.retire_time,
         });
@@ -257,6 +273,22 @@ impl StakingContract {
         })
     }
 
+    /// Starts a new epoch for all validators once the rewards of the finalized epoch were paid
+    /// out, see `Validator::start_epoch`.
+    /// XXX This is public to fill the genesis staking contract
+    pub fn start_epoch(&mut self) -> Result<(), AccountError> {
+        let validator_keys: Vec<BlsPublicKey> = self.active_validators_by_key.keys()
+            .chain(self.inactive_validators_by_key.keys())
+            .cloned()
+            .collect();
+        for validator_key in validator_keys {
+            let mut entry = self.remove_validator(&validator_key).unwrap();
+            entry.start_epoch();
+            self.restore_validator(entry)?;
+        }
+        Ok(())
+    }
+
     /// Reverts an unparking transaction.
     pub(super) fn revert_unpark_validator(&mut self, validator_key: &BlsPublicKey, receipt: UnparkReceipt) -> Result<(), AccountError> {
         if receipt.current_epoch {
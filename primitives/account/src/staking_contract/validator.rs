@@ -10,6 +10,7 @@ use beserial::{Deserialize, DeserializeWithLength, ReadBytesExt, Serialize, Seri
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
 use keys::Address;
 use primitives::coin::Coin;
+use primitives::policy;
 
 use crate::{Account, AccountError};
 
@@ -18,39 +19,144 @@ pub struct Validator {
     pub balance: Coin,
     pub reward_address: Address,
     pub validator_key: BlsPublicKey,
+    /// Share of the rewards the validator keeps before the rest is split with its stakers,
+    /// in basis points (see `policy::COMMISSION_RATE_MAX`).
+    pub commission_rate: u16,
+    /// Commission rate that takes effect in the next epoch, after the rewards of the current epoch
+    /// were paid out.
+    pub pending_commission_rate: Option<u16>,
     pub active_stake_by_address: RwLock<BTreeMap<Address, Coin>>,
+    /// Active stake of each staker at the start of the current epoch.
+    pub epoch_stake_by_address: RwLock<BTreeMap<Address, Coin>>,
 }
 
 impl Validator {
-    pub fn new(initial_balance: Coin, reward_address: Address, validator_key: BlsPublicKey) -> Self {
+    pub fn new(initial_balance: Coin, reward_address: Address, validator_key: BlsPublicKey, commission_rate: u16) -> Self {
         Validator {
             balance: initial_balance,
             reward_address,
             validator_key,
+            commission_rate,
+            pending_commission_rate: None,
             active_stake_by_address: Default::default(),
+            epoch_stake_by_address: Default::default(),
         }
     }
 
-    pub fn update_validator(&self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>) -> Self {
-        let active_stake_by_address = mem::take(self.active_stake_by_address.write().deref_mut());
-        Validator {
-            balance: self.balance,
-            reward_address: new_reward_address.unwrap_or_else(|| self.reward_address.clone()),
-            validator_key: new_validator_key.unwrap_or_else(|| self.validator_key.clone()),
-            active_stake_by_address: RwLock::new(active_stake_by_address),
+    /// Updates the reward address and the validator key and replaces the pending commission rate.
+    pub fn update_validator(&self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>, pending_commission_rate: Option<u16>) -> Self {
+        let mut validator = self.with_balance(self.balance);
+        if let Some(new_reward_address) = new_reward_address {
+            validator.reward_address = new_reward_address;
         }
+        if let Some(new_validator_key) = new_validator_key {
+            validator.validator_key = new_validator_key;
+        }
+        validator.pending_commission_rate = pending_commission_rate;
+        validator
+    }
+
+    /// Starts a new epoch: The pending commission rate takes effect and the current active stake
+    /// becomes the stake that earns the rewards of the new epoch.
+    pub fn start_epoch(&self) -> Self {
+        let mut validator = self.with_balance(self.balance);
+        validator.commission_rate = self.pending_commission_rate.unwrap_or(self.commission_rate);
+        validator.pending_commission_rate = None;
+        *validator.epoch_stake_by_address.write() = validator.active_stake_by_address.read().clone();
+        validator
     }
 
     fn with_balance(&self, balance: Coin) -> Self {
         let active_stake_by_address = mem::take(self.active_stake_by_address.write().deref_mut());
+        let epoch_stake_by_address = mem::take(self.epoch_stake_by_address.write().deref_mut());
         Validator {
             balance,
             reward_address: self.reward_address.clone(),
             validator_key: self.validator_key.clone(),
+            commission_rate: self.commission_rate,
+            pending_commission_rate: self.pending_commission_rate,
             active_stake_by_address: RwLock::new(active_stake_by_address),
+            epoch_stake_by_address: RwLock::new(epoch_stake_by_address),
         }
     }
 
+    /// Total stake delegated to this validator, i.e. the balance without the validator's own deposit.
+    pub fn total_stake(&self) -> Coin {
+        self.active_stake_by_address.read().values()
+            .fold(Coin::ZERO, |total, &stake| total + stake)
+    }
+
+    /// Stake of each staker that was active during the whole current epoch, i.e. the stake at the
+    /// start of the epoch minus what was retired since.
+    pub fn eligible_stake_by_address(&self) -> BTreeMap<Address, Coin> {
+        let active_stake_by_address = self.active_stake_by_address.read();
+        self.epoch_stake_by_address.read().iter()
+            .filter_map(|(staker_address, &epoch_stake)| {
+                let active_stake = active_stake_by_address.get(staker_address).cloned().unwrap_or(Coin::ZERO);
+                let stake = active_stake.min(epoch_stake);
+                if stake.is_zero() { None } else { Some((staker_address.clone(), stake)) }
+            })
+            .collect()
+    }
+
+    /// Total stake delegated to this validator that earns rewards in the current epoch.
+    pub fn eligible_stake(&self) -> Coin {
+        self.eligible_stake_by_address().values()
+            .fold(Coin::ZERO, |total, &stake| total + stake)
+    }
+
+    /// Computes the part of `reward` that belongs to the stakers of this validator.
+    /// The validator first takes its commission. The remainder is split pro rata between the
+    /// validator's own deposit and the stake that was delegated during the whole epoch.
+    /// The rest of `reward` belongs to the validator.
+    pub fn stakers_reward(&self, reward: Coin) -> Coin {
+        let deposit = u64::from(self.balance) - u64::from(self.total_stake());
+        let eligible_stake = u64::from(self.eligible_stake());
+        if eligible_stake == 0 {
+            return Coin::ZERO;
+        }
+
+        let reward = u64::from(reward) as u128;
+        let commission = reward * u128::from(self.commission_rate) / u128::from(policy::COMMISSION_RATE_MAX);
+        let stakers_reward = (reward - commission) * eligible_stake as u128 / (deposit + eligible_stake) as u128;
+        Coin::from_u64_unchecked(stakers_reward as u64)
+    }
+
+    /// Splits `reward` pro rata between the stakers of this validator, according to the stake that
+    /// was active during the whole epoch.
+    /// The remainder of the integer division is handed out one unit at a time in address order.
+    pub fn split_stakers_reward(&self, reward: Coin) -> BTreeMap<Address, Coin> {
+        let eligible_stake_by_address = self.eligible_stake_by_address();
+        let total_stake = eligible_stake_by_address.values()
+            .fold(0u128, |total, &stake| total + u64::from(stake) as u128);
+        if total_stake == 0 {
+            return BTreeMap::new();
+        }
+
+        let reward = u64::from(reward) as u128;
+        let mut remainder = reward;
+        let mut rewards = BTreeMap::new();
+        for (staker_address, &stake) in eligible_stake_by_address.iter() {
+            let share = reward * u64::from(stake) as u128 / total_stake;
+            remainder -= share;
+            rewards.insert(staker_address.clone(), share as u64);
+        }
+
+        // The remainder is smaller than the number of stakers.
+        for share in rewards.values_mut() {
+            if remainder == 0 {
+                break;
+            }
+            *share += 1;
+            remainder -= 1;
+        }
+
+        rewards.into_iter()
+            .filter(|(_, share)| *share > 0)
+            .map(|(staker_address, share)| (staker_address, Coin::from_u64_unchecked(share)))
+            .collect()
+    }
+
     pub fn add_stake(&self, staker_address: Address, stake: Coin) -> Result<Self, AccountError> {
         let new_balance = Account::balance_add(self.balance, stake)?;
         let validator = self.with_balance(new_balance);
@@ -80,6 +186,51 @@ impl Validator {
         let validator = self.with_balance(new_balance);
         Ok(validator)
     }
+
+    /// Adds the stakes of several stakers at once. Either all stakes are added or none is.
+    pub fn add_stakes(&self, stakes: &BTreeMap<Address, Coin>) -> Result<Self, AccountError> {
+        let mut new_balance = self.balance;
+        for &stake in stakes.values() {
+            new_balance = Account::balance_add(new_balance, stake)?;
+        }
+        let validator = self.with_balance(new_balance);
+
+        // We do not need to check for overflows here, because the new balance is always larger.
+        let mut active_stake_by_address = validator.active_stake_by_address.write();
+        for (staker_address, &stake) in stakes.iter() {
+            *active_stake_by_address.entry(staker_address.clone())
+                .or_insert(Coin::ZERO) += stake;
+        }
+        drop(active_stake_by_address);
+        Ok(validator)
+    }
+
+    /// Removes the stakes of several stakers at once. Either all stakes are removed or none is.
+    pub fn sub_stakes(&self, stakes: &BTreeMap<Address, Coin>, not_present_error: AccountError) -> Result<Self, AccountError> {
+        // Check all stakes before changing any of them.
+        let mut new_balance = self.balance;
+        let mut new_stakes = Vec::with_capacity(stakes.len());
+        let active_stake_by_address = self.active_stake_by_address.read();
+        for (staker_address, &value) in stakes.iter() {
+            let stake = active_stake_by_address.get(staker_address)
+                .ok_or_else(|| not_present_error.clone())?;
+            new_stakes.push((staker_address, Account::balance_sub(*stake, value)?));
+            new_balance = Account::balance_sub(new_balance, value)?;
+        }
+        drop(active_stake_by_address);
+
+        let validator = self.with_balance(new_balance);
+        let mut active_stake_by_address = validator.active_stake_by_address.write();
+        for (staker_address, stake) in new_stakes {
+            if stake.is_zero() {
+                active_stake_by_address.remove(staker_address);
+            } else {
+                active_stake_by_address.insert(staker_address.clone(), stake);
+            }
+        }
+        drop(active_stake_by_address);
+        Ok(validator)
+    }
 }
 
 impl Serialize for Validator {
@@ -88,7 +239,10 @@ impl Serialize for Validator {
         size += Serialize::serialize(&self.balance, writer)?;
         size += Serialize::serialize(&self.reward_address, writer)?;
         size += Serialize::serialize(&self.validator_key, writer)?;
+        size += Serialize::serialize(&self.commission_rate, writer)?;
+        size += Serialize::serialize(&self.pending_commission_rate, writer)?;
         size += SerializeWithLength::serialize::<u32, _>(self.active_stake_by_address.read().deref(), writer)?;
+        size += SerializeWithLength::serialize::<u32, _>(self.epoch_stake_by_address.read().deref(), writer)?;
         Ok(size)
     }
 
@@ -97,7 +251,10 @@ impl Serialize for Validator {
         size += Serialize::serialized_size(&self.balance);
         size += Serialize::serialized_size(&self.reward_address);
         size += Serialize::serialized_size(&self.validator_key);
+        size += Serialize::serialized_size(&self.commission_rate);
+        size += Serialize::serialized_size(&self.pending_commission_rate);
         size += SerializeWithLength::serialized_size::<u32>(self.active_stake_by_address.read().deref());
+        size += SerializeWithLength::serialized_size::<u32>(self.epoch_stake_by_address.read().deref());
         size
     }
 }
@@ -107,12 +264,18 @@ impl Deserialize for Validator {
         let balance = Deserialize::deserialize(reader)?;
         let reward_address = Deserialize::deserialize(reader)?;
         let validator_key = Deserialize::deserialize(reader)?;
+        let commission_rate = Deserialize::deserialize(reader)?;
+        let pending_commission_rate = Deserialize::deserialize(reader)?;
         let active_stake_by_address: BTreeMap<Address, Coin> = DeserializeWithLength::deserialize::<u32, _>(reader)?;
+        let epoch_stake_by_address: BTreeMap<Address, Coin> = DeserializeWithLength::deserialize::<u32, _>(reader)?;
         Ok(Validator {
             balance,
             reward_address,
             validator_key,
+            commission_rate,
+            pending_commission_rate,
             active_stake_by_address: RwLock::new(active_stake_by_address),
+            epoch_stake_by_address: RwLock::new(epoch_stake_by_address),
         })
     }
 }
@@ -146,7 +309,10 @@ impl Clone for Validator {
             balance: self.balance,
             reward_address: self.reward_address.clone(),
             validator_key: self.validator_key.clone(),
+            commission_rate: self.commission_rate,
+            pending_commission_rate: self.pending_commission_rate,
             active_stake_by_address: RwLock::new(self.active_stake_by_address.read().clone()),
+            epoch_stake_by_address: RwLock::new(self.epoch_stake_by_address.read().clone()),
         }
     }
 }
@@ -215,12 +381,16 @@ impl ValidatorEntry {
         }
     }
 
-    pub fn update_validator(&mut self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>) {
+    pub fn update_validator(&mut self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>, pending_commission_rate: Option<u16>) {
         self.replace(Ok(Arc::new(
-            self.as_validator().update_validator(new_reward_address, new_validator_key)
+            self.as_validator().update_validator(new_reward_address, new_validator_key, pending_commission_rate)
         )))
     }
 
+    pub fn start_epoch(&mut self) {
+        self.replace(Ok(Arc::new(self.as_validator().start_epoch())))
+    }
+
     /// This function will only change the validator entry if add_stake is successful.
     pub fn try_add_stake(&mut self, staker_address: Address, stake: Coin) {
         let new_validator = self.as_validator().add_stake(staker_address, stake);
@@ -232,4 +402,16 @@ impl ValidatorEntry {
         let new_validator = self.as_validator().sub_stake(staker_address, value, not_present_error);
         self.replace(new_validator.map(Arc::new));
     }
+
+    /// This function will only change the validator entry if add_stakes is successful.
+    pub fn try_add_stakes(&mut self, stakes: &BTreeMap<Address, Coin>) {
+        let new_validator = self.as_validator().add_stakes(stakes);
+        self.replace(new_validator.map(Arc::new));
+    }
+
+    /// This function will only change the validator entry if sub_stakes is successful.
+    pub fn try_sub_stakes(&mut self, stakes: &BTreeMap<Address, Coin>, not_present_error: AccountError) {
+        let new_validator = self.as_validator().sub_stakes(stakes, not_present_error);
+        self.replace(new_validator.map(Arc::new));
+    }
 }
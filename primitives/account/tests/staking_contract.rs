@@ -19,7 +19,7 @@ use nimiq_transaction::account::AccountTransactionVerification;
 use nimiq_transaction::account::staking_contract::{IncomingStakingTransactionData, OutgoingStakingTransactionProof, SelfStakingTransactionData};
 
 const CONTRACT_1: &str = "00000000000000000000000000000000000000000000000000000000";
-const CONTRACT_2: &str = "0000000023c34600000000010000000023c3460003030303030303030303030303030303030303038dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609000000020202020202020202020202020202020202020202000000000bebc2005e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e0000000005f5e10000000000000000000000000000000000";
+const CONTRACT_2: &str = "0000000023c34600000000010000000023c3460003030303030303030303030303030303030303038dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609000000000000020202020202020202020202020202020202020202000000000bebc2005e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e0000000005f5e1000000000000000000000000000000000000000000";
 const VALIDATOR_KEY: &str = "8dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609";
 const VALIDATOR_SECRET_KEY: &str = "49ea68eb6b8afdf4ca4d4c0a0b295c76ca85225293693bc30e755476492b707f";
 const STAKER_ADDRESS: &str = "9cd82948650d902d95d52ea2ec91eae6deb0c9fe";
@@ -39,7 +39,7 @@ const STAKER_PRIVATE_KEY: &str = "b410a7a583cbc13ef4f1cbddace30928bcb4f9c1372241
 //        previous_epoch_parking: Default::default(),
 //        inactive_stake_by_address: Default::default()
 //    };
-//    contract.create_validator(key_pair.public.compress(), Address::from([3u8; 20]), 300_000_000.try_into().unwrap());
+//    contract.create_validator(key_pair.public.compress(), Address::from([3u8; 20]), 0, 300_000_000.try_into().unwrap());
 //    contract.stake(Address::from([2u8; 20]), 200_000_000.try_into().unwrap(), &key_pair.public.compress());
 //    contract.stake(Address::from([0x5eu8; 20]), 100_000_000.try_into().unwrap(), &key_pair.public.compress());
 //    assert_eq!(&hex::encode(contract.serialize_to_vec()), "");
@@ -117,6 +117,7 @@ fn it_can_verify_validator_and_staking_transaction() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: keypair.sign(&validator_key.serialize_to_vec()).compress(),
         reward_address: Address::from([3u8; 20]),
+        commission_rate: 0,
     }, 100_000_000);
 
     // Valid
@@ -133,6 +134,7 @@ fn it_can_verify_validator_and_staking_transaction() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: invalid_pok.compress(),
         reward_address: Address::from([3u8; 20]),
+        commission_rate: 0,
     }, 100_000_000);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
 
@@ -164,6 +166,7 @@ fn it_can_apply_validator_and_staking_transaction() {
         validator_key: bls_pair.public.compress(),
         proof_of_knowledge: proof_of_knowledge.clone(),
         reward_address: Default::default(),
+        commission_rate: 0,
     }, 150_000_000);
     assert_eq!(StakingContract::check_incoming_transaction(&tx_1, 2), Ok(()));
     assert_eq!(contract.commit_incoming_transaction(&tx_1, 2), Ok(None));
@@ -489,8 +492,165 @@ fn it_can_verify_inherent() {
         value: Coin::ZERO,
         data: Vec::new(),
     };
-    assert_eq!(contract.check_inherent(&inherent_1, 0), Err(AccountError::InvalidForTarget));
-    assert_eq!(contract.commit_inherent(&inherent_1, 0), Err(AccountError::InvalidForTarget));
+    assert_eq!(contract.check_inherent(&inherent_1, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.commit_inherent(&inherent_1, 0), Err(AccountError::InvalidInherent));
+}
+
+#[test]
+fn it_can_distribute_staker_rewards() {
+    let key_pair = ed25519_key_pair();
+    let bls_pair = bls_key_pair();
+    let validator_key = bls_pair.public.compress();
+    let staker_address = Address::from_any_str(STAKER_ADDRESS).unwrap();
+
+    // Validator with a commission of 10%
+    let mut contract = make_empty_contract();
+    contract.create_validator(validator_key.clone(), Address::from(&key_pair), 1_000, Coin::from_u64_unchecked(100_000_000)).unwrap();
+    contract.stake(Address::from(&key_pair), Coin::from_u64_unchecked(150_000_000), &validator_key).unwrap();
+    contract.stake(staker_address.clone(), Coin::from_u64_unchecked(50_000_000), &validator_key).unwrap();
+    contract.start_epoch().unwrap();
+
+    // The validator keeps its commission and the share of its own deposit.
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.total_stake(), Coin::from_u64_unchecked(200_000_000));
+    let stakers_reward = validator.stakers_reward(Coin::from_u64_unchecked(1_004));
+    assert_eq!(stakers_reward, Coin::from_u64_unchecked(602));
+
+    let inherent = Inherent {
+        ty: InherentType::Reward,
+        target: Address::from([1u8; 20]),
+        value: stakers_reward,
+        data: validator_key.serialize_to_vec(),
+    };
+    assert_eq!(contract.check_inherent(&inherent, 0), Ok(()));
+    let receipt = contract.commit_inherent(&inherent, 0).unwrap();
+    assert!(receipt.is_some());
+
+    // The reward is split pro rata, the remainder goes to the first staker in address order.
+    let mut stakes = vec![
+        (Address::from(&key_pair), 150_000_451),
+        (staker_address.clone(), 50_000_150),
+    ];
+    stakes.sort();
+    stakes[0].1 += 1;
+    let validator = contract.get_validator(&validator_key).unwrap();
+    for (address, stake) in stakes {
+        assert_eq!(validator.active_stake_by_address.read().get(&address), Some(&Coin::from_u64_unchecked(stake)));
+    }
+    assert_eq!(validator.balance, Coin::from_u64_unchecked(300_000_602));
+    assert_eq!(contract.balance, Coin::from_u64_unchecked(300_000_602));
+
+    // Receipts that don't match the distributed reward can't be reverted and leave the contract unchanged.
+    let staker_stake = validator.active_stake_by_address.read().get(&staker_address).cloned();
+    let mut wrong_total = 1u32.serialize_to_vec();
+    wrong_total.extend(staker_address.serialize_to_vec());
+    wrong_total.extend(Coin::from_u64_unchecked(150).serialize_to_vec());
+    let mut unknown_staker = 2u32.serialize_to_vec();
+    unknown_staker.extend(Address::from([0u8; 20]).serialize_to_vec());
+    unknown_staker.extend(Coin::from_u64_unchecked(452).serialize_to_vec());
+    unknown_staker.extend(staker_address.serialize_to_vec());
+    unknown_staker.extend(Coin::from_u64_unchecked(150).serialize_to_vec());
+    for tampered_receipt in vec![wrong_total, unknown_staker] {
+        assert_eq!(contract.revert_inherent(&inherent, 0, Some(&tampered_receipt)), Err(AccountError::InvalidReceipt));
+        let validator = contract.get_validator(&validator_key).unwrap();
+        assert_eq!(validator.active_stake_by_address.read().get(&staker_address).cloned(), staker_stake);
+        assert_eq!(validator.total_stake(), Coin::from_u64_unchecked(200_000_602));
+        assert_eq!(validator.balance, Coin::from_u64_unchecked(300_000_602));
+        assert_eq!(contract.balance, Coin::from_u64_unchecked(300_000_602));
+    }
+
+    // Revert the reward.
+    contract.revert_inherent(&inherent, 0, receipt.as_ref()).unwrap();
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.active_stake_by_address.read().get(&Address::from(&key_pair)), Some(&Coin::from_u64_unchecked(150_000_000)));
+    assert_eq!(validator.active_stake_by_address.read().get(&staker_address), Some(&Coin::from_u64_unchecked(50_000_000)));
+    assert_eq!(validator.balance, Coin::from_u64_unchecked(300_000_000));
+    assert_eq!(contract.balance, Coin::from_u64_unchecked(300_000_000));
+
+    // Validators without stakers can't receive staker rewards.
+    let mut contract = make_empty_contract();
+    contract.create_validator(validator_key.clone(), Address::from(&key_pair), 1_000, Coin::from_u64_unchecked(100_000_000)).unwrap();
+    assert_eq!(contract.check_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+}
+
+#[test]
+fn it_only_rewards_stake_of_the_whole_epoch() {
+    let key_pair = ed25519_key_pair();
+    let bls_pair = bls_key_pair();
+    let validator_key = bls_pair.public.compress();
+    let staker_address = Address::from_any_str(STAKER_ADDRESS).unwrap();
+
+    let mut contract = make_empty_contract();
+    contract.create_validator(validator_key.clone(), Address::from(&key_pair), 0, Coin::from_u64_unchecked(100_000_000)).unwrap();
+    contract.stake(Address::from(&key_pair), Coin::from_u64_unchecked(100_000_000), &validator_key).unwrap();
+    contract.start_epoch().unwrap();
+
+    // Stake added during the epoch doesn't earn rewards yet.
+    contract.stake(staker_address.clone(), Coin::from_u64_unchecked(100_000_000), &validator_key).unwrap();
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.total_stake(), Coin::from_u64_unchecked(200_000_000));
+    assert_eq!(validator.eligible_stake(), Coin::from_u64_unchecked(100_000_000));
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(500));
+    let rewards = validator.split_stakers_reward(Coin::from_u64_unchecked(500));
+    assert_eq!(rewards.len(), 1);
+    assert_eq!(rewards.get(&Address::from(&key_pair)), Some(&Coin::from_u64_unchecked(500)));
+
+    // It does in the next epoch.
+    contract.start_epoch().unwrap();
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.eligible_stake(), Coin::from_u64_unchecked(200_000_000));
+
+    // A staker that retires stake during the epoch only earns rewards for the remaining stake.
+    let validator = validator.sub_stake(&staker_address, Coin::from_u64_unchecked(60_000_000), AccountError::InvalidForRecipient).unwrap();
+    assert_eq!(validator.eligible_stake_by_address().get(&staker_address), Some(&Coin::from_u64_unchecked(40_000_000)));
+
+    // Stake that is added back doesn't earn more than the stake at the start of the epoch.
+    let validator = validator.add_stake(staker_address.clone(), Coin::from_u64_unchecked(100_000_000)).unwrap();
+    assert_eq!(validator.eligible_stake_by_address().get(&staker_address), Some(&Coin::from_u64_unchecked(100_000_000)));
+}
+
+#[test]
+fn it_applies_commission_updates_in_the_next_epoch() {
+    let key_pair = ed25519_key_pair();
+    let bls_pair = bls_key_pair();
+    let validator_key = bls_pair.public.compress();
+    let mut contract = make_sample_contract(&key_pair, &bls_pair);
+    contract.start_epoch().unwrap();
+
+    let update = IncomingStakingTransactionData::UpdateValidator {
+        old_validator_key: validator_key.clone(),
+        new_validator_key: None,
+        new_proof_of_knowledge: None,
+        new_reward_address: None,
+        new_commission_rate: Some(5_000),
+        signature: Default::default(),
+    };
+    let tx = make_signed_incoming_transaction(update, 0, &bls_pair);
+    let receipt = contract.commit_incoming_transaction(&tx, 2).unwrap();
+
+    // The new commission rate doesn't apply to the rewards of the current epoch.
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission_rate, 0);
+    assert_eq!(validator.pending_commission_rate, Some(5_000));
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(500));
+
+    // Reverting the update drops the pending commission rate.
+    contract.revert_incoming_transaction(&tx, 2, receipt.as_ref()).unwrap();
+    assert_eq!(contract.get_validator(&validator_key).unwrap().pending_commission_rate, None);
+    contract.commit_incoming_transaction(&tx, 2).unwrap();
+
+    // Once the epoch is finalized, the rewards were paid out and the new rate takes effect.
+    let finalize_epoch = Inherent {
+        ty: InherentType::FinalizeEpoch,
+        target: Address::from([1u8; 20]),
+        value: Coin::ZERO,
+        data: Vec::new(),
+    };
+    contract.commit_inherent(&finalize_epoch, 128).unwrap();
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission_rate, 5_000);
+    assert_eq!(validator.pending_commission_rate, None);
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(250));
 }
 
 #[test]
@@ -1030,6 +1190,7 @@ fn it_can_build_a_validator_set() {
     contract.create_validator(
         validator1.clone(),
         staker1.clone(),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
 
@@ -1057,11 +1218,13 @@ fn it_can_build_a_validator_set() {
     contract.create_validator(
         validator2.clone(),
         staker2.clone(),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
     contract.create_validator(
         validator3.clone(),
         staker3.clone(),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
 
@@ -1129,6 +1292,7 @@ fn it_can_apply_validator_signalling() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: bls_pair.sign(&validator_key.serialize_to_vec()).compress(),
         reward_address: Address::from([3u8; 20]),
+        commission_rate: 0,
     }, 100_000_000);
 
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
@@ -1174,7 +1338,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: Some(validator_key2.clone()),
         new_proof_of_knowledge: Some(bls_pair2.sign(&validator_key.serialize_to_vec()).compress()),
         signature: Default::default(),
-        new_reward_address: None
+        new_reward_address: None,
+        new_commission_rate: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
 
@@ -1183,7 +1348,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: Some(validator_key2.clone()),
         new_proof_of_knowledge: Some(bls_pair2.sign(&validator_key2.serialize_to_vec()).compress()),
         signature: Default::default(),
-        new_reward_address: None
+        new_reward_address: None,
+        new_commission_rate: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
     assert_eq!(StakingContract::check_incoming_transaction(&tx, 3), Ok(()));
@@ -1267,7 +1433,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: None,
         new_proof_of_knowledge: None,
         signature: Default::default(),
-        new_reward_address: Some(Address::from([4u8; 20]))
+        new_reward_address: Some(Address::from([4u8; 20])),
+        new_commission_rate: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
     assert_eq!(StakingContract::check_incoming_transaction(&tx, 3), Ok(()));
@@ -1446,6 +1613,7 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key.clone(),
         Address::from([3u8; 20]),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
     let contract_backup = contract.clone();
@@ -1626,6 +1794,7 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key2.clone(),
         Address::from([3u8;20]),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
 
@@ -2032,6 +2201,7 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key2.clone(),
         Address::from([3u8;20]),
+        0,
         Coin::from_u64_unchecked(100_000_000)
     ).unwrap();
 
@@ -2259,6 +2429,7 @@ fn make_sample_contract(key_pair: &KeyPair, bls_pair: &BlsKeyPair) -> StakingCon
     contract.create_validator(
         bls_pair.public.compress(),
         Address::from(key_pair),
+        0,
         Coin::from_u64_unchecked(150_000_000)
     ).unwrap();
     contract.stake(
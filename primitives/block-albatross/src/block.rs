@@ -330,6 +330,8 @@ impl block_base::Block for Block {
     type Header = BlockHeader;
     type Error = BlockError;
 
+    const MAX_SIZE: usize = MicroBlock::MAX_SIZE;
+
     fn hash(&self) -> Blake2bHash {
         self.hash()
     }
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use beserial::{Deserialize, Serialize};
-use hash::SerializeContent;
+use hash::{Hash, SerializeContent};
 use hash_derive::SerializeContent;
 use vrf::VrfSeed;
 
@@ -28,6 +28,8 @@ impl signed::Message for ViewChange {
     const PREFIX: u8 = signed::PREFIX_VIEW_CHANGE;
 }
 
+impl Hash for ViewChange { }
+
 pub type SignedViewChange = signed::SignedMessage<ViewChange>;
 pub type ViewChangeProof = signed::AggregateProof<ViewChange>;
 pub type ViewChangeProofBuilder = signed::AggregateProofBuilder<ViewChange>;
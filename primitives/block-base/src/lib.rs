@@ -13,6 +13,9 @@ pub trait Block: Serialize + Deserialize + Send + Sync {
     type Header: BlockHeader;
     type Error: BlockError;
 
+    /// Maximum size of a block that contains transactions.
+    const MAX_SIZE: usize;
+
     fn hash(&self) -> Blake2bHash;
 
     fn prev_hash(&self) -> &Blake2bHash;
@@ -130,6 +130,10 @@ pub const MIN_STAKE: u64 = 1;
 /// Minimum initial_stake for validators in units
 pub const MIN_VALIDATOR_STAKE: u64 = 100_000_000;
 
+/// Maximum commission rate of a validator. Commission rates are given in basis points,
+/// i.e. 10_000 means that the validator keeps the whole reward.
+pub const COMMISSION_RATE_MAX: u16 = 10_000;
+
 /// Returns the height of the next macro block after given `block_height`
 #[inline]
 pub fn macro_block_after(block_number: u32) -> u32 {
@@ -1,6 +1,7 @@
 use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
 use bls::bls12_381::{CompressedPublicKey as BlsPublicKey, CompressedSignature as BlsSignature};
 use keys::Address;
+use primitives::policy;
 
 use crate::{AccountType, Transaction, TransactionError};
 use crate::SignatureProof;
@@ -58,12 +59,15 @@ pub enum IncomingStakingTransactionData {
         validator_key: BlsPublicKey,
         proof_of_knowledge: BlsSignature,
         reward_address: Address,
+        /// Share of the epoch reward the validator keeps, in basis points.
+        commission_rate: u16,
     },
     UpdateValidator {
         old_validator_key: BlsPublicKey,
         new_validator_key: Option<BlsPublicKey>,
         new_proof_of_knowledge: Option<BlsSignature>,
         new_reward_address: Option<Address>,
+        new_commission_rate: Option<u16>,
         signature: BlsSignature,
     },
     RetireValidator {
@@ -101,22 +105,28 @@ impl IncomingStakingTransactionData {
     pub fn verify(&self, transaction: &Transaction) -> Result<(), TransactionError> {
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, ..
+                validator_key, proof_of_knowledge, commission_rate, ..
             } => {
                 // Check proof of knowledge.
                 verify_proof_of_knowledge(validator_key, proof_of_knowledge)?;
+
+                verify_commission_rate(*commission_rate)?;
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, signature, new_reward_address
+                old_validator_key, new_validator_key, new_proof_of_knowledge, signature, new_reward_address, new_commission_rate
             } => {
                 // Check signature and proof of knowledge.
                 verify_transaction_signature(transaction, old_validator_key, signature)?;
 
                 // Do not allow updates without any effect.
-                if new_validator_key.is_none() && new_reward_address.is_none() {
+                if new_validator_key.is_none() && new_reward_address.is_none() && new_commission_rate.is_none() {
                     return Err(TransactionError::InvalidData);
                 }
 
+                if let Some(new_commission_rate) = new_commission_rate {
+                    verify_commission_rate(*new_commission_rate)?;
+                }
+
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     verify_proof_of_knowledge(new_validator_key, new_proof_of_knowledge)?;
                 }
@@ -186,20 +196,22 @@ impl Serialize for IncomingStakingTransactionData {
         let mut size = 0;
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, reward_address
+                validator_key, proof_of_knowledge, reward_address, commission_rate
             } => {
                 size += Serialize::serialize(&IncomingStakingTransactionType::CreateValidator, writer)?;
                 size += Serialize::serialize(validator_key, writer)?;
                 size += Serialize::serialize(proof_of_knowledge, writer)?;
                 size += Serialize::serialize(reward_address, writer)?;
+                size += Serialize::serialize(commission_rate, writer)?;
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, signature
+                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, new_commission_rate, signature
             } => {
                 size += Serialize::serialize(&IncomingStakingTransactionType::UpdateValidator, writer)?;
                 size += Serialize::serialize(old_validator_key, writer)?;
                 size += Serialize::serialize(&new_validator_key.is_some(), writer)?;
                 size += Serialize::serialize(&new_reward_address.is_some(), writer)?;
+                size += Serialize::serialize(&new_commission_rate.is_some(), writer)?;
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     size += Serialize::serialize(new_validator_key, writer)?;
                     size += Serialize::serialize(new_proof_of_knowledge, writer)?;
@@ -207,6 +219,9 @@ impl Serialize for IncomingStakingTransactionData {
                 if let Some(new_reward_address) = new_reward_address {
                     size += Serialize::serialize(new_reward_address, writer)?;
                 }
+                if let Some(new_commission_rate) = new_commission_rate {
+                    size += Serialize::serialize(new_commission_rate, writer)?;
+                }
                 size += Serialize::serialize(signature, writer)?;
             },
             IncomingStakingTransactionData::RetireValidator {
@@ -245,20 +260,22 @@ impl Serialize for IncomingStakingTransactionData {
         let mut size = 0;
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, reward_address
+                validator_key, proof_of_knowledge, reward_address, commission_rate
             } => {
                 size += Serialize::serialized_size(&IncomingStakingTransactionType::CreateValidator);
                 size += Serialize::serialized_size(validator_key);
                 size += Serialize::serialized_size(proof_of_knowledge);
                 size += Serialize::serialized_size(reward_address);
+                size += Serialize::serialized_size(commission_rate);
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, signature
+                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, new_commission_rate, signature
             } => {
                 size += Serialize::serialized_size(&IncomingStakingTransactionType::UpdateValidator);
                 size += Serialize::serialized_size(old_validator_key);
                 size += Serialize::serialized_size(&new_validator_key.is_some());
                 size += Serialize::serialized_size(&new_reward_address.is_some());
+                size += Serialize::serialized_size(&new_commission_rate.is_some());
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     size += Serialize::serialized_size(new_validator_key);
                     size += Serialize::serialized_size(new_proof_of_knowledge);
@@ -266,6 +283,9 @@ impl Serialize for IncomingStakingTransactionData {
                 if let Some(new_reward_address) = new_reward_address {
                     size += Serialize::serialized_size(new_reward_address);
                 }
+                if let Some(new_commission_rate) = new_commission_rate {
+                    size += Serialize::serialized_size(new_commission_rate);
+                }
                 size += Serialize::serialized_size(signature);
             },
             IncomingStakingTransactionData::RetireValidator {
@@ -310,16 +330,19 @@ impl Deserialize for IncomingStakingTransactionData {
                     validator_key: Deserialize::deserialize(reader)?,
                     proof_of_knowledge: Deserialize::deserialize(reader)?,
                     reward_address: Deserialize::deserialize(reader)?,
+                    commission_rate: Deserialize::deserialize(reader)?,
                 })
             },
             IncomingStakingTransactionType::UpdateValidator => {
                 let old_validator_key: BlsPublicKey = Deserialize::deserialize(reader)?;
                 let updates_key: bool = Deserialize::deserialize(reader)?;
                 let updates_address: bool = Deserialize::deserialize(reader)?;
+                let updates_commission_rate: bool = Deserialize::deserialize(reader)?;
 
                 let mut new_validator_key = None;
                 let mut new_proof_of_knowledge = None;
                 let mut new_reward_address = None;
+                let mut new_commission_rate = None;
                 if updates_key {
                     new_validator_key = Some(Deserialize::deserialize(reader)?);
                     new_proof_of_knowledge = Some(Deserialize::deserialize(reader)?);
@@ -327,6 +350,9 @@ impl Deserialize for IncomingStakingTransactionData {
                 if updates_address {
                     new_reward_address = Some(Deserialize::deserialize(reader)?);
                 }
+                if updates_commission_rate {
+                    new_commission_rate = Some(Deserialize::deserialize(reader)?);
+                }
                 let signature = Deserialize::deserialize(reader)?;
 
                 Ok(IncomingStakingTransactionData::UpdateValidator {
@@ -334,6 +360,7 @@ impl Deserialize for IncomingStakingTransactionData {
                     new_validator_key,
                     new_proof_of_knowledge,
                     new_reward_address,
+                    new_commission_rate,
                     signature,
                 })
             },
@@ -585,3 +612,10 @@ pub fn verify_proof_of_knowledge(validator_key: &BlsPublicKey, proof_of_knowledg
     }
     Ok(())
 }
+
+pub fn verify_commission_rate(commission_rate: u16) -> Result<(), TransactionError> {
+    if commission_rate > policy::COMMISSION_RATE_MAX {
+        return Err(TransactionError::InvalidData)
+    }
+    Ok(())
+}
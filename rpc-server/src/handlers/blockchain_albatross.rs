@@ -1,41 +1,66 @@
 use std::borrow::Borrow;
 use std::convert::TryInto;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use futures::Future;
 use json::{JsonValue, Null, object};
 
-use account::Account;
+use account::{Account, AccountsTreeLeave};
 use account::staking_contract::{InactiveStake, InactiveValidator, Validator};
+use beserial::{Deserialize, Serialize};
 use block_albatross::{Block, ForkProof, signed};
 use blockchain_albatross::Blockchain;
+use blockchain_albatross::reward_registry::{SlashEvent, SlashReason};
+use blockchain_albatross::transaction_store::HistoryEntry;
 use blockchain_base::AbstractBlockchain;
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
+use consensus::{AlbatrossConsensusProtocol, Consensus};
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use network_primitives::networks::NetworkInfo;
 use primitives::policy;
 use primitives::slot::{Slot, SlotBand, Slots};
+use transaction::TransactionReceipt;
 
 use crate::handler::Method;
-use crate::handlers::blockchain::BlockchainHandler;
+use crate::handlers::blockchain::{parse_hash, BlockchainHandler};
 use crate::handlers::mempool::{transaction_to_obj, TransactionContext};
 use crate::handlers::Module;
+use crate::handlers::wallet::AccountsInUseLookup;
 use crate::rpc_not_implemented;
 use json::object::Object;
 
 pub struct BlockchainAlbatrossHandler {
     pub blockchain: Arc<Blockchain>,
+    pub consensus: Arc<Consensus<AlbatrossConsensusProtocol>>,
     generic: BlockchainHandler<Blockchain>,
 }
 
 impl BlockchainAlbatrossHandler {
-    pub fn new(blockchain: Arc<Blockchain>) -> Self {
+    pub fn new(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>) -> Self {
+        let blockchain = Arc::clone(&consensus.blockchain);
         BlockchainAlbatrossHandler {
             generic: BlockchainHandler::new(blockchain.clone()),
             blockchain,
+            consensus,
         }
     }
 
+    /// Accounts are in use if they have a history or aren't empty. Light nodes and nodes
+    /// bootstrapped from a snapshot don't know the history, so they only find accounts that
+    /// aren't empty.
+    pub fn accounts_in_use(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>) -> AccountsInUseLookup {
+        Box::new(move |addresses: &[Address]| -> Result<Vec<bool>, String> {
+            let accounts = consensus.get_accounts(addresses.to_vec()).wait()
+                .map_err(|e| e.to_string())?;
+            Ok(addresses.iter().zip(accounts.iter())
+                .map(|(address, account)| !account.is_initial()
+                    || !consensus.blockchain.get_history_by_address(address, 1, None).is_empty())
+                .collect())
+        })
+    }
+
     // Blocks
 
     /// Returns the current epoch number.
@@ -191,6 +216,46 @@ impl BlockchainAlbatrossHandler {
         })
     }
 
+    /// Returns the slash events of an epoch.
+    /// Parameters:
+    /// - epoch (number, optional): Default is the current epoch. Only the current and the
+    ///   previous epoch are available.
+    /// - validatorKey (string, optional): Only return slashes of this validator (BLS, hex-encoded).
+    ///
+    /// Returns an array of slash event objects:
+    /// ```text
+    /// {
+    ///     blockNumber: number, (block that contains the slash)
+    ///     epoch: number,
+    ///     reason: "forkProof" | "viewChange",
+    ///     slashedBlockNumber: number,
+    ///     slashedViewNumber: number,
+    ///     slot: { index: number, publicKey: string, rewardAddress: string },
+    ///     forkProof: { blockNumber: number, viewNumber: number, parentHash: string, hashes: Array<string>, headers: Array<string> }, (only for fork proofs)
+    /// }
+    /// ```
+    pub(crate) fn slash_events(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let epoch = match params.get(0) {
+            None | Some(JsonValue::Null) => policy::epoch_at(self.blockchain.height()),
+            Some(epoch) => epoch.as_u32()
+                .ok_or_else(|| object!{"message" => "Invalid epoch"})?,
+        };
+
+        let validator_key = match params.get(1) {
+            None | Some(JsonValue::Null) => None,
+            Some(key) => Some(key.as_str()
+                .ok_or_else(|| object!{"message" => "Invalid validator key"})
+                .and_then(|it| hex::decode(it)
+                    .map_err(|_| object!{"message" => "Validator key must be hex-encoded"}))
+                .and_then(|it| BlsPublicKey::deserialize_from_vec(&it)
+                    .map_err(|_| object!{"message" => "Invalid public key"}))?),
+        };
+
+        Ok(JsonValue::Array(self.blockchain.get_slash_events(epoch, validator_key.as_ref()).iter()
+            .map(|(event, slot)| Self::slash_event_to_obj(event, slot))
+            .collect()))
+    }
+
     // Transactions
 
     /// Retrieves information about a transaction from its hex encoded form.
@@ -260,8 +325,11 @@ impl BlockchainAlbatrossHandler {
     ///     transactionIndex: number,
     /// }
     /// ```
-    pub(crate) fn get_transaction_by_hash(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        rpc_not_implemented()
+    pub(crate) fn get_transaction_by_hash(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        params.get(0)
+            .ok_or(object!{"message" => "First argument must be hash"})
+            .and_then(parse_hash)
+            .and_then(|h| self.get_transaction_by_hash_helper(&h))
     }
 
     /// Retrieves a transaction receipt by its hash.
@@ -280,8 +348,7 @@ impl BlockchainAlbatrossHandler {
     ///     transactionIndex: number,
     /// }
     /// ```
-    pub(crate) fn get_transaction_receipt(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        /*
+    pub(crate) fn get_transaction_receipt(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let hash = params.get(0).and_then(JsonValue::as_str)
             .ok_or_else(|| object!{"message" => "Invalid transaction hash"})
             .and_then(|s| Blake2bHash::from_str(s)
@@ -291,24 +358,88 @@ impl BlockchainAlbatrossHandler {
             .ok_or_else(|| object!{"message" => "Transaction not found"})?;
 
         // Get block which contains the transaction. If we don't find the block (for what reason?),
-        // return an error
-        let block = self.blockchain.get_block(&transaction_info.block_hash, false, true);
+        // the timestamps will be omitted.
+        let block = self.blockchain.get_block(&transaction_info.block_hash, false, false);
 
         let transaction_index = transaction_info.index;
         Ok(self.transaction_receipt_to_obj(&transaction_info.into(),
                                            Some(transaction_index),
                                            block.as_ref()))
-        */
-        rpc_not_implemented()
+    }
+
+    /// Retrieves the transactions and rewards of an address, newest first.
+    /// Parameters:
+    /// - address (string)
+    /// - limit (number, optional): Default is 1000.
+    /// - cursor (number, optional): Only return entries older than this cursor. Pass the cursor of
+    ///     the last entry of the previous page to get the next page.
+    ///
+    /// Returns a list of entries:
+    /// ```text
+    /// Array<{
+    ///     type: "transaction" | "reward",
+    ///     cursor: number,
+    ///     transactionHash: string, // only for transactions
+    ///     transactionIndex: number, // only for transactions
+    ///     recipientAddress: string, // only for rewards
+    ///     value: number, // only for rewards
+    ///     blockHash: string,
+    ///     blockNumber: number,
+    ///     timestamp: number,
+    ///     timestampMillis: number,
+    ///     confirmations: number,
+    /// }>
+    /// ```
+    pub(crate) fn get_transactions_by_address(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let address = params.get(0).and_then(JsonValue::as_str)
+            .ok_or_else(|| object!{"message" => "Invalid address"})
+            .and_then(|s| Address::from_any_str(s)
+                .map_err(|_| object!{"message" => "Invalid address"}))?;
+
+        let limit = match params.get(1) {
+            None | Some(JsonValue::Null) => 1000,
+            Some(limit) => limit.as_usize()
+                .ok_or_else(|| object!{"message" => "Invalid limit"})?,
+        };
+
+        let cursor = match params.get(2) {
+            None | Some(JsonValue::Null) => None,
+            Some(cursor) => Some(cursor.as_u32()
+                .ok_or_else(|| object!{"message" => "Invalid cursor"})?),
+        };
+
+        Ok(JsonValue::Array(self.blockchain
+            .get_history_by_address(&address, limit, cursor)
+            .into_iter()
+            .map(|(cursor, entry)| self.history_entry_to_obj(cursor, entry))
+            .collect()))
     }
 
     // Accounts
 
+    /// Look up the balance of an address.
+    /// Parameters:
+    /// - address (string)
+    ///
+    /// Returns the amount in Luna (10000 = 1 NIM):
+    /// ```text
+    /// 1200000
+    /// ```
+    pub(crate) fn get_balance(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let address = params.get(0).and_then(JsonValue::as_str)
+            .ok_or_else(|| object!{"message" => "Invalid address"})
+            .and_then(|s| Address::from_any_str(s)
+                .map_err(|_| object!{"message" => "Invalid address"}))?;
+
+        let account = self.get_account(address)?;
+        Ok(JsonValue::from(u64::from(account.balance())))
+    }
+
     // Lists all stakes
     pub(crate) fn list_stakes(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let genesis_account = NetworkInfo::from_network_id(self.blockchain.network_id)
             .validator_registry_address().unwrap();
-        let account = self.blockchain.get_account(&genesis_account);
+        let account = self.get_account(genesis_account.clone())?;
         let contract = match account {
             Account::Staking(c) => c,
             _ => return Err("No contract at staking contract address".into()),
@@ -334,6 +465,13 @@ impl BlockchainAlbatrossHandler {
 
     // Helper functions
 
+    /// Light blockchains don't have an accounts tree, so their accounts are requested from full nodes.
+    fn get_account(&self, address: Address) -> Result<Account, JsonValue> {
+        self.consensus.get_accounts(vec![address]).wait()
+            .map(|mut accounts| accounts.remove(0))
+            .map_err(|e| object!{"message" => format!("Failed to get account: {}", e)})
+    }
+
     fn proof_to_object<M: signed::Message>(proof: &signed::AggregateProof<M>) -> JsonValue {
         object!{
             "signature" => format!("{}", proof.signature),
@@ -426,6 +564,69 @@ impl BlockchainAlbatrossHandler {
         }
     }
 
+    fn get_transaction_by_hash_helper(&self, hash: &Blake2bHash) -> Result<JsonValue, JsonValue> {
+        // Get transaction info, which includes Block hash, transaction hash, and transaction index.
+        // Return an error if the transaction doesn't exist.
+        let transaction_info = self.blockchain.get_transaction_info_by_hash(hash)
+            .ok_or_else(|| object!{"message" => "Transaction not found"})?;
+
+        // The transaction is known, but the body of its block has been deleted.
+        if self.blockchain.is_pruned(transaction_info.block_number) {
+            return Err(object!{"message" => "Transaction has been pruned"});
+        }
+
+        // Get block which contains the transaction. If we don't find the block (for what reason?),
+        // return an error
+        let block = self.blockchain.get_block(&transaction_info.block_hash, false, true)
+            .ok_or_else(|| object!{"message" => "Block not found"})?;
+
+        // Transactions of epochs that were synced without their micro blocks refer to the macro
+        // block of the epoch, which doesn't contain them.
+        if let Block::Macro(_) = block {
+            return Err(object!{"message" => "Transaction unavailable, its micro block is unknown"});
+        }
+
+        self.generic.get_transaction_by_block_and_index(&block, transaction_info.index)
+    }
+
+    fn transaction_receipt_to_obj(&self, receipt: &TransactionReceipt, index: Option<u16>, block: Option<&Block>) -> JsonValue {
+        // Albatross block timestamps are in milliseconds.
+        object!{
+            "transactionHash" => receipt.transaction_hash.to_hex(),
+            "blockNumber" => receipt.block_height,
+            "blockHash" => receipt.block_hash.to_hex(),
+            "confirmations" => self.blockchain.height() - receipt.block_height,
+            "timestamp" => block.map(|block| (block.timestamp() / 1000).into()).unwrap_or(Null),
+            "timestampMillis" => block.map(|block| block.timestamp().into()).unwrap_or(Null),
+            "transactionIndex" => index.map(|i| i.into()).unwrap_or(Null)
+        }
+    }
+
+    fn history_entry_to_obj(&self, cursor: u32, entry: HistoryEntry) -> JsonValue {
+        let block = self.blockchain.get_block(entry.block_hash(), false, false);
+
+        let mut obj = match entry {
+            HistoryEntry::Transaction(info) => {
+                let index = info.index;
+                let mut obj = self.transaction_receipt_to_obj(&info.into(), Some(index), block.as_ref());
+                obj["type"] = "transaction".into();
+                obj
+            },
+            HistoryEntry::Reward(info) => object!{
+                "type" => "reward",
+                "recipientAddress" => info.recipient.to_user_friendly_address(),
+                "value" => u64::from(info.value),
+                "blockHash" => info.block_hash.to_hex(),
+                "blockNumber" => info.block_number,
+                "confirmations" => self.blockchain.height() - info.block_number,
+                "timestamp" => block.as_ref().map(|block| (block.timestamp() / 1000).into()).unwrap_or(Null),
+                "timestampMillis" => block.as_ref().map(|block| block.timestamp().into()).unwrap_or(Null),
+            },
+        };
+        obj["cursor"] = cursor.into();
+        obj
+    }
+
     fn slots_to_obj(slots: &Slots) -> JsonValue {
         JsonValue::Array(slots.combined().into_iter()
             .map(|(slot, first_slot_number)| {
@@ -450,6 +651,33 @@ impl BlockchainAlbatrossHandler {
         }
     }
 
+    fn slash_event_to_obj(event: &SlashEvent, slot: &Slot) -> JsonValue {
+        let mut obj = object! {
+            "blockNumber" => event.block_number,
+            "epoch" => event.epoch(),
+            "slashedBlockNumber" => event.reason.block_number(),
+            "slashedViewNumber" => event.reason.view_number(),
+            "slot" => Self::slot_to_obj(slot, event.slot_number),
+        };
+
+        match event.reason {
+            SlashReason::ForkProof(ref fork_proof) => {
+                let mut fork_proof_obj = Self::fork_proof_to_obj(fork_proof);
+                fork_proof_obj["headers"] = vec![
+                    hex::encode(fork_proof.header1.serialize_to_vec()),
+                    hex::encode(fork_proof.header2.serialize_to_vec()),
+                ].into();
+                obj["reason"] = "forkProof".into();
+                obj["forkProof"] = fork_proof_obj;
+            },
+            SlashReason::ViewChange { .. } => {
+                obj["reason"] = "viewChange".into();
+            },
+        }
+
+        obj
+    }
+
     fn slot_to_obj(slot: &Slot, slot_number: u16) -> JsonValue {
         object! {
             "index" => slot_number,
@@ -469,6 +697,8 @@ impl BlockchainAlbatrossHandler {
             "publicKey" => hex::encode(&validator.validator_key),
             "balance" => u64::from(validator.balance),
             "rewardAddress" => validator.reward_address.to_user_friendly_address(),
+            "commissionRate" => validator.commission_rate,
+            "pendingCommissionRate" => validator.pending_commission_rate,
             "stakes" => JsonValue::Object(stakes),
         }
     }
@@ -497,7 +727,7 @@ impl Module for BlockchainAlbatrossHandler {
         "getTransactionReceipt" => get_transaction_receipt,
         "getTransactionByBlockHashAndIndex" => generic.get_transaction_by_block_hash_and_index,
         "getTransactionByBlockNumberAndIndex" => generic.get_transaction_by_block_number_and_index,
-        "getTransactionsByAddress" => generic.get_transactions_by_address,
+        "getTransactionsByAddress" => get_transactions_by_address,
 
         // Blockchain
         "blockNumber" => generic.block_number,
@@ -508,9 +738,10 @@ impl Module for BlockchainAlbatrossHandler {
         "getBlockTransactionCountByHash" => generic.get_block_transaction_count_by_hash,
         "getBlockTransactionCountByNumber" => generic.get_block_transaction_count_by_number,
         "slotState" => slot_state,
+        "slashEvents" => slash_events,
 
         // Accounts
-        "getBalance" => generic.get_balance,
+        "getBalance" => get_balance,
         "listStakes" => list_stakes,
     }
 }
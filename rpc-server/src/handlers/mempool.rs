@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -6,12 +7,14 @@ use json::{Array, JsonValue, Null, object};
 use json::object::Object;
 use parking_lot::RwLock;
 
+use account::Account;
 use beserial::{Deserialize, Serialize};
 use consensus::ConsensusProtocol;
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use nimiq_mempool::Mempool;
 use nimiq_mempool::ReturnCode;
+use nimiq_mempool::TRANSACTION_RELAY_FEE_MIN;
 use primitives::account::AccountType;
 use primitives::coin::Coin;
 use primitives::networks::NetworkId;
@@ -21,9 +24,14 @@ use crate::handler::Method;
 use crate::handlers::Module;
 use crate::handlers::wallet::UnlockedWalletManager;
 
+/// Looks up the accounts of transactions pushed to the mempool, for blockchains that don't have
+/// an accounts tree.
+pub type AccountsLookup = Box<dyn Fn(Vec<Address>) -> Result<Vec<Account>, String> + Send + Sync>;
+
 pub struct MempoolHandler<P: ConsensusProtocol + 'static> {
     pub mempool: Arc<Mempool<P::Blockchain>>,
     pub unlocked_wallets: Option<Arc<RwLock<UnlockedWalletManager>>>,
+    accounts_lookup: Option<AccountsLookup>,
 }
 
 impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
@@ -31,6 +39,16 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
         MempoolHandler {
             mempool,
             unlocked_wallets,
+            accounts_lookup: None,
+        }
+    }
+
+    /// Pushes transactions with the accounts of sender and recipient from `accounts_lookup`.
+    pub fn with_accounts_lookup(mempool: Arc<Mempool<P::Blockchain>>, unlocked_wallets: Option<Arc<RwLock<UnlockedWalletManager>>>, accounts_lookup: AccountsLookup) -> Self {
+        MempoolHandler {
+            mempool,
+            unlocked_wallets,
+            accounts_lookup: Some(accounts_lookup),
         }
     }
 
@@ -87,6 +105,42 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
         Ok(JsonValue::Object(transactions_per_bucket))
     }
 
+    /// Suggests a fee/byte for a transaction that should be included within the given number of blocks.
+    /// The estimate considers both the transactions waiting in the mempool and the fees paid in recent blocks.
+    /// Transactions paying less than `relayFeePerByte` are free transactions, which are limited per sender.
+    /// Parameters:
+    /// - targetBlocks (number, optional): Default is `1`.
+    ///
+    /// The result looks like the following:
+    /// ```text
+    /// {
+    ///     targetBlocks: number,
+    ///     feePerByte: number,
+    ///     mempoolFeePerByte: number,
+    ///     historyFeePerByte: number,
+    ///     historyBlocks: number,
+    ///     relayFeePerByte: number,
+    /// }
+    /// ```
+    pub(crate) fn estimate_fee(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let target_blocks = match params.get(0) {
+            None | Some(JsonValue::Null) => 1,
+            Some(target_blocks) => target_blocks.as_u32()
+                .filter(|&target_blocks| target_blocks > 0)
+                .ok_or_else(|| object! {"message" => "Target blocks must be a positive number"} )?,
+        };
+
+        let estimate = self.mempool.estimate_fee(target_blocks);
+        Ok(object! {
+            "targetBlocks" => estimate.target_blocks,
+            "feePerByte" => estimate.fee_per_byte,
+            "mempoolFeePerByte" => estimate.mempool_fee_per_byte,
+            "historyFeePerByte" => estimate.history_fee_per_byte,
+            "historyBlocks" => estimate.history_blocks,
+            "relayFeePerByte" => TRANSACTION_RELAY_FEE_MIN,
+        })
+    }
+
     /// Sends a raw transaction.
     /// Parameters:
     /// - transaction (string)
@@ -219,7 +273,17 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
 
     pub(crate) fn push_transaction(&self, transaction: Transaction) -> Result<JsonValue, JsonValue> {
         let txid = transaction.hash::<Blake2bHash>();
-        match self.mempool.push_transaction(transaction) {
+        let return_code = match self.accounts_lookup {
+            Some(ref accounts_lookup) => {
+                let addresses = vec![transaction.sender.clone(), transaction.recipient.clone()];
+                let accounts = accounts_lookup(addresses.clone())
+                    .map_err(|e| object! {"message" => format!("Failed to get accounts: {}", e)})?;
+                let accounts: HashMap<Address, Account> = addresses.into_iter().zip(accounts).collect();
+                self.mempool.push_transaction_with_accounts(transaction, accounts)
+            },
+            None => self.mempool.push_transaction(transaction),
+        };
+        match return_code {
             ReturnCode::Accepted | ReturnCode::Known => Ok(txid.to_hex().into()),
             code => Err(object! {"message" => format!("Rejected: {:?}", code)})
         }
@@ -330,6 +394,7 @@ impl<P: ConsensusProtocol + 'static> Module for MempoolHandler<P> {
         "sendTransaction" => send_transaction,
         "mempoolContent" => mempool_content,
         "mempool" => mempool,
+        "estimateFee" => estimate_fee,
         "getMempoolTransaction" => get_transaction,
     }
 }
@@ -1,18 +1,20 @@
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 
+use futures::Future;
 use json::{JsonValue, Null, object};
 use parking_lot::RwLock;
 
 use beserial::{Deserialize, Serialize};
 use blockchain_albatross::Blockchain;
 use bls::bls12_381::{CompressedPublicKey, CompressedSignature};
-use consensus::AlbatrossConsensusProtocol;
+use consensus::{AlbatrossConsensusProtocol, Consensus};
 use keys::Address;
 use network_primitives::networks::NetworkInfo;
 use nimiq_mempool::Mempool;
 use primitives::account::AccountType;
 use primitives::coin::Coin;
+use primitives::policy;
 use transaction::account::staking_contract::{IncomingStakingTransactionData, OutgoingStakingTransactionProof, SelfStakingTransactionData};
 use transaction::Transaction;
 use validator::validator::Validator;
@@ -32,15 +34,26 @@ pub struct MempoolAlbatrossHandler {
 
 impl MempoolAlbatrossHandler {
     pub fn new(
-        mempool: Arc<Mempool<Blockchain>>,
+        consensus: Arc<Consensus<AlbatrossConsensusProtocol>>,
         validator: Option<Arc<Validator>>,
         unlocked_wallets: Option<Arc<RwLock<UnlockedWalletManager>>>,
     ) -> Self {
+        let mempool = Arc::clone(&consensus.mempool);
+        let generic = if consensus.blockchain.is_light() {
+            // Light blockchains don't have any accounts to check transactions against.
+            MempoolHandler::with_accounts_lookup(Arc::clone(&mempool), unlocked_wallets.as_ref().map(Arc::clone), Box::new(move |addresses| {
+                consensus.get_accounts(addresses).wait()
+                    .map_err(|e| e.to_string())
+            }))
+        } else {
+            MempoolHandler::new(Arc::clone(&mempool), unlocked_wallets.as_ref().map(Arc::clone))
+        };
+
         Self {
-            mempool: Arc::clone(&mempool),
+            mempool,
             validator,
-            unlocked_wallets: unlocked_wallets.as_ref().map(Arc::clone),
-            generic: MempoolHandler::new(mempool, unlocked_wallets),
+            unlocked_wallets,
+            generic,
         }
     }
 
@@ -66,6 +79,7 @@ impl MempoolAlbatrossHandler {
     /// - reward_address: NIM address used for the reward
     /// - amount: Initial staking amount in Luna
     /// - fee: Fee for transaction in Luna
+    /// - commission_rate: Share of the rewards the validator keeps, in basis points (optional, default 0)
     pub(crate) fn create_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
         let validator_key = params.get(1)
@@ -93,6 +107,12 @@ impl MempoolAlbatrossHandler {
             .unwrap_or(0)
             .try_into()
             .map_err(|e| object! {"message" => format!("Invalid fee: {}", e)})?;
+        let commission_rate = match params.get(6) {
+            None | Some(JsonValue::Null) => 0,
+            Some(commission_rate) => commission_rate.as_u16()
+                .filter(|&commission_rate| commission_rate <= policy::COMMISSION_RATE_MAX)
+                .ok_or_else(|| object! {"message" => "Invalid commission rate"})?,
+        };
 
         let network_id = self.mempool.network_id();
         let staking_contract = NetworkInfo::from_network_id(network_id)
@@ -101,6 +121,7 @@ impl MempoolAlbatrossHandler {
             validator_key,
             reward_address,
             proof_of_knowledge,
+            commission_rate,
         };
 
         let mut tx = Transaction::new_extended(
@@ -375,6 +396,7 @@ impl Module for MempoolAlbatrossHandler {
         "sendTransaction" => generic.send_transaction,
         "mempoolContent" => generic.mempool_content,
         "mempool" => generic.mempool,
+        "estimateFee" => generic.estimate_fee,
         "createValidator" => create_validator,
         "retireValidator" => retire_validator,
         "reactivateValidator" => reactivate_validator,
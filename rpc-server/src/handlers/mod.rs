@@ -33,6 +33,8 @@ pub mod blockchain_albatross;
 pub mod mempool;
 pub mod mempool_albatross;
 pub mod network;
+#[cfg(feature="validator")]
+pub mod validator_albatross;
 pub mod wallet;
 
 
@@ -46,7 +48,9 @@ pub use self::blockchain_albatross::BlockchainAlbatrossHandler;
 pub use self::mempool::MempoolHandler;
 pub use self::mempool_albatross::MempoolAlbatrossHandler;
 pub use self::network::NetworkHandler;
-pub use self::wallet::{WalletHandler, UnlockedWalletManager};
+#[cfg(feature="validator")]
+pub use self::validator_albatross::ValidatorAlbatrossHandler;
+pub use self::wallet::{AccountsInUseLookup, WalletHandler, UnlockedWalletManager};
 
 
 pub trait Module: Send + Sync {
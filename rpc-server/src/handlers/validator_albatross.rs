@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use json::{JsonValue, Null, object};
+
+use validator::validator::{Validator, ValidatorStatus};
+
+use crate::handler::Method;
+use crate::handlers::Module;
+
+pub struct ValidatorAlbatrossHandler {
+    validator: Arc<Validator>
+}
+
+impl ValidatorAlbatrossHandler {
+    pub fn new(validator: Arc<Validator>) -> Self {
+        Self {
+            validator,
+        }
+    }
+
+    /// Returns the status of the validator and its performance in the current epoch.
+    ///
+    /// The status object contains:
+    /// ```text
+    /// {
+    ///     status: "none" | "synced" | "potential" | "active",
+    ///     validatorKey: string,
+    ///     validatorIndex: number | null,
+    ///     slots: Array<number>,
+    ///     viewNumber: number,
+    ///     blocksProduced: number,
+    ///     blocksMissed: number,
+    ///     activeViewChange: { blockNumber: number, newViewNumber: number } | null,
+    ///     viewChanges: Array<{ blockNumber: number, newViewNumber: number, votes: number }>,
+    ///     pbft: Array<{ hash: string, prepareVotes: number, commitVotes: number }>,
+    /// }
+    /// ```
+    pub(crate) fn validator_status(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let info = self.validator.state_info();
+
+        let status = match info.status {
+            ValidatorStatus::None => "none",
+            ValidatorStatus::Synced => "synced",
+            ValidatorStatus::Potential => "potential",
+            ValidatorStatus::Active => "active",
+        };
+
+        let active_view_change = info.active_view_change
+            .map(|view_change| object! {
+                "blockNumber" => view_change.block_number,
+                "newViewNumber" => view_change.new_view_number,
+            })
+            .unwrap_or(Null);
+
+        let view_changes = self.validator.validator_network.view_change_progress().into_iter()
+            .map(|progress| object! {
+                "blockNumber" => progress.view_change.block_number,
+                "newViewNumber" => progress.view_change.new_view_number,
+                "votes" => progress.votes,
+            })
+            .collect::<Vec<JsonValue>>();
+
+        let pbft = self.validator.validator_network.pbft_progress().into_iter()
+            .map(|progress| object! {
+                "hash" => progress.hash.to_hex(),
+                "prepareVotes" => progress.prepare_votes,
+                "commitVotes" => progress.commit_votes,
+            })
+            .collect::<Vec<JsonValue>>();
+
+        Ok(object! {
+            "status" => status,
+            "validatorKey" => self.validator.validator_key.public.compress().to_string(),
+            "validatorIndex" => info.pk_idx.map(JsonValue::from).unwrap_or(Null),
+            "slots" => info.slot_numbers,
+            "viewNumber" => info.view_number,
+            "blocksProduced" => info.epoch_stats.blocks_produced,
+            "blocksMissed" => info.epoch_stats.blocks_missed,
+            "activeViewChange" => active_view_change,
+            "viewChanges" => view_changes,
+            "pbft" => pbft,
+        })
+    }
+}
+
+impl Module for ValidatorAlbatrossHandler {
+    rpc_module_methods! {
+        "validatorStatus" => validator_status,
+    }
+}
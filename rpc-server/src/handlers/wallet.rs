@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use hex;
 use json::{JsonValue, Null, object};
@@ -9,48 +10,94 @@ use parking_lot::RwLock;
 use beserial::{Deserialize, Serialize};
 use keys::{Address, KeyPair, PrivateKey, PublicKey, Signature};
 use nimiq_database::Environment;
-use nimiq_wallet::{WalletAccount, WalletStore};
+use nimiq_mnemonic::Mnemonic;
+use nimiq_wallet::{HdWallet, Keystore, WalletAccount, WalletStore};
 use utils::otp::{Locked, Unlocked};
+use utils::timers::Timers;
 
 use crate::handler::Method;
 use crate::handlers::Module;
 
 pub struct UnlockedWalletManager {
     pub unlocked_wallets: HashMap<Address, Unlocked<WalletAccount>>,
+    /// The time at which accounts that were unlocked for a limited duration are locked again
+    expiries: HashMap<Address, Instant>,
 }
 
 impl UnlockedWalletManager {
-    fn new() -> Self {
+    pub fn new() -> Self {
         UnlockedWalletManager {
             unlocked_wallets: HashMap::new(),
+            expiries: HashMap::new(),
         }
     }
 
-    fn insert(&mut self, wallet: Unlocked<WalletAccount>) {
+    /// Keeps `wallet` unlocked until `expiry`, or until it is removed if `expiry` is `None`.
+    fn insert(&mut self, wallet: Unlocked<WalletAccount>, expiry: Option<Instant>) {
         info!("Unlocking {:?}", &wallet.address);
+        match expiry {
+            Some(expiry) => self.expiries.insert(wallet.address.clone(), expiry),
+            None => self.expiries.remove(&wallet.address),
+        };
         self.unlocked_wallets.insert(wallet.address.clone(), wallet);
     }
 
     pub fn get(&self, address: &Address) -> Option<&WalletAccount> {
         info!("Accessing {:?}", address);
+        // The account might not have been locked yet if the timer is late.
+        if self.expiries.get(address).map_or(false, |&expiry| expiry <= Instant::now()) {
+            return None;
+        }
         self.unlocked_wallets.get(address).map(|unlocked| Unlocked::unlocked_data(unlocked))
     }
 
+    /// Returns whether the account is unlocked and, if it is unlocked for a limited duration,
+    /// the remaining time until it is locked again.
+    pub fn unlock_time_remaining(&self, address: &Address) -> (bool, Option<Duration>) {
+        let now = Instant::now();
+        match self.expiries.get(address) {
+            Some(&expiry) if expiry <= now => (false, None),
+            Some(&expiry) => (true, Some(expiry - now)),
+            None => (self.unlocked_wallets.contains_key(address), None),
+        }
+    }
+
+    /// Removes the account from memory. Dropping the unlocked account clears its key material.
     fn remove(&mut self, address: &Address) -> Option<Unlocked<WalletAccount>> {
+        self.expiries.remove(address);
         self.unlocked_wallets.remove(address)
     }
+
+    /// Removes the account if its unlock duration has passed.
+    fn remove_expired(&mut self, address: &Address) {
+        if self.expiries.get(address).map_or(false, |&expiry| expiry <= Instant::now()) {
+            info!("Locking {:?} after unlock duration passed", address);
+            self.remove(address);
+        }
+    }
 }
 
+/// Returns for each address whether its account is in use, e.g. because it has a history. This is
+/// used to find the accounts of HD wallets.
+pub type AccountsInUseLookup = Box<dyn Fn(&[Address]) -> Result<Vec<bool>, String> + Send + Sync>;
+
 pub struct WalletHandler {
     wallet_store: WalletStore,
+    accounts_in_use: AccountsInUseLookup,
     pub unlocked_wallets: Arc<RwLock<UnlockedWalletManager>>,
+    /// Timers to lock accounts again after their unlock duration
+    timers: Timers<Address>,
 }
 
 impl WalletHandler {
-    pub fn new(env: Environment) -> Self {
+    /// Accounts are unlocked in `unlocked_wallets`, which may be shared with other handlers.
+    /// HD wallets are restored with the accounts `accounts_in_use` reports.
+    pub fn new(env: Environment, unlocked_wallets: Arc<RwLock<UnlockedWalletManager>>, accounts_in_use: AccountsInUseLookup) -> Self {
         WalletHandler {
             wallet_store: WalletStore::new(env),
-            unlocked_wallets: Arc::new(RwLock::new(UnlockedWalletManager::new())),
+            accounts_in_use,
+            unlocked_wallets,
+            timers: Timers::new(),
         }
     }
 
@@ -97,6 +144,7 @@ impl WalletHandler {
             .unwrap_or_else(|| &Null).as_str()
             .ok_or_else(|| object!{"message" => "Address must be a string"})?)
             .map_err(|_|  object!{"message" => "Address invalid"})?;
+        self.timers.clear_delay(&account_address);
         self.unlocked_wallets.write().remove(&account_address);
         Ok(JsonValue::Boolean(true))
     }
@@ -104,13 +152,42 @@ impl WalletHandler {
     /// Creates a new account.
     /// Parameters:
     /// - passphrase (optional, string): The passphrase to lock the key with.
-    /// Returns the user friendly address corresponding to the private key.
+    /// - hd (optional, bool): Whether to create a new HD wallet and return its first account.
+    ///   Default is `false`.
+    ///
+    /// The return value is an object:
+    /// {
+    ///     address: string,
+    ///     publicKey: string,
+    ///     privateKey: string,
+    /// }
+    ///
+    /// For HD wallets, it additionally contains `walletId`, `path` and the `mnemonic`, which
+    /// is needed to restore the wallet.
     pub(crate) fn create_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         // FIXME: We're not clearing the passphrase right now.
         let passphrase = params.get(0).map(|s: &JsonValue| s.as_str()
                 .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
             ).unwrap_or_else(|| Ok(""))?;
 
+        let hd = params.get(1).unwrap_or_else(|| &JsonValue::Boolean(false)).as_bool()
+            .ok_or_else(|| object!{"message" => "Optional hd argument must be a boolean value"})?;
+
+        if hd {
+            let (mut wallet, mnemonic) = HdWallet::generate();
+            let account = wallet.derive_next();
+            self.put_hd_wallet(&wallet, &[account.clone()], passphrase)?;
+
+            return Ok(object!{
+                "address" => account.address.to_user_friendly_address(),
+                "publicKey" => account.key_pair.public.to_hex(),
+                "privateKey" => account.key_pair.private.to_hex(),
+                "walletId" => wallet.id().to_user_friendly_address(),
+                "path" => wallet.derivation_path(0),
+                "mnemonic" => mnemonic.to_string(),
+            });
+        }
+
         let account = WalletAccount::generate();
         let address = account.address.clone();
         let locked_account = Locked::with_defaults(account.clone(), passphrase.as_bytes())
@@ -127,11 +204,163 @@ impl WalletHandler {
         })
     }
 
+    /// Imports an HD wallet from a mnemonic and restores all accounts that are in use.
+    /// Parameters:
+    /// - mnemonic (string): The words, separated by spaces.
+    /// - passphrase (optional, string): The passphrase to lock the wallet and its accounts with.
+    /// - mnemonicPassword (optional, string): The password the mnemonic was protected with.
+    ///
+    /// The return value is an object:
+    /// {
+    ///     walletId: string,
+    ///     accounts: [{ address: string, path: string }],
+    /// }
+    pub(crate) fn import_mnemonic(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let words = params.get(0).unwrap_or(&Null).as_str()
+            .ok_or_else(|| object!{"message" => "Mnemonic must be a string"})?;
+        let mnemonic = Mnemonic::from_str(&words.split_whitespace().collect::<Vec<&str>>().join(" "))
+            .map_err(|_| object!{"message" => "Invalid mnemonic"})?;
+
+        // FIXME: We're not clearing the passphrase right now.
+        let passphrase = params.get(1).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
+            ).unwrap_or_else(|| Ok(""))?;
+
+        let mnemonic_password = params.get(2).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Mnemonic password must be a string"})
+            ).transpose()?;
+
+        let mut wallet = HdWallet::from_mnemonic(&mnemonic, mnemonic_password)
+            .map_err(|e| object!{"message" => e.to_string()})?;
+        let accounts = wallet.restore(|addresses| (self.accounts_in_use)(addresses))
+            .map_err(|e| object!{"message" => format!("Failed to restore accounts: {}", e)})?;
+        self.put_hd_wallet(&wallet, &accounts, passphrase)?;
+
+        Ok(object!{
+            "walletId" => wallet.id().to_user_friendly_address(),
+            "accounts" => wallet.account_indices().iter().zip(accounts.iter())
+                .map(|(&index, account)| object!{
+                    "address" => account.address.to_user_friendly_address(),
+                    "path" => wallet.derivation_path(index),
+                })
+                .collect::<Vec<JsonValue>>(),
+        })
+    }
+
+    /// Derives the next account of an HD wallet.
+    /// Parameters:
+    /// - walletId (string): The ID of the wallet, as returned by `importMnemonic`.
+    /// - passphrase (optional, string): The passphrase the wallet is locked with.
+    ///
+    /// The return value is an object:
+    /// {
+    ///     address: string,
+    ///     publicKey: string,
+    ///     path: string,
+    /// }
+    pub(crate) fn derive_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let wallet_id = Address::from_any_str(params.get(0)
+            .unwrap_or_else(|| &Null).as_str()
+            .ok_or_else(|| object!{"message" => "Wallet ID must be a string"})?)
+            .map_err(|_|  object!{"message" => "Wallet ID invalid"})?;
+
+        // FIXME: We're not clearing the passphrase right now.
+        let passphrase = params.get(1).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
+            ).unwrap_or_else(|| Ok(""))?;
+
+        let wallet = self.wallet_store.get_hd(&wallet_id, None)
+            .ok_or_else(|| object!{"message" => "Wallet does not exist"})?;
+        let mut wallet = wallet.unlock(passphrase.as_bytes())
+            .map(Unlocked::into_unlocked_data)
+            .map_err(|_| object!{"message" => "Invalid passphrase"})?;
+
+        let account = wallet.derive_next();
+        let index = *wallet.account_indices().last().unwrap();
+        self.put_hd_wallet(&wallet, &[account.clone()], passphrase)?;
+
+        Ok(object!{
+            "address" => account.address.to_user_friendly_address(),
+            "publicKey" => account.key_pair.public.to_hex(),
+            "path" => wallet.derivation_path(index),
+        })
+    }
+
+    /// Exports an account as a password-encrypted keystore (see `Keystore`).
+    /// Parameters:
+    /// - address (string)
+    /// - passphrase (optional, string): The passphrase the account is locked with.
+    /// - password (optional, string): The password to encrypt the keystore with.
+    ///   Defaults to the passphrase.
+    /// Returns the hex encoded keystore.
+    pub(crate) fn export_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let account_address = Address::from_any_str(params.get(0)
+            .unwrap_or_else(|| &Null).as_str()
+            .ok_or_else(|| object!{"message" => "Address must be a string"})?)
+            .map_err(|_|  object!{"message" => "Address invalid"})?;
+
+        // FIXME: We're not clearing the passphrase right now.
+        let passphrase = params.get(1).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
+            ).unwrap_or_else(|| Ok(""))?;
+        let password = params.get(2).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Password must be a string"})
+            ).unwrap_or_else(|| Ok(passphrase))?;
+
+        let account = self.wallet_store.get(&account_address, None)
+            .ok_or_else(|| object!{"message" => "Address does not exist"})?;
+        let account = account.unlock(passphrase.as_bytes())
+            .map(Unlocked::into_unlocked_data)
+            .map_err(|_| object!{"message" => "Invalid passphrase"})?;
+
+        let keystore = Keystore::lock(account, password.as_bytes())
+            .map_err(|e| object!{"message" => e.to_string()})?;
+        Ok(JsonValue::String(hex::encode(keystore.to_bytes())))
+    }
+
+    /// Imports an account from a keystore created by `exportAccount` or the `nimiq-keystore` tool.
+    /// Parameters:
+    /// - keystore (string): The hex encoded keystore.
+    /// - password (optional, string): The password the keystore is encrypted with.
+    /// - passphrase (optional, string): The passphrase to lock the account with.
+    ///   Defaults to the password.
+    /// Returns the user friendly address of the account.
+    pub(crate) fn import_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let raw = hex::decode(params.get(0)
+            .unwrap_or(&Null)
+            .as_str()
+            .ok_or_else(|| object!{"message" => "Keystore must be a string"})?)
+            .map_err(|_| object!{"message" => "Keystore must be a hex string"})?;
+        let keystore = Keystore::from_bytes(&raw)
+            .map_err(|e| object!{"message" => e.to_string()})?;
+
+        // FIXME: We're not clearing the passphrase right now.
+        let password = params.get(1).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Password must be a string"})
+            ).unwrap_or_else(|| Ok(""))?;
+        let passphrase = params.get(2).map(|s: &JsonValue| s.as_str()
+                .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
+            ).unwrap_or_else(|| Ok(password))?;
+
+        let account = keystore.unlock(password.as_bytes())
+            .map_err(|e| object!{"message" => e.to_string()})?;
+        let address = account.address.clone();
+        let account = Locked::with_defaults(account, passphrase.as_bytes())
+            .map_err(|e| object!{"message" => format!("Error while importing: {:?}", e)})?;
+
+        let mut txn = self.wallet_store.create_write_transaction();
+        self.wallet_store.put(&address, &account, &mut txn);
+        txn.commit();
+
+        Ok(JsonValue::String(address.to_user_friendly_address()))
+    }
+
     /// Unlocks a wallet account in memory.
     /// Parameters:
     /// - address (string)
     /// - passphrase (string)
-    /// - duration (number, not supported yet)
+    /// - duration (optional, number): The number of seconds after which the account is locked
+    ///   again. Without it, the account stays unlocked until `lockAccount` is called.
     pub(crate) fn unlock_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let account_address = Address::from_any_str(params.get(0)
             .unwrap_or_else(|| &Null).as_str()
@@ -143,18 +372,61 @@ impl WalletHandler {
                 .ok_or_else(|| object!{"message" => "Passphrase must be a string"})
             ).unwrap_or_else(|| Ok(""))?;
 
-        // TODO: duration
+        let duration = match params.get(2) {
+            Some(duration) if !duration.is_null() => Some(duration.as_u64()
+                .filter(|&seconds| seconds > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| object!{"message" => "Duration must be a positive number of seconds"})?),
+            _ => None,
+        };
 
         let account = self.wallet_store.get(&account_address, None)
             .ok_or_else(|| object!{"message" => "Address does not exist"})?;
 
-        let unlocked_account = account.unlock(passphrase.as_bytes());
-        if let Ok(unlocked_account) = unlocked_account {
-            self.unlocked_wallets.write().insert(unlocked_account);
-            Ok(JsonValue::Boolean(true))
+        let unlocked_account = account.unlock(passphrase.as_bytes())
+            .map_err(|_| object!{"message" => "Invalid passphrase"})?;
+
+        if let Some(duration) = duration {
+            let expiry = Instant::now().checked_add(duration)
+                .ok_or_else(|| object!{"message" => "Duration is too long"})?;
+            self.unlocked_wallets.write().insert(unlocked_account, Some(expiry));
+
+            let unlocked_wallets = Arc::downgrade(&self.unlocked_wallets);
+            let address = account_address.clone();
+            self.timers.reset_delay(account_address, move || {
+                if let Some(unlocked_wallets) = unlocked_wallets.upgrade() {
+                    unlocked_wallets.write().remove_expired(&address);
+                }
+            }, duration);
         } else {
-            Err(object!{"message" => "Invalid passphrase"})
+            self.timers.clear_delay(&account_address);
+            self.unlocked_wallets.write().insert(unlocked_account, None);
         }
+
+        Ok(JsonValue::Boolean(true))
+    }
+
+    /// Returns the remaining time until an unlocked account is locked again.
+    /// Parameters:
+    /// - address (string)
+    ///
+    /// The return value is an object:
+    /// {
+    ///     unlocked: bool,
+    ///     remaining: number, // seconds, or null if the account is locked or unlocked
+    ///                        // until `lockAccount` is called
+    /// }
+    pub(crate) fn get_unlock_time(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let account_address = Address::from_any_str(params.get(0)
+            .unwrap_or_else(|| &Null).as_str()
+            .ok_or_else(|| object!{"message" => "Address must be a string"})?)
+            .map_err(|_|  object!{"message" => "Address invalid"})?;
+
+        let (unlocked, remaining) = self.unlocked_wallets.read().unlock_time_remaining(&account_address);
+        Ok(object!{
+            "unlocked" => unlocked,
+            "remaining" => remaining.map(|remaining| remaining.as_secs()),
+        })
     }
 
     /// Signs a message with a given address.
@@ -257,6 +529,24 @@ impl WalletHandler {
         Ok(JsonValue::Boolean(WalletAccount::verify_message(&public_key, &message, &signature)))
     }
 
+    /// Stores `wallet` and its derived `accounts`. The accounts are stored like any other account,
+    /// so that they can be unlocked and used to sign.
+    fn put_hd_wallet(&self, wallet: &HdWallet, accounts: &[WalletAccount], passphrase: &str) -> Result<(), JsonValue> {
+        let locked_wallet = Locked::with_defaults(wallet.clone(), passphrase.as_bytes())
+            .map_err(|e| object!{"message" => format!("Error while importing: {:?}", e)})?;
+
+        let mut txn = self.wallet_store.create_write_transaction();
+        self.wallet_store.put_hd(&wallet.id(), &locked_wallet, &mut txn);
+        for account in accounts {
+            let locked_account = Locked::with_defaults(account.clone(), passphrase.as_bytes())
+                .map_err(|e| object!{"message" => format!("Error while importing: {:?}", e)})?;
+            self.wallet_store.put(&account.address, &locked_account, &mut txn);
+        }
+        txn.commit();
+
+        Ok(())
+    }
+
     fn sign_message(&self, message: &[u8], wallet: &WalletAccount) -> JsonValue {
         let (public_key, signature) = wallet.sign_message(&message);
         let public_key = Serialize::serialize_to_vec(&public_key);
@@ -279,9 +569,97 @@ impl Module for WalletHandler {
         "listAccounts" => list_accounts,
         "lockAccount" => lock_account,
         "createAccount" => create_account,
+        "importMnemonic" => import_mnemonic,
+        "deriveAccount" => derive_account,
+        "exportAccount" => export_account,
+        "importAccount" => import_account,
         "unlockAccount" => unlock_account,
+        "getUnlockTime" => get_unlock_time,
 //        "sendTransaction" => send_transaction,
         "sign" => sign,
         "verifySignature" => verify_signature,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    fn unlocked_account() -> Unlocked<WalletAccount> {
+        Unlocked::new(WalletAccount::generate(), b"", 1, 8).unwrap()
+    }
+
+    #[test]
+    fn it_locks_accounts_after_the_unlock_duration() {
+        let mut manager = UnlockedWalletManager::new();
+        let account = unlocked_account();
+        let address = account.address.clone();
+
+        manager.insert(account, Some(Instant::now() + Duration::from_millis(50)));
+        assert!(manager.get(&address).is_some());
+        let (unlocked, remaining) = manager.unlock_time_remaining(&address);
+        assert!(unlocked);
+        assert!(remaining.unwrap() <= Duration::from_millis(50));
+
+        // Not expired yet
+        manager.remove_expired(&address);
+        assert!(manager.get(&address).is_some());
+
+        sleep(Duration::from_millis(60));
+        // The account is locked even if the timer didn't fire yet.
+        assert!(manager.get(&address).is_none());
+        assert_eq!(manager.unlock_time_remaining(&address), (false, None));
+
+        manager.remove_expired(&address);
+        assert!(!manager.unlocked_wallets.contains_key(&address));
+        assert_eq!(manager.unlock_time_remaining(&address), (false, None));
+    }
+
+    #[test]
+    fn it_unlocks_accounts_again_with_a_new_duration() {
+        let mut manager = UnlockedWalletManager::new();
+        let account = unlocked_account();
+        let address = account.address.clone();
+        let key_pair = account.key_pair.clone();
+
+        manager.insert(account, Some(Instant::now() + Duration::from_millis(50)));
+        manager.insert(Unlocked::new(WalletAccount::from(key_pair.clone()), b"", 1, 8).unwrap(), Some(Instant::now() + Duration::from_secs(300)));
+
+        sleep(Duration::from_millis(60));
+        // The earlier expiry doesn't lock the account anymore.
+        manager.remove_expired(&address);
+        assert!(manager.get(&address).is_some());
+        let remaining = manager.unlock_time_remaining(&address).1.unwrap();
+        assert!(remaining > Duration::from_secs(299));
+
+        // Relocking removes the account and its expiry.
+        assert!(manager.remove(&address).is_some());
+        assert!(manager.get(&address).is_none());
+        assert_eq!(manager.unlock_time_remaining(&address), (false, None));
+
+        // Unlocking without a duration replaces an earlier expiry.
+        manager.insert(Unlocked::new(WalletAccount::from(key_pair.clone()), b"", 1, 8).unwrap(), Some(Instant::now()));
+        manager.insert(Unlocked::new(WalletAccount::from(key_pair), b"", 1, 8).unwrap(), None);
+        manager.remove_expired(&address);
+        assert!(manager.get(&address).is_some());
+        assert_eq!(manager.unlock_time_remaining(&address), (true, None));
+    }
+
+    #[test]
+    fn it_keeps_accounts_unlocked_without_a_duration() {
+        let mut manager = UnlockedWalletManager::new();
+        let account = unlocked_account();
+        let address = account.address.clone();
+
+        manager.insert(account, None);
+        sleep(Duration::from_millis(10));
+        manager.remove_expired(&address);
+        assert!(manager.get(&address).is_some());
+        assert_eq!(manager.unlock_time_remaining(&address), (true, None));
+
+        assert!(manager.remove(&address).is_some());
+        assert_eq!(manager.unlock_time_remaining(&address), (false, None));
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use futures::{future, Future, IntoFuture, stream::Stream};
+use futures_cpupool::CpuPool;
 use hyper::{Body, Method, Request, Response, StatusCode};
 use hyper::header::HeaderValue;
 use json::{Array, JsonValue, Null, array, object};
@@ -15,13 +16,17 @@ pub trait Handler: Send + Sync {
 }
 
 pub struct Service<H> where H: Handler {
-    handler: Arc<H>
+    handler: Arc<H>,
+    /// Requests are handled on this pool, since handlers may block, e.g. while they request
+    /// accounts from peers.
+    cpu_pool: CpuPool,
 }
 
 impl<H> Service<H> where H: Handler {
-    pub fn new(handler: Arc<H>) -> Self {
+    pub fn new(handler: Arc<H>, cpu_pool: CpuPool) -> Self {
         Service {
             handler,
+            cpu_pool,
         }
     }
 }
@@ -42,53 +47,28 @@ impl std::fmt::Display for Never {
     }
 }
 
-fn handle_request<H>(handler: Arc<H>, str_o: Result<&str, std::str::Utf8Error>) -> Response<Body> where H: Handler {
-    let mut builder = Response::builder();
-    builder.header("Content-Type", "application/json");
-    if str_o.is_err() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid encoding"
-                }
-            })))
-            .unwrap();
-    }
-    let json_o = json::parse(str_o.unwrap());
-    if json_o.is_err() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid JSON"
-                }
-            })))
-            .unwrap();
+fn invalid_request(message: &str) -> JsonValue {
+    object! {
+        "jsonrpc" => "2.0",
+        "id" => Null,
+        "error" => object!{
+            "code" => -32600,
+            "message" => message
+        }
     }
-    let mut json = json_o.unwrap();
+}
+
+/// Handles a single or batch JSON-RPC request and returns the response object(s).
+/// Returns an error response if the request as a whole is malformed.
+pub fn handle_json_request<H>(handler: &H, request: &str) -> Result<JsonValue, JsonValue> where H: Handler + ?Sized {
+    let mut json = json::parse(request)
+        .map_err(|_| invalid_request("Invalid JSON"))?;
     let single = json.is_object();
     if single {
         json = array![json];
     }
     if !json.is_array() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid request"
-                }
-            })))
-            .unwrap();
+        return Err(invalid_request("Invalid request"));
     }
     let mut results = vec![];
     for msg in json.members() {
@@ -142,9 +122,27 @@ fn handle_request<H>(handler: Arc<H>, str_o: Result<&str, std::str::Utf8Error>)
     }
 
     if single {
-        builder.body(Body::from(results.pop().map(json::stringify).unwrap_or_else(String::new))).unwrap()
+        Ok(results.pop().unwrap_or(Null))
     } else {
-        builder.body(Body::from(json::stringify(JsonValue::Array(results)))).unwrap()
+        Ok(JsonValue::Array(results))
+    }
+}
+
+fn handle_request<H>(handler: Arc<H>, str_o: Result<&str, std::str::Utf8Error>) -> Response<Body> where H: Handler {
+    let mut builder = Response::builder();
+    builder.header("Content-Type", "application/json");
+
+    let result = str_o
+        .map_err(|_| invalid_request("Invalid encoding"))
+        .and_then(|s| handle_json_request(handler.as_ref(), s));
+
+    match result {
+        Ok(JsonValue::Null) => builder.body(Body::from("")).unwrap(),
+        Ok(response) => builder.body(Body::from(json::stringify(response))).unwrap(),
+        Err(error) => builder
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(json::stringify(error)))
+            .unwrap(),
     }
 }
 
@@ -199,8 +197,11 @@ impl<H> hyper::service::Service for Service<H> where H: Handler + 'static {
                         .body(Body::from(""))
                         .unwrap()));
                 }
+                let cpu_pool = self.cpu_pool.clone();
                 Box::new(req.into_body().concat2()
-                    .map(|b| handle_request(handler, std::str::from_utf8(&b))))
+                    .and_then(move |b| cpu_pool.spawn_fn(move || {
+                        Ok(handle_request(handler, std::str::from_utf8(&b)))
+                    })))
             },
             _ => Box::new(future::ok(Response::new(Body::from(""))))
         }
@@ -23,6 +23,7 @@ use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 use futures::future::Future;
+use futures_cpupool::CpuPool;
 use hyper::Server;
 use json::{JsonValue, object};
 
@@ -62,9 +63,10 @@ impl RpcServer {
         let handler = Arc::new(Handler::new(config));
 
         let handler2 = Arc::clone(&handler);
+        let cpu_pool = CpuPool::new_num_cpus();
         let future = Box::new(Server::try_bind(&SocketAddr::new(ip, port))?
             .serve(move || {
-                jsonrpc::Service::new(Arc::clone(&handler2))
+                jsonrpc::Service::new(Arc::clone(&handler2), cpu_pool.clone())
             })
             .map_err(|e| error!("RPC server failed: {}", e)));
 
@@ -0,0 +1,399 @@
+extern crate nimiq_bls as bls;
+extern crate nimiq_keys as keys;
+extern crate nimiq_network_primitives as network_primitives;
+extern crate nimiq_primitives as primitives;
+extern crate nimiq_transaction as transaction;
+extern crate nimiq_transaction_builder as transaction_builder;
+extern crate nimiq_wallet as wallet;
+
+mod template;
+#[cfg(test)]
+mod tests;
+
+use std::fs;
+use std::process::exit;
+use std::str::FromStr;
+
+use clap::{App, Arg, ArgMatches, crate_version, crate_authors, crate_description};
+use failure::{Error, Fail};
+
+use beserial::{Deserialize, Serialize};
+use bls::bls12_381::{CompressedPublicKey as BlsCompressedPublicKey, KeyPair as BlsKeyPair, PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
+use keys::{Address, KeyPair, PrivateKey};
+use network_primitives::networks::NetworkInfo;
+use primitives::account::AccountType;
+use primitives::coin::Coin;
+use primitives::networks::NetworkId;
+use transaction::Transaction;
+use transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
+use nimiq_tools::password::read_password;
+use transaction_builder::{Recipient, TransactionBuilder};
+use wallet::Keystore;
+
+use crate::template::{HashAlgorithmTemplate, HtlcResolutionTemplate, Template, TransactionTemplate};
+
+
+/// Builds and signs transactions from templates without network access.
+///
+/// Keys are given as hex on the command line or as keystore files (see `nimiq-keystore`).
+/// The signed transaction is printed as hex, to be broadcast from an online machine.
+fn run_app() -> Result<(), Error> {
+    let matches = App::new("Build transaction")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about(crate_description!())
+        .arg(Arg::with_name("template")
+            .value_name("TEMPLATE")
+            .help("The transaction template (TOML, or JSON if the file ends with .json).")
+            .required(true))
+        .arg(Arg::with_name("secret_key")
+            .short("k")
+            .long("secret-key")
+            .value_name("SECRET_KEY")
+            .help("A secret key to sign the transaction with. Can be given multiple times.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("keystore")
+            .long("keystore")
+            .value_name("FILE")
+            .help("A keystore containing a key to sign the transaction with. Can be given multiple times.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("password")
+            .short("p")
+            .long("password")
+            .value_name("PASSWORD")
+            .help("The password of the keystores. Read from STDIN if not given.")
+            .takes_value(true))
+        .arg(Arg::with_name("bls_secret_key")
+            .long("bls-secret-key")
+            .value_name("SECRET_KEY")
+            .help("The validator's BLS secret key, needed for validator transactions.")
+            .takes_value(true))
+        .get_matches();
+
+    let path = matches.value_of("template").unwrap();
+    let template = parse_template(path, &fs::read_to_string(path)?)?;
+
+    let keys = Keys::from_matches(&matches)?;
+    let transaction = build_transaction(&template, &keys)?;
+
+    println!("{}", hex::encode(transaction.serialize_to_vec()));
+    Ok(())
+}
+
+/// Parses a template read from `path`, as JSON if the path ends with `.json` and as TOML
+/// otherwise.
+fn parse_template(path: &str, content: &str) -> Result<Template, Error> {
+    if path.ends_with(".json") {
+        Ok(serde_json::from_str(content)?)
+    } else {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+/// The keys available for signing.
+struct Keys {
+    key_pairs: Vec<KeyPair>,
+    bls_key_pair: Option<BlsKeyPair>,
+}
+
+impl Keys {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let mut key_pairs = Vec::new();
+        for secret_key in matches.values_of("secret_key").into_iter().flatten() {
+            key_pairs.push(KeyPair::from(PrivateKey::from_str(secret_key)?));
+        }
+
+        let keystores = matches.values_of("keystore").map(|values| values.collect::<Vec<&str>>()).unwrap_or_default();
+        if !keystores.is_empty() {
+            let password = read_password(matches.value_of("password"))?;
+            for keystore in keystores {
+                let keystore = Keystore::from_bytes(&fs::read(keystore)?)?;
+                key_pairs.push(keystore.unlock(password.as_bytes())?.key_pair);
+            }
+        }
+
+        let bls_key_pair = match matches.value_of("bls_secret_key") {
+            Some(secret_key) => {
+                let secret_key = BlsSecretKey::deserialize_from_vec(&hex::decode(secret_key)?)?;
+                Some(BlsKeyPair::from(secret_key))
+            },
+            None => None,
+        };
+
+        Ok(Keys { key_pairs, bls_key_pair })
+    }
+
+    /// Returns the key for `address`, or the only key if no address is given.
+    fn key_pair(&self, address: Option<&Address>) -> Result<&KeyPair, AppError> {
+        match address {
+            Some(address) => self.key_pairs.iter()
+                .find(|key_pair| &Address::from(*key_pair) == address)
+                .ok_or_else(|| AppError::MissingKey(address.to_user_friendly_address())),
+            None if self.key_pairs.len() == 1 => Ok(&self.key_pairs[0]),
+            None if self.key_pairs.is_empty() => Err(AppError::NoKey),
+            None => Err(AppError::AmbiguousKey),
+        }
+    }
+
+    fn bls_key_pair(&self) -> Result<&BlsKeyPair, AppError> {
+        self.bls_key_pair.as_ref().ok_or(AppError::NoBlsKey)
+    }
+}
+
+/// Builds and signs the transaction described by `template` and checks that it is valid.
+fn build_transaction(template: &Template, keys: &Keys) -> Result<Transaction, Error> {
+    let network_id = NetworkId::from_str(&template.network)?;
+    let transaction = sign_transaction(template, keys)?;
+    transaction.verify(network_id)
+        .map_err(|e| AppError::InvalidTransaction(format!("{:?}", e)))?;
+    Ok(transaction)
+}
+
+fn sign_transaction(template: &Template, keys: &Keys) -> Result<Transaction, Error> {
+    let network_id = NetworkId::from_str(&template.network)?;
+    let fee = coin(template.fee)?;
+    let validity_start_height = template.validity_start_height;
+    let staking_contract = match template.staking_contract {
+        Some(ref address) => parse_address(address)?,
+        None => NetworkInfo::from_network_id(network_id).validator_registry_address()
+            .cloned()
+            .ok_or(AppError::NoStakingContract)?,
+    };
+
+    // Sets the fields that all transactions have in common.
+    let builder = |sender: Address, sender_type: AccountType, recipient: Recipient, value: Coin| {
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(sender)
+            .with_sender_type(sender_type)
+            .with_recipient(recipient)
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        builder
+    };
+
+    let signer = template.signer.as_ref().map(|address| parse_address(address)).transpose()?;
+    let signer = || keys.key_pair(signer.as_ref());
+
+    let transaction = match template.transaction {
+        TransactionTemplate::Basic { ref recipient, value } => {
+            let key_pair = signer()?;
+            let mut proof_builder = builder(Address::from(key_pair), AccountType::Basic, Recipient::new_basic(parse_address(recipient)?), coin(value)?)
+                .generate()?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(key_pair);
+            proof_builder.generate()
+        },
+        TransactionTemplate::HtlcCreation { ref htlc_sender, ref htlc_recipient, ref hash_root, hash_algorithm, hash_count, timeout, value } => {
+            let key_pair = signer()?;
+            let mut recipient = Recipient::new_htlc_builder();
+            recipient.with_sender(match htlc_sender {
+                    Some(htlc_sender) => parse_address(htlc_sender)?,
+                    None => Address::from(key_pair),
+                })
+                .with_recipient(parse_address(htlc_recipient)?)
+                .with_hash(parse_hash(hash_root)?, hash_count, parse_hash_algorithm(hash_algorithm))
+                .with_timeout_block(timeout);
+
+            let mut proof_builder = builder(Address::from(key_pair), AccountType::Basic, recipient.generate()?, coin(value)?)
+                .generate()?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(key_pair);
+            proof_builder.generate()
+        },
+        TransactionTemplate::HtlcResolution { ref contract, ref recipient, value, resolution, ref htlc_sender, ref htlc_recipient, ref pre_image, ref hash_root, hash_algorithm, hash_count } => {
+            let htlc_sender = htlc_sender.as_ref().map(|address| parse_address(address)).transpose()?;
+            let htlc_recipient = htlc_recipient.as_ref().map(|address| parse_address(address)).transpose()?;
+
+            let mut proof_builder = builder(parse_address(contract)?, AccountType::HTLC, Recipient::new_basic(parse_address(recipient)?), coin(value)?)
+                .generate()?
+                .unwrap_htlc();
+            match resolution {
+                HtlcResolutionTemplate::Regular => {
+                    let pre_image = pre_image.as_ref().ok_or(AppError::MissingField("pre_image"))?;
+                    let hash_root = hash_root.as_ref().ok_or(AppError::MissingField("hash_root"))?;
+                    let signature = proof_builder.signature_with_key_pair(keys.key_pair(htlc_recipient.as_ref())?);
+                    proof_builder.regular_transfer(parse_hash_algorithm(hash_algorithm), parse_hash(pre_image)?, hash_count, parse_hash(hash_root)?, signature);
+                },
+                HtlcResolutionTemplate::Early => {
+                    let htlc_sender = htlc_sender.ok_or(AppError::MissingField("htlc_sender"))?;
+                    let htlc_recipient = htlc_recipient.ok_or(AppError::MissingField("htlc_recipient"))?;
+                    let sender_signature = proof_builder.signature_with_key_pair(keys.key_pair(Some(&htlc_sender))?);
+                    let recipient_signature = proof_builder.signature_with_key_pair(keys.key_pair(Some(&htlc_recipient))?);
+                    proof_builder.early_resolve(sender_signature, recipient_signature);
+                },
+                HtlcResolutionTemplate::Timeout => {
+                    let signature = proof_builder.signature_with_key_pair(keys.key_pair(htlc_sender.as_ref())?);
+                    proof_builder.timeout_resolve(signature);
+                },
+            }
+            proof_builder.generate()
+        },
+        TransactionTemplate::VestingCreation { ref owner, start_block, step_blocks, step_amount, total_amount, value } => {
+            let key_pair = signer()?;
+            let owner = match owner {
+                Some(owner) => parse_address(owner)?,
+                None => Address::from(key_pair),
+            };
+            let mut recipient = Recipient::new_vesting_builder(owner);
+            recipient.with_start_block(start_block)
+                .with_step_distance(step_blocks)
+                .with_step_amount(coin(step_amount)?)
+                .with_total_amount(coin(total_amount)?);
+
+            let mut proof_builder = builder(Address::from(key_pair), AccountType::Basic, recipient.generate()?, coin(value)?)
+                .generate()?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(key_pair);
+            proof_builder.generate()
+        },
+        TransactionTemplate::VestingWithdrawal { ref contract, ref recipient, value } => {
+            let mut proof_builder = builder(parse_address(contract)?, AccountType::Vesting, Recipient::new_basic(parse_address(recipient)?), coin(value)?)
+                .generate()?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(signer()?);
+            proof_builder.generate()
+        },
+        TransactionTemplate::Stake { ref validator_key, value } => {
+            Some(TransactionBuilder::new_stake(staking_contract, signer()?, &parse_validator_key(validator_key)?, coin(value)?, fee, validity_start_height, network_id))
+        },
+        TransactionTemplate::RetireStake { ref validator_key, value } => {
+            Some(TransactionBuilder::new_retire(staking_contract, signer()?, &parse_validator_key(validator_key)?, coin(value)?, fee, validity_start_height, network_id))
+        },
+        TransactionTemplate::ReactivateStake { ref validator_key, value } => {
+            Some(TransactionBuilder::new_reactivate(staking_contract, signer()?, &parse_validator_key(validator_key)?, coin(value)?, fee, validity_start_height, network_id))
+        },
+        TransactionTemplate::Unstake { ref recipient, value } => {
+            Some(TransactionBuilder::new_unstake(staking_contract, signer()?, parse_address(recipient)?, coin(value)?, fee, validity_start_height, network_id))
+        },
+        TransactionTemplate::CreateValidator { ref reward_address, commission_rate, value } => {
+            let key_pair = signer()?;
+            let reward_address = match reward_address {
+                Some(reward_address) => parse_address(reward_address)?,
+                None => Address::from(key_pair),
+            };
+            let mut recipient = Recipient::new_staking_builder(staking_contract);
+            recipient.create_validator(keys.bls_key_pair()?, reward_address, commission_rate);
+
+            let mut proof_builder = builder(Address::from(key_pair), AccountType::Basic, recipient.generate().ok_or(AppError::InvalidRecipient)?, coin(value)?)
+                .generate()?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(key_pair);
+            proof_builder.generate()
+        },
+        TransactionTemplate::UpdateValidator { .. }
+        | TransactionTemplate::RetireValidator
+        | TransactionTemplate::ReactivateValidator
+        | TransactionTemplate::UnparkValidator => {
+            let key_pair = signer()?;
+            let bls_key_pair = keys.bls_key_pair()?;
+            let mut recipient = Recipient::new_staking_builder(staking_contract);
+            match template.transaction {
+                TransactionTemplate::UpdateValidator { ref new_reward_address, new_commission_rate } => {
+                    let new_reward_address = new_reward_address.as_ref().map(|address| parse_address(address)).transpose()?;
+                    recipient.update_validator(&bls_key_pair.public, None, new_reward_address, new_commission_rate)
+                },
+                TransactionTemplate::RetireValidator => recipient.retire_validator(&bls_key_pair.public),
+                TransactionTemplate::ReactivateValidator => recipient.reactivate_validator(&bls_key_pair.public),
+                _ => recipient.unpark_validator(&bls_key_pair.public),
+            };
+
+            // Signalling transactions are signed by the validator key first.
+            let mut signalling_proof_builder = builder(Address::from(key_pair), AccountType::Basic, recipient.generate().ok_or(AppError::InvalidRecipient)?, Coin::ZERO)
+                .generate()?
+                .unwrap_signalling();
+            signalling_proof_builder.sign_with_validator_key_pair(bls_key_pair);
+
+            let mut proof_builder = signalling_proof_builder.generate()
+                .ok_or(AppError::MissingProof)?
+                .unwrap_basic();
+            proof_builder.sign_with_key_pair(key_pair);
+            proof_builder.generate()
+        },
+        TransactionTemplate::DropValidator { ref recipient, value } => {
+            let mut proof_builder = builder(staking_contract, AccountType::Staking, Recipient::new_basic(parse_address(recipient)?), coin(value)?)
+                .generate()?
+                .unwrap_staking();
+            proof_builder.drop_validator(keys.bls_key_pair()?);
+            proof_builder.generate()
+        },
+    };
+
+    Ok(transaction.ok_or(AppError::MissingProof)?)
+}
+
+fn parse_address(address: &str) -> Result<Address, Error> {
+    Ok(Address::from_any_str(address)?)
+}
+
+fn coin(value: u64) -> Result<Coin, AppError> {
+    if value > Coin::MAX_SAFE_VALUE {
+        return Err(AppError::InvalidValue(value));
+    }
+    Ok(Coin::from_u64_unchecked(value))
+}
+
+fn parse_hash(hash: &str) -> Result<AnyHash, Error> {
+    let raw = hex::decode(hash)?;
+    if raw.len() != AnyHash::SIZE {
+        return Err(AppError::InvalidHash(hash.to_string()).into());
+    }
+    Ok(AnyHash::from(&raw[..]))
+}
+
+fn parse_hash_algorithm(hash_algorithm: HashAlgorithmTemplate) -> HashAlgorithm {
+    match hash_algorithm {
+        HashAlgorithmTemplate::Blake2b => HashAlgorithm::Blake2b,
+        HashAlgorithmTemplate::Sha256 => HashAlgorithm::Sha256,
+    }
+}
+
+fn parse_validator_key(validator_key: &str) -> Result<BlsPublicKey, Error> {
+    BlsCompressedPublicKey::from_str(validator_key)?
+        .uncompress()
+        .map_err(|_| AppError::InvalidValidatorKey.into())
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+
+#[derive(Debug, Fail)]
+enum AppError {
+    #[fail(display = "No secret key given")]
+    NoKey,
+    #[fail(display = "Several secret keys given, but no signer address")]
+    AmbiguousKey,
+    #[fail(display = "No secret key given for {}", _0)]
+    MissingKey(String),
+    #[fail(display = "No BLS secret key given")]
+    NoBlsKey,
+    #[fail(display = "The network has no staking contract, please set staking_contract")]
+    NoStakingContract,
+    #[fail(display = "Template field {} is missing", _0)]
+    MissingField(&'static str),
+    #[fail(display = "Invalid value: {}", _0)]
+    InvalidValue(u64),
+    #[fail(display = "Invalid hash: {}", _0)]
+    InvalidHash(String),
+    #[fail(display = "Invalid validator key")]
+    InvalidValidatorKey,
+    #[fail(display = "Invalid recipient")]
+    InvalidRecipient,
+    #[fail(display = "The transaction proof could not be created")]
+    MissingProof,
+    #[fail(display = "The transaction is invalid: {}", _0)]
+    InvalidTransaction(String),
+}
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+
+/// A template for a transaction, read from a TOML or JSON file.
+///
+/// Addresses are given in any format accepted by `Address::from_any_str`, keys and hashes as hex
+/// and all values and fees in Luna.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    pub network: String,
+    pub validity_start_height: u32,
+    #[serde(default)]
+    pub fee: u64,
+    /// The address of the key that signs the transaction. Only needed if several keys are given.
+    pub signer: Option<String>,
+    /// Defaults to the staking contract of the network.
+    pub staking_contract: Option<String>,
+    pub transaction: TransactionTemplate,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransactionTemplate {
+    Basic {
+        recipient: String,
+        value: u64,
+    },
+    HtlcCreation {
+        /// Defaults to the signer.
+        htlc_sender: Option<String>,
+        htlc_recipient: String,
+        hash_root: String,
+        #[serde(default)]
+        hash_algorithm: HashAlgorithmTemplate,
+        #[serde(default = "default_hash_count")]
+        hash_count: u8,
+        timeout: u32,
+        value: u64,
+    },
+    HtlcResolution {
+        contract: String,
+        recipient: String,
+        value: u64,
+        resolution: HtlcResolutionTemplate,
+        /// Required for early and timeout resolutions if several keys are given.
+        htlc_sender: Option<String>,
+        /// Required for early and regular resolutions if several keys are given.
+        htlc_recipient: Option<String>,
+        /// Only used for regular resolutions, like `hash_root`, `hash_algorithm` and `hash_count`.
+        pre_image: Option<String>,
+        hash_root: Option<String>,
+        #[serde(default)]
+        hash_algorithm: HashAlgorithmTemplate,
+        #[serde(default = "default_hash_count")]
+        hash_count: u8,
+    },
+    VestingCreation {
+        /// Defaults to the signer.
+        owner: Option<String>,
+        start_block: u32,
+        step_blocks: u32,
+        step_amount: u64,
+        total_amount: u64,
+        value: u64,
+    },
+    VestingWithdrawal {
+        contract: String,
+        recipient: String,
+        value: u64,
+    },
+    Stake {
+        validator_key: String,
+        value: u64,
+    },
+    RetireStake {
+        validator_key: String,
+        value: u64,
+    },
+    ReactivateStake {
+        validator_key: String,
+        value: u64,
+    },
+    Unstake {
+        recipient: String,
+        value: u64,
+    },
+    CreateValidator {
+        /// Defaults to the signer.
+        reward_address: Option<String>,
+        commission_rate: u16,
+        value: u64,
+    },
+    UpdateValidator {
+        new_reward_address: Option<String>,
+        new_commission_rate: Option<u16>,
+    },
+    RetireValidator,
+    ReactivateValidator,
+    UnparkValidator,
+    DropValidator {
+        recipient: String,
+        value: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithmTemplate {
+    Blake2b,
+    Sha256,
+}
+
+impl Default for HashAlgorithmTemplate {
+    fn default() -> Self {
+        HashAlgorithmTemplate::Sha256
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcResolutionTemplate {
+    /// The recipient presents the pre-image of the hash root.
+    Regular,
+    /// Sender and recipient both sign.
+    Early,
+    /// The sender withdraws after the timeout.
+    Timeout,
+}
+
+fn default_hash_count() -> u8 {
+    1
+}
@@ -0,0 +1,202 @@
+use keys::SecureGenerate;
+use nimiq_hash::{Hasher, Sha256Hasher};
+
+use super::*;
+
+fn generate_keys(count: usize) -> Keys {
+    Keys {
+        key_pairs: (0..count).map(|_| KeyPair::generate_default_csprng()).collect(),
+        bls_key_pair: Some(BlsKeyPair::generate_default_csprng()),
+    }
+}
+
+fn address(key_pair: &KeyPair) -> String {
+    Address::from(key_pair).to_user_friendly_address()
+}
+
+fn random_address() -> String {
+    address(&KeyPair::generate_default_csprng())
+}
+
+fn validator_key(keys: &Keys) -> String {
+    keys.bls_key_pair.as_ref().unwrap().public.compress().to_hex()
+}
+
+/// Builds a transaction on the dev network from the `[transaction]` section of a template.
+fn build(keys: &Keys, transaction: &str) -> Result<Transaction, Error> {
+    let content = format!("network = \"devalbatross\"\nvalidity_start_height = 1000\nfee = 138\n\n[transaction]\n{}", transaction);
+    build_transaction(&parse_template("template.toml", &content)?, keys)
+}
+
+#[test]
+fn it_builds_the_example_template() {
+    let template = parse_template("example.toml", include_str!("example.toml")).unwrap();
+    assert_eq!(template.network, "devalbatross");
+    match template.transaction {
+        TransactionTemplate::HtlcCreation { .. } => {},
+        _ => panic!("The example should create an HTLC"),
+    }
+
+    let transaction = build_transaction(&template, &generate_keys(1)).unwrap();
+    assert_eq!(transaction.network_id, NetworkId::DevAlbatross);
+    assert_eq!(transaction.recipient_type, AccountType::HTLC);
+}
+
+#[test]
+fn it_builds_basic_transactions() {
+    let keys = generate_keys(1);
+    let recipient = random_address();
+    let transaction = build(&keys, &format!("type = \"basic\"\nrecipient = \"{}\"\nvalue = 1000", recipient)).unwrap();
+    assert_eq!(transaction.sender, Address::from(&keys.key_pairs[0]));
+    assert_eq!(transaction.recipient.to_user_friendly_address(), recipient);
+    assert_eq!(transaction.value, Coin::from_u64_unchecked(1000));
+    assert_eq!(transaction.fee, Coin::from_u64_unchecked(138));
+}
+
+#[test]
+fn it_builds_json_templates() {
+    let keys = generate_keys(1);
+    let content = format!(r#"{{
+        "network": "devalbatross",
+        "validity_start_height": 1000,
+        "transaction": {{ "type": "basic", "recipient": "{}", "value": 1000 }}
+    }}"#, random_address());
+    let template = parse_template("template.json", &content).unwrap();
+    build_transaction(&template, &keys).unwrap();
+}
+
+#[test]
+fn it_builds_htlc_creations() {
+    let keys = generate_keys(1);
+    let transaction = build(&keys, &format!(r#"
+        type = "htlc_creation"
+        htlc_recipient = "{}"
+        hash_root = "{}"
+        hash_algorithm = "blake2b"
+        hash_count = 2
+        timeout = 2000
+        value = 1000
+    "#, random_address(), hex::encode([1u8; 32]))).unwrap();
+    assert_eq!(transaction.recipient_type, AccountType::HTLC);
+}
+
+#[test]
+fn it_builds_htlc_resolutions() {
+    let pre_image = [1u8; 32];
+    let hash_root = Sha256Hasher::default().digest(&pre_image);
+
+    // regular
+    let keys = generate_keys(1);
+    let transaction = build(&keys, &format!(r#"
+        type = "htlc_resolution"
+        contract = "{}"
+        recipient = "{}"
+        value = 1000
+        resolution = "regular"
+        pre_image = "{}"
+        hash_root = "{}"
+    "#, random_address(), address(&keys.key_pairs[0]), hex::encode(pre_image), hash_root.to_hex())).unwrap();
+    assert_eq!(transaction.sender_type, AccountType::HTLC);
+
+    // A wrong pre-image results in an invalid transaction.
+    assert!(build(&keys, &format!(r#"
+        type = "htlc_resolution"
+        contract = "{}"
+        recipient = "{}"
+        value = 1000
+        resolution = "regular"
+        pre_image = "{}"
+        hash_root = "{}"
+    "#, random_address(), address(&keys.key_pairs[0]), hex::encode([2u8; 32]), hash_root.to_hex())).is_err());
+
+    // early
+    let keys = generate_keys(2);
+    build(&keys, &format!(r#"
+        type = "htlc_resolution"
+        contract = "{}"
+        recipient = "{}"
+        value = 1000
+        resolution = "early"
+        htlc_sender = "{}"
+        htlc_recipient = "{}"
+    "#, random_address(), address(&keys.key_pairs[0]), address(&keys.key_pairs[0]), address(&keys.key_pairs[1]))).unwrap();
+
+    // timeout
+    let keys = generate_keys(1);
+    build(&keys, &format!(r#"
+        type = "htlc_resolution"
+        contract = "{}"
+        recipient = "{}"
+        value = 1000
+        resolution = "timeout"
+    "#, random_address(), address(&keys.key_pairs[0]))).unwrap();
+}
+
+#[test]
+fn it_builds_vesting_transactions() {
+    let keys = generate_keys(1);
+    let transaction = build(&keys, r#"
+        type = "vesting_creation"
+        start_block = 1000
+        step_blocks = 100
+        step_amount = 500
+        total_amount = 1000
+        value = 1000
+    "#).unwrap();
+    assert_eq!(transaction.recipient_type, AccountType::Vesting);
+
+    let transaction = build(&keys, &format!(r#"
+        type = "vesting_withdrawal"
+        contract = "{}"
+        recipient = "{}"
+        value = 500
+    "#, transaction.recipient.to_user_friendly_address(), address(&keys.key_pairs[0]))).unwrap();
+    assert_eq!(transaction.sender_type, AccountType::Vesting);
+}
+
+#[test]
+fn it_builds_staking_transactions() {
+    let keys = generate_keys(1);
+    let validator_key = validator_key(&keys);
+
+    for transaction_type in &["stake", "retire_stake", "reactivate_stake"] {
+        let transaction = build(&keys, &format!("type = \"{}\"\nvalidator_key = \"{}\"\nvalue = 1000", transaction_type, validator_key)).unwrap();
+        assert_eq!(transaction.recipient_type, AccountType::Staking);
+    }
+
+    let transaction = build(&keys, &format!("type = \"unstake\"\nrecipient = \"{}\"\nvalue = 1000", address(&keys.key_pairs[0]))).unwrap();
+    assert_eq!(transaction.sender_type, AccountType::Staking);
+}
+
+#[test]
+fn it_builds_validator_transactions() {
+    let keys = generate_keys(1);
+
+    let transaction = build(&keys, "type = \"create_validator\"\ncommission_rate = 100\nvalue = 100000000").unwrap();
+    assert_eq!(transaction.recipient_type, AccountType::Staking);
+
+    // The validator stake must not be below the minimum.
+    assert!(build(&keys, "type = \"create_validator\"\ncommission_rate = 100\nvalue = 1000").is_err());
+
+    build(&keys, "type = \"update_validator\"\nnew_commission_rate = 200").unwrap();
+    for transaction_type in &["retire_validator", "reactivate_validator", "unpark_validator"] {
+        let transaction = build(&keys, &format!("type = \"{}\"", transaction_type)).unwrap();
+        assert_eq!(transaction.value, Coin::ZERO);
+    }
+
+    let transaction = build(&keys, &format!("type = \"drop_validator\"\nrecipient = \"{}\"\nvalue = 100000000", address(&keys.key_pairs[0]))).unwrap();
+    assert_eq!(transaction.sender_type, AccountType::Staking);
+}
+
+#[test]
+fn it_requires_the_right_keys() {
+    let transaction = format!("type = \"basic\"\nrecipient = \"{}\"\nvalue = 1000", random_address());
+    assert!(build(&generate_keys(0), &transaction).is_err());
+    assert!(build(&generate_keys(2), &transaction).is_err());
+
+    let keys = Keys {
+        bls_key_pair: None,
+        ..generate_keys(1)
+    };
+    assert!(build(&keys, "type = \"retire_validator\"").is_err());
+}
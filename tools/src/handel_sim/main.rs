@@ -0,0 +1,265 @@
+extern crate nimiq_handel as handel;
+
+use std::process::exit;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{App, Arg, ArgMatches, crate_version, crate_authors, crate_description};
+use failure::{Error, Fail};
+
+use handel::config::Config;
+use handel::simulator::{ByzantineBehavior, Simulation, SimulationConfig, SimulationResult};
+use handel::timeout::TimeoutKind;
+
+
+fn run_app() -> Result<(), Error> {
+    let matches = App::new("Handel simulator")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about(crate_description!())
+        .arg(Arg::with_name("nodes")
+            .short("n")
+            .long("nodes")
+            .value_name("COUNT")
+            .help("Number of nodes (default: 64)")
+            .takes_value(true))
+        .arg(Arg::with_name("threshold")
+            .long("threshold")
+            .value_name("VOTES")
+            .help("Number of votes needed (default: more than two thirds of the nodes)")
+            .takes_value(true))
+        .arg(Arg::with_name("byzantine")
+            .short("b")
+            .long("byzantine")
+            .value_name("COUNT")
+            .help("Number of byzantine nodes (default: 0)")
+            .takes_value(true))
+        .arg(Arg::with_name("byzantine_behavior")
+            .long("byzantine-behavior")
+            .value_name("BEHAVIOR")
+            .possible_values(&["silent", "forging"])
+            .help("How byzantine nodes behave (default: silent)")
+            .takes_value(true))
+        .arg(Arg::with_name("latency")
+            .short("l")
+            .long("latency")
+            .value_name("MILLIS")
+            .help("Minimum message latency (default: 50)")
+            .takes_value(true))
+        .arg(Arg::with_name("jitter")
+            .short("j")
+            .long("jitter")
+            .value_name("MILLIS")
+            .help("Maximum additional random message latency (default: 50)")
+            .takes_value(true))
+        .arg(Arg::with_name("loss")
+            .long("loss")
+            .value_name("RATE")
+            .help("Probability that a message is lost (default: 0)")
+            .takes_value(true))
+        .arg(Arg::with_name("verify")
+            .long("verify")
+            .help("Verify BLS signatures (slow)")
+            .takes_value(false))
+        .arg(Arg::with_name("max_duration")
+            .long("max-duration")
+            .value_name("SECS")
+            .help("Abort a run after SECS of simulated time (default: 60)")
+            .takes_value(true))
+        .arg(Arg::with_name("runs")
+            .short("r")
+            .long("runs")
+            .value_name("COUNT")
+            .help("Number of runs with different seeds per configuration (default: 5)")
+            .takes_value(true))
+        .arg(Arg::with_name("seed")
+            .short("s")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seed of the first run (default: 0)")
+            .takes_value(true))
+        .arg(Arg::with_name("update_interval")
+            .long("update-interval")
+            .value_name("MILLIS,...")
+            .help("Handel update intervals to simulate")
+            .takes_value(true))
+        .arg(Arg::with_name("timeout_strategy")
+            .long("timeout-strategy")
+            .value_name("STRATEGY,...")
+            .help("Timeout strategies to simulate: linear, exponential, adaptive (default: linear)")
+            .takes_value(true))
+        .arg(Arg::with_name("timeout")
+            .long("timeout")
+            .value_name("MILLIS,...")
+            .help("Level timeouts to simulate. This is the period of the linear strategy, the timeout \
+                   of level 0 of the exponential strategy and the initial timeout of the adaptive strategy \
+                   (default: 500)")
+            .takes_value(true))
+        .arg(Arg::with_name("peer_count")
+            .long("peer-count")
+            .value_name("COUNT,...")
+            .help("Handel peer counts to simulate")
+            .takes_value(true))
+        .arg(Arg::with_name("update_count")
+            .long("update-count")
+            .value_name("COUNT,...")
+            .help("Handel update counts to simulate")
+            .takes_value(true))
+        .get_matches();
+
+    let num_nodes = parse_value(&matches, "nodes", 64usize)?;
+    let threshold = parse_value(&matches, "threshold", SimulationConfig::two_third_threshold(num_nodes))?;
+    let byzantine_behavior = match matches.value_of("byzantine_behavior") {
+        Some("forging") => ByzantineBehavior::Forging,
+        _ => ByzantineBehavior::Silent,
+    };
+    let runs = parse_value(&matches, "runs", 5u64)?;
+    let seed = parse_value(&matches, "seed", 0u64)?;
+
+    let base_config = SimulationConfig {
+        num_nodes,
+        threshold,
+        num_byzantine: parse_value(&matches, "byzantine", 0usize)?,
+        byzantine_behavior,
+        latency: Duration::from_millis(parse_value(&matches, "latency", 50u64)?),
+        jitter: Duration::from_millis(parse_value(&matches, "jitter", 50u64)?),
+        loss_rate: parse_value(&matches, "loss", 0f64)?,
+        verify_signatures: matches.is_present("verify"),
+        max_duration: Duration::from_secs(parse_value(&matches, "max_duration", 60u64)?),
+        seed,
+        handel: Config::default(),
+    };
+    if base_config.num_byzantine > num_nodes {
+        return Err(AppError::Byzantine.into());
+    }
+    if base_config.loss_rate < 0.0 || base_config.loss_rate > 1.0 {
+        return Err(AppError::LossRate.into());
+    }
+
+    let default = Config::default();
+    let update_intervals = parse_list(&matches, "update_interval", default.update_interval.as_millis() as u64)?;
+    let timeout_strategies = parse_list(&matches, "timeout_strategy", TimeoutKind::Linear)?;
+    let timeouts = parse_list(&matches, "timeout", 500u64)?;
+    let peer_counts = parse_list(&matches, "peer_count", default.peer_count)?;
+    let update_counts = parse_list(&matches, "update_count", default.update_count)?;
+
+    println!("# nodes={}, threshold={}, byzantine={} ({:?}), latency={:?}+{:?}, loss={}, runs={}",
+             num_nodes, threshold, base_config.num_byzantine, byzantine_behavior, base_config.latency,
+             base_config.jitter, base_config.loss_rate, runs);
+    println!("{:>10} {:>11} {:>10} {:>6} {:>6} | {:>9} {:>10} {:>10} {:>10} {:>10} {:>10}",
+             "interval", "strategy", "timeout", "peers", "upd", "complete", "mean", "median", "max", "msgs/node", "kB/node");
+
+    for &update_interval in &update_intervals {
+        for &timeout_strategy in &timeout_strategies {
+            for &timeout in &timeouts {
+                for &peer_count in &peer_counts {
+                    for &update_count in &update_counts {
+                        let handel = Config {
+                            update_count,
+                            update_interval: Duration::from_millis(update_interval),
+                            timeout: Duration::from_millis(timeout),
+                            timeout_strategy,
+                            peer_count,
+                        };
+                        // An adaptive strategy learns from all runs of a configuration.
+                        let strategy = handel.create_timeouts();
+
+                        let results = (0..runs)
+                            .map(|run| Simulation::new(SimulationConfig {
+                                seed: seed + run,
+                                handel: handel.clone(),
+                                timeouts: Arc::clone(&strategy),
+                                ..base_config.clone()
+                            }).run())
+                            .collect::<Vec<SimulationResult>>();
+
+                        print_results(&handel, &results);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_results(config: &Config, results: &[SimulationResult]) {
+    let completed = results.iter()
+        .filter_map(SimulationResult::time_to_threshold)
+        .collect::<Vec<Duration>>();
+    let medians = results.iter()
+        .filter_map(SimulationResult::median_time_to_threshold)
+        .collect::<Vec<Duration>>();
+    let messages_per_node = results.iter()
+        .map(SimulationResult::messages_per_node)
+        .sum::<f64>() / results.len() as f64;
+    let kb_per_node = results.iter()
+        .map(|result| result.bytes_sent as f64 / result.completion_times.len().max(1) as f64 / 1000f64)
+        .sum::<f64>() / results.len() as f64;
+
+    println!("{:>10} {:>11} {:>10} {:>6} {:>6} | {:>9} {:>10} {:>10} {:>10} {:>10.1} {:>10.1}",
+             format!("{}ms", config.update_interval.as_millis()),
+             config.timeout_strategy.as_str(),
+             format!("{}ms", config.timeout.as_millis()),
+             config.peer_count,
+             config.update_count,
+             format!("{}/{}", completed.len(), results.len()),
+             format_duration(mean(&completed)),
+             format_duration(mean(&medians)),
+             format_duration(completed.iter().max().cloned()),
+             messages_per_node,
+             kb_per_node);
+}
+
+fn mean(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        None
+    } else {
+        Some(durations.iter().sum::<Duration>() / durations.len() as u32)
+    }
+}
+
+fn format_duration(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) => format!("{}ms", duration.as_millis()),
+        None => "-".to_string(),
+    }
+}
+
+fn parse_value<T: FromStr>(matches: &ArgMatches, name: &'static str, default: T) -> Result<T, AppError> {
+    match matches.value_of(name) {
+        Some(s) => T::from_str(s).map_err(|_| AppError::InvalidArgument(name)),
+        None => Ok(default),
+    }
+}
+
+fn parse_list<T: FromStr>(matches: &ArgMatches, name: &'static str, default: T) -> Result<Vec<T>, AppError> {
+    match matches.value_of(name) {
+        Some(s) => s.split(',')
+            .map(|item| T::from_str(item.trim()).map_err(|_| AppError::InvalidArgument(name)))
+            .collect(),
+        None => Ok(vec![default]),
+    }
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+
+#[derive(Debug, Fail)]
+enum AppError {
+    #[fail(display = "Invalid value for argument: {}", _0)]
+    InvalidArgument(&'static str),
+    #[fail(display = "More byzantine nodes than nodes")]
+    Byzantine,
+    #[fail(display = "Loss rate must be between 0 and 1")]
+    LossRate,
+}
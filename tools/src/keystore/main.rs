@@ -0,0 +1,116 @@
+extern crate nimiq_keys as keys;
+extern crate nimiq_wallet as wallet;
+
+use std::fs;
+use std::process::exit;
+use std::str::FromStr;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand, crate_version, crate_authors, crate_description};
+use failure::{Error, Fail};
+
+use keys::{KeyPair, PrivateKey};
+use nimiq_tools::password::read_password;
+use wallet::{Keystore, WalletAccount};
+
+
+fn run_app() -> Result<(), Error> {
+    let file_arg = Arg::with_name("file")
+        .value_name("FILE")
+        .help("The keystore file.")
+        .required(true);
+    let password_arg = Arg::with_name("password")
+        .short("p")
+        .long("password")
+        .value_name("PASSWORD")
+        .help("The password of the keystore. Read from STDIN if not given.")
+        .takes_value(true);
+
+    let matches = App::new("Keystore")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about(crate_description!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("create")
+            .about("Creates a keystore with a new random account.")
+            .arg(file_arg.clone())
+            .arg(password_arg.clone()))
+        .subcommand(SubCommand::with_name("import")
+            .about("Creates a keystore from a hex encoded private key.")
+            .arg(file_arg.clone())
+            .arg(Arg::with_name("secret_key")
+                .short("k")
+                .long("secret-key")
+                .value_name("SECRET_KEY")
+                .help("The private key to store.")
+                .takes_value(true)
+                .required(true))
+            .arg(password_arg.clone()))
+        .subcommand(SubCommand::with_name("export")
+            .about("Decrypts a keystore and prints its private key.")
+            .arg(file_arg.clone())
+            .arg(password_arg))
+        .subcommand(SubCommand::with_name("info")
+            .about("Prints the address of a keystore without decrypting it.")
+            .arg(file_arg))
+        .get_matches();
+
+    match matches.subcommand() {
+        ("create", Some(matches)) => {
+            write_keystore(matches, WalletAccount::generate())
+        },
+        ("import", Some(matches)) => {
+            let private_key = PrivateKey::from_str(matches.value_of("secret_key").unwrap())?;
+            write_keystore(matches, WalletAccount::from(KeyPair::from(private_key)))
+        },
+        ("export", Some(matches)) => {
+            let keystore = read_keystore(matches)?;
+            let account = keystore.unlock(read_password(matches.value_of("password"))?.as_bytes())?;
+            println!("Address:       {}", account.address.to_user_friendly_address());
+            println!("Public Key:    {}", account.key_pair.public.to_hex());
+            println!("Private Key:   {}", account.key_pair.private.to_hex());
+            Ok(())
+        },
+        ("info", Some(matches)) => {
+            let keystore = read_keystore(matches)?;
+            println!("Address:       {}", keystore.address().to_user_friendly_address());
+            println!("Version:       {}", Keystore::VERSION);
+            Ok(())
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn read_keystore(matches: &ArgMatches) -> Result<Keystore, Error> {
+    let bytes = fs::read(matches.value_of("file").unwrap())?;
+    Ok(Keystore::from_bytes(&bytes)?)
+}
+
+fn write_keystore(matches: &ArgMatches, account: WalletAccount) -> Result<(), Error> {
+    let path = matches.value_of("file").unwrap();
+    if fs::metadata(path).is_ok() {
+        return Err(AppError::FileExists.into());
+    }
+
+    let address = account.address.clone();
+    let keystore = Keystore::lock(account, read_password(matches.value_of("password"))?.as_bytes())?;
+    fs::write(path, keystore.to_bytes())?;
+    println!("Address:       {}", address.to_user_friendly_address());
+    Ok(())
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+
+#[derive(Debug, Fail)]
+enum AppError {
+    #[fail(display = "Keystore file already exists")]
+    FileExists,
+}
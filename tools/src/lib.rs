@@ -0,0 +1,3 @@
+//! Functionality shared by the tools.
+
+pub mod password;
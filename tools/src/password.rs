@@ -0,0 +1,14 @@
+use std::io::{self, stdin};
+
+/// Returns `password` if it was given, e.g. on the command line, and otherwise reads the password
+/// from STDIN.
+pub fn read_password(password: Option<&str>) -> io::Result<String> {
+    if let Some(password) = password {
+        return Ok(password.to_string());
+    }
+
+    eprintln!("Password:");
+    let mut line = String::new();
+    stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(|c| c == '\n' || c == '\r').to_string())
+}
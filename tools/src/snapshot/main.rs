@@ -0,0 +1,78 @@
+extern crate nimiq_blockchain_albatross as blockchain;
+extern crate nimiq_database as database;
+extern crate nimiq_primitives as primitives;
+
+use std::process::exit;
+use std::str::FromStr;
+
+use clap::{App, Arg, crate_version, crate_authors, crate_description};
+use failure::{Error, Fail};
+
+use blockchain::Blockchain;
+use database::lmdb::{LmdbEnvironment, open};
+use primitives::networks::NetworkId;
+
+
+fn run_app() -> Result<(), Error> {
+    let matches = App::new("Export accounts snapshot")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about(crate_description!())
+        .arg(Arg::with_name("database")
+            .short("d")
+            .long("database")
+            .value_name("PATH")
+            .help("Path of the consensus database. The client must not be running.")
+            .takes_value(true))
+        .arg(Arg::with_name("network_id")
+            .short("N")
+            .long("network")
+            .value_name("NETWORK")
+            .help("Set network ID")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Write the snapshot to FILE.")
+            .takes_value(true))
+        .get_matches();
+
+    let db_path = matches.value_of("database")
+        .ok_or(AppError::Database)?;
+    let output = matches.value_of("output")
+        .ok_or(AppError::Output)?;
+    let network_id = match matches.value_of("network_id") {
+        Some(s) => NetworkId::from_str(s)?,
+        None => NetworkId::DevAlbatross
+    };
+
+    let env = LmdbEnvironment::new(db_path, 50 * 1024 * 1024, 20, open::NOMETASYNC)?;
+    let blockchain = Blockchain::new(env, network_id)?;
+
+    let snapshot = blockchain.export_accounts_snapshot()?;
+    snapshot.write_to_file(output)?;
+
+    println!("Exported {} accounts at macro block #{} (state root {})",
+             snapshot.accounts.len(), snapshot.macro_block.header.block_number, snapshot.state_root);
+    Ok(())
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+
+#[derive(Debug, Fail)]
+enum AppError {
+    #[fail(display = "Database path is missing")]
+    Database,
+    #[fail(display = "Output file is missing")]
+    Output,
+}
@@ -1,3 +1,5 @@
+#[macro_use]
+extern crate beserial_derive;
 extern crate nimiq_bls as bls;
 extern crate nimiq_hash as hash;
 extern crate nimiq_keys as keys;
@@ -7,9 +7,11 @@ use primitives::account::AccountType;
 use transaction::{SignatureProof, Transaction, TransactionFlags};
 
 use crate::proof::htlc_contract::HtlcProofBuilder;
+use crate::proof::multisig::{MultisigError, MultisigSession};
 use crate::proof::staking_contract::{SignallingProofBuilder, StakingProofBuilder};
 
 pub mod htlc_contract;
+pub mod multisig;
 pub mod staking_contract;
 
 /// The `TransactionProofBuilder` subsumes the builders used to populate a transaction
@@ -226,7 +228,7 @@ impl TransactionProofBuilder {
     ///
     /// let sender_address = Address::from(&key_pair.public);
     /// let mut recipient = Recipient::new_staking_builder(staking_contract_address);
-    /// recipient.update_validator(&bls_key_pair.public, None, Some(sender_address.clone()));
+    /// recipient.update_validator(&bls_key_pair.public, None, Some(sender_address.clone()), None);
     ///
     /// let tx_builder = TransactionBuilder::with_required(
     ///     sender_address,
@@ -369,6 +371,73 @@ impl BasicProofBuilder {
         self
     }
 
+    /// This method sets the required `signature` proof from a completed [`MultisigSession`]
+    /// for transactions that originate in a multisig account.
+    /// The session must have been created for this builder's transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nimiq_keys::{Address, KeyPair};
+    /// use nimiq_keys::multisig::CommitmentPair;
+    /// use nimiq_transaction_builder::{Recipient, TransactionBuilder};
+    /// use nimiq_transaction_builder::proof::multisig::{multisig_address, MultisigSession};
+    /// use nimiq_primitives::coin::Coin;
+    /// use nimiq_primitives::networks::NetworkId;
+    /// # use nimiq_utils::key_rng::SecureGenerate;
+    ///
+    /// // A 2-of-3 multisig account.
+    /// let key_pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_default_csprng()).collect();
+    /// let public_keys: Vec<_> = key_pairs.iter().map(|key_pair| key_pair.public).collect();
+    /// let sender_address = multisig_address(&public_keys, 2);
+    ///
+    /// let recipient = Recipient::new_basic(
+    ///     Address::from_any_str("NQ25 B7NR A1HC V4R2 YRKD 20PR RPGS MNV7 D812").unwrap()
+    /// );
+    /// let builder = TransactionBuilder::with_required(
+    ///     sender_address,
+    ///     recipient,
+    ///     Coin::from_u64_unchecked(100),
+    ///     1,
+    ///     NetworkId::Main
+    /// );
+    /// let mut basic_proof_builder = builder.generate().unwrap().unwrap_basic();
+    ///
+    /// // The first two owners sign.
+    /// let signers = &key_pairs[..2];
+    /// let mut session = MultisigSession::new(
+    ///     basic_proof_builder.transaction.clone(),
+    ///     &public_keys,
+    ///     2,
+    ///     &[signers[0].public, signers[1].public]
+    /// ).unwrap();
+    ///
+    /// // First round: commitments.
+    /// let commitment_pairs: Vec<CommitmentPair> = signers.iter().map(|_| CommitmentPair::generate_default_csprng()).collect();
+    /// for (key_pair, commitment_pair) in signers.iter().zip(commitment_pairs.iter()) {
+    ///     session.add_commitment(&key_pair.public, *commitment_pair.commitment()).unwrap();
+    /// }
+    ///
+    /// // Second round: partial signatures.
+    /// for (key_pair, commitment_pair) in signers.iter().zip(commitment_pairs.iter()) {
+    ///     session.partial_sign(key_pair, commitment_pair.random_secret()).unwrap();
+    /// }
+    ///
+    /// basic_proof_builder.sign_with_multisig_session(&session).unwrap();
+    /// let final_transaction = basic_proof_builder.generate();
+    /// assert!(final_transaction.is_some());
+    /// assert!(final_transaction.unwrap().verify(NetworkId::Main).is_ok());
+    /// ```
+    ///
+    /// [`MultisigSession`]: multisig/struct.MultisigSession.html
+    pub fn sign_with_multisig_session(&mut self, session: &MultisigSession) -> Result<&mut Self, MultisigError> {
+        if session.transaction.serialize_content() != self.transaction.serialize_content() {
+            return Err(MultisigError::SessionMismatch);
+        }
+        self.signature = Some(session.signature_proof()?);
+        Ok(self)
+    }
+
     /// This method generates the final transaction if the signature has been set correctly.
     /// Otherwise, it returns `None`.
     pub fn generate(self) -> Option<Transaction> {
@@ -0,0 +1,268 @@
+use failure::Fail;
+
+use beserial::{Deserialize, DeserializeWithLength, ReadBytesExt, Serialize, SerializingError};
+use hash::Blake2bHasher;
+use keys::{Address, KeyPair, PublicKey};
+use keys::multisig::{Commitment, PartialSignature, RandomSecret};
+use transaction::{SignatureProof, Transaction};
+use utils::merkle::{compute_root_from_content, Blake2bMerklePath};
+
+/// Signing a transaction from a multisig account can fail if the participants don't agree
+/// on the signing session or if messages are missing.
+/// In these cases, a `MultisigError` is returned.
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum MultisigError {
+    /// The number of required signatures must be at least one and at most the number of owners.
+    #[fail(display = "The number of required signatures is invalid.")]
+    InvalidThreshold,
+    /// Exactly the number of required signatures has to be used, and every signer must be
+    /// an owner of the account.
+    #[fail(display = "The signers are invalid.")]
+    InvalidSigners,
+    /// The public key is not one of the signers of this session.
+    #[fail(display = "The public key is not a signer of this session.")]
+    UnknownSigner,
+    /// Two sessions can only be merged if they sign the same transaction with the same signers.
+    #[fail(display = "The sessions don't match.")]
+    SessionMismatch,
+    /// Partial signatures can only be created once all signers committed.
+    #[fail(display = "Commitments are missing.")]
+    MissingCommitments,
+    /// The signature proof can only be created once all signers signed.
+    #[fail(display = "Partial signatures are missing.")]
+    MissingPartialSignatures,
+    /// The partial signatures don't add up to a valid signature, e.g. because a signer
+    /// used a different commitment than it announced.
+    #[fail(display = "The aggregated signature is invalid.")]
+    InvalidSignature,
+}
+
+/// Returns the public keys of a multisig account owned by `public_keys`, of which
+/// `min_signatures` have to sign.
+///
+/// Every combination of `min_signatures` owners signs with the aggregate of their public keys
+/// (see `PublicKey::aggregate`), so the account has one public key per combination.
+/// The keys are sorted.
+pub fn multisig_public_keys(public_keys: &[PublicKey], min_signatures: usize) -> Vec<PublicKey> {
+    let mut public_keys = public_keys.to_vec();
+    public_keys.sort();
+
+    let mut aggregated_keys: Vec<PublicKey> = combinations(&public_keys, min_signatures).iter()
+        .map(|signers| PublicKey::aggregate(signers))
+        .collect();
+    aggregated_keys.sort();
+    aggregated_keys
+}
+
+/// Returns the address of a multisig account, which is the root of the Merkle tree over
+/// its public keys (see `multisig_public_keys`).
+pub fn multisig_address(public_keys: &[PublicKey], min_signatures: usize) -> Address {
+    let public_keys = multisig_public_keys(public_keys, min_signatures);
+    Address::from(compute_root_from_content::<Blake2bHasher, PublicKey>(&public_keys))
+}
+
+/// All combinations of `k` elements of `values`, keeping their order.
+fn combinations<T: Clone>(values: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if values.len() < k {
+        return Vec::new();
+    }
+
+    let mut result = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        for mut combination in combinations(&values[i + 1..], k - 1) {
+            combination.insert(0, value.clone());
+            result.push(combination);
+        }
+    }
+    result
+}
+
+/// The `MultisigSession` holds the state of signing a transaction from a multisig account.
+/// It is serializable, so that it can be passed between the signers.
+///
+/// Signing takes two rounds:
+/// 1. Every signer creates a `CommitmentPair`, keeps its random secret and adds the commitment
+///    to the session using [`add_commitment`].
+/// 2. Once all commitments are known, every signer computes its partial signature using
+///    [`partial_sign`] and the random secret from the first round.
+///
+/// Sessions of different signers can be combined using [`merge`]. Once all partial signatures
+/// are known, the final proof is built by [`signature_proof`] or
+/// [`BasicProofBuilder::sign_with_multisig_session`].
+///
+/// A random secret must never be used for more than one session.
+///
+/// [`add_commitment`]: struct.MultisigSession.html#method.add_commitment
+/// [`partial_sign`]: struct.MultisigSession.html#method.partial_sign
+/// [`merge`]: struct.MultisigSession.html#method.merge
+/// [`signature_proof`]: struct.MultisigSession.html#method.signature_proof
+/// [`BasicProofBuilder::sign_with_multisig_session`]: ../struct.BasicProofBuilder.html#method.sign_with_multisig_session
+#[derive(Clone, Debug, Serialize)]
+pub struct MultisigSession {
+    pub transaction: Transaction,
+    /// All owners of the account, sorted
+    #[beserial(len_type(u8))]
+    public_keys: Vec<PublicKey>,
+    min_signatures: u8,
+    /// The owners that sign the transaction, sorted
+    #[beserial(len_type(u8))]
+    signers: Vec<PublicKey>,
+    /// The commitments of the signers, in the order of `signers`
+    #[beserial(len_type(u8))]
+    commitments: Vec<Option<Commitment>>,
+    /// The partial signatures of the signers, in the order of `signers`
+    #[beserial(len_type(u8))]
+    partial_signatures: Vec<Option<PartialSignature>>,
+}
+
+impl Deserialize for MultisigSession {
+    /// Sessions are received from other signers, so they are checked like in `MultisigSession::new`.
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let transaction: Transaction = Deserialize::deserialize(reader)?;
+        let public_keys: Vec<PublicKey> = DeserializeWithLength::deserialize::<u8, R>(reader)?;
+        let min_signatures: u8 = Deserialize::deserialize(reader)?;
+        let signers: Vec<PublicKey> = DeserializeWithLength::deserialize::<u8, R>(reader)?;
+        let commitments: Vec<Option<Commitment>> = DeserializeWithLength::deserialize::<u8, R>(reader)?;
+        let partial_signatures: Vec<Option<PartialSignature>> = DeserializeWithLength::deserialize::<u8, R>(reader)?;
+
+        let mut session = MultisigSession::new(transaction, &public_keys, min_signatures, &signers)
+            .map_err(|_| SerializingError::InvalidValue)?;
+
+        // The keys must already be sorted and unique, since commitments and partial signatures
+        // are in the order of `signers`.
+        if session.public_keys != public_keys || session.signers != signers
+            || commitments.len() != signers.len() || partial_signatures.len() != signers.len() {
+            return Err(SerializingError::InvalidValue);
+        }
+
+        session.commitments = commitments;
+        session.partial_signatures = partial_signatures;
+        Ok(session)
+    }
+}
+
+impl MultisigSession {
+    /// Creates a new session to sign `transaction` from the multisig account owned by
+    /// `public_keys` with `signers`.
+    pub fn new(transaction: Transaction, public_keys: &[PublicKey], min_signatures: u8, signers: &[PublicKey]) -> Result<Self, MultisigError> {
+        let mut public_keys = public_keys.to_vec();
+        public_keys.sort();
+        public_keys.dedup();
+        if min_signatures == 0 || min_signatures as usize > public_keys.len() || public_keys.len() > u8::max_value() as usize {
+            return Err(MultisigError::InvalidThreshold);
+        }
+
+        let mut signers = signers.to_vec();
+        signers.sort();
+        signers.dedup();
+        if signers.len() != min_signatures as usize || signers.iter().any(|signer| !public_keys.contains(signer)) {
+            return Err(MultisigError::InvalidSigners);
+        }
+
+        Ok(MultisigSession {
+            transaction,
+            public_keys,
+            min_signatures,
+            commitments: vec![None; signers.len()],
+            partial_signatures: vec![None; signers.len()],
+            signers,
+        })
+    }
+
+    /// The address of the multisig account.
+    pub fn address(&self) -> Address {
+        multisig_address(&self.public_keys, self.min_signatures as usize)
+    }
+
+    pub fn signers(&self) -> &[PublicKey] {
+        &self.signers
+    }
+
+    /// Adds the `commitment` of the signer with `public_key` (first round).
+    pub fn add_commitment(&mut self, public_key: &PublicKey, commitment: Commitment) -> Result<&mut Self, MultisigError> {
+        let index = self.signer_index(public_key)?;
+        self.commitments[index] = Some(commitment);
+        Ok(self)
+    }
+
+    /// Returns the aggregated commitment once all signers committed.
+    pub fn aggregated_commitment(&self) -> Option<Commitment> {
+        let commitments = self.commitments.iter().cloned().collect::<Option<Vec<Commitment>>>()?;
+        Some(commitments.iter().sum())
+    }
+
+    /// Computes and adds the partial signature of `key_pair` (second round).
+    /// `random_secret` must belong to the commitment the signer added in the first round.
+    pub fn partial_sign(&mut self, key_pair: &KeyPair, random_secret: &RandomSecret) -> Result<&mut Self, MultisigError> {
+        let index = self.signer_index(&key_pair.public)?;
+        let commitments = self.commitments.iter().cloned().collect::<Option<Vec<Commitment>>>()
+            .ok_or(MultisigError::MissingCommitments)?;
+
+        let (partial_signature, _, _) = key_pair.partial_sign(&self.signers, random_secret, &commitments, &self.transaction.serialize_content());
+        self.partial_signatures[index] = Some(partial_signature);
+        Ok(self)
+    }
+
+    /// Adds the `partial_signature` of the signer with `public_key`.
+    pub fn add_partial_signature(&mut self, public_key: &PublicKey, partial_signature: PartialSignature) -> Result<&mut Self, MultisigError> {
+        let index = self.signer_index(public_key)?;
+        self.partial_signatures[index] = Some(partial_signature);
+        Ok(self)
+    }
+
+    /// Adds the commitments and partial signatures of `other`, which must sign the same
+    /// transaction with the same signers.
+    pub fn merge(&mut self, other: &MultisigSession) -> Result<&mut Self, MultisigError> {
+        if self.transaction.serialize_content() != other.transaction.serialize_content()
+            || self.public_keys != other.public_keys
+            || self.min_signatures != other.min_signatures
+            || self.signers != other.signers
+            || other.commitments.len() != self.signers.len()
+            || other.partial_signatures.len() != self.signers.len() {
+            return Err(MultisigError::SessionMismatch);
+        }
+
+        for (commitment, other) in self.commitments.iter_mut().zip(other.commitments.iter()) {
+            if other.is_some() {
+                *commitment = *other;
+            }
+        }
+        for (partial_signature, other) in self.partial_signatures.iter_mut().zip(other.partial_signatures.iter()) {
+            if other.is_some() {
+                *partial_signature = *other;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Builds the signature proof for the transaction once all signers signed.
+    pub fn signature_proof(&self) -> Result<SignatureProof, MultisigError> {
+        let commitment = self.aggregated_commitment()
+            .ok_or(MultisigError::MissingCommitments)?;
+        let partial_signatures = self.partial_signatures.iter().cloned().collect::<Option<Vec<PartialSignature>>>()
+            .ok_or(MultisigError::MissingPartialSignatures)?;
+
+        let signature = partial_signatures.iter().sum::<PartialSignature>().to_signature(&commitment);
+        let public_key = PublicKey::aggregate(&self.signers);
+        if !public_key.verify(&signature, &self.transaction.serialize_content()) {
+            return Err(MultisigError::InvalidSignature);
+        }
+
+        let public_keys = multisig_public_keys(&self.public_keys, self.min_signatures as usize);
+        Ok(SignatureProof {
+            public_key,
+            merkle_path: Blake2bMerklePath::new::<Blake2bHasher, PublicKey>(&public_keys, &public_key),
+            signature,
+        })
+    }
+
+    fn signer_index(&self, public_key: &PublicKey) -> Result<usize, MultisigError> {
+        self.signers.iter()
+            .position(|signer| signer == public_key)
+            .filter(|&index| index < self.commitments.len() && index < self.partial_signatures.len())
+            .ok_or(MultisigError::UnknownSigner)
+    }
+}
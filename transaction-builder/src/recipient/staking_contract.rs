@@ -92,37 +92,41 @@ impl StakingRecipientBuilder {
     }
 
     /// This method allows to create a new validator entry using a BLS key pair `key_pair`.
-    /// All rewards for this validator will be paid out to its `reward_address`.
-    pub fn create_validator(&mut self, key_pair: &KeyPair, reward_address: Address) -> &mut Self {
+    /// The validator's part of the rewards will be paid out to its `reward_address`.
+    /// The validator keeps `commission_rate` (in basis points) of its rewards,
+    /// the rest is split with its stakers.
+    pub fn create_validator(&mut self, key_pair: &KeyPair, reward_address: Address, commission_rate: u16) -> &mut Self {
         self.staking_data = Some(StakingTransaction::IncomingTransaction(
             IncomingStakingTransactionData::CreateValidator {
                 validator_key: key_pair.public.compress(),
                 proof_of_knowledge: StakingRecipientBuilder::generate_proof_of_knowledge(&key_pair),
                 reward_address,
+                commission_rate,
             },
         ));
         self
     }
 
     /// This method allows to create a new validator entry and also generates a new key pair for it.
-    /// All rewards for this validator will be paid out to its `reward_address`.
+    /// The validator's part of the rewards will be paid out to its `reward_address`.
     ///
     /// The method returns the generated BLS key pair.
-    pub fn create_validator_with_new_bls_key(&mut self, reward_address: Address) -> KeyPair {
+    pub fn create_validator_with_new_bls_key(&mut self, reward_address: Address, commission_rate: u16) -> KeyPair {
         let key = KeyPair::generate_default_csprng();
-        self.create_validator(&key, reward_address);
+        self.create_validator(&key, reward_address, commission_rate);
         key
     }
 
     /// This method allows to update the details of an existing validator entry with the
     /// public key `old_validator_key`.
-    /// Both the key pair and the reward address can be updated.
+    /// The key pair, the reward address and the commission rate can be updated.
     /// All updates will only take effect starting in the following epoch.
     pub fn update_validator(
         &mut self,
         old_validator_key: &PublicKey,
         new_key_pair: Option<&KeyPair>,
         new_reward_address: Option<Address>,
+        new_commission_rate: Option<u16>,
     ) -> &mut Self {
         self.staking_data = Some(StakingTransaction::IncomingTransaction(
             IncomingStakingTransactionData::UpdateValidator {
@@ -131,6 +135,7 @@ impl StakingRecipientBuilder {
                 new_proof_of_knowledge: new_key_pair
                     .map(|key| StakingRecipientBuilder::generate_proof_of_knowledge(&key)),
                 new_reward_address,
+                new_commission_rate,
                 signature: Default::default(),
             },
         ));
@@ -238,7 +243,7 @@ impl StakingRecipientBuilder {
     /// let staking_contract = Address::from_any_str("NQ25 B7NR A1HC V4R2 YRKD 20PR RPGS MNV7 D812").unwrap();
     /// let reward_address = Address::from_any_str("NQ46 MNYU LQ93 GYYS P5DC YA51 L5JP UPUT KR62").unwrap();
     /// let mut recipient_builder = Recipient::new_staking_builder(staking_contract);
-    /// recipient_builder.create_validator(&validator_key_pair, reward_address);
+    /// recipient_builder.create_validator(&validator_key_pair, reward_address, 1_000);
     /// let recipient = recipient_builder.generate();
     /// assert!(recipient.is_some());
     /// ```
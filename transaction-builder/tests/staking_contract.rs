@@ -105,6 +105,7 @@ fn it_can_verify_validator_transactions() {
             validator_key: bls_pair.public.compress(),
             proof_of_knowledge: bls_pair.sign(&bls_pair.public).compress(),
             reward_address: Address::from_any_str(STAKER_ADDRESS).unwrap(),
+            commission_rate: 1_000,
         },
         150_000_000,
         &bls_pair,
@@ -112,7 +113,7 @@ fn it_can_verify_validator_transactions() {
     );
 
     let mut recipient = Recipient::new_staking_builder(Address::from([1u8; 20]));
-    recipient.create_validator(&bls_pair, Address::from_any_str(STAKER_ADDRESS).unwrap());
+    recipient.create_validator(&bls_pair, Address::from_any_str(STAKER_ADDRESS).unwrap(), 1_000);
 
     let mut tx_builder = TransactionBuilder::new();
     tx_builder
@@ -135,6 +136,7 @@ fn it_can_verify_validator_transactions() {
             new_validator_key: None,
             new_proof_of_knowledge: None,
             new_reward_address: Some(Address::from([1u8; 20])),
+            new_commission_rate: None,
             signature: Default::default(),
         },
         0,
@@ -143,7 +145,7 @@ fn it_can_verify_validator_transactions() {
     );
 
     let mut recipient = Recipient::new_staking_builder(Address::from([1u8; 20]));
-    recipient.update_validator(&bls_pair.public, None, Some(Address::from([1u8; 20])));
+    recipient.update_validator(&bls_pair.public, None, Some(Address::from([1u8; 20])), None);
 
     let mut tx_builder = TransactionBuilder::new();
     tx_builder
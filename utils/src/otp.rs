@@ -147,6 +147,16 @@ impl<T: Clear + Deserialize + Serialize> Locked<T> {
         Self::new(secret, password, OtpLock::<T>::DEFAULT_ITERATIONS, OtpLock::<T>::DEFAULT_SALT_LENGTH)
     }
 
+    /// The number of Argon2 iterations the data is locked with.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// The salt the data is locked with.
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
     /// Calling code should make sure to clear the password from memory after use.
     /// The integrity of the output value is not checked.
     pub fn unlock_unchecked(self, password: &[u8]) -> Result<Unlocked<T>, Locked<T>> {
@@ -2,6 +2,8 @@
 
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate beserial_derive;
 extern crate nimiq_macros as macros;
 extern crate nimiq_handel as handel;
 
@@ -31,4 +33,6 @@ pub mod error;
 pub mod slash;
 pub mod signature_aggregation;
 pub mod pool;
+pub mod signing_journal;
+pub mod voting_state;
 
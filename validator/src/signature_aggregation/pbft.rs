@@ -17,9 +17,10 @@ use handel::store::{SignatureStore, ReplaceStore};
 use handel::aggregation::Aggregation;
 use handel::config::Config;
 use handel::partitioner::BinomialPartitioner;
-use handel::verifier::MultithreadedVerifier;
+use handel::verifier::BatchVerifier;
 use handel::multisig::{Signature, MultiSignature, IndividualSignature};
 use handel::identity::WeightRegistry;
+use handel::timeout::TimeoutStrategy;
 
 use super::voting::{VotingProtocol, Tag, VotingEvaluator, VotingSender, ValidatorRegistry};
 use crate::pool::ValidatorPool;
@@ -89,20 +90,25 @@ pub struct PbftCommitProtocol {
 
     /// The verifier for the commit phase. The difference from the prepare verifier is only the message
     /// hash, which uses a different prefix.
-    verifier: Arc<MultithreadedVerifier<ValidatorRegistry>>,
+    verifier: Arc<BatchVerifier<ValidatorRegistry>>,
 
     sender: Arc<VotingSender<PbftCommitMessage>>,
 
+    /// The level timeouts. Commit levels complete at a different pace than prepare levels, since
+    /// they can only complete after the prepare phase, so they have their own timeouts.
+    timeouts: Arc<dyn TimeoutStrategy>,
+
     prepare_aggregation: Arc<Aggregation<PbftPrepareProtocol>>,
 }
 
 impl Protocol for PbftCommitProtocol {
     type Registry = ValidatorRegistry;
-    type Verifier = MultithreadedVerifier<ValidatorRegistry>;
+    type Verifier = BatchVerifier<ValidatorRegistry>;
     type Store = ReplaceStore<BinomialPartitioner>;
     type Evaluator = PbftCommitEvaluator;
     type Partitioner = BinomialPartitioner;
     type Sender = VotingSender<PbftCommitMessage>;
+    type Timeouts = dyn TimeoutStrategy;
 
     fn registry(&self) -> Arc<Self::Registry> {
         Arc::clone(&self.prepare_aggregation.protocol.registry())
@@ -120,6 +126,10 @@ impl Protocol for PbftCommitProtocol {
         Arc::clone(&self.evaluator)
     }
 
+    fn timeouts(&self) -> Arc<Self::Timeouts> {
+        Arc::clone(&self.timeouts)
+    }
+
     fn partitioner(&self) -> Arc<Self::Partitioner> {
         Arc::clone(&self.prepare_aggregation.protocol.partitioner())
     }
@@ -134,12 +144,12 @@ impl Protocol for PbftCommitProtocol {
 }
 
 impl PbftCommitProtocol {
-    pub fn new(prepare_aggregation: Arc<Aggregation<PbftPrepareProtocol>>) -> Self {
+    pub fn new(prepare_aggregation: Arc<Aggregation<PbftPrepareProtocol>>, timeouts: Arc<dyn TimeoutStrategy>) -> Self {
         let prepare_protocol = &prepare_aggregation.protocol;
 
         let tag = PbftCommitMessage::from(prepare_protocol.tag.block_hash.clone());
         let registry = Arc::clone(&prepare_protocol.registry());
-        let verifier = Arc::new(MultithreadedVerifier::shared(
+        let verifier = Arc::new(BatchVerifier::shared(
             tag.hash_with_prefix(),
             Arc::clone(&registry),
         ));
@@ -163,6 +173,7 @@ impl PbftCommitProtocol {
             evaluator,
             verifier,
             sender,
+            timeouts,
             prepare_aggregation,
         }
     }
@@ -193,7 +204,7 @@ pub struct PbftAggregation {
 
 
 impl PbftAggregation {
-    pub fn new(proposal_hash: Blake2bHash, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, config: Option<Config>) -> Self {
+    pub fn new(proposal_hash: Blake2bHash, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, prepare_timeouts: Arc<dyn TimeoutStrategy>, commit_timeouts: Arc<dyn TimeoutStrategy>, config: Option<Config>) -> Self {
         let config = config.unwrap_or_default();
 
         // create prepare aggregation
@@ -201,11 +212,12 @@ impl PbftAggregation {
             PbftPrepareMessage::from(proposal_hash.clone()),
             node_id,
             validators,
+            prepare_timeouts,
         );
         let prepare_aggregation = Aggregation::new(prepare_protocol, config.clone());
 
         // create commit aggregation
-        let commit_protocol = PbftCommitProtocol::new(Arc::clone(&prepare_aggregation));
+        let commit_protocol = PbftCommitProtocol::new(Arc::clone(&prepare_aggregation), commit_timeouts);
         let commit_aggregation = Aggregation::new(commit_protocol, config);
 
         Self {
@@ -16,7 +16,7 @@ use collections::bitset::BitSet;
 use handel::protocol::Protocol;
 use handel::multisig::{IndividualSignature, Signature};
 use handel::identity::{IdentityRegistry, WeightRegistry};
-use handel::verifier::MultithreadedVerifier;
+use handel::verifier::BatchVerifier;
 use handel::config::Config;
 use handel::store::ReplaceStore;
 use handel::partitioner::BinomialPartitioner;
@@ -25,6 +25,7 @@ use handel::update::{LevelUpdate, LevelUpdateMessage};
 use handel::aggregation::Aggregation;
 use handel::store::SignatureStore;
 use handel::sender::Sender;
+use handel::timeout::TimeoutStrategy;
 
 use crate::pool::ValidatorPool;
 
@@ -122,7 +123,7 @@ pub struct VotingProtocol<T: Tag> {
 
     // TODO: This should not be part of the protocol. Except that it holds the hash which depends
     // on the *specific* item being hashed (including prefix)
-    verifier: Arc<MultithreadedVerifier<ValidatorRegistry>>,
+    verifier: Arc<BatchVerifier<ValidatorRegistry>>,
 
     partitioner: Arc<BinomialPartitioner>,
     store: Arc<RwLock<ReplaceStore<BinomialPartitioner>>>,
@@ -130,11 +131,15 @@ pub struct VotingProtocol<T: Tag> {
     /// The evaluator being used. This either just counts votes
     evaluator: Arc<VotingEvaluator>,
 
+    /// The level timeouts. This is shared between aggregations, so that adaptive timeouts can
+    /// learn from previous aggregations.
+    timeouts: Arc<dyn TimeoutStrategy>,
+
     sender: Arc<VotingSender<T>>,
 }
 
 impl<T: Tag> VotingProtocol<T> {
-    pub fn new(tag: T, node_id: usize, validators: Arc<RwLock<ValidatorPool>>) -> Self {
+    pub fn new(tag: T, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, timeouts: Arc<dyn TimeoutStrategy>) -> Self {
         let guard = validators.read();
 
         let num_validators = guard.active_validator_count();
@@ -146,11 +151,10 @@ impl<T: Tag> VotingProtocol<T> {
         }
 
         let registry = Arc::new(ValidatorRegistry::new(Arc::clone(&validators)));
-        let verifier = Arc::new(MultithreadedVerifier::shared(
+        let verifier = Arc::new(BatchVerifier::shared(
             tag.hash_with_prefix(),
             Arc::clone(&registry),
         ));
-        //let timeouts = Arc::new(LinearTimeout::new(config.timeout));
         let partitioner = Arc::new(BinomialPartitioner::new(
             node_id,
             num_validators,
@@ -171,6 +175,7 @@ impl<T: Tag> VotingProtocol<T> {
             partitioner,
             store,
             evaluator,
+            timeouts,
             node_id,
             sender,
         }
@@ -195,11 +200,12 @@ impl<T: Tag> fmt::Debug for VotingProtocol<T> {
 
 impl<T: Tag> Protocol for VotingProtocol<T> {
     type Registry = ValidatorRegistry;
-    type Verifier = MultithreadedVerifier<ValidatorRegistry>;
+    type Verifier = BatchVerifier<ValidatorRegistry>;
     type Store = ReplaceStore<BinomialPartitioner>;
     type Evaluator = VotingEvaluator;
     type Partitioner = BinomialPartitioner;
     type Sender = VotingSender<T>;
+    type Timeouts = dyn TimeoutStrategy;
 
     fn registry(&self) -> Arc<Self::Registry> {
         Arc::clone(&self.registry)
@@ -217,6 +223,10 @@ impl<T: Tag> Protocol for VotingProtocol<T> {
         Arc::clone(&self.evaluator)
     }
 
+    fn timeouts(&self) -> Arc<Self::Timeouts> {
+        Arc::clone(&self.timeouts)
+    }
+
     fn partitioner(&self) -> Arc<Self::Partitioner> {
         Arc::clone(&self.partitioner)
     }
@@ -237,9 +247,9 @@ pub struct VoteAggregation<T: Tag> {
 }
 
 impl<T: Tag> VoteAggregation<T> {
-    pub fn new(tag: T, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, config: Option<Config>) -> Self {
+    pub fn new(tag: T, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, timeouts: Arc<dyn TimeoutStrategy>, config: Option<Config>) -> Self {
         let config = config.unwrap_or_default();
-        let protocol = VotingProtocol::new(tag, node_id, validators);
+        let protocol = VotingProtocol::new(tag, node_id, validators, timeouts);
         let aggregation = Aggregation::new(protocol, config);
         Self { inner: aggregation }
     }
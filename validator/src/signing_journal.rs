@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+use std::io;
+
+use failure::Fail;
+
+use beserial::{Deserialize, Serialize};
+use database::{AsDatabaseBytes, Database, Environment, FromDatabaseValue, ReadTransaction, WriteTransaction};
+use database::cursor::{ReadCursor, WriteCursor};
+use hash::Blake2bHash;
+
+/// Types of messages a validator signs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum SignedMessageType {
+    MicroBlock = 1,
+    ViewChange = 2,
+    PbftProposal = 3,
+    PbftPrepare = 4,
+    PbftCommit = 5,
+}
+
+#[derive(Clone, Debug, Fail)]
+pub enum SigningJournalError {
+    #[fail(display = "Already signed a conflicting {:?} at #{}.{}: {}", message_type, block_number, view_number, signed_hash)]
+    Conflict {
+        message_type: SignedMessageType,
+        block_number: u32,
+        view_number: u32,
+        signed_hash: Blake2bHash,
+    },
+}
+
+/// Key of a journal entry. Since beserial encodes integers big-endian, entries are
+/// ordered by block number, which allows pruning old entries with a cursor.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct JournalKey {
+    block_number: u32,
+    view_number: u32,
+    message_type: SignedMessageType,
+}
+
+impl AsDatabaseBytes for JournalKey {
+    fn as_database_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.serialize_to_vec())
+    }
+}
+
+impl FromDatabaseValue for JournalKey {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// On-disk journal of all messages signed by the validator.
+///
+/// Every message is recorded by its block number, view number and type together with the
+/// hash of the signed content *before* the signature is released. The validator refuses to
+/// sign a message if the journal already contains a different one for the same slot. This
+/// prevents equivocation (and thus slashing) after a crash, restart or failover.
+#[derive(Debug)]
+pub struct SigningJournal {
+    env: Environment,
+    journal_db: Database,
+}
+
+impl SigningJournal {
+    const JOURNAL_DB_NAME: &'static str = "ValidatorSigningJournal";
+
+    pub fn new(env: Environment) -> Self {
+        let journal_db = env.open_database(Self::JOURNAL_DB_NAME.to_string());
+        SigningJournal { env, journal_db }
+    }
+
+    /// Returns the hash of the message of type `message_type` that was signed at
+    /// `block_number`.`view_number`, if any.
+    pub fn get(&self, message_type: SignedMessageType, block_number: u32, view_number: u32) -> Option<Blake2bHash> {
+        let key = JournalKey { block_number, view_number, message_type };
+        ReadTransaction::new(&self.env).get(&self.journal_db, &key)
+    }
+
+    /// Records that the message with hash `hash` is about to be signed.
+    /// Signing the same message again is allowed, but if a different message of the same type
+    /// was signed at `block_number`.`view_number` before, this fails and the message must not
+    /// be signed.
+    pub fn record(&self, message_type: SignedMessageType, block_number: u32, view_number: u32, hash: &Blake2bHash) -> Result<(), SigningJournalError> {
+        let key = JournalKey { block_number, view_number, message_type };
+
+        let mut txn = WriteTransaction::new(&self.env);
+        match txn.get::<JournalKey, Blake2bHash>(&self.journal_db, &key) {
+            Some(ref signed_hash) if signed_hash == hash => {
+                txn.abort();
+                Ok(())
+            },
+            Some(signed_hash) => {
+                txn.abort();
+                Err(SigningJournalError::Conflict { message_type, block_number, view_number, signed_hash })
+            },
+            None => {
+                txn.put(&self.journal_db, &key, hash);
+                txn.commit();
+                Ok(())
+            },
+        }
+    }
+
+    /// Removes all entries for blocks before `block_number`.
+    pub fn prune(&self, block_number: u32) {
+        let mut txn = WriteTransaction::new(&self.env);
+        {
+            let mut cursor = txn.write_cursor(&self.journal_db);
+            let mut pos: Option<(JournalKey, Blake2bHash)> = cursor.first();
+
+            while let Some((key, _)) = pos {
+                if key.block_number >= block_number {
+                    break;
+                }
+                cursor.remove();
+                pos = cursor.next();
+            }
+        }
+        txn.commit();
+    }
+}
@@ -31,6 +31,7 @@ use blockchain_albatross::{Blockchain, ForkEvent};
 use blockchain_base::{AbstractBlockchain, BlockchainEvent};
 use bls::bls12_381::KeyPair;
 use consensus::{AlbatrossConsensusProtocol, Consensus, ConsensusEvent};
+use handel::config::Config as HandelConfig;
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use macros::upgrade_weak;
@@ -38,14 +39,17 @@ use network_primitives::networks::NetworkInfo;
 use network_primitives::validator_info::{SignedValidatorInfo, ValidatorInfo};
 use primitives::coin::Coin;
 use primitives::policy;
+use primitives::slot::SlotBand;
 use transaction_builder::{Recipient, TransactionBuilder};
 use utils::mutable_once::MutableOnce;
 use utils::observer::ListenerHandle;
 use utils::timers::Timers;
 
 use crate::error::Error;
+use crate::signing_journal::{SignedMessageType, SigningJournal};
 use crate::slash::ForkProofPool;
 use crate::validator_network::{ValidatorNetwork, ValidatorNetworkEvent};
+use crate::voting_state::VotingStateStore;
 
 #[derive(Clone, Debug)]
 pub enum SlotChange  {
@@ -61,6 +65,27 @@ pub enum ValidatorStatus {
     Active,
 }
 
+/// Blocks produced and missed by the validator in the current epoch.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EpochStats {
+    pub blocks_produced: u32,
+    /// Number of our slots that were skipped by a view change.
+    pub blocks_missed: u32,
+}
+
+/// Snapshot of the validator's state for monitoring.
+#[derive(Clone, Debug)]
+pub struct ValidatorStateInfo {
+    pub status: ValidatorStatus,
+    pub pk_idx: Option<u16>,
+    /// Slot numbers assigned to us in the current epoch.
+    pub slot_numbers: Vec<u16>,
+    pub view_number: u32,
+    /// The view change we started and that hasn't completed yet.
+    pub active_view_change: Option<ViewChange>,
+    pub epoch_stats: EpochStats,
+}
+
 struct ValidatorListeners {
     consensus: ListenerHandle,
     blockchain: ListenerHandle,
@@ -76,6 +101,9 @@ pub struct Validator {
     pub validator_key: KeyPair,
     pub validator_wallet_key: Option<keys::KeyPair>,
 
+    signing_journal: SigningJournal,
+    voting_state: VotingStateStore,
+
     timers: Timers<ValidatorTimer>,
 
     state: RwLock<ValidatorState>,
@@ -97,13 +125,14 @@ pub struct ValidatorState {
     view_number: u32,
     active_view_change: Option<ViewChange>,
     proposed_extrinsics: HashMap<Blake2bHash, MacroExtrinsics>,
+    epoch_stats: EpochStats,
 }
 
 impl Validator {
     const BLOCK_TIMEOUT: Duration = Duration::from_secs(10);
     //const PBFT_TIMEOUT: Duration = Duration::from_secs(60);
 
-    pub fn new(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>, validator_key: KeyPair, validator_wallet_key: Option<keys::KeyPair>) -> Result<Arc<Self>, Error> {
+    pub fn new(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>, validator_key: KeyPair, validator_wallet_key: Option<keys::KeyPair>, handel_config: HandelConfig) -> Result<Arc<Self>, Error> {
         let compressed_public_key = validator_key.public.compress();
         let info = ValidatorInfo {
             public_key: compressed_public_key,
@@ -111,9 +140,11 @@ impl Validator {
             udp_address: None,
             valid_from: consensus.blockchain.block_number(),
         };
-        let validator_network = ValidatorNetwork::new(consensus.network.clone(), consensus.blockchain.clone(), SignedValidatorInfo::from_message(info, &validator_key.secret, 0));
+        let validator_network = ValidatorNetwork::new(consensus.network.clone(), consensus.blockchain.clone(), SignedValidatorInfo::from_message(info, &validator_key.secret, 0), handel_config);
         let block_producer = BlockProducer::new(consensus.blockchain.clone(), consensus.mempool.clone(), validator_key.clone());
         let view_number = consensus.blockchain.next_view_number();
+        let signing_journal = SigningJournal::new(consensus.env.clone());
+        let voting_state = VotingStateStore::new(consensus.env.clone());
 
         debug!("Initializing validator");
 
@@ -125,6 +156,8 @@ impl Validator {
 
             validator_key,
             validator_wallet_key,
+            signing_journal,
+            voting_state,
             timers: Timers::new(),
 
             state: RwLock::new(ValidatorState {
@@ -135,6 +168,7 @@ impl Validator {
                 view_number,
                 active_view_change: None,
                 proposed_extrinsics: HashMap::new(),
+                epoch_stats: EpochStats::default(),
             }),
 
             self_weak: MutableOnce::new(Weak::new()),
@@ -210,10 +244,58 @@ impl Validator {
         let state = self.state.read();
         if state.status == ValidatorStatus::Active {
             drop(state);
+            self.restore_voting_state();
             self.on_slot_change(SlotChange::NextBlock);
         }
     }
 
+    /// Continues voting for the pBFT proposals and the view change we voted for before the
+    /// validator was restarted. Only messages we signed before are used, so this can't produce
+    /// conflicting signatures.
+    fn restore_voting_state(&self) {
+        let next_block_number = self.blockchain.height() + 1;
+        self.voting_state.prune(next_block_number);
+        let voting_state = self.voting_state.get();
+
+        for proposal in voting_state.proposals {
+            let hash: Blake2bHash = proposal.message.header.hash();
+
+            // Our prepare message is pushed when the validator network notifies us about the
+            // proposal.
+            if self.validator_network.get_pbft_proposal(&hash).is_none() {
+                info!("Restoring pBFT proposal: {}", hash);
+                if let Err(e) = self.validator_network.on_pbft_proposal(proposal) {
+                    debug!("Failed to restore pBFT proposal: {}", e);
+                    continue;
+                }
+            }
+
+            if let Some(commit) = voting_state.commits.iter().find(|commit| commit.message.block_hash == hash) {
+                self.validator_network.push_commit(commit.clone())
+                    .unwrap_or_else(|e| debug!("Failed to restore pBFT commit: {}", e));
+            }
+        }
+
+        if let Some(signed_view_change) = voting_state.view_change {
+            let _lock = self.blockchain.lock();
+            let mut state = self.state.write();
+
+            let view_change = signed_view_change.message.clone();
+            let is_current = view_change.block_number == next_block_number
+                && &view_change.prev_seed == self.blockchain.head().seed()
+                && view_change.new_view_number > state.view_number;
+            if state.active_view_change.is_none() && is_current {
+                info!("Restoring view change to {}", view_change);
+                state.view_number = view_change.new_view_number - 1;
+                state.active_view_change = Some(view_change);
+
+                drop(state);
+
+                self.validator_network.start_view_change(signed_view_change);
+            }
+        }
+    }
+
     pub fn on_consensus_lost(&self) {
         trace!("Consensus lost");
         let mut state = self.state.write();
@@ -234,6 +316,7 @@ impl Validator {
             BlockchainEvent::Finalized(hash) => {
                 // Init new validator epoch
                 self.init_epoch();
+                self.prune_signing_journal();
                 self.validator_network.on_blockchain_changed(hash);
             },
 
@@ -249,6 +332,9 @@ impl Validator {
             }
         }
 
+        // Votes for blocks we already have are not needed anymore.
+        self.voting_state.prune(self.blockchain.height() + 1);
+
         let mut state = self.state.write();
 
         // The new block might increase the view number before we actually finish the view change
@@ -317,6 +403,7 @@ impl Validator {
     fn init_epoch(&self) {
         let mut state = self.state.write();
         state.view_number = 0;
+        state.epoch_stats = EpochStats::default();
 
         match self.get_pk_idx_and_slots() {
             Some((pk_idx, slots)) => {
@@ -354,6 +441,10 @@ impl Validator {
 
         let mut state = self.state.write();
         state.fork_proof_pool.apply_block(&block);
+
+        let (produced, missed) = self.count_our_slots(&block);
+        state.epoch_stats.blocks_produced += produced;
+        state.epoch_stats.blocks_missed += missed;
     }
 
     // Sets the state according to the rebranch
@@ -361,9 +452,61 @@ impl Validator {
         let mut state = self.state.write();
         for (_hash, block) in old_chain.iter() {
             state.fork_proof_pool.revert_block(block);
+
+            let (produced, missed) = self.count_our_slots(block);
+            state.epoch_stats.blocks_produced = state.epoch_stats.blocks_produced.saturating_sub(produced);
+            state.epoch_stats.blocks_missed = state.epoch_stats.blocks_missed.saturating_sub(missed);
         }
         for (_hash, block) in new_chain.iter() {
             state.fork_proof_pool.apply_block(&block);
+
+            let (produced, missed) = self.count_our_slots(block);
+            state.epoch_stats.blocks_produced += produced;
+            state.epoch_stats.blocks_missed += missed;
+        }
+    }
+
+    /// Returns whether `block` was produced by us (0 or 1) and the number of our slots that
+    /// were skipped by view changes before it.
+    fn count_our_slots(&self, block: &Block) -> (u32, u32) {
+        let our_public_key = self.validator_key.public.compress();
+        let block_number = block.block_number();
+        let is_our_slot = |view_number| self.blockchain.get_slot_at(block_number, view_number, None)
+            .map_or(false, |(slot, _)| slot.public_key().compressed() == &our_public_key);
+
+        // View numbers continue from the parent block.
+        let first_view_number = self.blockchain.get_block(block.parent_hash(), false, false)
+            .map_or(0, |parent| parent.next_view_number());
+
+        let produced = if is_our_slot(block.view_number()) { 1 } else { 0 };
+        let missed = (first_view_number..block.view_number())
+            .filter(|&view_number| is_our_slot(view_number))
+            .count();
+        (produced, missed as u32)
+    }
+
+    /// Returns a snapshot of the validator's state.
+    pub fn state_info(&self) -> ValidatorStateInfo {
+        let state = self.state.read();
+
+        let slot_numbers = match (state.pk_idx, state.slots) {
+            (Some(pk_idx), Some(slots)) => {
+                let first_slot_number: u16 = self.blockchain.current_validators().iter()
+                    .take(pk_idx as usize)
+                    .map(|validator| validator.num_slots())
+                    .sum();
+                (first_slot_number..first_slot_number + slots).collect()
+            },
+            _ => Vec::new(),
+        };
+
+        ValidatorStateInfo {
+            status: state.status,
+            pk_idx: state.pk_idx,
+            slot_numbers,
+            view_number: state.view_number,
+            active_view_change: state.active_view_change.clone(),
+            epoch_stats: state.epoch_stats,
         }
     }
 
@@ -384,8 +527,7 @@ impl Validator {
                 self.on_slot_change(slot_change);
             },
             ValidatorNetworkEvent::PbftProposal(proposal) => {
-                let hash: Blake2bHash = proposal.header.hash();
-                self.on_pbft_proposal(&hash);
+                self.on_pbft_proposal(proposal);
             },
             ValidatorNetworkEvent::PbftPrepareComplete(hash) => {
                 self.on_pbft_prepare_complete(hash);
@@ -496,7 +638,8 @@ impl Validator {
         }
     }
 
-    pub fn on_pbft_proposal(&self, hash: &Blake2bHash) {
+    pub fn on_pbft_proposal(&self, proposal: &PbftProposal) {
+        let hash: Blake2bHash = proposal.header.hash();
         let state = self.state.write();
         trace!("Received proposal: {}", hash);
         // View change messages should only be sent by active validators.
@@ -509,12 +652,34 @@ impl Validator {
 
         drop(state);
 
-        trace!("Signing prepare: pk_idx={}", pk_idx);
-        let prepare_message = SignedPbftPrepareMessage::from_message(
-            PbftPrepareMessage { block_hash: hash.clone() },
-            &self.validator_key.secret,
-            pk_idx
-        );
+        let prepare_message = match self.voting_state.get().prepare(&hash) {
+            // We already signed a prepare message before the validator was restarted.
+            Some(prepare_message) => prepare_message.clone(),
+            None => {
+                if let Err(e) = self.signing_journal.record(SignedMessageType::PbftPrepare, proposal.header.block_number, proposal.header.view_number, &hash) {
+                    error!("Refusing to sign pBFT prepare: {}", e);
+                    return;
+                }
+
+                let signed_proposal = match self.validator_network.get_signed_pbft_proposal(&hash) {
+                    Some(signed_proposal) => signed_proposal,
+                    None => {
+                        debug!("Not signing pBFT prepare for unknown proposal: {}", hash);
+                        return;
+                    },
+                };
+
+                trace!("Signing prepare: pk_idx={}", pk_idx);
+                let prepare_message = SignedPbftPrepareMessage::from_message(
+                    PbftPrepareMessage { block_hash: hash.clone() },
+                    &self.validator_key.secret,
+                    pk_idx
+                );
+                self.voting_state.put_proposal(signed_proposal);
+                self.voting_state.put_prepare(prepare_message.clone());
+                prepare_message
+            },
+        };
 
         self.validator_network.push_prepare(prepare_message)
             .unwrap_or_else(|e| debug!("Failed to push pBFT prepare: {}", e));
@@ -533,15 +698,32 @@ impl Validator {
 
         drop(state);
 
-        trace!("Signing commit message: pk_idx={}", pk_idx);
-        let commit_message = SignedPbftCommitMessage::from_message(
-            PbftCommitMessage { block_hash: hash.clone() },
-            &self.validator_key.secret,
-            pk_idx
-        );
+        // If we committed before the validator was restarted, our commit is sent again.
+        let commit_message = self.voting_state.commit_or_sign(hash, || {
+            let proposal = match self.validator_network.get_pbft_proposal(hash) {
+                Some(proposal) => proposal,
+                None => {
+                    debug!("Not signing pBFT commit for unknown proposal: {}", hash);
+                    return None;
+                },
+            };
+            if let Err(e) = self.signing_journal.record(SignedMessageType::PbftCommit, proposal.header.block_number, proposal.header.view_number, hash) {
+                error!("Refusing to sign pBFT commit: {}", e);
+                return None;
+            }
 
-        self.validator_network.push_commit(commit_message)
-            .unwrap_or_else(|e| debug!("Failed to push pBFT commit: {}", e));
+            trace!("Signing commit message: pk_idx={}", pk_idx);
+            Some(SignedPbftCommitMessage::from_message(
+                PbftCommitMessage { block_hash: hash.clone() },
+                &self.validator_key.secret,
+                pk_idx
+            ))
+        });
+
+        if let Some(commit_message) = commit_message {
+            self.validator_network.push_commit(commit_message)
+                .unwrap_or_else(|e| debug!("Failed to push pBFT commit: {}", e));
+        }
     }
 
     pub fn on_pbft_commit_complete(&self, hash: &Blake2bHash, proposal: &PbftProposal, proof: &PbftProof) {
@@ -587,10 +769,16 @@ impl Validator {
         let new_view_number = state.view_number + 1;
         let message = ViewChange { block_number, new_view_number, prev_seed };
 
+        if let Err(e) = self.signing_journal.record(SignedMessageType::ViewChange, block_number, new_view_number, &message.hash()) {
+            error!("Refusing to sign view change: {}", e);
+            return;
+        }
+
         info!("Starting view change to {}", message);
 
         let pk_idx = state.pk_idx.expect("Checked above that we are an active validator");
         let view_change_message = SignedViewChange::from_message(message.clone(), &self.validator_key.secret, pk_idx);
+        self.voting_state.put_view_change(view_change_message.clone());
         state.active_view_change = Some(message);
 
         drop(state);
@@ -599,6 +787,13 @@ impl Validator {
         self.validator_network.start_view_change(view_change_message);
      }
 
+    /// Removes journal entries that can't be used in fork proofs anymore.
+    /// We keep the entries of the current and the previous epoch.
+    fn prune_signing_journal(&self) {
+        let epoch = policy::epoch_at(self.blockchain.height() + 1);
+        self.signing_journal.prune(policy::first_block_of_registry(epoch));
+    }
+
     fn get_pk_idx_and_slots(&self) -> Option<(u16, u16)> {
         self.blockchain.current_validators()
             .find_idx_and_num_slots_by_public_key(&self.validator_key.public.compress())
@@ -618,7 +813,12 @@ impl Validator {
         // FIXME: Don't use network time
         let timestamp = self.consensus.network.network_time.now();
         let (pbft_proposal, proposed_extrinsics) = self.block_producer.next_macro_block_proposal(timestamp, view_number, view_change);
-        state.proposed_extrinsics.insert(pbft_proposal.header.hash(), proposed_extrinsics);
+        let hash: Blake2bHash = pbft_proposal.header.hash();
+        if let Err(e) = self.signing_journal.record(SignedMessageType::PbftProposal, block_number, view_number, &hash) {
+            error!("Refusing to sign pBFT proposal: {}", e);
+            return;
+        }
+        state.proposed_extrinsics.insert(hash, proposed_extrinsics);
         let pk_idx = state.pk_idx.expect("Checked that we are an active validator before entering this function");
 
         drop(state);
@@ -649,6 +849,14 @@ impl Validator {
         let timestamp = self.consensus.network.network_time.now();
 
         let block = self.block_producer.next_micro_block(fork_proofs, timestamp, view_number, vec![], view_change_proof);
+
+        // The block producer already signed the block, but the signature must not leave this
+        // validator if we signed a different block for this slot before.
+        if let Err(e) = self.signing_journal.record(SignedMessageType::MicroBlock, block_number, view_number, &block.header.hash()) {
+            error!("Refusing to sign micro block: {}", e);
+            return;
+        }
+
         info!("Produced block #{}.{}: {}",
               block.header.block_number,
               block.header.view_number,
@@ -26,6 +26,8 @@ use utils::mutable_once::MutableOnce;
 use utils::observer::{Notifier, weak_listener, weak_passthru_listener};
 use handel::aggregation::AggregationEvent;
 use handel::update::LevelUpdateMessage;
+use handel::config::Config as HandelConfig;
+use handel::timeout::TimeoutStrategy;
 use bls::bls12_381::CompressedPublicKey;
 
 use crate::validator_agent::{ValidatorAgent, ValidatorAgentEvent};
@@ -112,8 +114,8 @@ struct PbftState {
 }
 
 impl PbftState {
-    pub fn new(block_hash: Blake2bHash, proposal: SignedPbftProposal, node_id: usize, validators: Arc<RwLock<ValidatorPool>>) -> Self {
-        let aggregation = Arc::new(RwLock::new(PbftAggregation::new(block_hash.clone(), node_id, validators, None)));
+    pub fn new(block_hash: Blake2bHash, proposal: SignedPbftProposal, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, prepare_timeouts: Arc<dyn TimeoutStrategy>, commit_timeouts: Arc<dyn TimeoutStrategy>, handel_config: HandelConfig) -> Self {
+        let aggregation = Arc::new(RwLock::new(PbftAggregation::new(block_hash.clone(), node_id, validators, prepare_timeouts, commit_timeouts, Some(handel_config))));
         Self {
             proposal,
             block_hash,
@@ -214,6 +216,19 @@ pub struct ValidatorNetwork {
     /// NOTE: To avoid circular dead-locks, always acquire this after the validator pool lock.
     pub validators: Arc<RwLock<ValidatorPool>>,
 
+    /// Configuration of the signature aggregations
+    handel_config: HandelConfig,
+
+    /// Level timeouts of the view change aggregations. They are shared, so that adaptive timeouts
+    /// can learn from previous view changes.
+    view_change_timeouts: Arc<dyn TimeoutStrategy>,
+
+    /// Level timeouts of the pBFT prepare aggregations
+    pbft_prepare_timeouts: Arc<dyn TimeoutStrategy>,
+
+    /// Level timeouts of the pBFT commit aggregations
+    pbft_commit_timeouts: Arc<dyn TimeoutStrategy>,
+
     self_weak: MutableOnce<Weak<ValidatorNetwork>>,
     pub notifier: RwLock<Notifier<'static, ValidatorNetworkEvent>>,
 }
@@ -221,7 +236,7 @@ pub struct ValidatorNetwork {
 impl ValidatorNetwork {
     const LIMIT_POTENTIAL_VALIDATOR_INFOS: usize = 64;
 
-    pub fn new(network: Arc<Network<Blockchain>>, blockchain: Arc<Blockchain>, info: SignedValidatorInfo) -> Arc<Self> {
+    pub fn new(network: Arc<Network<Blockchain>>, blockchain: Arc<Blockchain>, info: SignedValidatorInfo, handel_config: HandelConfig) -> Arc<Self> {
         let pool = ValidatorPool::new(Arc::clone(&network));
 
         // blacklist ourself
@@ -232,6 +247,10 @@ impl ValidatorNetwork {
             info,
             state: RwLock::new(ValidatorNetworkState::default()),
             validators: Arc::new(RwLock::new(pool)),
+            view_change_timeouts: handel_config.create_timeouts(),
+            pbft_prepare_timeouts: handel_config.create_timeouts(),
+            pbft_commit_timeouts: handel_config.create_timeouts(),
+            handel_config,
             self_weak: MutableOnce::new(Weak::new()),
             notifier: RwLock::new(Notifier::new()),
         });
@@ -544,6 +563,9 @@ impl ValidatorNetwork {
             signed_proposal.clone(),
             validator_id,
             Arc::clone(&self.validators),
+            Arc::clone(&self.pbft_prepare_timeouts),
+            Arc::clone(&self.pbft_commit_timeouts),
+            self.handel_config.clone(),
         );
 
         let chain_height = self.blockchain.height();
@@ -737,7 +759,8 @@ impl ValidatorNetwork {
             view_change.clone(),
             node_id,
             Arc::clone(&self.validators),
-            None
+            Arc::clone(&self.view_change_timeouts),
+            Some(self.handel_config.clone()),
         );
         debug!("New view change for: {}, node_id={}", view_change, node_id);
 
@@ -764,6 +787,42 @@ impl ValidatorNetwork {
         self.on_pbft_proposal(signed_proposal)
     }
 
+    /// Returns the vote progress of all view changes that are currently being aggregated.
+    pub fn view_change_progress(&self) -> Vec<ViewChangeUpdateEvent> {
+        self.state.read().view_changes.iter()
+            .map(|(view_change, aggregation)| ViewChangeUpdateEvent {
+                view_change: view_change.clone(),
+                votes: aggregation.votes(),
+            })
+            .collect()
+    }
+
+    /// Returns the vote progress of all pBFT proposals that are currently being aggregated.
+    pub fn pbft_progress(&self) -> Vec<PbftUpdateEvent> {
+        self.state.read().pbft_states.iter()
+            .map(|pbft| {
+                let (prepare_votes, commit_votes) = pbft.aggregation.read().votes();
+                PbftUpdateEvent {
+                    hash: pbft.block_hash.clone(),
+                    prepare_votes,
+                    commit_votes,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the pBFT proposal for block `hash` if it is currently being aggregated.
+    pub fn get_pbft_proposal(&self, hash: &Blake2bHash) -> Option<PbftProposal> {
+        self.state.read().get_pbft_state(hash)
+            .map(|pbft| pbft.proposal.message.clone())
+    }
+
+    /// Returns the signed pBFT proposal for block `hash` if it is currently being aggregated.
+    pub fn get_signed_pbft_proposal(&self, hash: &Blake2bHash) -> Option<SignedPbftProposal> {
+        self.state.read().get_pbft_state(hash)
+            .map(|pbft| pbft.proposal.clone())
+    }
+
     pub fn push_prepare(&self, signed_prepare: SignedPbftPrepareMessage) -> Result<(), ValidatorNetworkError> {
         trace!("Push prepare: {:#?}", signed_prepare);
         let state = self.state.read();
@@ -0,0 +1,151 @@
+use std::borrow::Cow;
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use block_albatross::{SignedPbftCommitMessage, SignedPbftPrepareMessage, SignedPbftProposal, SignedViewChange};
+use database::{AsDatabaseBytes, Database, Environment, FromDatabaseValue, ReadTransaction, WriteTransaction};
+use hash::{Blake2bHash, Hash};
+
+/// The votes of the validator for the next block that are still being aggregated.
+///
+/// All messages in here were already signed by the validator (or, for proposals, are the
+/// proposals it voted for). Restoring them after a restart thus never leads to a conflicting
+/// signature.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VotingState {
+    /// The pBFT proposals we signed a prepare message for
+    #[beserial(len_type(u16))]
+    pub proposals: Vec<SignedPbftProposal>,
+    /// Our pBFT prepare messages
+    #[beserial(len_type(u16))]
+    pub prepares: Vec<SignedPbftPrepareMessage>,
+    /// Our pBFT commit messages
+    #[beserial(len_type(u16))]
+    pub commits: Vec<SignedPbftCommitMessage>,
+    /// Our view change that didn't complete yet
+    pub view_change: Option<SignedViewChange>,
+}
+
+impl VotingState {
+    pub fn prepare(&self, block_hash: &Blake2bHash) -> Option<&SignedPbftPrepareMessage> {
+        self.prepares.iter().find(|prepare| &prepare.message.block_hash == block_hash)
+    }
+
+    pub fn commit(&self, block_hash: &Blake2bHash) -> Option<&SignedPbftCommitMessage> {
+        self.commits.iter().find(|commit| &commit.message.block_hash == block_hash)
+    }
+
+    /// Removes everything that concerns blocks before `block_number`.
+    fn prune(&mut self, block_number: u32) {
+        self.proposals.retain(|proposal| proposal.message.header.block_number >= block_number);
+
+        let proposal_hashes = self.proposals.iter()
+            .map(|proposal| proposal.message.header.hash())
+            .collect::<Vec<Blake2bHash>>();
+        self.prepares.retain(|prepare| proposal_hashes.contains(&prepare.message.block_hash));
+        self.commits.retain(|commit| proposal_hashes.contains(&commit.message.block_hash));
+
+        if self.view_change.as_ref().map_or(false, |view_change| view_change.message.block_number < block_number) {
+            self.view_change = None;
+        }
+    }
+}
+
+impl AsDatabaseBytes for VotingState {
+    fn as_database_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.serialize_to_vec())
+    }
+}
+
+impl FromDatabaseValue for VotingState {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// Persists the `VotingState` of the validator.
+///
+/// Without this, a validator that is restarted while a macro block or a view change is being
+/// aggregated loses its votes and can't help to reach the threshold anymore.
+#[derive(Debug)]
+pub struct VotingStateStore {
+    env: Environment,
+    voting_state_db: Database,
+}
+
+impl VotingStateStore {
+    const VOTING_STATE_DB_NAME: &'static str = "ValidatorVotingState";
+    const VOTING_STATE_KEY: &'static str = "state";
+
+    pub fn new(env: Environment) -> Self {
+        let voting_state_db = env.open_database(Self::VOTING_STATE_DB_NAME.to_string());
+        VotingStateStore { env, voting_state_db }
+    }
+
+    pub fn get(&self) -> VotingState {
+        ReadTransaction::new(&self.env).get(&self.voting_state_db, Self::VOTING_STATE_KEY)
+            .unwrap_or_default()
+    }
+
+    /// Records a proposal we're voting for. Must be called before our prepare message for it is
+    /// put.
+    pub fn put_proposal(&self, proposal: SignedPbftProposal) {
+        let hash: Blake2bHash = proposal.message.header.hash();
+        self.update(move |state| {
+            let known = state.proposals.iter()
+                .any(|other| other.message.header.hash::<Blake2bHash>() == hash);
+            if !known {
+                state.proposals.push(proposal);
+            }
+        });
+    }
+
+    pub fn put_prepare(&self, prepare: SignedPbftPrepareMessage) {
+        self.update(move |state| {
+            if state.prepare(&prepare.message.block_hash).is_none() {
+                state.prepares.push(prepare);
+            }
+        });
+    }
+
+    pub fn put_commit(&self, commit: SignedPbftCommitMessage) {
+        self.update(move |state| {
+            if state.commit(&commit.message.block_hash).is_none() {
+                state.commits.push(commit);
+            }
+        });
+    }
+
+    /// Returns our commit message for `block_hash`. If we didn't commit to it before, e.g. before
+    /// the validator was restarted, the commit is signed with `sign` and recorded.
+    pub fn commit_or_sign<F>(&self, block_hash: &Blake2bHash, sign: F) -> Option<SignedPbftCommitMessage>
+        where F: FnOnce() -> Option<SignedPbftCommitMessage> {
+        if let Some(commit) = self.get().commit(block_hash) {
+            return Some(commit.clone());
+        }
+
+        let commit = sign()?;
+        self.put_commit(commit.clone());
+        Some(commit)
+    }
+
+    /// Records our current view change. This replaces the previous one.
+    pub fn put_view_change(&self, view_change: SignedViewChange) {
+        self.update(move |state| state.view_change = Some(view_change));
+    }
+
+    /// Removes everything that concerns blocks before `block_number`.
+    pub fn prune(&self, block_number: u32) {
+        self.update(|state| state.prune(block_number));
+    }
+
+    fn update<F: FnOnce(&mut VotingState)>(&self, f: F) {
+        let mut txn = WriteTransaction::new(&self.env);
+        let mut state: VotingState = txn.get(&self.voting_state_db, Self::VOTING_STATE_KEY)
+            .unwrap_or_default();
+        f(&mut state);
+        txn.put(&self.voting_state_db, Self::VOTING_STATE_KEY, &state);
+        txn.commit();
+    }
+}
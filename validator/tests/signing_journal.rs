@@ -0,0 +1,47 @@
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_validator::signing_journal::{SignedMessageType, SigningJournal};
+
+#[test]
+fn it_refuses_conflicting_messages() {
+    let env = VolatileEnvironment::new(1).unwrap();
+    let journal = SigningJournal::new(env.clone());
+
+    let hash_1: Blake2bHash = "block 1".hash();
+    let hash_2: Blake2bHash = "block 2".hash();
+
+    assert!(journal.record(SignedMessageType::MicroBlock, 1, 0, &hash_1).is_ok());
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 1, 0), Some(hash_1.clone()));
+
+    // Signing the same message again is fine.
+    assert!(journal.record(SignedMessageType::MicroBlock, 1, 0, &hash_1).is_ok());
+
+    // Signing a different message for the same slot isn't.
+    assert!(journal.record(SignedMessageType::MicroBlock, 1, 0, &hash_2).is_err());
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 1, 0), Some(hash_1.clone()));
+
+    // Other views and message types don't conflict.
+    assert!(journal.record(SignedMessageType::MicroBlock, 1, 1, &hash_2).is_ok());
+    assert!(journal.record(SignedMessageType::ViewChange, 1, 0, &hash_2).is_ok());
+
+    // The journal survives a restart.
+    let journal = SigningJournal::new(env);
+    assert!(journal.record(SignedMessageType::MicroBlock, 1, 0, &hash_2).is_err());
+}
+
+#[test]
+fn it_prunes_old_entries() {
+    let env = VolatileEnvironment::new(1).unwrap();
+    let journal = SigningJournal::new(env);
+
+    let hash: Blake2bHash = "block".hash();
+    for block_number in 1..=300 {
+        journal.record(SignedMessageType::MicroBlock, block_number, 0, &hash).unwrap();
+    }
+
+    journal.prune(257);
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 1, 0), None);
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 256, 0), None);
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 257, 0), Some(hash.clone()));
+    assert_eq!(journal.get(SignedMessageType::MicroBlock, 300, 0), Some(hash));
+}
@@ -0,0 +1,117 @@
+use nimiq_block_albatross::{
+    MacroHeader, PbftCommitMessage, PbftPrepareMessage, PbftProposal, SignedPbftCommitMessage,
+    SignedPbftPrepareMessage, SignedPbftProposal, SignedViewChange, ViewChange,
+};
+use nimiq_bls::bls12_381::KeyPair;
+use nimiq_bls::SecureGenerate;
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_primitives::slot::ValidatorSlotBand;
+use nimiq_validator::voting_state::VotingStateStore;
+
+fn proposal(key_pair: &KeyPair, block_number: u32) -> SignedPbftProposal {
+    let hash: Blake2bHash = "parent".hash();
+    let header = MacroHeader {
+        version: 1,
+        validators: Vec::<ValidatorSlotBand>::new().into_iter().collect(),
+        block_number,
+        view_number: 0,
+        parent_macro_hash: hash.clone(),
+        seed: key_pair.sign_hash(hash.clone()).compress().into(),
+        parent_hash: hash.clone(),
+        state_root: hash.clone(),
+        extrinsics_root: hash.clone(),
+        transactions_root: hash,
+        timestamp: 0,
+    };
+    SignedPbftProposal::from_message(PbftProposal { header, view_change: None }, &key_pair.secret, 0)
+}
+
+fn view_change(key_pair: &KeyPair, block_number: u32) -> SignedViewChange {
+    let message = ViewChange {
+        block_number,
+        new_view_number: 1,
+        prev_seed: key_pair.sign_hash("seed".hash()).compress().into(),
+    };
+    SignedViewChange::from_message(message, &key_pair.secret, 0)
+}
+
+#[test]
+fn it_restores_votes() {
+    let env = VolatileEnvironment::new(1).unwrap();
+    let key_pair = KeyPair::generate_default_csprng();
+
+    let proposal = proposal(&key_pair, 128);
+    let hash: Blake2bHash = proposal.message.header.hash();
+    let prepare = SignedPbftPrepareMessage::from_message(PbftPrepareMessage { block_hash: hash.clone() }, &key_pair.secret, 0);
+    let commit = SignedPbftCommitMessage::from_message(PbftCommitMessage { block_hash: hash.clone() }, &key_pair.secret, 0);
+
+    let store = VotingStateStore::new(env.clone());
+    store.put_proposal(proposal.clone());
+    store.put_prepare(prepare.clone());
+    store.put_commit(commit.clone());
+    store.put_view_change(view_change(&key_pair, 128));
+
+    // Putting the same messages again doesn't duplicate them.
+    store.put_proposal(proposal);
+    store.put_prepare(prepare.clone());
+
+    // The state survives a restart.
+    let state = VotingStateStore::new(env).get();
+    assert_eq!(state.proposals.len(), 1);
+    assert_eq!(state.prepares.len(), 1);
+    assert_eq!(state.prepare(&hash).unwrap().signature, prepare.signature);
+    assert_eq!(state.commit(&hash).unwrap().signature, commit.signature);
+    assert_eq!(state.view_change.unwrap().message.block_number, 128);
+}
+
+#[test]
+fn it_prunes_old_votes() {
+    let env = VolatileEnvironment::new(1).unwrap();
+    let key_pair = KeyPair::generate_default_csprng();
+    let store = VotingStateStore::new(env);
+
+    let old_proposal = proposal(&key_pair, 128);
+    let old_hash: Blake2bHash = old_proposal.message.header.hash();
+    let new_proposal = proposal(&key_pair, 256);
+    let new_hash: Blake2bHash = new_proposal.message.header.hash();
+
+    store.put_proposal(old_proposal);
+    store.put_prepare(SignedPbftPrepareMessage::from_message(PbftPrepareMessage { block_hash: old_hash.clone() }, &key_pair.secret, 0));
+    store.put_proposal(new_proposal);
+    store.put_prepare(SignedPbftPrepareMessage::from_message(PbftPrepareMessage { block_hash: new_hash.clone() }, &key_pair.secret, 0));
+    store.put_view_change(view_change(&key_pair, 200));
+
+    store.prune(200);
+    let state = store.get();
+    assert_eq!(state.proposals.len(), 1);
+    assert!(state.prepare(&old_hash).is_none());
+    assert!(state.prepare(&new_hash).is_some());
+    assert!(state.view_change.is_some());
+
+    store.prune(201);
+    assert!(store.get().view_change.is_none());
+}
+
+#[test]
+fn it_sends_the_stored_commit_after_a_restart() {
+    let env = VolatileEnvironment::new(1).unwrap();
+    let key_pair = KeyPair::generate_default_csprng();
+
+    let proposal = proposal(&key_pair, 128);
+    let hash: Blake2bHash = proposal.message.header.hash();
+    let sign = || Some(SignedPbftCommitMessage::from_message(PbftCommitMessage { block_hash: hash.clone() }, &key_pair.secret, 0));
+
+    // We commit once the prepare phase completed.
+    let store = VotingStateStore::new(env.clone());
+    store.put_proposal(proposal);
+    let commit = store.commit_or_sign(&hash, sign).expect("Expected a commit to be signed");
+
+    // After a restart, the prepare phase completes again and the stored commit is sent without
+    // signing a new one.
+    let store = VotingStateStore::new(env);
+    let restored = store.commit_or_sign(&hash, || panic!("Commit was signed again"))
+        .expect("Expected the stored commit to be sent");
+    assert_eq!(restored.signature, commit.signature);
+    assert_eq!(store.get().commits.len(), 1);
+}
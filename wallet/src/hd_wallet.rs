@@ -0,0 +1,184 @@
+use std::io;
+
+use failure::Fail;
+
+use beserial::{Deserialize, Serialize};
+use database::{FromDatabaseValue, IntoDatabaseValue};
+use key_derivation::ExtendedPrivateKey;
+use keys::{Address, KeyPair};
+use mnemonic::{Entropy, Mnemonic, MnemonicType, WORDLIST_EN};
+use nimiq_utils::key_rng::{Rng, SecureRng};
+use nimiq_utils::otp::Verify;
+
+use crate::wallet_account::WalletAccount;
+
+/// Importing an HD wallet fails if the mnemonic or the derivation path are invalid.
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum HdWalletError {
+    #[fail(display = "The mnemonic is invalid.")]
+    InvalidMnemonic,
+    #[fail(display = "The derivation path is invalid.")]
+    InvalidDerivationPath,
+}
+
+/// A hierarchical deterministic wallet.
+///
+/// All accounts are derived from the seed of a mnemonic along the paths `<base_path>/<index>'`.
+/// The wallet remembers the indices of the accounts that are in use, so that the next account
+/// can be derived on demand.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HdWallet {
+    #[beserial(len_type(u8))]
+    seed: Vec<u8>,
+    #[beserial(len_type(u8))]
+    base_path: String,
+    /// The indices of the derived accounts, sorted
+    #[beserial(len_type(u16))]
+    account_indices: Vec<u32>,
+}
+
+impl Verify for HdWallet {
+    fn verify(&self) -> bool {
+        !self.seed.is_empty() && ExtendedPrivateKey::is_valid_path(&self.base_path)
+    }
+}
+
+impl HdWallet {
+    /// The path below which accounts are derived by default (BIP44 with Nimiq's coin type).
+    pub const DEFAULT_BASE_PATH: &'static str = "m/44'/242'/0'";
+
+    /// The number of consecutive unused accounts after which `restore` stops scanning.
+    pub const GAP_LIMIT: u32 = 20;
+
+    /// Creates a wallet from a new random mnemonic, which is returned alongside it.
+    /// The wallet has no accounts yet.
+    pub fn generate() -> (Self, Mnemonic) {
+        let mut entropy = [0u8; Entropy::SIZE];
+        SecureRng::default().fill(&mut entropy[..]);
+        let mnemonic = Entropy::from(entropy).to_mnemonic(WORDLIST_EN);
+
+        let wallet = HdWallet::from_mnemonic(&mnemonic, None)
+            .expect("Generated mnemonic must be valid");
+        (wallet, mnemonic)
+    }
+
+    /// Creates a wallet from `mnemonic` (BIP39 or legacy), deriving accounts below
+    /// `DEFAULT_BASE_PATH`. The wallet has no accounts yet.
+    pub fn from_mnemonic(mnemonic: &Mnemonic, password: Option<&str>) -> Result<Self, HdWalletError> {
+        HdWallet::from_mnemonic_with_path(mnemonic, password, Self::DEFAULT_BASE_PATH)
+    }
+
+    pub fn from_mnemonic_with_path(mnemonic: &Mnemonic, password: Option<&str>, base_path: &str) -> Result<Self, HdWalletError> {
+        if mnemonic.get_type(WORDLIST_EN) == MnemonicType::INVALID {
+            return Err(HdWalletError::InvalidMnemonic);
+        }
+        if !ExtendedPrivateKey::is_valid_path(base_path) {
+            return Err(HdWalletError::InvalidDerivationPath);
+        }
+
+        let seed = mnemonic.to_seed(password)
+            .map_err(|_| HdWalletError::InvalidMnemonic)?;
+        Ok(HdWallet {
+            seed,
+            base_path: base_path.to_string(),
+            account_indices: Vec::new(),
+        })
+    }
+
+    /// The ID of the wallet, which is the address of its first account.
+    /// It doesn't depend on which accounts were derived.
+    pub fn id(&self) -> Address {
+        self.derive_account(0).address
+    }
+
+    pub fn derivation_path(&self, index: u32) -> String {
+        format!("{}/{}'", self.base_path, index)
+    }
+
+    /// The indices of the accounts in use.
+    pub fn account_indices(&self) -> &[u32] {
+        &self.account_indices
+    }
+
+    /// The accounts in use, in the order of their indices.
+    pub fn accounts(&self) -> Vec<WalletAccount> {
+        self.account_indices.iter()
+            .map(|&index| self.derive_account(index))
+            .collect()
+    }
+
+    /// Derives the account at `index` without marking it as used.
+    pub fn derive_account(&self, index: u32) -> WalletAccount {
+        let key = ExtendedPrivateKey::from_seed(self.seed.clone())
+            .derive_path(&self.derivation_path(index))
+            .expect("Derivation path was checked on creation");
+        WalletAccount::from(KeyPair::from(key.into_private_key()))
+    }
+
+    /// Derives the account following the last one in use and marks it as used.
+    pub fn derive_next(&mut self) -> WalletAccount {
+        let index = self.account_indices.last().map_or(0, |&index| index + 1);
+        self.account_indices.push(index);
+        self.derive_account(index)
+    }
+
+    /// Finds the accounts that are in use and marks them as used.
+    ///
+    /// Accounts are derived in order until `GAP_LIMIT` consecutive accounts are unused according
+    /// to `is_used`, e.g. because they have no history. `is_used` is called with the addresses of
+    /// up to `GAP_LIMIT` accounts at once and returns whether each of them is in use, so that
+    /// the accounts can be looked up in batches. If no account is in use, the first one is
+    /// marked as used, so that the wallet always has an account after restoring.
+    ///
+    /// Returns the accounts in use, or the first error of `is_used`.
+    pub fn restore<F, E>(&mut self, mut is_used: F) -> Result<Vec<WalletAccount>, E>
+        where F: FnMut(&[Address]) -> Result<Vec<bool>, E>
+    {
+        let mut index = 0;
+        let mut unused = 0;
+        while unused < Self::GAP_LIMIT {
+            // Check the accounts up to the end of the current gap at once.
+            let addresses: Vec<Address> = (index..index + Self::GAP_LIMIT - unused)
+                .map(|index| self.derive_account(index).address)
+                .collect();
+            let used = is_used(&addresses)?;
+            for i in 0..addresses.len() {
+                if used.get(i).cloned().unwrap_or(false) {
+                    self.mark_used(index);
+                    unused = 0;
+                } else {
+                    unused += 1;
+                }
+                index += 1;
+            }
+        }
+
+        if self.account_indices.is_empty() {
+            self.mark_used(0);
+        }
+        Ok(self.accounts())
+    }
+
+    fn mark_used(&mut self, index: u32) {
+        if let Err(pos) = self.account_indices.binary_search(&index) {
+            self.account_indices.insert(pos, index);
+        }
+    }
+}
+
+impl IntoDatabaseValue for HdWallet {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for HdWallet {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
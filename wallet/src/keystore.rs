@@ -0,0 +1,145 @@
+use failure::Fail;
+
+use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
+use keys::Address;
+use nimiq_utils::otp::{Locked, OtpLock, Unlocked};
+
+use crate::wallet_account::WalletAccount;
+
+/// Reading a keystore fails if the data isn't a keystore of a supported version or if the
+/// password is wrong.
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum KeystoreError {
+    #[fail(display = "The data is not a keystore.")]
+    InvalidFormat,
+    #[fail(display = "The keystore version {} is not supported.", _0)]
+    UnsupportedVersion(u8),
+    #[fail(display = "The encryption parameters of the keystore are not supported.")]
+    UnsupportedParameters,
+    #[fail(display = "The password is invalid.")]
+    InvalidPassword,
+    #[fail(display = "The account could not be encrypted.")]
+    EncryptionFailed,
+}
+
+/// A portable, password-encrypted wallet account, e.g. to back up a key or move it to
+/// another node.
+///
+/// The account is encrypted the same way as in the `WalletStore` (see `utils::otp::Locked`),
+/// using the default Argon2 parameters. Version 1 keystores must use exactly these parameters,
+/// so that an imported keystore can't force a weak encryption or an expensive key derivation.
+/// The address is stored in plain text, so that a keystore
+/// can be identified without the password.
+///
+/// Binary format:
+/// - magic bytes `NQKS`
+/// - version (u8)
+/// - address (20 bytes)
+/// - the locked account (see `Locked`)
+pub struct Keystore {
+    address: Address,
+    account: Locked<WalletAccount>,
+}
+
+impl Keystore {
+    pub const MAGIC: [u8; 4] = *b"NQKS";
+    pub const VERSION: u8 = 1;
+
+    /// Encrypts `account` with `password`.
+    pub fn lock(account: WalletAccount, password: &[u8]) -> Result<Self, KeystoreError> {
+        let address = account.address.clone();
+        let account = Locked::with_defaults(account, password)
+            .map_err(|_| KeystoreError::EncryptionFailed)?;
+        Ok(Keystore { address, account })
+    }
+
+    /// The address of the account, which can be read without the password.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Decrypts the account with `password`.
+    pub fn unlock(self, password: &[u8]) -> Result<WalletAccount, KeystoreError> {
+        let account = self.account.unlock(password)
+            .map(Unlocked::into_unlocked_data)
+            .map_err(|_| KeystoreError::InvalidPassword)?;
+
+        // The address in plain text could have been tampered with.
+        if account.address != self.address {
+            return Err(KeystoreError::InvalidFormat);
+        }
+        Ok(account)
+    }
+
+    /// Reads a keystore, checking its version and encryption parameters.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KeystoreError> {
+        if bytes.len() <= Self::MAGIC.len() || bytes[..Self::MAGIC.len()] != Self::MAGIC {
+            return Err(KeystoreError::InvalidFormat);
+        }
+
+        let version = bytes[Self::MAGIC.len()];
+        if version != Self::VERSION {
+            return Err(KeystoreError::UnsupportedVersion(version));
+        }
+
+        let reader = &mut &bytes[Self::MAGIC.len() + 1..];
+        let address: Address = Deserialize::deserialize(reader)
+            .map_err(|_| KeystoreError::InvalidFormat)?;
+        let account: Locked<WalletAccount> = Deserialize::deserialize(reader)
+            .map_err(|_| KeystoreError::InvalidFormat)?;
+        Self::check_parameters(&account)?;
+        Ok(Keystore { address, account })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.serialize_to_vec()
+    }
+
+    fn check_parameters(account: &Locked<WalletAccount>) -> Result<(), KeystoreError> {
+        if account.iterations() != OtpLock::<WalletAccount>::DEFAULT_ITERATIONS
+            || account.salt().len() != OtpLock::<WalletAccount>::DEFAULT_SALT_LENGTH {
+            return Err(KeystoreError::UnsupportedParameters);
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Keystore {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        writer.write_all(&Self::MAGIC)?;
+        let mut size = Self::MAGIC.len();
+        size += Serialize::serialize(&Self::VERSION, writer)?;
+        size += Serialize::serialize(&self.address, writer)?;
+        size += Serialize::serialize(&self.account, writer)?;
+        Ok(size)
+    }
+
+    fn serialized_size(&self) -> usize {
+        let mut size = Self::MAGIC.len();
+        size += Serialize::serialized_size(&Self::VERSION);
+        size += Serialize::serialized_size(&self.address);
+        size += Serialize::serialized_size(&self.account);
+        size
+    }
+}
+
+impl Deserialize for Keystore {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(SerializingError::InvalidValue);
+        }
+
+        let version: u8 = Deserialize::deserialize(reader)?;
+        if version != Self::VERSION {
+            return Err(SerializingError::InvalidValue);
+        }
+
+        let address: Address = Deserialize::deserialize(reader)?;
+        let account: Locked<WalletAccount> = Deserialize::deserialize(reader)?;
+        Self::check_parameters(&account)
+            .map_err(|_| SerializingError::InvalidValue)?;
+        Ok(Keystore { address, account })
+    }
+}
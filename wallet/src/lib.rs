@@ -2,12 +2,20 @@
 extern crate beserial_derive;
 extern crate nimiq_keys as keys;
 extern crate nimiq_key_derivation as key_derivation;
+extern crate nimiq_mnemonic as mnemonic;
 extern crate nimiq_primitives as primitives;
 extern crate nimiq_transaction as transaction;
+extern crate nimiq_transaction_builder as transaction_builder;
 extern crate nimiq_database as database;
 
+mod hd_wallet;
+mod keystore;
+mod multisig_account;
 mod wallet_account;
 mod wallet_store;
 
+pub use hd_wallet::{HdWallet, HdWalletError};
+pub use keystore::{Keystore, KeystoreError};
+pub use multisig_account::MultisigAccount;
 pub use wallet_account::WalletAccount;
 pub use wallet_store::WalletStore;
@@ -0,0 +1,112 @@
+use std::io;
+
+use beserial::{Deserialize, DeserializeWithLength, ReadBytesExt, Serialize, SerializingError};
+use database::{FromDatabaseValue, IntoDatabaseValue};
+use keys::{Address, KeyPair, PublicKey, SecureGenerate};
+use keys::multisig::CommitmentPair;
+use nimiq_utils::otp::Verify;
+use primitives::coin::Coin;
+use primitives::networks::NetworkId;
+use transaction::Transaction;
+use transaction_builder::proof::multisig::{multisig_address, MultisigError, MultisigSession};
+
+/// A wallet for an account that is controlled by `min_signatures` out of a set of owners,
+/// one of which is the owner of this wallet.
+///
+/// The address is derived from the public keys of all owners (see `multisig_address`).
+/// Transactions are signed in a `MultisigSession` that is passed between the signers.
+#[derive(Default, Debug, Clone, Serialize, PartialEq)]
+pub struct MultisigAccount {
+    pub key_pair: KeyPair,
+    pub min_signatures: u8,
+    /// The public keys of all owners, sorted
+    #[beserial(len_type(u8))]
+    pub public_keys: Vec<PublicKey>,
+    #[beserial(skip)]
+    pub address: Address,
+}
+
+impl Verify for MultisigAccount {
+    fn verify(&self) -> bool {
+        // Check that the public key corresponds to the private key.
+        PublicKey::from(&self.key_pair.private) == self.key_pair.public
+            && self.public_keys.contains(&self.key_pair.public)
+    }
+}
+
+impl MultisigAccount {
+    pub fn new(key_pair: KeyPair, min_signatures: u8, public_keys: &[PublicKey]) -> Result<Self, MultisigError> {
+        let mut public_keys = public_keys.to_vec();
+        if !public_keys.contains(&key_pair.public) {
+            public_keys.push(key_pair.public);
+        }
+        public_keys.sort();
+        public_keys.dedup();
+
+        if min_signatures == 0 || min_signatures as usize > public_keys.len() || public_keys.len() > u8::max_value() as usize {
+            return Err(MultisigError::InvalidThreshold);
+        }
+
+        let address = multisig_address(&public_keys, min_signatures as usize);
+        Ok(MultisigAccount {
+            key_pair,
+            min_signatures,
+            public_keys,
+            address,
+        })
+    }
+
+    /// Creates an unsigned transaction from this account.
+    /// It is signed in a session started with `start_session`.
+    pub fn create_transaction(&self, recipient: Address, value: Coin, fee: Coin, validity_start_height: u32, network_id: NetworkId) -> Transaction {
+        Transaction::new_basic(self.address.clone(), recipient, value, fee, validity_start_height, network_id)
+    }
+
+    /// Starts a session to sign `transaction` with `signers`.
+    pub fn start_session(&self, transaction: Transaction, signers: &[PublicKey]) -> Result<MultisigSession, MultisigError> {
+        MultisigSession::new(transaction, &self.public_keys, self.min_signatures, signers)
+    }
+
+    /// Adds our commitment to `session` (first round).
+    ///
+    /// The returned commitment pair is needed for `partial_sign` and must not be used for
+    /// another session.
+    pub fn commit(&self, session: &mut MultisigSession) -> Result<CommitmentPair, MultisigError> {
+        let commitment_pair = CommitmentPair::generate_default_csprng();
+        session.add_commitment(&self.key_pair.public, *commitment_pair.commitment())?;
+        Ok(commitment_pair)
+    }
+
+    /// Adds our partial signature to `session` (second round).
+    pub fn partial_sign(&self, session: &mut MultisigSession, commitment_pair: &CommitmentPair) -> Result<(), MultisigError> {
+        session.partial_sign(&self.key_pair, commitment_pair.random_secret())?;
+        Ok(())
+    }
+}
+
+impl Deserialize for MultisigAccount {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let key_pair: KeyPair = Deserialize::deserialize(reader)?;
+        let min_signatures: u8 = Deserialize::deserialize(reader)?;
+        let public_keys: Vec<PublicKey> = DeserializeWithLength::deserialize::<u8, R>(reader)?;
+        MultisigAccount::new(key_pair, min_signatures, &public_keys)
+            .map_err(|_| SerializingError::InvalidValue)
+    }
+}
+
+impl IntoDatabaseValue for MultisigAccount {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for MultisigAccount {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
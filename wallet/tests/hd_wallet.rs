@@ -0,0 +1,97 @@
+extern crate beserial;
+extern crate nimiq_database as database;
+extern crate nimiq_key_derivation as key_derivation;
+extern crate nimiq_mnemonic as mnemonic;
+extern crate nimiq_utils as utils;
+extern crate nimiq_wallet as wallet;
+
+use std::collections::HashSet;
+
+use beserial::{Deserialize, Serialize};
+use database::volatile::VolatileEnvironment;
+use key_derivation::ExtendedPrivateKey;
+use mnemonic::Mnemonic;
+use utils::otp::Locked;
+use wallet::{HdWallet, HdWalletError, WalletStore};
+
+const MNEMONIC: &str = "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold";
+
+#[test]
+fn it_derives_accounts_along_the_path() {
+    let mnemonic = Mnemonic::from(MNEMONIC);
+    let mut wallet = HdWallet::from_mnemonic(&mnemonic, None).unwrap();
+    assert!(wallet.account_indices().is_empty());
+
+    let first = wallet.derive_next();
+    let second = wallet.derive_next();
+    assert_eq!(wallet.account_indices(), &[0, 1]);
+    assert_ne!(first.address, second.address);
+    assert_eq!(wallet.id(), first.address);
+
+    let master_key = ExtendedPrivateKey::from_seed(mnemonic.to_seed(None).unwrap());
+    assert_eq!(second.address, master_key.derive_path("m/44'/242'/0'/1'").unwrap().to_address());
+
+    // A password yields a different wallet.
+    let other = HdWallet::from_mnemonic(&mnemonic, Some("password")).unwrap();
+    assert_ne!(other.id(), wallet.id());
+}
+
+#[test]
+fn it_rejects_invalid_input() {
+    let invalid = Mnemonic::from("void come effort suffer camp survey nimiq heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold");
+    assert_eq!(HdWallet::from_mnemonic(&invalid, None), Err(HdWalletError::InvalidMnemonic));
+    assert_eq!(HdWallet::from_mnemonic_with_path(&Mnemonic::from(MNEMONIC), None, "m/44'/242"), Err(HdWalletError::InvalidDerivationPath));
+}
+
+#[test]
+fn it_restores_used_accounts() {
+    let mnemonic = Mnemonic::from(MNEMONIC);
+    let wallet = HdWallet::from_mnemonic(&mnemonic, None).unwrap();
+
+    // Accounts 1 and 5 have a history, account 30 is beyond the gap limit.
+    let used: HashSet<_> = [1, 5, 30].iter()
+        .map(|&index| wallet.derive_account(index).address)
+        .collect();
+
+    let mut restored = HdWallet::from_mnemonic(&mnemonic, None).unwrap();
+    let accounts = restored.restore::<_, ()>(|addresses| Ok(addresses.iter()
+        .map(|address| used.contains(address))
+        .collect())).unwrap();
+    assert_eq!(restored.account_indices(), &[1, 5]);
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[1].address, wallet.derive_account(5).address);
+
+    // The next account follows the last used one.
+    assert_eq!(restored.derive_next().address, wallet.derive_account(6).address);
+
+    // Without any used accounts, the first account is used.
+    let mut empty = HdWallet::from_mnemonic(&mnemonic, None).unwrap();
+    empty.restore::<_, ()>(|addresses| Ok(vec![false; addresses.len()])).unwrap();
+    assert_eq!(empty.account_indices(), &[0]);
+
+    // Errors of the lookup are returned.
+    let mut failed = HdWallet::from_mnemonic(&mnemonic, None).unwrap();
+    assert_eq!(failed.restore(|_| Err("lookup failed")).err(), Some("lookup failed"));
+}
+
+#[test]
+fn it_stores_hd_wallets() {
+    let (mut wallet, mnemonic) = HdWallet::generate();
+    assert_eq!(HdWallet::from_mnemonic(&mnemonic, None).unwrap().id(), wallet.id());
+    wallet.derive_next();
+
+    let serialized = wallet.serialize_to_vec();
+    assert_eq!(HdWallet::deserialize_from_vec(&serialized).unwrap(), wallet);
+
+    let env = VolatileEnvironment::new(3).unwrap();
+    let store = WalletStore::new(env);
+    let locked = Locked::with_defaults(wallet.clone(), b"password").unwrap();
+    let mut txn = store.create_write_transaction();
+    store.put_hd(&wallet.id(), &locked, &mut txn);
+    txn.commit();
+
+    assert_eq!(store.list_hd(None), vec![wallet.id()]);
+    let unlocked = store.get_hd(&wallet.id(), None).unwrap()
+        .unlock(b"password").ok().unwrap();
+    assert_eq!(*unlocked, wallet);
+}
@@ -0,0 +1,70 @@
+extern crate beserial;
+extern crate nimiq_utils as utils;
+extern crate nimiq_wallet as wallet;
+
+use beserial::Serialize;
+use utils::otp::{Locked, OtpLock};
+use wallet::{Keystore, KeystoreError, WalletAccount};
+
+fn keystore_bytes(account: WalletAccount, iterations: u32, salt_length: usize) -> Vec<u8> {
+    let mut bytes = Keystore::MAGIC.to_vec();
+    bytes.push(Keystore::VERSION);
+    account.address.serialize(&mut bytes).unwrap();
+    Locked::new(account, b"password", iterations, salt_length).unwrap()
+        .serialize(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn it_can_lock_and_unlock_accounts() {
+    let account = WalletAccount::generate();
+    let keystore = Keystore::lock(account.clone(), b"password").unwrap();
+    assert_eq!(keystore.address(), &account.address);
+
+    let bytes = keystore.to_bytes();
+    assert_eq!(&bytes[..4], b"NQKS");
+    assert_eq!(bytes[4], Keystore::VERSION);
+
+    let keystore = Keystore::from_bytes(&bytes).unwrap();
+    assert_eq!(keystore.address(), &account.address);
+    assert_eq!(keystore.unlock(b"password").unwrap(), account);
+
+    let keystore = Keystore::from_bytes(&bytes).unwrap();
+    assert_eq!(keystore.unlock(b"wrong").err(), Some(KeystoreError::InvalidPassword));
+}
+
+#[test]
+fn it_rejects_invalid_keystores() {
+    let account = WalletAccount::generate();
+    let mut bytes = Keystore::lock(account, b"password").unwrap().to_bytes();
+
+    assert_eq!(Keystore::from_bytes(&bytes[..4]).err(), Some(KeystoreError::InvalidFormat));
+    assert_eq!(Keystore::from_bytes(&bytes[..30]).err(), Some(KeystoreError::InvalidFormat));
+
+    bytes[4] = Keystore::VERSION + 1;
+    assert_eq!(Keystore::from_bytes(&bytes).err(), Some(KeystoreError::UnsupportedVersion(Keystore::VERSION + 1)));
+
+    bytes[0] = b'X';
+    assert_eq!(Keystore::from_bytes(&bytes).err(), Some(KeystoreError::InvalidFormat));
+}
+
+#[test]
+fn it_rejects_keystores_with_tampered_parameters() {
+    let iterations = OtpLock::<WalletAccount>::DEFAULT_ITERATIONS;
+    let salt_length = OtpLock::<WalletAccount>::DEFAULT_SALT_LENGTH;
+
+    let bytes = keystore_bytes(WalletAccount::generate(), iterations, salt_length);
+    assert!(Keystore::from_bytes(&bytes).is_ok());
+
+    let bytes = keystore_bytes(WalletAccount::generate(), 1, salt_length);
+    assert_eq!(Keystore::from_bytes(&bytes).err(), Some(KeystoreError::UnsupportedParameters));
+
+    let bytes = keystore_bytes(WalletAccount::generate(), iterations, 8);
+    assert_eq!(Keystore::from_bytes(&bytes).err(), Some(KeystoreError::UnsupportedParameters));
+
+    // Tamper with the iterations of a valid keystore, which are stored at the end.
+    let mut bytes = Keystore::lock(WalletAccount::generate(), b"password").unwrap().to_bytes();
+    let len = bytes.len();
+    bytes[len - 4..].copy_from_slice(&u32::max_value().to_be_bytes());
+    assert_eq!(Keystore::from_bytes(&bytes).err(), Some(KeystoreError::UnsupportedParameters));
+}
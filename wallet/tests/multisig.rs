@@ -0,0 +1,143 @@
+extern crate beserial;
+extern crate nimiq_keys as keys;
+extern crate nimiq_primitives as primitives;
+extern crate nimiq_transaction_builder as transaction_builder;
+extern crate nimiq_wallet as wallet;
+
+use beserial::{Deserialize, Serialize, SerializeWithLength};
+use keys::{Address, KeyPair, PublicKey, SecureGenerate};
+use primitives::coin::Coin;
+use primitives::networks::NetworkId;
+use transaction_builder::proof::multisig::{MultisigError, MultisigSession};
+use wallet::MultisigAccount;
+
+fn accounts() -> Vec<MultisigAccount> {
+    let key_pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate_default_csprng()).collect();
+    let public_keys: Vec<PublicKey> = key_pairs.iter().map(|key_pair| key_pair.public).collect();
+    key_pairs.into_iter()
+        .map(|key_pair| MultisigAccount::new(key_pair, 2, &public_keys).unwrap())
+        .collect()
+}
+
+/// Sends the session to another signer.
+fn transfer(session: &MultisigSession) -> MultisigSession {
+    Deserialize::deserialize_from_vec(&session.serialize_to_vec()).unwrap()
+}
+
+/// Serializes a session with the given fields and no commitments or partial signatures yet.
+fn session_bytes(session: &MultisigSession, public_keys: &[PublicKey], min_signatures: u8, signers: &[PublicKey], num_commitments: usize) -> Vec<u8> {
+    let mut bytes = session.transaction.serialize_to_vec();
+    public_keys.to_vec().serialize::<u8, _>(&mut bytes).unwrap();
+    min_signatures.serialize(&mut bytes).unwrap();
+    signers.to_vec().serialize::<u8, _>(&mut bytes).unwrap();
+    // An absent commitment or partial signature is serialized like `None::<u8>`.
+    vec![None::<u8>; num_commitments].serialize::<u8, _>(&mut bytes).unwrap();
+    vec![None::<u8>; signers.len()].serialize::<u8, _>(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn test_address() {
+    let accounts = accounts();
+    assert_eq!(accounts[0].address, accounts[1].address);
+    assert_eq!(accounts[0].address, accounts[2].address);
+
+    // A different threshold results in a different account.
+    let other = MultisigAccount::new(accounts[0].key_pair.clone(), 3, &accounts[0].public_keys).unwrap();
+    assert_ne!(accounts[0].address, other.address);
+
+    assert_eq!(MultisigAccount::new(accounts[0].key_pair.clone(), 4, &accounts[0].public_keys), Err(MultisigError::InvalidThreshold));
+}
+
+#[test]
+fn test_sign_transaction() {
+    let accounts = accounts();
+    let signers = [accounts[2].key_pair.public, accounts[0].key_pair.public];
+
+    let transaction = accounts[0].create_transaction(
+        Address::from_user_friendly_address("NQ16 C3HR 85U8 P7MK F52R E9RG SA3Y Q69C X563").unwrap(),
+        Coin::from_u64_unchecked(42),
+        Coin::ZERO,
+        0,
+        NetworkId::Main,
+    );
+    let mut session = accounts[0].start_session(transaction, &signers).unwrap();
+    assert_eq!(session.address(), accounts[0].address);
+
+    // The third owner doesn't sign.
+    assert_eq!(accounts[1].commit(&mut session).err(), Some(MultisigError::UnknownSigner));
+
+    // First round
+    let commitment_0 = accounts[0].commit(&mut session).unwrap();
+    let mut session_2 = transfer(&session);
+    let commitment_2 = accounts[2].commit(&mut session_2).unwrap();
+    assert!(session_2.aggregated_commitment().is_some());
+
+    // Second round
+    accounts[2].partial_sign(&mut session_2, &commitment_2).unwrap();
+    assert_eq!(session_2.signature_proof().err(), Some(MultisigError::MissingPartialSignatures));
+    let mut session = transfer(&session_2);
+    accounts[0].partial_sign(&mut session, &commitment_0).unwrap();
+
+    let proof = session.signature_proof().unwrap();
+    assert!(proof.is_signed_by(&accounts[0].address));
+
+    let mut transaction = session.transaction.clone();
+    transaction.proof = proof.serialize_to_vec();
+    assert_eq!(Ok(()), transaction.verify(NetworkId::Main));
+}
+
+#[test]
+fn test_merge_sessions() {
+    let accounts = accounts();
+    let signers = [accounts[0].key_pair.public, accounts[1].key_pair.public];
+    let transaction = accounts[0].create_transaction(Address::from([1u8; Address::SIZE]), Coin::from_u64_unchecked(42), Coin::ZERO, 0, NetworkId::Main);
+
+    let mut session_0 = accounts[0].start_session(transaction.clone(), &signers).unwrap();
+    let mut session_1 = accounts[1].start_session(transaction, &signers).unwrap();
+    let commitment_0 = accounts[0].commit(&mut session_0).unwrap();
+    let commitment_1 = accounts[1].commit(&mut session_1).unwrap();
+    session_0.merge(&session_1).unwrap();
+    session_1.merge(&session_0).unwrap();
+
+    accounts[0].partial_sign(&mut session_0, &commitment_0).unwrap();
+    accounts[1].partial_sign(&mut session_1, &commitment_1).unwrap();
+    session_0.merge(&session_1).unwrap();
+    assert!(session_0.signature_proof().is_ok());
+
+    // Sessions for other transactions can't be merged.
+    let other = accounts[0].create_transaction(Address::from([2u8; Address::SIZE]), Coin::from_u64_unchecked(42), Coin::ZERO, 0, NetworkId::Main);
+    let other_session = accounts[0].start_session(other, &signers).unwrap();
+    assert_eq!(session_0.merge(&other_session).err(), Some(MultisigError::SessionMismatch));
+}
+
+#[test]
+fn test_deserialize_malformed_session() {
+    let accounts = accounts();
+    let transaction = accounts[0].create_transaction(Address::from([1u8; Address::SIZE]), Coin::from_u64_unchecked(42), Coin::ZERO, 0, NetworkId::Main);
+    let mut signers = vec![accounts[0].key_pair.public, accounts[1].key_pair.public];
+    signers.sort();
+    let session = accounts[0].start_session(transaction, &signers).unwrap();
+    let public_keys = accounts[0].public_keys.clone();
+
+    let bytes = session_bytes(&session, &public_keys, 2, &signers, 2);
+    assert_eq!(bytes, session.serialize_to_vec());
+    assert!(MultisigSession::deserialize_from_vec(&bytes).is_ok());
+
+    // Commitments don't match the signers.
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &public_keys, 2, &signers, 1)).is_err());
+    // Unsorted signers
+    let unsorted: Vec<PublicKey> = signers.iter().rev().cloned().collect();
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &public_keys, 2, &unsorted, 2)).is_err());
+    // Unsorted or duplicate owners
+    let unsorted: Vec<PublicKey> = public_keys.iter().rev().cloned().collect();
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &unsorted, 2, &signers, 2)).is_err());
+    let duplicate = vec![public_keys[0], public_keys[0], public_keys[1], public_keys[2]];
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &duplicate, 2, &signers, 2)).is_err());
+    // A signer that isn't an owner
+    let mut outsider = vec![signers[0], KeyPair::generate_default_csprng().public];
+    outsider.sort();
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &public_keys, 2, &outsider, 2)).is_err());
+    // Too few signers for the threshold
+    assert!(MultisigSession::deserialize_from_vec(&session_bytes(&session, &public_keys, 2, &signers[..1], 1)).is_err());
+}
@@ -1,38 +1,61 @@
 #[macro_use]
 extern crate log;
 
+extern crate nimiq_account as account;
+extern crate nimiq_block_albatross as block_albatross;
+extern crate nimiq_bls as bls;
 extern crate nimiq_consensus as consensus;
+extern crate nimiq_keys as keys;
+extern crate nimiq_mempool as mempool;
+extern crate nimiq_transaction as transaction;
 extern crate nimiq_utils as utils;
 extern crate nimiq_blockchain_albatross as blockchain_albatross;
 extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_hash as hash;
+extern crate nimiq_rpc_server as rpc_server;
 #[cfg(feature="validator")]
 extern crate nimiq_validator as validator;
 
+pub mod subscription;
+
 use std::net::{IpAddr, SocketAddr};
-use std::sync::Arc;
-use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Error as IoError, ErrorKind};
+use std::str::from_utf8;
 
-use futures::{Future, Stream, IntoFuture};
+use futures::{future, Future, Stream, IntoFuture};
 use futures::sink::Sink;
 use futures::sync::mpsc::{channel, Sender};
+use futures_cpupool::CpuPool;
 use tokio::net::{TcpListener};
-use tokio_tungstenite::accept_async;
+use http::StatusCode;
+use tokio_tungstenite::accept_hdr_async;
 use tokio_tungstenite::tungstenite::{Message, Error as WsError};
+use tokio_tungstenite::tungstenite::handshake::server::{Callback, ErrorResponse, Request};
 use parking_lot::RwLock;
-use json::{JsonValue, object};
+use json::{Array, JsonValue, object};
 
 use utils::unique_id::UniqueId;
 use consensus::{Consensus, AlbatrossConsensusProtocol};
 use blockchain_base::AbstractBlockchain;
+use account::Account;
+use block_albatross::Block;
+use blockchain_albatross::Blockchain;
 use blockchain_albatross::blockchain::BlockchainEvent;
-use hash::{Hash, Blake2bHash};
+use mempool::MempoolEvent;
+use transaction::Transaction;
+use hash::{Blake2bHash, Hash};
+use keys::Address;
+use rpc_server::error::AuthenticationError;
+use rpc_server::jsonrpc::{self, Handler};
 #[cfg(feature="validator")]
 use validator::validator_network::ValidatorNetworkEvent;
 #[cfg(feature="validator")]
 use validator::validator::Validator;
 
+use crate::subscription::Subscription;
+
 pub type WsRpcServerFuture = Box<dyn Future<Item=(), Error=()> + Send + Sync + 'static>;
 
 type WsRpcConnections = Arc<RwLock<HashMap<UniqueId, WsRpcConnection>>>;
@@ -40,6 +63,122 @@ type WsRpcConnections = Arc<RwLock<HashMap<UniqueId, WsRpcConnection>>>;
 struct WsRpcConnection {
     address: SocketAddr,
     tx: Sender<Message>,
+    authorized: bool,
+    subscriptions: BTreeMap<u32, Subscription>,
+    next_subscription_id: u32,
+}
+
+/// Wraps the RPC handler for a single connection. Clients have to call `authorize` with the
+/// configured credentials before they can call any other method or receive events.
+/// Events are only sent for the topics a client subscribed to using `subscribe`.
+struct WsRpcConnectionHandler {
+    connection_id: UniqueId,
+    connections: WsRpcConnections,
+    handler: Arc<dyn Handler>,
+}
+
+impl WsRpcConnectionHandler {
+    fn is_authorized(&self) -> bool {
+        self.connections.read().get(&self.connection_id)
+            .map(|connection| connection.authorized)
+            .unwrap_or(false)
+    }
+
+    fn authorize_connection(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let username = params.get(0).and_then(JsonValue::as_str).unwrap_or("");
+        let password = params.get(1).and_then(JsonValue::as_str).unwrap_or("");
+
+        self.authorize(username, password)
+            .map_err(|e| object!{"message" => e.to_string()})?;
+
+        if let Some(connection) = self.connections.write().get_mut(&self.connection_id) {
+            connection.authorized = true;
+        }
+        Ok(true.into())
+    }
+
+    /// Parameters: topic (string), followed by the topic's arguments:
+    ///  - `heads`, `finalized`: none
+    ///  - `transactions`: array of addresses, optionally followed by the direction to filter on
+    ///    (`sender`, `recipient` or `both`, default `both`)
+    ///  - `staking`: validator key (hex)
+    ///
+    /// Returns the subscription ID, which is included in every event sent for this subscription.
+    fn subscribe(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let subscription = Subscription::from_params(params)?;
+
+        let mut connections = self.connections.write();
+        let connection = connections.get_mut(&self.connection_id)
+            .ok_or_else(|| object!{"message" => "Connection closed"})?;
+
+        let subscription_id = connection.next_subscription_id;
+        connection.next_subscription_id += 1;
+        connection.subscriptions.insert(subscription_id, subscription);
+        Ok(subscription_id.into())
+    }
+
+    /// Parameters: subscription ID (number)
+    ///
+    /// Returns whether the subscription existed.
+    fn unsubscribe(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let subscription_id = params.get(0)
+            .and_then(JsonValue::as_u32)
+            .ok_or_else(|| object!{"message" => "Invalid subscription ID"})?;
+
+        let mut connections = self.connections.write();
+        let connection = connections.get_mut(&self.connection_id)
+            .ok_or_else(|| object!{"message" => "Connection closed"})?;
+
+        Ok(connection.subscriptions.remove(&subscription_id).is_some().into())
+    }
+}
+
+impl Handler for WsRpcConnectionHandler {
+    fn call_method(&self, name: &str, params: Array) -> Option<Result<JsonValue, JsonValue>> {
+        if name == "authorize" {
+            return Some(self.authorize_connection(&params));
+        }
+
+        if !self.is_authorized() {
+            return Some(Err(object!{"message" => AuthenticationError::IncorrectCredentials.to_string()}));
+        }
+
+        match name {
+            "subscribe" => Some(self.subscribe(&params)),
+            "unsubscribe" => Some(self.unsubscribe(&params)),
+            _ => self.handler.call_method(name, params),
+        }
+    }
+
+    fn authorize(&self, username: &str, password: &str) -> Result<(), AuthenticationError> {
+        self.handler.authorize(username, password)
+    }
+}
+
+/// Rejects the websocket handshake of web pages whose origin is not allowed, so that they can't
+/// use the RPC methods of the node. Browsers always send the `Origin` header, other clients don't
+/// need to.
+struct OriginCheck {
+    allowed_origins: Arc<Vec<String>>,
+}
+
+impl Callback for OriginCheck {
+    fn on_request(self, request: &Request) -> Result<Option<Vec<(String, String)>>, ErrorResponse> {
+        if let Some(origin) = request.headers.find_first("Origin") {
+            let is_allowed = from_utf8(origin)
+                .map(|origin| self.allowed_origins.iter().any(|allowed| allowed == origin))
+                .unwrap_or(false);
+            if !is_allowed {
+                warn!("Rejecting connection from origin {}", String::from_utf8_lossy(origin));
+                return Err(ErrorResponse {
+                    error_code: StatusCode::FORBIDDEN,
+                    headers: None,
+                    body: Some("Origin not allowed".to_string()),
+                });
+            }
+        }
+        Ok(None)
+    }
 }
 
 pub struct WsRpcServer {
@@ -50,13 +189,19 @@ pub struct WsRpcServer {
 impl WsRpcServer {
     const QUEUE_SIZE: usize = 64;
 
-    pub fn new(ip: IpAddr, port: u16) -> Result<Self, IoError>
+    /// Only web pages from `allowed_origins` can connect to the server.
+    pub fn new(ip: IpAddr, port: u16, handler: Arc<dyn Handler>, allowed_origins: Vec<String>) -> Result<Self, IoError>
     {
         let socket = TcpListener::bind(&SocketAddr::new(ip, port))?;
+        let allowed_origins = Arc::new(allowed_origins);
 
         let connections = Arc::new(RwLock::new(HashMap::new()));
         let connections_tcp = Arc::clone(&connections);
 
+        // Requests are handled on a separate pool, since handlers may block, e.g. while they
+        // request accounts from peers.
+        let cpu_pool = CpuPool::new_num_cpus();
+
         // Listen for incoming connections, do websocket handshake and put them in connections.
         let future = socket.incoming()
             .for_each(move |stream| {
@@ -68,7 +213,20 @@ impl WsRpcServer {
                 let connections_stream = Arc::clone(&connections_tcp);
                 let connections_err = Arc::clone(&connections_tcp);
 
-                accept_async(stream)
+                // Connections are authorized right away if no credentials are configured.
+                let authorized = handler.authorize("", "").is_ok();
+                let connection_handler = Arc::new(WsRpcConnectionHandler {
+                    connection_id,
+                    connections: Arc::clone(&connections_tcp),
+                    handler: Arc::clone(&handler),
+                });
+                let cpu_pool = cpu_pool.clone();
+
+                let origin_check = OriginCheck {
+                    allowed_origins: Arc::clone(&allowed_origins),
+                };
+
+                accept_hdr_async(stream, origin_check)
                     .and_then(move |ws_stream| {
                         // Split stream
                         let (sink, stream) = ws_stream.split();
@@ -79,18 +237,12 @@ impl WsRpcServer {
                         // Send everything from the MSPC channel
                         let send_future = sink.send_all(rx.map_err(|_| WsError::ConnectionClosed));
 
-                        // Receive messages (and ignore them)
+                        // Receive messages and answer JSON-RPC requests
                         let connection_id_recv = connection_id;
                         let connections_recv = Arc::clone(&connections_stream);
+                        let tx_recv = tx.clone();
                         let recv_future = stream
-                            .for_each(move |message: Message| {
-                                // Received message. We ignore those. But we could use this for
-                                // authentication and just set a flag in the connection to enable
-                                // streaming events.
-                                //
-                                // TODO: We'll also receive close frames, which let's us close the
-                                // connection gracefully.
-
+                            .for_each(move |message: Message| -> Box<dyn Future<Item=(), Error=WsError> + Send> {
                                 // Log message
                                 debug!("Received message from #{}: {}", connection_id_recv, message);
 
@@ -98,18 +250,35 @@ impl WsRpcServer {
                                 match message {
                                     Message::Close(_close_frame_opt) => {
                                         // Remove connection from connections map
-                                        let _connection = connections_recv.write().remove(&connection_id_recv)
-                                            .ok_or(WsError::AlreadyClosed)?;
+                                        let result = connections_recv.write().remove(&connection_id_recv)
+                                            .map(|_| ())
+                                            .ok_or(WsError::AlreadyClosed);
 
-                                        Ok(())
+                                        Box::new(future::result(result))
                                     },
-                                    Message::Text(_message) => {
-                                        // TODO: Handle authentication
-                                        Ok(())
+                                    Message::Text(request) => {
+                                        let connection_handler = Arc::clone(&connection_handler);
+                                        let tx = tx_recv.clone();
+                                        let response_future = cpu_pool.spawn_fn(move || -> Result<_, WsError> {
+                                            Ok(jsonrpc::handle_json_request(connection_handler.as_ref(), &request))
+                                        });
+
+                                        Box::new(response_future.and_then(move |result| -> Box<dyn Future<Item=(), Error=WsError> + Send> {
+                                            let response = match result {
+                                                Ok(JsonValue::Null) => return Box::new(future::ok(())),
+                                                Ok(response) => response,
+                                                Err(error) => error,
+                                            };
+
+                                            // Wait for space in the queue, we don't want to drop responses.
+                                            Box::new(tx.send(Message::Text(response.dump()))
+                                                .map(|_| ())
+                                                .map_err(|_| WsError::ConnectionClosed))
+                                        }))
                                     },
                                     _ => {
                                         // Abort connection for everything else
-                                        Err(WsError::ConnectionClosed)
+                                        Box::new(future::err(WsError::ConnectionClosed))
                                     }
                                 }
                             });
@@ -119,6 +288,9 @@ impl WsRpcServer {
                             .insert(connection_id.clone(), WsRpcConnection {
                                 address,
                                 tx,
+                                authorized,
+                                subscriptions: BTreeMap::new(),
+                                next_subscription_id: 0,
                             });
 
                         let connection_future = send_future
@@ -149,12 +321,29 @@ impl WsRpcServer {
 
     pub fn register_blockchain(&self, consensus: Arc<Consensus<AlbatrossConsensusProtocol>>) {
         let connections_listener = Arc::clone(&self.connections);
+        let blockchain = Arc::downgrade(&consensus.blockchain);
 
         consensus.blockchain.register_listener(move |event: &BlockchainEvent| {
             if !connections_listener.read().is_empty() {
                 if let Some(message) = Self::map_blockchain_event(event) {
-                    Self::broadcast_message(&connections_listener, message)
+                    match event {
+                        BlockchainEvent::Finalized(_) => Self::broadcast_message(&connections_listener, message, Subscription::is_finalized),
+                        _ => Self::broadcast_message(&connections_listener, message, Subscription::is_heads),
+                    }
                 }
+                Self::notify_staking_changes(&connections_listener, &blockchain, event);
+            }
+        });
+    }
+
+    pub fn register_mempool(&self, consensus: Arc<Consensus<AlbatrossConsensusProtocol>>) {
+        let connections_listener = Arc::clone(&self.connections);
+
+        consensus.mempool.notifier.write().register(move |event: &MempoolEvent| {
+            if !connections_listener.read().is_empty() {
+                let (message, transaction) = Self::map_mempool_event(event);
+                Self::broadcast_message(&connections_listener, message,
+                    |subscription| subscription.matches_transaction(transaction))
             }
         });
     }
@@ -166,7 +355,7 @@ impl WsRpcServer {
         validator.validator_network.notifier.write().register(move |event: &ValidatorNetworkEvent| {
             if !connections_listener.read().is_empty() {
                 if let Some(message) = Self::map_validator_event(event) {
-                    Self::broadcast_message(&connections_listener, message)
+                    Self::broadcast_message(&connections_listener, message, Subscription::is_validator)
                 }
             }
         });
@@ -205,6 +394,32 @@ impl WsRpcServer {
         })
     }
 
+    fn map_mempool_event(event: &MempoolEvent) -> (JsonValue, &Transaction) {
+        let (event_type, hash, transaction) = match event {
+            MempoolEvent::TransactionAdded(hash, transaction) => ("transactionAdded", hash.clone(), transaction),
+            MempoolEvent::TransactionRestored(transaction) => ("transactionRestored", transaction.hash::<Blake2bHash>(), transaction),
+            MempoolEvent::TransactionMined(transaction) => ("transactionMined", transaction.hash::<Blake2bHash>(), transaction),
+            MempoolEvent::TransactionEvicted(transaction) => ("transactionEvicted", transaction.hash::<Blake2bHash>(), transaction),
+            MempoolEvent::TransactionReplaced(transaction, _) => ("transactionReplaced", transaction.hash::<Blake2bHash>(), transaction),
+        };
+
+        let mut message = object!{
+            "eventType" => event_type,
+            "hash" => hash.to_string(),
+            "sender" => transaction.sender.to_user_friendly_address(),
+            "recipient" => transaction.recipient.to_user_friendly_address(),
+            "value" => u64::from(transaction.value),
+            "fee" => u64::from(transaction.fee),
+            "validityStartHeight" => transaction.validity_start_height,
+            "data" => hex::encode(&transaction.data),
+            "flags" => transaction.flags.bits(),
+        };
+        if let MempoolEvent::TransactionReplaced(_, replaced_by) = event {
+            message["replacedBy"] = replaced_by.to_string().into();
+        }
+        (message, &**transaction)
+    }
+
     #[cfg(feature="validator")]
     fn map_validator_event(event: &ValidatorNetworkEvent) -> Option<JsonValue> {
         Some(match event {
@@ -229,19 +444,121 @@ impl WsRpcServer {
         })
     }
 
-    fn broadcast_message(connections: &WsRpcConnections, message: JsonValue) {
-        // Convert JSON message to Websocket TEXT frame
-        let message = Message::Text(message.dump());
+    /// Sends a `stakingChanged` event to every staking subscription whose validator changed
+    /// since it was last notified. The first notification after subscribing contains the
+    /// current state of the validator.
+    ///
+    /// The staking contract is only read for events that touch it, or to send the first state to
+    /// new subscriptions.
+    fn notify_staking_changes(connections: &WsRpcConnections, blockchain: &Weak<Blockchain>, event: &BlockchainEvent) {
+        let (has_staking_subscriptions, has_new_staking_subscriptions) = connections.read().values()
+            .flat_map(|connection| connection.subscriptions.values())
+            .fold((false, false), |(any, any_new), subscription| match subscription {
+                Subscription::Staking { state, .. } => (true, any_new || state.is_none()),
+                _ => (any, any_new),
+            });
+        if !has_staking_subscriptions {
+            return;
+        }
 
+        let blockchain = match blockchain.upgrade() {
+            Some(blockchain) => blockchain,
+            None => return,
+        };
+        let staking_contract_address = match blockchain.validator_registry_address() {
+            Some(address) => address.clone(),
+            None => return,
+        };
+        if !has_new_staking_subscriptions && !Self::touches_staking_contract(&blockchain, event, &staking_contract_address) {
+            return;
+        }
+        let contract = match blockchain.get_account(&staking_contract_address) {
+            Account::Staking(contract) => contract,
+            // Nodes without the accounts state, e.g. light nodes, can't report staking changes.
+            _ => return,
+        };
+
+        for connection in connections.write().values_mut() {
+            if !connection.authorized {
+                continue;
+            }
+
+            for (subscription_id, subscription) in connection.subscriptions.iter_mut() {
+                if let Some(state) = subscription.update_staking_state(&contract) {
+                    let message = object!{
+                        "eventType" => "stakingChanged",
+                        "subscription" => *subscription_id,
+                        "validator" => state,
+                    };
+                    Self::send_message(connection.address, &connection.tx, Message::Text(message.dump()));
+                }
+            }
+        }
+    }
+
+    /// Returns whether the blocks of `event` may have changed the staking contract.
+    fn touches_staking_contract(blockchain: &Blockchain, event: &BlockchainEvent, staking_contract_address: &Address) -> bool {
+        match event {
+            // Only macro blocks are finalized, and they always change the staking contract.
+            BlockchainEvent::Finalized(_) => true,
+            BlockchainEvent::Extended(block_hash) => blockchain.get_block(block_hash, false, true)
+                .map_or(true, |block| Self::block_touches_staking_contract(blockchain, &block, staking_contract_address)),
+            BlockchainEvent::Rebranched(reverted, adopted) => reverted.iter().chain(adopted.iter())
+                .any(|(_, block)| Self::block_touches_staking_contract(blockchain, block, staking_contract_address)),
+        }
+    }
+
+    fn block_touches_staking_contract(blockchain: &Blockchain, block: &Block, staking_contract_address: &Address) -> bool {
+        let micro_block = match block {
+            Block::Macro(_) => return true,
+            Block::Micro(micro_block) => micro_block,
+        };
+        let extrinsics = match &micro_block.extrinsics {
+            Some(extrinsics) => extrinsics,
+            None => return true,
+        };
+
+        // Fork proofs and view changes slash validators.
+        if !extrinsics.fork_proofs.is_empty() {
+            return true;
+        }
+        let has_view_changes = blockchain.get_block(&micro_block.header.parent_hash, true, false)
+            .map_or(true, |parent| parent.next_view_number() < micro_block.header.view_number);
+
+        has_view_changes || extrinsics.transactions.iter()
+            .any(|tx| &tx.sender == staking_contract_address || &tx.recipient == staking_contract_address)
+    }
+
+    /// Sends `message` once for every subscription `filter` matches. The subscription ID is added
+    /// to the message as `subscription`.
+    fn broadcast_message<F: Fn(&Subscription) -> bool>(connections: &WsRpcConnections, mut message: JsonValue, filter: F) {
         for (_connection_id, connection) in connections.read().iter() {
-            let mut tx = connection.tx.clone();
+            // Only authorized clients receive events.
+            if !connection.authorized {
+                continue;
+            }
+
+            for (subscription_id, subscription) in connection.subscriptions.iter() {
+                if !filter(subscription) {
+                    continue;
+                }
+
+                message["subscription"] = (*subscription_id).into();
 
-            // If the buffer is full, we drop the event. It's not the end of the world.
-            if let Err(e) = tx.try_send(message.clone()) {
-                warn!("Unable to send event to {}: {}", connection.address, e);
+                // Convert JSON message to Websocket TEXT frame
+                Self::send_message(connection.address, &connection.tx, Message::Text(message.dump()));
             }
         }
     }
+
+    fn send_message(address: SocketAddr, tx: &Sender<Message>, message: Message) {
+        let mut tx = tx.clone();
+
+        // If the buffer is full, we drop the event. It's not the end of the world.
+        if let Err(e) = tx.try_send(message) {
+            warn!("Unable to send event to {}: {}", address, e);
+        }
+    }
 }
 
 impl IntoFuture for WsRpcServer {
@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use json::{JsonValue, object};
+
+use account::staking_contract::StakingContract;
+use beserial::Deserialize;
+use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
+use keys::Address;
+use transaction::Transaction;
+
+/// Which side of a transaction an address filter applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionDirection {
+    Sender,
+    Recipient,
+    Both,
+}
+
+impl TransactionDirection {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "sender" => Some(TransactionDirection::Sender),
+            "recipient" => Some(TransactionDirection::Recipient),
+            "both" => Some(TransactionDirection::Both),
+            _ => None,
+        }
+    }
+}
+
+/// An event stream a websocket client can subscribe to.
+pub enum Subscription {
+    /// New chain heads (`blockchainExtended` and `blockchainRebranched` events).
+    Heads,
+    /// Finalized macro blocks (`blockchainFinalized` events).
+    Finalized,
+    /// Mempool transactions sent from and/or to one of the given addresses.
+    Transactions {
+        addresses: HashSet<Address>,
+        direction: TransactionDirection,
+    },
+    /// Changes to the staking contract entry of a validator.
+    /// `state` holds the last state sent to the client, so we only notify about actual changes.
+    Staking {
+        validator_key: BlsPublicKey,
+        state: Option<JsonValue>,
+    },
+    /// PBFT and view change updates of the local validator.
+    #[cfg(feature="validator")]
+    Validator,
+}
+
+impl Subscription {
+    /// Parses the parameters of a `subscribe` call: the topic name, followed by its arguments.
+    pub fn from_params(params: &[JsonValue]) -> Result<Self, JsonValue> {
+        let topic = params.get(0)
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| object!{"message" => "Missing subscription topic"})?;
+
+        Ok(match topic {
+            "heads" => Subscription::Heads,
+            "finalized" => Subscription::Finalized,
+            "transactions" => {
+                let addresses = match params.get(1) {
+                    Some(JsonValue::Array(addresses)) => addresses.iter()
+                        .map(Self::parse_address)
+                        .collect::<Result<HashSet<Address>, JsonValue>>()?,
+                    _ => return Err(object!{"message" => "Expected an array of addresses"}),
+                };
+                let direction = match params.get(2) {
+                    None | Some(JsonValue::Null) => TransactionDirection::Both,
+                    Some(direction) => direction.as_str()
+                        .and_then(TransactionDirection::from_str)
+                        .ok_or_else(|| object!{"message" => "Direction must be one of: sender, recipient, both"})?,
+                };
+                Subscription::Transactions { addresses, direction }
+            },
+            "staking" => {
+                let validator_key = params.get(1)
+                    .and_then(JsonValue::as_str)
+                    .ok_or_else(|| object!{"message" => "Invalid validator key"})
+                    .and_then(|it| hex::decode(it)
+                        .map_err(|_| object!{"message" => "Validator key must be hex-encoded"}))
+                    .and_then(|it| BlsPublicKey::deserialize_from_vec(&it)
+                        .map_err(|_| object!{"message" => "Invalid public key"}))?;
+                Subscription::Staking { validator_key, state: None }
+            },
+            #[cfg(feature="validator")]
+            "validator" => Subscription::Validator,
+            _ => return Err(object!{"message" => format!("Unknown subscription topic: {}", topic)}),
+        })
+    }
+
+    fn parse_address(value: &JsonValue) -> Result<Address, JsonValue> {
+        value.as_str()
+            .ok_or_else(|| object!{"message" => "Invalid address"})
+            .and_then(|it| Address::from_user_friendly_address(it)
+                .map_err(|_| object!{"message" => format!("Invalid address: {}", it)}))
+    }
+
+    pub fn is_heads(&self) -> bool {
+        match self {
+            Subscription::Heads => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        match self {
+            Subscription::Finalized => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(feature="validator")]
+    pub fn is_validator(&self) -> bool {
+        match self {
+            Subscription::Validator => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if this is a transaction subscription matching the sender and/or recipient
+    /// of `transaction`.
+    pub fn matches_transaction(&self, transaction: &Transaction) -> bool {
+        match self {
+            Subscription::Transactions { addresses, direction } => {
+                let sender = addresses.contains(&transaction.sender);
+                let recipient = addresses.contains(&transaction.recipient);
+                match direction {
+                    TransactionDirection::Sender => sender,
+                    TransactionDirection::Recipient => recipient,
+                    TransactionDirection::Both => sender || recipient,
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// For staking subscriptions, computes the current state of the validator in `contract`.
+    /// Returns the new state if it changed since the last call.
+    pub fn update_staking_state(&mut self, contract: &StakingContract) -> Option<JsonValue> {
+        match self {
+            Subscription::Staking { validator_key, state } => {
+                let new_state = Self::validator_state(contract, validator_key);
+                if state.as_ref() == Some(&new_state) {
+                    return None;
+                }
+                *state = Some(new_state.clone());
+                Some(new_state)
+            },
+            _ => None,
+        }
+    }
+
+    fn validator_state(contract: &StakingContract, validator_key: &BlsPublicKey) -> JsonValue {
+        let (status, retire_time) = if contract.active_validators_by_key.contains_key(validator_key) {
+            ("active", JsonValue::Null)
+        } else if let Some(inactive_validator) = contract.inactive_validators_by_key.get(validator_key) {
+            ("inactive", inactive_validator.retire_time.into())
+        } else {
+            ("unknown", JsonValue::Null)
+        };
+
+        let validator = match contract.get_validator(validator_key) {
+            Some(validator) => validator,
+            None => return object!{
+                "publicKey" => hex::encode(validator_key),
+                "status" => status,
+            },
+        };
+
+        let mut stakes = json::object::Object::new();
+        for (address, &stake) in validator.active_stake_by_address.read().iter() {
+            stakes.insert(&address.to_user_friendly_address(), u64::from(stake).into());
+        }
+
+        object!{
+            "publicKey" => hex::encode(validator_key),
+            "status" => status,
+            "balance" => u64::from(validator.balance),
+            "rewardAddress" => validator.reward_address.to_user_friendly_address(),
+            "commissionRate" => validator.commission_rate,
+            "pendingCommissionRate" => validator.pending_commission_rate,
+            "stakes" => JsonValue::Object(stakes),
+            "retireTime" => retire_time,
+            "parked" => contract.current_epoch_parking.contains(validator_key)
+                || contract.previous_epoch_parking.contains(validator_key),
+        }
+    }
+}